@@ -0,0 +1,157 @@
+//Common interface implemented by every authenticated encryption scheme in the crate
+
+/// The error returned by every `Aead` operation.
+///
+/// It deliberately carries no detail: a wrong key, nonce, associated data, tag or a truncated
+/// ciphertext all look the same to the caller, so a failed decryption reveals nothing about why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AeadError;
+
+impl std::fmt::Display for AeadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "aead operation failed")
+    }
+}
+
+impl std::error::Error for AeadError {}
+
+/// An authenticated encryption scheme with associated data.
+///
+/// `seal` encrypts and authenticates the plaintext together with the associated data, and
+/// `open` only returns the plaintext if the ciphertext, tag and associated data are all intact.
+/// Sealed messages are the ciphertext followed by the tag (AES-SIV puts the tag first, as its RFC
+/// does), the detached variants work in place and keep the tag separate.
+/// A nonce must never be reused under the same key, unless the scheme is misuse resistant.
+pub trait Aead: Sized {
+    /// Size in bytes of the key
+    const KEY_SIZE: usize;
+    /// Size in bytes of the nonce
+    const NONCE_SIZE: usize;
+    /// Size in bytes of the authentication tag
+    const TAG_SIZE: usize;
+
+    /// Create an instance from a key of `KEY_SIZE` bytes
+    fn new(key: &[u8]) -> Result<Self, AeadError>;
+
+    /// Encrypt `data` in place and return the tag
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError>;
+
+    /// Check `tag` and decrypt `data` in place, `data` is left untouched if the check fails
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError>;
+
+    /// Encrypt `buffer` in place and append the tag to it
+    fn seal_in_place(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), AeadError> {
+        let tag = self.seal_in_place_detached(nonce, aad, buffer)?;
+        buffer.extend_from_slice(&tag);
+        Ok(())
+    }
+
+    /// Check and remove the tag at the end of `buffer`, then decrypt the rest in place
+    fn open_in_place(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), AeadError> {
+        if buffer.len() < Self::TAG_SIZE {
+            return Err(AeadError);
+        }
+        let data_len = buffer.len() - Self::TAG_SIZE;
+        let (data, tag) = buffer.split_at_mut(data_len);
+        self.open_in_place_detached(nonce, aad, data, tag)?;
+        buffer.truncate(data_len);
+        Ok(())
+    }
+
+    /// Encrypt `plaintext`, returning the ciphertext followed by the tag
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, AeadError> {
+        let mut buffer = plaintext.to_vec();
+        self.seal_in_place(nonce, aad, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Check the tag at the end of `ciphertext` and return the decrypted rest
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, AeadError> {
+        let mut buffer = ciphertext.to_vec();
+        self.open_in_place(nonce, aad, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::{Aead, AeadError};
+    use crate::aes_gcm::{Aes128Gcm, Aes256Gcm};
+    use crate::chacha20_poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+
+    // what a service does with the cipher picked from its configuration
+    fn round_trip<A: Aead>() {
+        let aead = A::new(&vec![0x42; A::KEY_SIZE]).unwrap();
+        let nonce = vec![7; A::NONCE_SIZE];
+        let sealed = aead.seal(&nonce, b"route", b"event payload").unwrap();
+        assert_eq!(sealed.len(), b"event payload".len() + A::TAG_SIZE);
+        assert_eq!(
+            aead.open(&nonce, b"route", &sealed).unwrap(),
+            b"event payload"
+        );
+
+        let mut corrupted = sealed.clone();
+        corrupted[0] ^= 1;
+        assert_eq!(aead.open(&nonce, b"route", &corrupted), Err(AeadError));
+        assert_eq!(aead.open(&nonce, b"other", &sealed), Err(AeadError));
+        assert_eq!(
+            aead.open(&nonce, b"route", &sealed[..A::TAG_SIZE - 1]),
+            Err(AeadError)
+        );
+
+        let mut buffer = b"event payload".to_vec();
+        aead.seal_in_place(&nonce, b"route", &mut buffer).unwrap();
+        assert_eq!(buffer, sealed);
+        aead.open_in_place(&nonce, b"route", &mut buffer).unwrap();
+        assert_eq!(buffer, b"event payload");
+    }
+
+    #[test]
+    fn every_aead_round_trips() {
+        round_trip::<Aes128Gcm>();
+        round_trip::<Aes256Gcm>();
+        round_trip::<ChaCha20Poly1305>();
+        round_trip::<XChaCha20Poly1305>();
+    }
+
+    fn rejects_bad_sizes<A: Aead>() {
+        assert!(A::new(&vec![0; A::KEY_SIZE + 1]).is_err());
+        let aead = A::new(&vec![0; A::KEY_SIZE]).unwrap();
+        assert!(
+            aead.seal(&vec![0; A::NONCE_SIZE - 1], b"", b"data")
+                .is_err()
+        );
+        let mut data = [0u8; 4];
+        let nonce = vec![0; A::NONCE_SIZE];
+        let result = aead.open_in_place_detached(&nonce, b"", &mut data, &[0; 15]);
+        assert_eq!(result, Err(AeadError));
+    }
+
+    #[test]
+    fn bad_sizes_are_errors() {
+        rejects_bad_sizes::<Aes128Gcm>();
+        rejects_bad_sizes::<ChaCha20Poly1305>();
+        rejects_bad_sizes::<XChaCha20Poly1305>();
+    }
+}
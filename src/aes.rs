@@ -1,116 +1,51 @@
-use core::error;
-use std::ops::BitXor;
-
 /**
-* This is an implementation of the AES encryption algorithim
-* It has a block size of 16 bytes and currently assumes a key size of 16 bytes as well.
+* This is an implementation of the AES encryption algorithim from FIPS 197.
+* It has a block size of 16 bytes and supports 16, 24 and 32 byte keys (AES-128, AES-192 and AES-256).
+* `Aes` is the block cipher that the modes of operation build on, `STATE` encrypts one block at a time.
 */
+use crate::rng::CryptoRng;
 
-///Fixed key size for AES-128 for now
-const KEY_SIZE: u8 = 16;
+///The block size of AES in bytes
+pub const BLOCK_SIZE: usize = 16;
 
-///Constants used for key arithmetic in AES
-const KEY_CONST_1: u16 = (0x1 << 8) + 0x1B;
-const KEY_CONST_2: u16 = (0x01 << 8) + 0x00;
+///Most rounds used by any key size, AES-256 uses 14
+const MAX_ROUNDS: usize = 14;
 
-///A struct to hold the key
-pub union Key {
-    pub bytes: [u8; 16],
-    words: [AESWord; 4],
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AesError {
+    ///Keys must be 16, 24 or 32 bytes
+    InvalidKeyLength,
+    ///The data is not a length the mode of operation can process
+    InvalidDataLength,
 }
 
-impl Key {
-    /// This function generates the first 10 round constants
-    fn generate_rconi() -> [AESWord; 10] {
-        let mut rconi: [AESWord; 10] = [AESWord {
-            bytes: [0, 0, 0, 0],
-        }; 10];
-        rconi[0] = AESWord {
-            bytes: [1, 0, 0, 0],
-        };
-
-        for i in 1..10 {
-            if unsafe { rconi[i - 1].bytes[0] < 0x80 } {
-                rconi[i] = AESWord {
-                    bytes: [2 * rconi[i - 1].get_0th(), 0, 0, 0],
-                };
-            } else {
-                rconi[i] = AESWord {
-                    bytes: [
-                        unsafe {
-                            (((2 * (rconi[i - 1].bytes[0] as u16)) ^ KEY_CONST_1) % KEY_CONST_2)
-                                as u8
-                        },
-                        0,
-                        0,
-                        0,
-                    ],
-                };
+impl std::fmt::Display for AesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AesError::InvalidKeyLength => write!(f, "AES keys must be 16, 24 or 32 bytes"),
+            AesError::InvalidDataLength => {
+                write!(
+                    f,
+                    "the data length is not supported by the mode of operation"
+                )
             }
         }
-
-        rconi
-    }
-
-    pub fn expand(&self) -> [AESWord; 44] {
-        let rconi: [AESWord; 10] = Self::generate_rconi();
-
-        let mut expanded_words: [AESWord; 44] = [AESWord {
-            bytes: [0, 0, 0, 0],
-        }; 44];
-
-        for i in 0..44 {
-            if i < 4 {
-                expanded_words[i] = unsafe { self.words[i].clone() };
-            } else if i % 4 == 0 {
-                expanded_words[i] = expanded_words[i - 4]
-                    ^ AESWord {
-                        num: unsafe { expanded_words[i - 1].num.rotate_left(8) },
-                    }
-                    .sub_word()
-                    ^ rconi[(i / 4) - 1];
-            } else {
-                expanded_words[i] = expanded_words[i - 4] ^ expanded_words[i - 1];
-            }
-        }
-
-        expanded_words
     }
 }
 
-#[derive(Copy, Clone)]
-union AESWord {
-    bytes: [u8; 4],
-    num: u32,
-}
-
-impl AESWord {
-    pub fn get_0th(self: Self) -> u8 {
-        unsafe { self.bytes[0] }
-    }
+impl std::error::Error for AesError {}
 
-    pub fn sub_word(self: Self) -> AESWord {
-        AESWord {
-            bytes: [
-                unsafe { s_box_lookup(self.bytes[0]) },
-                unsafe { s_box_lookup(self.bytes[1]) },
-                unsafe { s_box_lookup(self.bytes[2]) },
-                unsafe { s_box_lookup(self.bytes[3]) },
-            ],
-        }
-    }
-    pub fn zeroes() -> AESWord {
-        AESWord {
-            bytes: [0, 0, 0, 0],
-        }
-    }
+///A struct to hold an AES-128 key
+#[derive(Clone, Copy)]
+pub struct Key {
+    pub bytes: [u8; 16],
 }
 
-impl BitXor for AESWord {
-    type Output = Self;
-    fn bitxor(self, rhs: Self) -> Self {
-        Self {
-            num: unsafe { self.num ^ rhs.num },
+impl Key {
+    /// Generate a fresh random key
+    pub fn generate(rng: &mut impl CryptoRng) -> Key {
+        Key {
+            bytes: rng.random_array(),
         }
     }
 }
@@ -135,6 +70,26 @@ pub const AES_SBOX: [u8; 256] = [
     0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
 ];
 
+// AES inverse S-box lookup table
+const AES_INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
 /// Apply the AES S-box to a single byte
 #[inline(always)]
 pub fn s_box_lookup(byte: u8) -> u8 {
@@ -161,157 +116,253 @@ pub fn s_box(byte_in: u8) -> u8 {
     return output;
 }
 */
-union state_data {
-    words: [AESWord; 4],
-    bytes: [u8; 16],
+
+/// Multiply by x in GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1
+#[inline(always)]
+fn xtime(byte: u8) -> u8 {
+    (byte << 1) ^ (((byte >> 7) & 1) * 0x1b)
+}
+
+/// Multiply two elements of GF(2^8)
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        product ^= a * (b & 1);
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+/// The AES block cipher with an expanded key schedule
+#[derive(Clone)]
+pub struct Aes {
+    round_keys: [[u8; BLOCK_SIZE]; MAX_ROUNDS + 1],
+    rounds: usize,
+}
+
+impl Aes {
+    pub fn new(key: &[u8]) -> Result<Aes, AesError> {
+        let rounds = match key.len() {
+            16 => 10,
+            24 => 12,
+            32 => 14,
+            _ => return Err(AesError::InvalidKeyLength),
+        };
+
+        // expand the key into 4 * (rounds + 1) words
+        let key_words = key.len() / 4;
+        let total_words = 4 * (rounds + 1);
+        let mut words = [[0u8; 4]; 4 * (MAX_ROUNDS + 1)];
+        for (word, bytes) in words.iter_mut().zip(key.chunks_exact(4)) {
+            word.copy_from_slice(bytes);
+        }
+        let mut rcon = 1u8;
+        for i in key_words..total_words {
+            let mut temp = words[i - 1];
+            if i % key_words == 0 {
+                temp.rotate_left(1);
+                temp = temp.map(s_box_lookup);
+                temp[0] ^= rcon;
+                rcon = xtime(rcon);
+            } else if key_words > 6 && i % key_words == 4 {
+                temp = temp.map(s_box_lookup);
+            }
+            for j in 0..4 {
+                words[i][j] = words[i - key_words][j] ^ temp[j];
+            }
+        }
+
+        let mut round_keys = [[0u8; BLOCK_SIZE]; MAX_ROUNDS + 1];
+        for (round_key, round_words) in round_keys.iter_mut().zip(words.chunks_exact(4)) {
+            for (bytes, word) in round_key.chunks_exact_mut(4).zip(round_words) {
+                bytes.copy_from_slice(word);
+            }
+        }
+        Ok(Aes { round_keys, rounds })
+    }
+
+    /// The key size in bytes
+    pub fn key_size(&self) -> usize {
+        4 * (self.rounds - 6)
+    }
+
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        add_round_key(block, &self.round_keys[0]);
+        for round in 1..self.rounds {
+            sub_bytes(block, &AES_SBOX);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, &self.round_keys[round]);
+        }
+        sub_bytes(block, &AES_SBOX);
+        shift_rows(block);
+        add_round_key(block, &self.round_keys[self.rounds]);
+    }
+
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        add_round_key(block, &self.round_keys[self.rounds]);
+        for round in (1..self.rounds).rev() {
+            inv_shift_rows(block);
+            sub_bytes(block, &AES_INV_SBOX);
+            add_round_key(block, &self.round_keys[round]);
+            inv_mix_columns(block);
+        }
+        inv_shift_rows(block);
+        sub_bytes(block, &AES_INV_SBOX);
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+/// Multiply a block, read as a big endian integer, by x in GF(2^128) modulo
+/// x^128 + x^7 + x^2 + x + 1, the doubling CMAC, OCB and SIV derive their subkeys with
+pub(crate) fn double_block(block: u128) -> u128 {
+    (block << 1) ^ (0x87 & 0u128.wrapping_sub(block >> 127))
+}
+
+// The state is held column by column, byte 4 * c + r is row r of column c
+
+fn add_round_key(block: &mut [u8; BLOCK_SIZE], round_key: &[u8; BLOCK_SIZE]) {
+    for (byte, key_byte) in block.iter_mut().zip(round_key) {
+        *byte ^= key_byte;
+    }
+}
+
+fn sub_bytes(block: &mut [u8; BLOCK_SIZE], table: &[u8; 256]) {
+    for byte in block.iter_mut() {
+        *byte = table[*byte as usize];
+    }
+}
+
+fn shift_rows(block: &mut [u8; BLOCK_SIZE]) {
+    let old = *block;
+    for c in 0..4 {
+        for r in 1..4 {
+            block[4 * c + r] = old[4 * ((c + r) % 4) + r];
+        }
+    }
+}
+
+fn inv_shift_rows(block: &mut [u8; BLOCK_SIZE]) {
+    let old = *block;
+    for c in 0..4 {
+        for r in 1..4 {
+            block[4 * ((c + r) % 4) + r] = old[4 * c + r];
+        }
+    }
+}
+
+fn mix_columns(block: &mut [u8; BLOCK_SIZE]) {
+    for column in block.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+        let all = a0 ^ a1 ^ a2 ^ a3;
+        column[0] ^= all ^ xtime(a0 ^ a1);
+        column[1] ^= all ^ xtime(a1 ^ a2);
+        column[2] ^= all ^ xtime(a2 ^ a3);
+        column[3] ^= all ^ xtime(a3 ^ a0);
+    }
+}
+
+fn inv_mix_columns(block: &mut [u8; BLOCK_SIZE]) {
+    for column in block.chunks_exact_mut(4) {
+        let a = [column[0], column[1], column[2], column[3]];
+        for (r, byte) in column.iter_mut().enumerate() {
+            *byte = gf_mul(a[r], 0x0e)
+                ^ gf_mul(a[(r + 1) % 4], 0x0b)
+                ^ gf_mul(a[(r + 2) % 4], 0x0d)
+                ^ gf_mul(a[(r + 3) % 4], 0x09);
+        }
+    }
 }
 
 pub struct STATE {
-    data: state_data,
-    key: Key,
-    expanded_key: [AESWord; 44],
+    data: [u8; BLOCK_SIZE],
+    cipher: Aes,
 }
 
 impl STATE {
     pub fn init(key: Key) -> STATE {
         STATE {
-            data: state_data {
-                words: [
-                    AESWord::zeroes(),
-                    AESWord::zeroes(),
-                    AESWord::zeroes(),
-                    AESWord::zeroes(),
-                ],
-            },
-            key,
-            expanded_key: [
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-                AESWord::zeroes(),
-            ],
+            data: [0; BLOCK_SIZE],
+            cipher: Aes::new(&key.bytes).unwrap(),
         }
     }
 
+    /// Load up to one block of data, short blocks are padded with zeros
     pub fn update(&mut self, new_data: &[u8], len: usize) {
-        if len > 16 {
+        if len > BLOCK_SIZE {
             println!("too big");
         } else {
-            if len < 16 {
-                unsafe { self.data.bytes[len - 1..15].fill(0) };
-            }
-            for i in 0..len {
-                unsafe { self.data.bytes[i] = new_data[i] };
-            }
+            self.data = [0; BLOCK_SIZE];
+            self.data[..len].copy_from_slice(&new_data[..len]);
         }
     }
 
-    fn add_round_key(&mut self, key_start_index: usize) {
-        for i in 0..3 {
-            unsafe {
-                self.data.words[i] = self.data.words[i] ^ self.expanded_key[key_start_index + i]
-            };
-        }
+    /// Encrypt the loaded block into the first 16 bytes of `output_buffer`
+    pub fn encrypt(&mut self, output_buffer: &mut [u8]) {
+        let mut block = self.data;
+        self.cipher.encrypt_block(&mut block);
+        output_buffer[..BLOCK_SIZE].copy_from_slice(&block);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes::{self, Aes, AesError};
+    use crate::test_util::hex;
+
+    const PLAINTEXT: &str = "00112233445566778899aabbccddeeff";
 
-    fn sub_bytes(&mut self) {
-        unsafe { self.data.words[0].sub_word() };
-        unsafe { self.data.words[1].sub_word() };
-        unsafe { self.data.words[2].sub_word() };
-        unsafe { self.data.words[3].sub_word() };
+    fn check_fips197(key: &str, ciphertext: &str) {
+        let cipher = Aes::new(&hex(key)).unwrap();
+        let mut block: [u8; 16] = hex(PLAINTEXT).try_into().unwrap();
+        cipher.encrypt_block(&mut block);
+        assert_eq!(block.to_vec(), hex(ciphertext));
+        cipher.decrypt_block(&mut block);
+        assert_eq!(block.to_vec(), hex(PLAINTEXT));
     }
 
-    fn shift_rows(&mut self) {
-        unsafe { self.data.words[1].num = self.data.words[1].num.rotate_left(8) };
-        unsafe { self.data.words[2].num = self.data.words[2].num.rotate_left(16) };
-        unsafe { self.data.words[3].num = self.data.words[3].num.rotate_left(24) };
+    #[test]
+    fn fips197_aes128() {
+        check_fips197(
+            "000102030405060708090a0b0c0d0e0f",
+            "69c4e0d86a7b0430d8cdb78070b4c55a",
+        );
     }
 
-    fn mix_columns(&mut self) {
-        for i in 0..3 {
-            let new_0th: u8 = unsafe {
-                self.data.words[0].bytes[i] << 2
-                    ^ (self.data.words[1].bytes[i] * 3)
-                    ^ (self.data.words[2].bytes[i])
-                    ^ (self.data.words[3].bytes[i])
-            };
-            let new_1st: u8 = unsafe {
-                self.data.words[0].bytes[i]
-                    ^ (self.data.words[1].bytes[i] << 2)
-                    ^ (self.data.words[2].bytes[i] * 3)
-                    ^ (self.data.words[3].bytes[i])
-            };
-            let new_2nd: u8 = unsafe {
-                self.data.words[0].bytes[i]
-                    ^ (self.data.words[1].bytes[i])
-                    ^ (self.data.words[2].bytes[i] << 2)
-                    ^ (self.data.words[3].bytes[i] * 3)
-            };
-            let new_3rd: u8 = unsafe {
-                self.data.words[0].bytes[i] * 3
-                    ^ (self.data.words[1].bytes[i])
-                    ^ (self.data.words[2].bytes[i])
-                    ^ (self.data.words[3].bytes[i] << 2)
-            };
-            unsafe { self.data.words[0].bytes[i] = new_0th };
-            unsafe { self.data.words[1].bytes[i] = new_1st };
-            unsafe { self.data.words[2].bytes[i] = new_2nd };
-            unsafe { self.data.words[3].bytes[i] = new_3rd };
-        }
+    #[test]
+    fn fips197_aes192() {
+        check_fips197(
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "dda97ca4864cdfe06eaf70a0ec0d7191",
+        );
     }
 
-    pub fn encrypt(&mut self, output_buffer: &[u8]) {
-        // expand the key
-        self.expanded_key = self.key.expand().clone();
+    #[test]
+    fn fips197_aes256() {
+        check_fips197(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "8ea2b7ca516745bfeafc49904b496089",
+        );
+    }
 
-        // round 0
-        self.add_round_key(0);
+    #[test]
+    fn invalid_key_length() {
+        assert_eq!(Aes::new(&[0; 20]).err(), Some(AesError::InvalidKeyLength));
+        assert_eq!(Aes::new(&[0; 32]).unwrap().key_size(), 32);
+    }
 
-        for i in 1..10 {
-            self.sub_bytes();
-            self.shift_rows();
-            self.mix_columns();
-            self.add_round_key(4 * i);
-        }
+    #[test]
+    fn state_encrypts_one_block() {
+        // FIPS 197 appendix B
+        let key = aes::Key {
+            bytes: hex("2b7e151628aed2a6abf7158809cf4f3c").try_into().unwrap(),
+        };
+        let data = hex("3243f6a8885a308d313198a2e0370734");
+        let mut state = aes::STATE::init(key);
+        state.update(&data, 16);
+        let mut output = [0u8; 16];
+        state.encrypt(&mut output);
+        assert_eq!(output.to_vec(), hex("3925841d02dc09fbdc118597196a0b32"));
     }
 }
@@ -0,0 +1,231 @@
+/**
+* This is an implementation of the CBC mode of AES with ciphertext stealing, from the addendum to
+* NIST SP 800-38A. The last partial block is zero padded and encrypted as in CBC, and the
+* ciphertext block before it is cut to the length of the partial block, so the ciphertext is always
+* as long as the plaintext. The three variants only differ in the order of the last two blocks:
+* CS1 keeps them in order, CS2 swaps them when the last block is partial and CS3 (Kerberos,
+* RFC 3962) always swaps them. OpenSSL's AES-CBC-CTS uses CS1 by default.
+* The data must be at least one block long, the IV must be unpredictable and nothing is authenticated.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE};
+
+///Which of the last two ciphertext blocks comes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtsVariant {
+    ///The cut block then the last block
+    Cs1,
+    ///The last block then the cut block, only when the data is not a whole number of blocks
+    Cs2,
+    ///The last block then the cut block, always
+    Cs3,
+}
+
+fn xor_into(block: &mut [u8], other: &[u8]) {
+    for (byte, other_byte) in block.iter_mut().zip(other) {
+        *byte ^= other_byte;
+    }
+}
+
+#[derive(Clone)]
+pub struct AesCbcCts {
+    cipher: Aes,
+    variant: CtsVariant,
+}
+
+impl AesCbcCts {
+    pub fn new(key: &[u8], variant: CtsVariant) -> Result<AesCbcCts, AesError> {
+        Ok(AesCbcCts {
+            cipher: Aes::new(key)?,
+            variant,
+        })
+    }
+
+    /// Length of the last, possibly partial, block and whether it comes before the cut block
+    fn tail(&self, len: usize) -> (usize, bool) {
+        let last_len = (len - 1) % BLOCK_SIZE + 1;
+        let swapped = match self.variant {
+            CtsVariant::Cs1 => false,
+            CtsVariant::Cs2 => last_len < BLOCK_SIZE,
+            CtsVariant::Cs3 => true,
+        };
+        (last_len, swapped)
+    }
+
+    /// Encrypt `data` in place, it must be at least 16 bytes long
+    pub fn encrypt(&self, iv: &[u8; BLOCK_SIZE], data: &mut [u8]) -> Result<(), AesError> {
+        if data.len() < BLOCK_SIZE {
+            return Err(AesError::InvalidDataLength);
+        }
+        let (last_len, swapped) = self.tail(data.len());
+        // the blocks before the last one, which are chained as in plain CBC
+        let full_len = data.len() - last_len;
+
+        let mut previous = *iv;
+        for chunk in data[..full_len].chunks_exact_mut(BLOCK_SIZE) {
+            xor_into(chunk, &previous);
+            let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+            self.cipher.encrypt_block(block);
+            previous = *block;
+        }
+        if full_len == 0 {
+            // a single block is plain CBC
+            xor_into(data, &previous);
+            self.cipher.encrypt_block(data.try_into().unwrap());
+            return Ok(());
+        }
+
+        let mut last = previous;
+        xor_into(&mut last, &data[full_len..]);
+        self.cipher.encrypt_block(&mut last);
+
+        let cut_start = full_len - BLOCK_SIZE;
+        if swapped {
+            data[cut_start..cut_start + BLOCK_SIZE].copy_from_slice(&last);
+            data[cut_start + BLOCK_SIZE..].copy_from_slice(&previous[..last_len]);
+        } else {
+            data[cut_start..cut_start + last_len].copy_from_slice(&previous[..last_len]);
+            data[cut_start + last_len..].copy_from_slice(&last);
+        }
+        Ok(())
+    }
+
+    /// Decrypt `data` in place, it must be at least 16 bytes long
+    pub fn decrypt(&self, iv: &[u8; BLOCK_SIZE], data: &mut [u8]) -> Result<(), AesError> {
+        if data.len() < BLOCK_SIZE {
+            return Err(AesError::InvalidDataLength);
+        }
+        let (last_len, swapped) = self.tail(data.len());
+        // the blocks before the cut one, which are chained as in plain CBC
+        let chained_len = data.len().saturating_sub(last_len + BLOCK_SIZE);
+
+        let mut previous = *iv;
+        for chunk in data[..chained_len].chunks_exact_mut(BLOCK_SIZE) {
+            let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+            let ciphertext = *block;
+            self.cipher.decrypt_block(block);
+            xor_into(block, &previous);
+            previous = ciphertext;
+        }
+        if data.len() == BLOCK_SIZE {
+            let block: &mut [u8; BLOCK_SIZE] = data.try_into().unwrap();
+            self.cipher.decrypt_block(block);
+            xor_into(block, &previous);
+            return Ok(());
+        }
+
+        let tail = &mut data[chained_len..];
+        let (last, cut): ([u8; BLOCK_SIZE], &[u8]) = if swapped {
+            let (last, cut) = tail.split_at(BLOCK_SIZE);
+            (last.try_into().unwrap(), cut)
+        } else {
+            let (cut, last) = tail.split_at(last_len);
+            (last.try_into().unwrap(), cut)
+        };
+        // the padding of the last block was encrypted as the end of the cut block
+        let mut decrypted_last = last;
+        self.cipher.decrypt_block(&mut decrypted_last);
+        let mut cut_block = decrypted_last;
+        cut_block[..last_len].copy_from_slice(cut);
+
+        let mut last_plaintext = decrypted_last;
+        xor_into(&mut last_plaintext, &cut_block);
+        let mut cut_plaintext = cut_block;
+        self.cipher.decrypt_block(&mut cut_plaintext);
+        xor_into(&mut cut_plaintext, &previous);
+
+        tail[..BLOCK_SIZE].copy_from_slice(&cut_plaintext);
+        tail[BLOCK_SIZE..].copy_from_slice(&last_plaintext[..last_len]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes::AesError;
+    use crate::aes_cbc_cts::{AesCbcCts, CtsVariant};
+    use crate::test_util::hex;
+
+    // the RFC 3962 key and message, with outputs from OpenSSL's AES-128-CBC-CTS in each CTS mode
+    const KEY: &str = "636869636b656e207465726979616b69";
+    const MESSAGE: &[u8] = b"I would like the General Gau's Chicken, please, and wonton soup.";
+
+    fn check(variant: CtsVariant, len: usize, ciphertext: &str) {
+        let cts = AesCbcCts::new(&hex(KEY), variant).unwrap();
+        let mut data = MESSAGE[..len].to_vec();
+        cts.encrypt(&[0; 16], &mut data).unwrap();
+        assert_eq!(data, hex(ciphertext), "{variant:?} {len}");
+        cts.decrypt(&[0; 16], &mut data).unwrap();
+        assert_eq!(data, MESSAGE[..len]);
+    }
+
+    #[test]
+    fn cs1() {
+        check(CtsVariant::Cs1, 17, "97c6353568f2bf8cb4d8a580362da7ff7f");
+        check(
+            CtsVariant::Cs1,
+            31,
+            "97687268d6ecccc0c07b25e25ecfe5fc00783e0efdb2c1d445d4c8eff7ed22",
+        );
+        check(
+            CtsVariant::Cs1,
+            47,
+            "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5\
+             b3fffd940c16a18c1b5549d2f838029e",
+        );
+    }
+
+    #[test]
+    fn cs2() {
+        check(CtsVariant::Cs2, 17, "c6353568f2bf8cb4d8a580362da7ff7f97");
+        check(
+            CtsVariant::Cs2,
+            47,
+            "97687268d6ecccc0c07b25e25ecfe584b3fffd940c16a18c1b5549d2f838029e\
+             39312523a78662d5be7fcbcc98ebf5",
+        );
+        // whole blocks are left in CBC order
+        check(
+            CtsVariant::Cs2,
+            48,
+            "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a8\
+             9dad8bbb96c4cdc03bc103e1a194bbd8",
+        );
+    }
+
+    // these are also the RFC 3962 appendix B vectors
+    #[test]
+    fn cs3() {
+        check(
+            CtsVariant::Cs3,
+            31,
+            "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+        );
+        check(
+            CtsVariant::Cs3,
+            32,
+            "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+        );
+        check(
+            CtsVariant::Cs3,
+            64,
+            "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a8\
+             4807efe836ee89a526730dbc2f7bc8409dad8bbb96c4cdc03bc103e1a194bbd8",
+        );
+    }
+
+    #[test]
+    fn single_block_and_short_data() {
+        for variant in [CtsVariant::Cs1, CtsVariant::Cs2, CtsVariant::Cs3] {
+            check(variant, 16, "97687268d6ecccc0c07b25e25ecfe584");
+            let cts = AesCbcCts::new(&hex(KEY), variant).unwrap();
+            assert_eq!(
+                cts.encrypt(&[0; 16], &mut [0; 15]),
+                Err(AesError::InvalidDataLength)
+            );
+            assert_eq!(
+                cts.decrypt(&[0; 16], &mut [0; 15]),
+                Err(AesError::InvalidDataLength)
+            );
+        }
+    }
+}
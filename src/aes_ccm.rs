@@ -0,0 +1,215 @@
+/**
+* This is an implementation of the CCM mode of AES from NIST SP 800-38C and RFC 3610.
+* The tag is a CBC-MAC over a header block B0, the length prefixed associated data and the
+* payload, and is encrypted with counter block 0, while the payload is encrypted from counter block 1.
+* The nonce can be 7 to 13 bytes, which leaves an 8 to 2 byte length field, and the tag can be
+* any even length from 4 to 16 bytes, 802.15.4 and BLE use a 13 byte nonce with 4 to 16 byte tags.
+*/
+use crate::aead::{Aead, AeadError};
+use crate::aes::{Aes, BLOCK_SIZE};
+use crate::aes_ctr::AesCtr;
+use crate::constant_time;
+use crate::stream_cipher::StreamCipher;
+
+/// AES-CCM with a key of `KEY_SIZE` bytes, a nonce of `NONCE_SIZE` bytes and a tag of `TAG_SIZE` bytes
+#[derive(Clone)]
+pub struct AesCcm<const KEY_SIZE: usize, const NONCE_SIZE: usize, const TAG_SIZE: usize> {
+    cipher: Aes,
+}
+
+pub type Aes128Ccm = AesCcm<16, 13, 16>;
+pub type Aes256Ccm = AesCcm<32, 13, 16>;
+
+impl<const KEY_SIZE: usize, const NONCE_SIZE: usize, const TAG_SIZE: usize>
+    AesCcm<KEY_SIZE, NONCE_SIZE, TAG_SIZE>
+{
+    ///Size in bytes of the field holding the payload length
+    const LENGTH_SIZE: usize = 15 - NONCE_SIZE;
+
+    fn check_lengths(nonce: &[u8], data: &[u8]) -> Result<(), AeadError> {
+        let fits = Self::LENGTH_SIZE >= 8 || (data.len() as u64) >> (8 * Self::LENGTH_SIZE) == 0;
+        if nonce.len() != NONCE_SIZE || !fits {
+            return Err(AeadError);
+        }
+        Ok(())
+    }
+
+    /// A block made of `flags || nonce || value`, with `value` in the length field
+    fn format_block(flags: u8, nonce: &[u8], value: usize) -> [u8; BLOCK_SIZE] {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[0] = flags;
+        block[1..1 + NONCE_SIZE].copy_from_slice(nonce);
+        let value = (value as u64).to_be_bytes();
+        block[1 + NONCE_SIZE..].copy_from_slice(&value[8 - Self::LENGTH_SIZE..]);
+        block
+    }
+
+    fn cbc_mac(&self, nonce: &[u8], aad: &[u8], payload: &[u8]) -> [u8; BLOCK_SIZE] {
+        let adata_flag = if aad.is_empty() { 0 } else { 0x40 };
+        let flags = adata_flag | (((TAG_SIZE - 2) / 2) << 3) as u8 | (Self::LENGTH_SIZE - 1) as u8;
+        let mut mac = Self::format_block(flags, nonce, payload.len());
+        self.cipher.encrypt_block(&mut mac);
+
+        let mut absorb = |data: &[u8]| {
+            for chunk in data.chunks(BLOCK_SIZE) {
+                for (byte, data_byte) in mac.iter_mut().zip(chunk) {
+                    *byte ^= data_byte;
+                }
+                self.cipher.encrypt_block(&mut mac);
+            }
+        };
+
+        if !aad.is_empty() {
+            let mut encoded = if aad.len() < 0xff00 {
+                (aad.len() as u16).to_be_bytes().to_vec()
+            } else if aad.len() as u64 <= u32::MAX as u64 {
+                [&[0xff, 0xfe][..], &(aad.len() as u32).to_be_bytes()].concat()
+            } else {
+                [&[0xff, 0xff][..], &(aad.len() as u64).to_be_bytes()].concat()
+            };
+            encoded.extend_from_slice(aad);
+            absorb(&encoded);
+        }
+        absorb(payload);
+        mac
+    }
+
+    fn counter_mode(&self, nonce: &[u8]) -> AesCtr {
+        let flags = (Self::LENGTH_SIZE - 1) as u8;
+        AesCtr::with_cipher(self.cipher.clone(), &Self::format_block(flags, nonce, 0))
+    }
+
+    /// The CBC-MAC encrypted with counter block 0 and cut to the tag size
+    fn compute_tag(&self, nonce: &[u8], aad: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut tag = self.cbc_mac(nonce, aad, payload)[..TAG_SIZE].to_vec();
+        self.counter_mode(nonce).apply_keystream(&mut tag);
+        tag
+    }
+}
+
+impl<const KEY_SIZE: usize, const NONCE_SIZE: usize, const TAG_SIZE: usize> Aead
+    for AesCcm<KEY_SIZE, NONCE_SIZE, TAG_SIZE>
+{
+    const KEY_SIZE: usize = KEY_SIZE;
+    const NONCE_SIZE: usize = NONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    /// Fails on a wrong key length, or if the nonce or tag size is not one CCM allows
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        let valid_nonce = (7..=13).contains(&NONCE_SIZE);
+        let valid_tag = (4..=16).contains(&TAG_SIZE) && TAG_SIZE.is_multiple_of(2);
+        if key.len() != KEY_SIZE || !valid_nonce || !valid_tag {
+            return Err(AeadError);
+        }
+        Ok(AesCcm {
+            cipher: Aes::new(key).map_err(|_| AeadError)?,
+        })
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        Self::check_lengths(nonce, data)?;
+        let tag = self.compute_tag(nonce, aad, data);
+        let mut ctr = self.counter_mode(nonce);
+        ctr.seek(BLOCK_SIZE as u64);
+        ctr.apply_keystream(data);
+        Ok(tag)
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        Self::check_lengths(nonce, data)?;
+        let mut ctr = self.counter_mode(nonce);
+        ctr.seek(BLOCK_SIZE as u64);
+        ctr.apply_keystream(data);
+        if !constant_time::eq(&self.compute_tag(nonce, aad, data), tag) {
+            // apply the keystream again to restore the ciphertext, the caller must not see plaintext
+            // that failed authentication
+            ctr.seek(BLOCK_SIZE as u64);
+            ctr.apply_keystream(data);
+            return Err(AeadError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::{Aead, AeadError};
+    use crate::aes_ccm::{Aes128Ccm, AesCcm};
+    use crate::test_util::{check_aead, check_failed_open_leaves_ciphertext, hex};
+
+    #[test]
+    fn sp800_38c_examples() {
+        let key = "404142434445464748494a4b4c4d4e4f";
+        check_aead::<AesCcm<16, 7, 4>>(
+            key,
+            "10111213141516",
+            "0001020304050607",
+            "20212223",
+            "7162015b4dac255d",
+        );
+        check_aead::<AesCcm<16, 8, 6>>(
+            key,
+            "1011121314151617",
+            "000102030405060708090a0b0c0d0e0f",
+            "202122232425262728292a2b2c2d2e2f",
+            "d2a1f0e051ea5f62081a7792073d593d1fc64fbfaccd",
+        );
+        check_aead::<AesCcm<16, 12, 8>>(
+            key,
+            "101112131415161718191a1b",
+            "000102030405060708090a0b0c0d0e0f10111213",
+            "202122232425262728292a2b2c2d2e2f3031323334353637",
+            "e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5484392fbc1b09951",
+        );
+    }
+
+    #[test]
+    fn rfc3610_packet_vector_1() {
+        check_aead::<AesCcm<16, 13, 8>>(
+            "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            "00000003020100a0a1a2a3a4a5",
+            "0001020304050607",
+            "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e",
+            "588c979a61c663d2f066d0c2c0f989806d5f6b61dac38417e8d12cfdf926e0",
+        );
+    }
+
+    // associated data from 0xff00 bytes on gets the six byte length encoding
+    #[test]
+    fn long_associated_data() {
+        let aead = Aes128Ccm::new(&[0; 16]).unwrap();
+        let test_result = aead.seal(&[0; 13], &vec![0; 0xfeff], b"abc").unwrap();
+        assert_eq!(test_result, hex("b41a91d3ed9704c4328087d38a1ed7260aa8d8"));
+        let test_result = aead.seal(&[0; 13], &vec![0; 0xff00], b"abc").unwrap();
+        assert_eq!(test_result, hex("b41a91eac2f9d07dfdcc1fe9a7fc67c8dd5d7b"));
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(AesCcm::<16, 6, 16>::new(&[0; 16]).is_err());
+        assert!(AesCcm::<16, 14, 16>::new(&[0; 16]).is_err());
+        assert!(AesCcm::<16, 13, 5>::new(&[0; 16]).is_err());
+        assert!(AesCcm::<16, 13, 18>::new(&[0; 16]).is_err());
+
+        // a 13 byte nonce leaves two bytes for the payload length
+        let aead = Aes128Ccm::new(&[0; 16]).unwrap();
+        assert!(aead.seal(&[0; 13], b"", &vec![0; 0xffff]).is_ok());
+        assert_eq!(aead.seal(&[0; 13], b"", &vec![0; 0x10000]), Err(AeadError));
+    }
+
+    #[test]
+    fn failed_open_leaves_ciphertext() {
+        check_failed_open_leaves_ciphertext::<Aes128Ccm>(&[1; 16], &[2; 13], b"sensor reading");
+    }
+}
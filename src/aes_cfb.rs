@@ -0,0 +1,190 @@
+/**
+* This is an implementation of the CFB mode of AES from NIST SP 800-38A, with 1, 8 and 128 bit segments.
+* Each segment of ciphertext is the plaintext xored with the front of the encrypted shift register,
+* and is then shifted into the register, so a CFB-s stream takes one block encryption per s bits.
+* CFB1 processes the bits of each byte from the most significant one, as OpenSSL does.
+* The IV must not repeat under a key, and nothing is authenticated.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE};
+
+///The number of bits fed back into the shift register at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfbSegment {
+    ///CFB1
+    Bits1,
+    ///CFB8
+    Bits8,
+    ///CFB128
+    Bits128,
+}
+
+/// AES in CFB mode, encrypting or decrypting a stream that can be passed in pieces of any size
+#[derive(Clone)]
+pub struct AesCfb {
+    cipher: Aes,
+    segment: CfbSegment,
+    ///The shift register, for CFB128 the bytes before `offset` already hold the new ciphertext
+    register: [u8; BLOCK_SIZE],
+    ///The encrypted register, only kept between calls by CFB128
+    keystream: [u8; BLOCK_SIZE],
+    ///Bytes of the current CFB128 block already used
+    offset: usize,
+}
+
+impl AesCfb {
+    pub fn new(key: &[u8], iv: &[u8; BLOCK_SIZE], segment: CfbSegment) -> Result<AesCfb, AesError> {
+        Ok(AesCfb {
+            cipher: Aes::new(key)?,
+            segment,
+            register: *iv,
+            keystream: [0; BLOCK_SIZE],
+            offset: 0,
+        })
+    }
+
+    /// Encrypt the next `data.len()` bytes of the stream in place
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        self.process(data, true);
+    }
+
+    /// Decrypt the next `data.len()` bytes of the stream in place
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        self.process(data, false);
+    }
+
+    fn encrypted_register(&self) -> [u8; BLOCK_SIZE] {
+        let mut block = self.register;
+        self.cipher.encrypt_block(&mut block);
+        block
+    }
+
+    fn process(&mut self, data: &mut [u8], encrypt: bool) {
+        for byte in data.iter_mut() {
+            let input = *byte;
+            match self.segment {
+                CfbSegment::Bits128 => {
+                    if self.offset == 0 {
+                        self.keystream = self.encrypted_register();
+                    }
+                    *byte ^= self.keystream[self.offset];
+                    self.register[self.offset] = if encrypt { *byte } else { input };
+                    self.offset = (self.offset + 1) % BLOCK_SIZE;
+                }
+                CfbSegment::Bits8 => {
+                    *byte ^= self.encrypted_register()[0];
+                    self.register.copy_within(1.., 0);
+                    self.register[BLOCK_SIZE - 1] = if encrypt { *byte } else { input };
+                }
+                CfbSegment::Bits1 => {
+                    for bit in (0..8).rev() {
+                        let key_bit = self.encrypted_register()[0] >> 7;
+                        *byte ^= key_bit << bit;
+                        let fed_back = if encrypt { *byte } else { input };
+                        let register = u128::from_be_bytes(self.register);
+                        self.register =
+                            ((register << 1) | ((fed_back >> bit) & 1) as u128).to_be_bytes();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes_cfb::{AesCfb, CfbSegment};
+    use crate::test_util::hex;
+
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+    const IV: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    const KEY_128: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const KEY_256: &str = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+
+    fn check(key: &str, segment: CfbSegment, plaintext: &[u8], ciphertext: &[u8]) {
+        let mut data = plaintext.to_vec();
+        AesCfb::new(&hex(key), &IV, segment)
+            .unwrap()
+            .encrypt(&mut data);
+        assert_eq!(data, ciphertext);
+        AesCfb::new(&hex(key), &IV, segment)
+            .unwrap()
+            .decrypt(&mut data);
+        assert_eq!(data, plaintext);
+    }
+
+    // SP 800-38A F.3.1, F.3.7 and F.3.13, the CFB1 and CFB8 examples cover the first 16 and 18 bytes
+    #[test]
+    fn sp800_38a_aes128() {
+        let plaintext = hex(PLAINTEXT);
+        check(KEY_128, CfbSegment::Bits1, &plaintext[..2], &hex("68b3"));
+        check(
+            KEY_128,
+            CfbSegment::Bits8,
+            &plaintext[..18],
+            &hex("3b79424c9c0dd436bace9e0ed4586a4f32b9"),
+        );
+        check(
+            KEY_128,
+            CfbSegment::Bits128,
+            &plaintext,
+            &hex(
+                "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+                 26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6",
+            ),
+        );
+    }
+
+    // the full 64 byte message against openssl enc -aes-256-cfb1, -cfb8 and -cfb
+    #[test]
+    fn openssl_aes256() {
+        let plaintext = hex(PLAINTEXT);
+        check(
+            KEY_256,
+            CfbSegment::Bits1,
+            &plaintext,
+            &hex(
+                "9029c2ba5b7d440b562023deec3de5928e4fd76528e8cc3a548a0a49edf001d0\
+                 d163541e6192479f27fe19a4f75d600de033103f1d2bc1794ce1cf1464c0603b",
+            ),
+        );
+        check(
+            KEY_256,
+            CfbSegment::Bits8,
+            &plaintext,
+            &hex(
+                "dc1f1a8520a64db55fcc8ac554844e889700adc6e10c63cf2d8cd2d8ce668f3e\
+                 b9191719c47444fb43bff9b9883c2cd051120402009f974998c89d195722a75b",
+            ),
+        );
+        check(
+            KEY_256,
+            CfbSegment::Bits128,
+            &plaintext,
+            &hex(
+                "dc7e84bfda79164b7ecd8486985d386039ffed143b28b1c832113c6331e5407b\
+                 df10132415e54b92a13ed0a8267ae2f975a385741ab9cef82031623d55b1e471",
+            ),
+        );
+    }
+
+    #[test]
+    fn pieces_match_one_call() {
+        let plaintext = hex(PLAINTEXT);
+        for segment in [CfbSegment::Bits1, CfbSegment::Bits8, CfbSegment::Bits128] {
+            let mut whole = plaintext.clone();
+            AesCfb::new(&hex(KEY_128), &IV, segment)
+                .unwrap()
+                .encrypt(&mut whole);
+
+            let mut pieces = plaintext.clone();
+            let mut cfb = AesCfb::new(&hex(KEY_128), &IV, segment).unwrap();
+            let (first, rest) = pieces.split_at_mut(7);
+            let (second, third) = rest.split_at_mut(20);
+            cfb.encrypt(first);
+            cfb.encrypt(second);
+            cfb.encrypt(third);
+            assert_eq!(pieces, whole);
+        }
+    }
+}
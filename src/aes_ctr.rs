@@ -0,0 +1,142 @@
+/**
+* This is an implementation of the CTR mode of AES from NIST SP 800-38A.
+* The keystream is the encryption of successive counter blocks, where the whole 16 byte block
+* is incremented as a big endian number starting from the initial counter block.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE};
+use crate::stream_cipher::StreamCipher;
+
+#[derive(Clone)]
+pub struct AesCtr {
+    cipher: Aes,
+    initial_counter: u128,
+    keystream: [u8; BLOCK_SIZE],
+    ///The block index of `keystream`, if it has been generated
+    buffered_block: Option<u64>,
+    position: u64,
+}
+
+impl AesCtr {
+    pub fn new(key: &[u8], initial_counter: &[u8; BLOCK_SIZE]) -> Result<AesCtr, AesError> {
+        Ok(AesCtr::with_cipher(Aes::new(key)?, initial_counter))
+    }
+
+    /// Counter mode over an already expanded key
+    pub fn with_cipher(cipher: Aes, initial_counter: &[u8; BLOCK_SIZE]) -> AesCtr {
+        AesCtr {
+            cipher,
+            initial_counter: u128::from_be_bytes(*initial_counter),
+            keystream: [0; BLOCK_SIZE],
+            buffered_block: None,
+            position: 0,
+        }
+    }
+}
+
+impl StreamCipher for AesCtr {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        let mut done = 0;
+        while done < data.len() {
+            let block = self.position / BLOCK_SIZE as u64;
+            if self.buffered_block != Some(block) {
+                self.keystream = self
+                    .initial_counter
+                    .wrapping_add(block as u128)
+                    .to_be_bytes();
+                self.cipher.encrypt_block(&mut self.keystream);
+                self.buffered_block = Some(block);
+            }
+
+            let offset = (self.position % BLOCK_SIZE as u64) as usize;
+            let len = (data.len() - done).min(BLOCK_SIZE - offset);
+            for (byte, key_byte) in data[done..done + len]
+                .iter_mut()
+                .zip(&self.keystream[offset..])
+            {
+                *byte ^= key_byte;
+            }
+            self.position += len as u64;
+            done += len;
+        }
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes_ctr::AesCtr;
+    use crate::chacha20::ChaCha20;
+    use crate::stream_cipher::StreamCipher;
+    use crate::test_util::hex;
+
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn check_sp800_38a(key: &str, ciphertext: &str) {
+        let counter = hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff").try_into().unwrap();
+        let mut cipher = AesCtr::new(&hex(key), &counter).unwrap();
+        let mut data = hex(PLAINTEXT);
+        cipher.apply_keystream(&mut data);
+        assert_eq!(data, hex(ciphertext));
+
+        cipher.seek(0);
+        cipher.apply_keystream(&mut data);
+        assert_eq!(data, hex(PLAINTEXT));
+    }
+
+    #[test]
+    fn sp800_38a_ctr_aes128() {
+        check_sp800_38a(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+             5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee",
+        );
+    }
+
+    #[test]
+    fn sp800_38a_ctr_aes256() {
+        check_sp800_38a(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            "601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c5\
+             2b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6",
+        );
+    }
+
+    #[test]
+    fn counter_wraps_and_seeks() {
+        let counter = [0xff; 16];
+        let mut whole = vec![0u8; 48];
+        AesCtr::new(&[0; 16], &counter)
+            .unwrap()
+            .apply_keystream(&mut whole);
+
+        let mut cipher = AesCtr::new(&[0; 16], &counter).unwrap();
+        let mut tail = vec![0u8; 20];
+        cipher.seek(28);
+        cipher.apply_keystream(&mut tail);
+        assert_eq!(tail, whole[28..]);
+    }
+
+    #[test]
+    fn interchangeable_with_chacha20() {
+        let mut ciphers: Vec<Box<dyn StreamCipher>> = vec![
+            Box::new(AesCtr::new(&[1; 32], &[2; 16]).unwrap()),
+            Box::new(ChaCha20::new(&[1; 32], &[2; 12])),
+        ];
+        for cipher in ciphers.iter_mut() {
+            let mut data = b"attack at dawn".to_vec();
+            cipher.apply_keystream(&mut data);
+            assert_ne!(data, b"attack at dawn");
+            cipher.seek(0);
+            cipher.apply_keystream(&mut data);
+            assert_eq!(data, b"attack at dawn");
+        }
+    }
+}
@@ -0,0 +1,96 @@
+/**
+* This is an implementation of the ECB mode of AES from NIST SP 800-38A.
+* Every block is encrypted on its own under the same key, so equal plaintext blocks give equal
+* ciphertext blocks and the structure of the data shows through. It is only here to read data
+* from old tools and to show why a mode needs an IV or nonce, new code should use an AEAD.
+* No padding is applied, the data must be a whole number of blocks.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE};
+
+#[derive(Clone)]
+pub struct AesEcb {
+    cipher: Aes,
+}
+
+impl AesEcb {
+    pub fn new(key: &[u8]) -> Result<AesEcb, AesError> {
+        Ok(AesEcb {
+            cipher: Aes::new(key)?,
+        })
+    }
+
+    /// Encrypt `data` in place, it must be a multiple of 16 bytes long
+    pub fn encrypt(&self, data: &mut [u8]) -> Result<(), AesError> {
+        self.process(data, true)
+    }
+
+    /// Decrypt `data` in place, it must be a multiple of 16 bytes long
+    pub fn decrypt(&self, data: &mut [u8]) -> Result<(), AesError> {
+        self.process(data, false)
+    }
+
+    fn process(&self, data: &mut [u8], encrypt: bool) -> Result<(), AesError> {
+        if !data.len().is_multiple_of(BLOCK_SIZE) {
+            return Err(AesError::InvalidDataLength);
+        }
+        for chunk in data.chunks_exact_mut(BLOCK_SIZE) {
+            let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+            if encrypt {
+                self.cipher.encrypt_block(block);
+            } else {
+                self.cipher.decrypt_block(block);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes::AesError;
+    use crate::aes_ecb::AesEcb;
+    use crate::test_util::hex;
+
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn check(key: &str, ciphertext: &str) {
+        let ecb = AesEcb::new(&hex(key)).unwrap();
+        let mut data = hex(PLAINTEXT);
+        ecb.encrypt(&mut data).unwrap();
+        assert_eq!(data, hex(ciphertext));
+        ecb.decrypt(&mut data).unwrap();
+        assert_eq!(data, hex(PLAINTEXT));
+    }
+
+    // SP 800-38A F.1.1 and F.1.5
+    #[test]
+    fn sp800_38a_vectors() {
+        check(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf\
+             43b1cd7f598ece23881b00e3ed0306887b0c785e27e8ad3f8223207104725dd4",
+        );
+        check(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            "f3eed1bdb5d2a03c064b5a7e3db181f8591ccb10d410ed26dc5ba74a31362870\
+             b6ed21b99ca6f4f9f153e7b1beafed1d23304b7a39f9f3ff067d8d8f9e24ecc7",
+        );
+    }
+
+    #[test]
+    fn equal_blocks_leak() {
+        let ecb = AesEcb::new(&[5; 16]).unwrap();
+        let mut data = [0x41; 48];
+        ecb.encrypt(&mut data).unwrap();
+        assert_eq!(data[..16], data[16..32]);
+        assert_eq!(data[16..32], data[32..]);
+    }
+
+    #[test]
+    fn partial_block_rejected() {
+        let ecb = AesEcb::new(&[5; 16]).unwrap();
+        assert_eq!(ecb.encrypt(&mut [0; 17]), Err(AesError::InvalidDataLength));
+        assert!(ecb.encrypt(&mut []).is_ok());
+    }
+}
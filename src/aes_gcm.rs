@@ -0,0 +1,221 @@
+/**
+* This is an implementation of the Galois/Counter Mode of AES from NIST SP 800-38D,
+* with 96 bit nonces and 128 bit tags.
+* The data is encrypted in counter mode starting from the counter block nonce || 2, and the tag is
+* GHASH of the associated data and ciphertext, encrypted with the counter block nonce || 1.
+* GHASH multiplies in GF(2^128) bit by bit with masks instead of tables, so it runs in constant time.
+*/
+use crate::aead::{Aead, AeadError};
+use crate::aes::{Aes, BLOCK_SIZE};
+use crate::aes_ctr::AesCtr;
+use crate::constant_time;
+use crate::stream_cipher::StreamCipher;
+
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+///Longest plaintext that can be sealed under one nonce, 2^39 - 256 bits
+pub const MAX_PLAINTEXT_SIZE: u64 = (1 << 36) - 32;
+
+///The reduction polynomial x^128 + x^7 + x^2 + x + 1 in GCM's reflected bit order
+const R: u128 = 0xe1 << 120;
+
+/// x * y in GF(2^128), with blocks read as big endian integers the way SP 800-38D numbers bits
+pub(crate) fn gf_mul(x: u128, y: u128) -> u128 {
+    let mut z = 0u128;
+    let mut v = y;
+    for i in (0..128).rev() {
+        let bit = 0u128.wrapping_sub((x >> i) & 1);
+        z ^= v & bit;
+        let carry = 0u128.wrapping_sub(v & 1);
+        v = (v >> 1) ^ (R & carry);
+    }
+    z
+}
+
+/// The GHASH universal hash keyed with `h`
+#[derive(Clone)]
+pub(crate) struct GHash {
+    h: u128,
+    y: u128,
+}
+
+impl GHash {
+    pub(crate) fn new(h: u128) -> GHash {
+        GHash { h, y: 0 }
+    }
+
+    /// Absorb `data` zero padded to a whole number of blocks
+    pub(crate) fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(BLOCK_SIZE) {
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.y = gf_mul(self.y ^ u128::from_be_bytes(block), self.h);
+        }
+    }
+
+    /// Absorb the bit lengths of the associated data and ciphertext and return the hash
+    pub(crate) fn finalize(mut self, aad_len: usize, data_len: usize) -> u128 {
+        let lengths = ((aad_len as u128 * 8) << 64) | (data_len as u128 * 8);
+        self.y = gf_mul(self.y ^ lengths, self.h);
+        self.y
+    }
+}
+
+/// AES-GCM with a key of `KEY_SIZE` bytes
+#[derive(Clone)]
+pub struct AesGcm<const KEY_SIZE: usize> {
+    cipher: Aes,
+    ///The hash key, the encryption of the zero block
+    h: u128,
+}
+
+pub type Aes128Gcm = AesGcm<16>;
+pub type Aes192Gcm = AesGcm<24>;
+pub type Aes256Gcm = AesGcm<32>;
+
+impl<const KEY_SIZE: usize> AesGcm<KEY_SIZE> {
+    fn counter_block(nonce: &[u8], counter: u32) -> [u8; BLOCK_SIZE] {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..NONCE_SIZE].copy_from_slice(nonce);
+        block[NONCE_SIZE..].copy_from_slice(&counter.to_be_bytes());
+        block
+    }
+
+    fn compute_tag(&self, nonce: &[u8], aad: &[u8], data: &[u8]) -> [u8; TAG_SIZE] {
+        let mut ghash = GHash::new(self.h);
+        ghash.update_padded(aad);
+        ghash.update_padded(data);
+        let s = ghash.finalize(aad.len(), data.len());
+
+        let mut tag = Self::counter_block(nonce, 1);
+        self.cipher.encrypt_block(&mut tag);
+        (u128::from_be_bytes(tag) ^ s).to_be_bytes()
+    }
+
+    fn apply_keystream(&self, nonce: &[u8], data: &mut [u8]) {
+        AesCtr::with_cipher(self.cipher.clone(), &Self::counter_block(nonce, 2))
+            .apply_keystream(data);
+    }
+}
+
+impl<const KEY_SIZE: usize> Aead for AesGcm<KEY_SIZE> {
+    const KEY_SIZE: usize = KEY_SIZE;
+    const NONCE_SIZE: usize = NONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        if key.len() != KEY_SIZE {
+            return Err(AeadError);
+        }
+        let cipher = Aes::new(key).map_err(|_| AeadError)?;
+        let mut h = [0u8; BLOCK_SIZE];
+        cipher.encrypt_block(&mut h);
+        Ok(AesGcm {
+            cipher,
+            h: u128::from_be_bytes(h),
+        })
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        if nonce.len() != NONCE_SIZE || data.len() as u64 > MAX_PLAINTEXT_SIZE {
+            return Err(AeadError);
+        }
+        self.apply_keystream(nonce, data);
+        Ok(self.compute_tag(nonce, aad, data).to_vec())
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        if nonce.len() != NONCE_SIZE || data.len() as u64 > MAX_PLAINTEXT_SIZE {
+            return Err(AeadError);
+        }
+        if !constant_time::eq(&self.compute_tag(nonce, aad, data), tag) {
+            return Err(AeadError);
+        }
+        self.apply_keystream(nonce, data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::Aead;
+    use crate::aes_gcm::{Aes128Gcm, Aes192Gcm, Aes256Gcm};
+    use crate::test_util::check_aead;
+
+    // test cases from the original GCM specification by McGrew and Viega
+    const KEY: &str = "feffe9928665731c6d6a8f9467308308";
+    const NONCE: &str = "cafebabefacedbaddecaf888";
+    const AAD: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+    const PLAINTEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+                             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+
+    #[test]
+    fn aes128_test_cases_1_and_4() {
+        check_aead::<Aes128Gcm>(
+            &"00".repeat(16),
+            &"00".repeat(12),
+            "",
+            "",
+            "58e2fccefa7e3061367f1d57a4e7455a",
+        );
+        check_aead::<Aes128Gcm>(
+            KEY,
+            NONCE,
+            AAD,
+            PLAINTEXT,
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091\
+             5bc94fbc3221a5db94fae95ae7121a47",
+        );
+    }
+
+    #[test]
+    fn aes192_test_case_10() {
+        check_aead::<Aes192Gcm>(
+            &format!("{KEY}{}", &KEY[..16]),
+            NONCE,
+            AAD,
+            PLAINTEXT,
+            "3980ca0b3c00e841eb06fac4872a2757859e1ceaa6efd984628593b40ca1e19c\
+             7d773d00c144c525ac619d18c84a3f4718e2448b2fe324d9ccda2710\
+             2519498e80f1478f37ba55bd6d27618c",
+        );
+    }
+
+    #[test]
+    fn aes256_test_cases_14_and_16() {
+        check_aead::<Aes256Gcm>(
+            &"00".repeat(32),
+            &"00".repeat(12),
+            "",
+            &"00".repeat(16),
+            "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919",
+        );
+        check_aead::<Aes256Gcm>(
+            &KEY.repeat(2),
+            NONCE,
+            AAD,
+            PLAINTEXT,
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+             8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662\
+             76fc6ece0f4e1768cddf8853bb2d551b",
+        );
+    }
+
+    #[test]
+    fn wrong_key_size() {
+        assert!(Aes128Gcm::new(&[0; 32]).is_err());
+        assert!(Aes256Gcm::new(&[0; 16]).is_err());
+    }
+}
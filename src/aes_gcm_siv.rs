@@ -0,0 +1,266 @@
+/**
+* This is an implementation of the nonce misuse resistant AEAD AES-GCM-SIV from RFC 8452.
+* Per nonce message authentication and encryption keys are derived from the key, the tag is the
+* encryption of POLYVAL over the associated data and plaintext mixed with the nonce, and the
+* plaintext is encrypted in counter mode starting from the tag, with a 32 bit little endian counter.
+* Repeating a nonce only reveals whether two messages were equal. RFC 8452 defines a single
+* associated data string; callers with several components can use AES-SIV, whose S2V is the
+* standard construction over a vector of associated data.
+* POLYVAL is computed with the GHASH multiplication on byte reversed blocks, as RFC 8452 appendix A shows.
+*/
+use crate::aead::{Aead, AeadError};
+use crate::aes::{Aes, BLOCK_SIZE};
+use crate::aes_gcm::gf_mul;
+use crate::constant_time;
+
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+///Longest plaintext or associated data that can be sealed, 2^36 bytes
+pub const MAX_INPUT_SIZE: u64 = 1 << 36;
+
+///The reduction polynomial of GHASH, for multiplying by x in its bit order
+const R: u128 = 0xe1 << 120;
+
+/// POLYVAL(h, padded aad, padded data, bit lengths)
+fn polyval(h: &[u8; BLOCK_SIZE], aad: &[u8], data: &[u8]) -> [u8; BLOCK_SIZE] {
+    // POLYVAL(H, X) = ByteReverse(GHASH(mulX_GHASH(ByteReverse(H)), ByteReverse(X)))
+    let h = u128::from_le_bytes(*h);
+    let h = (h >> 1) ^ (R & 0u128.wrapping_sub(h & 1));
+
+    let mut y = 0u128;
+    for input in [aad, data] {
+        for chunk in input.chunks(BLOCK_SIZE) {
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            y = gf_mul(y ^ u128::from_le_bytes(block), h);
+        }
+    }
+    let lengths = ((data.len() as u128 * 8) << 64) | (aad.len() as u128 * 8);
+    gf_mul(y ^ lengths, h).to_le_bytes()
+}
+
+/// AES-GCM-SIV with a key of `KEY_SIZE` bytes, 16 or 32
+#[derive(Clone)]
+pub struct AesGcmSiv<const KEY_SIZE: usize> {
+    ///The key generating key the per nonce keys are derived with
+    cipher: Aes,
+}
+
+pub type Aes128GcmSiv = AesGcmSiv<16>;
+pub type Aes256GcmSiv = AesGcmSiv<32>;
+
+impl<const KEY_SIZE: usize> AesGcmSiv<KEY_SIZE> {
+    /// The message authentication key and the message encryption cipher for `nonce`
+    fn derive_keys(&self, nonce: &[u8]) -> ([u8; BLOCK_SIZE], Aes) {
+        let mut derived = vec![0u8; BLOCK_SIZE + KEY_SIZE];
+        for (i, half) in derived.chunks_exact_mut(8).enumerate() {
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+            block[4..].copy_from_slice(nonce);
+            self.cipher.encrypt_block(&mut block);
+            half.copy_from_slice(&block[..8]);
+        }
+        let auth_key = derived[..BLOCK_SIZE].try_into().unwrap();
+        let cipher = Aes::new(&derived[BLOCK_SIZE..]).unwrap();
+        (auth_key, cipher)
+    }
+
+    fn compute_tag(
+        cipher: &Aes,
+        auth_key: &[u8; BLOCK_SIZE],
+        nonce: &[u8],
+        aad: &[u8],
+        data: &[u8],
+    ) -> [u8; TAG_SIZE] {
+        let mut s = polyval(auth_key, aad, data);
+        for (byte, nonce_byte) in s.iter_mut().zip(nonce) {
+            *byte ^= nonce_byte;
+        }
+        s[15] &= 0x7f;
+        cipher.encrypt_block(&mut s);
+        s
+    }
+
+    fn apply_keystream(cipher: &Aes, tag: &[u8; TAG_SIZE], data: &mut [u8]) {
+        let mut counter_block = *tag;
+        counter_block[15] |= 0x80;
+        let first = u32::from_le_bytes(counter_block[..4].try_into().unwrap());
+        for (i, chunk) in data.chunks_mut(BLOCK_SIZE).enumerate() {
+            let mut keystream = counter_block;
+            keystream[..4].copy_from_slice(&first.wrapping_add(i as u32).to_le_bytes());
+            cipher.encrypt_block(&mut keystream);
+            for (byte, key_byte) in chunk.iter_mut().zip(keystream) {
+                *byte ^= key_byte;
+            }
+        }
+    }
+
+    fn check_lengths(nonce: &[u8], aad: &[u8], data: &[u8]) -> Result<(), AeadError> {
+        let too_long = aad.len() as u64 > MAX_INPUT_SIZE || data.len() as u64 > MAX_INPUT_SIZE;
+        if nonce.len() != NONCE_SIZE || too_long {
+            return Err(AeadError);
+        }
+        Ok(())
+    }
+}
+
+impl<const KEY_SIZE: usize> Aead for AesGcmSiv<KEY_SIZE> {
+    const KEY_SIZE: usize = KEY_SIZE;
+    const NONCE_SIZE: usize = NONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        if key.len() != KEY_SIZE || ![16, 32].contains(&KEY_SIZE) {
+            return Err(AeadError);
+        }
+        Ok(AesGcmSiv {
+            cipher: Aes::new(key).map_err(|_| AeadError)?,
+        })
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        Self::check_lengths(nonce, aad, data)?;
+        let (auth_key, cipher) = self.derive_keys(nonce);
+        let tag = Self::compute_tag(&cipher, &auth_key, nonce, aad, data);
+        Self::apply_keystream(&cipher, &tag, data);
+        Ok(tag.to_vec())
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        Self::check_lengths(nonce, aad, data)?;
+        let tag: &[u8; TAG_SIZE] = tag.try_into().map_err(|_| AeadError)?;
+        let (auth_key, cipher) = self.derive_keys(nonce);
+        Self::apply_keystream(&cipher, tag, data);
+        let expected = Self::compute_tag(&cipher, &auth_key, nonce, aad, data);
+        if !constant_time::eq(&expected, tag) {
+            Self::apply_keystream(&cipher, tag, data);
+            return Err(AeadError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::{Aead, AeadError};
+    use crate::aes_gcm_siv::{Aes128GcmSiv, Aes256GcmSiv, polyval};
+    use crate::test_util::{check_aead, hex};
+
+    // the POLYVAL example of RFC 8452 appendix A, and the length block checked against the definition
+    #[test]
+    fn polyval_example() {
+        let h = hex("25629347589242761d31f826ba4b757b").try_into().unwrap();
+        let x = hex("4f4f95668c83dfb6401762bb2d01a262d1a24ddd2721d006bbe45f20d3c9f362");
+        assert_eq!(
+            polyval_blocks(&h, &[&x]),
+            hex("f7a3b47b846119fae5b7866cf5e5b77e")
+        );
+
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&256u64.to_le_bytes());
+        assert_eq!(
+            polyval(&h, &x, &[]).to_vec(),
+            polyval_blocks(&h, &[&x, &lengths])
+        );
+    }
+
+    /// Plain POLYVAL over whole blocks, straight from the RFC definition with dot(a, b) = a * b * x^-128
+    fn polyval_blocks(h: &[u8; 16], inputs: &[&[u8]]) -> Vec<u8> {
+        // x^-128 mod x^128 + x^127 + x^126 + x^121 + 1
+        let x_inv: u128 = 0x92040000000000000000000000000001;
+        let mut s = 0u128;
+        let h = u128::from_le_bytes(*h);
+        for input in inputs {
+            for block in input.chunks(16) {
+                let block = u128::from_le_bytes(block.try_into().unwrap());
+                s = field_mul(field_mul(s ^ block, h), x_inv);
+            }
+        }
+        s.to_le_bytes().to_vec()
+    }
+
+    /// Product in GF(2^128) modulo x^128 + x^127 + x^126 + x^121 + 1, bit i is the coefficient of x^i
+    fn field_mul(a: u128, b: u128) -> u128 {
+        let mut product = 0u128;
+        let mut a = a;
+        for i in 0..128 {
+            if (b >> i) & 1 == 1 {
+                product ^= a;
+            }
+            let carry = a >> 127;
+            a <<= 1;
+            if carry == 1 {
+                a ^= (1 << 127) | (1 << 126) | (1 << 121) | 1;
+            }
+        }
+        product
+    }
+
+    #[test]
+    fn rfc8452_aes128_vectors() {
+        let key = "01000000000000000000000000000000";
+        let nonce = "030000000000000000000000";
+        check_aead::<Aes128GcmSiv>(key, nonce, "", "", "dc20e2d83f25705bb49e439eca56de25");
+        check_aead::<Aes128GcmSiv>(
+            key,
+            nonce,
+            "",
+            "0100000000000000",
+            "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+        );
+        check_aead::<Aes128GcmSiv>(
+            key,
+            nonce,
+            "01",
+            "0100000000000000000000000000000002000000000000000000000000000000\
+             03000000000000000000000000000000",
+            "b40d58244ef240ab9e4d263af6762ccb09d118b8f38659650d5a92ee441f5528\
+             c06f3f7b90c6af3170af2d29010bb3bb37f9dc5e8a17711dab871130bf58827c",
+        );
+        check_aead::<Aes128GcmSiv>(
+            "36864200e0eaf5284d884a0e77d31646",
+            "bae8e37fc83441b16034566b",
+            "7a806c",
+            "46bb91c3c5",
+            "84c31b530d2277a4ca425ed3e0c7de2c3426d309be",
+        );
+    }
+
+    #[test]
+    fn rfc8452_aes256_vector() {
+        check_aead::<Aes256GcmSiv>(
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "030000000000000000000000",
+            "0100000000000000",
+            "0100000000000000000000000000000002000000000000000000000000000000",
+            "22cd66b801de730d8dbfba3c56e99bcffc3808ea741a00f69444bf4ae8c8d420\
+             e6b706992d92119b93fbfeb17f2a392b",
+        );
+    }
+
+    #[test]
+    fn repeated_nonce_is_deterministic() {
+        let aead = Aes256GcmSiv::new(&[3; 32]).unwrap();
+        let first = aead.seal(&[0; 12], b"", b"same message").unwrap();
+        let second = aead.seal(&[0; 12], b"", b"same message").unwrap();
+        let other = aead.seal(&[0; 12], b"", b"other message").unwrap();
+        assert_eq!(first, second);
+        assert_ne!(first[first.len() - 16..], other[other.len() - 16..]);
+
+        let mut corrupted = first.clone();
+        corrupted[0] ^= 1;
+        assert_eq!(aead.open(&[0; 12], b"", &corrupted), Err(AeadError));
+        assert!(Aes128GcmSiv::new(&[0; 24]).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+/**
+* This is an implementation of the AES Key Wrap (KW) from RFC 3394 and AES Key Wrap with Padding (KWP)
+* from RFC 5649, both also in NIST SP 800-38F.
+* The key data is split into 8 byte halves that are mixed with an integrity check value by six
+* rounds of AES encryptions under the key encryption key, so a wrapped key is 8 bytes longer.
+* Unwrapping checks the integrity value, which is the fixed IV for KW and the IV with the data
+* length for KWP. KW needs at least 16 bytes of key data in 8 byte steps, KWP takes any length from 1 byte.
+*/
+use crate::aes::{Aes, BLOCK_SIZE, Key};
+use crate::constant_time;
+
+///Size in bytes of a half block, the unit the key data is split into
+const SEMIBLOCK_SIZE: usize = 8;
+///The default initial value of RFC 3394
+const DEFAULT_IV: [u8; SEMIBLOCK_SIZE] = [0xa6; SEMIBLOCK_SIZE];
+///The constant first half of the alternative initial value of RFC 5649
+const PADDED_IV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyWrapError {
+    ///The key encryption key is not 16, 24 or 32 bytes
+    InvalidKeyLength,
+    ///The key data or wrapped key is a length the wrap mode cannot process
+    InvalidInputLength,
+    ///The wrapped key was modified or wrapped under another key
+    IntegrityCheckFailed,
+}
+
+impl std::fmt::Display for KeyWrapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyWrapError::InvalidKeyLength => {
+                write!(f, "key encryption keys must be 16, 24 or 32 bytes")
+            }
+            KeyWrapError::InvalidInputLength => {
+                write!(f, "the input length is not supported by the key wrap mode")
+            }
+            KeyWrapError::IntegrityCheckFailed => {
+                write!(f, "the wrapped key failed its integrity check")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyWrapError {}
+
+/// Key wrapping under an AES key encryption key
+#[derive(Clone)]
+pub struct AesKw {
+    cipher: Aes,
+}
+
+impl AesKw {
+    pub fn new(kek: &[u8]) -> Result<AesKw, KeyWrapError> {
+        Ok(AesKw {
+            cipher: Aes::new(kek).map_err(|_| KeyWrapError::InvalidKeyLength)?,
+        })
+    }
+
+    /// Key wrapping under an AES-128 `Key`
+    pub fn with_key(kek: &Key) -> AesKw {
+        AesKw {
+            cipher: Aes::new(&kek.bytes).unwrap(),
+        }
+    }
+
+    /// Wrap `key_data` with KW, it must be at least 16 bytes and a multiple of 8 bytes long
+    pub fn wrap_key(&self, key_data: &[u8]) -> Result<Vec<u8>, KeyWrapError> {
+        if key_data.len() < 2 * SEMIBLOCK_SIZE || !key_data.len().is_multiple_of(SEMIBLOCK_SIZE) {
+            return Err(KeyWrapError::InvalidInputLength);
+        }
+        Ok(self.wrap_semiblocks(DEFAULT_IV, key_data))
+    }
+
+    /// Unwrap a key wrapped with KW
+    pub fn unwrap_key(&self, wrapped: &[u8]) -> Result<Vec<u8>, KeyWrapError> {
+        if wrapped.len() < 3 * SEMIBLOCK_SIZE || !wrapped.len().is_multiple_of(SEMIBLOCK_SIZE) {
+            return Err(KeyWrapError::InvalidInputLength);
+        }
+        let (iv, key_data) = self.unwrap_semiblocks(wrapped);
+        if !constant_time::eq(&iv, &DEFAULT_IV) {
+            return Err(KeyWrapError::IntegrityCheckFailed);
+        }
+        Ok(key_data)
+    }
+
+    /// Wrap an AES-128 data encryption key with KW
+    pub fn wrap_aes_key(&self, key: &Key) -> Vec<u8> {
+        self.wrap_semiblocks(DEFAULT_IV, &key.bytes)
+    }
+
+    /// Unwrap an AES-128 data encryption key wrapped with KW
+    pub fn unwrap_aes_key(&self, wrapped: &[u8]) -> Result<Key, KeyWrapError> {
+        let key_data = self.unwrap_key(wrapped)?;
+        Ok(Key {
+            bytes: key_data
+                .try_into()
+                .map_err(|_| KeyWrapError::InvalidInputLength)?,
+        })
+    }
+
+    /// Wrap `key_data` of 1 to 2^32 - 1 bytes with KWP
+    pub fn wrap_key_padded(&self, key_data: &[u8]) -> Result<Vec<u8>, KeyWrapError> {
+        if key_data.is_empty() || key_data.len() as u64 > u32::MAX as u64 {
+            return Err(KeyWrapError::InvalidInputLength);
+        }
+        let mut iv = [0u8; SEMIBLOCK_SIZE];
+        iv[..4].copy_from_slice(&PADDED_IV_PREFIX);
+        iv[4..].copy_from_slice(&(key_data.len() as u32).to_be_bytes());
+        let mut padded = key_data.to_vec();
+        padded.resize(key_data.len().next_multiple_of(SEMIBLOCK_SIZE), 0);
+
+        if padded.len() == SEMIBLOCK_SIZE {
+            // a single semiblock is encrypted with the IV as one AES block
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..SEMIBLOCK_SIZE].copy_from_slice(&iv);
+            block[SEMIBLOCK_SIZE..].copy_from_slice(&padded);
+            self.cipher.encrypt_block(&mut block);
+            return Ok(block.to_vec());
+        }
+        Ok(self.wrap_semiblocks(iv, &padded))
+    }
+
+    /// Unwrap a key wrapped with KWP
+    pub fn unwrap_key_padded(&self, wrapped: &[u8]) -> Result<Vec<u8>, KeyWrapError> {
+        if wrapped.len() < 2 * SEMIBLOCK_SIZE || !wrapped.len().is_multiple_of(SEMIBLOCK_SIZE) {
+            return Err(KeyWrapError::InvalidInputLength);
+        }
+        let (iv, mut padded) = if wrapped.len() == BLOCK_SIZE {
+            let mut block: [u8; BLOCK_SIZE] = wrapped.try_into().unwrap();
+            self.cipher.decrypt_block(&mut block);
+            let iv: [u8; SEMIBLOCK_SIZE] = block[..SEMIBLOCK_SIZE].try_into().unwrap();
+            (iv, block[SEMIBLOCK_SIZE..].to_vec())
+        } else {
+            self.unwrap_semiblocks(wrapped)
+        };
+
+        // the length must fall in the last semiblock and the padding after it must be zero
+        let length = u32::from_be_bytes(iv[4..].try_into().unwrap()) as usize;
+        let prefix_valid = constant_time::eq(&iv[..4], &PADDED_IV_PREFIX);
+        let length_valid = length <= padded.len() && length + SEMIBLOCK_SIZE > padded.len();
+        let padding = if length_valid { &padded[length..] } else { &[] };
+        let padding_valid = constant_time::eq(padding, &vec![0; padding.len()]);
+        if !(prefix_valid && length_valid && padding_valid) {
+            return Err(KeyWrapError::IntegrityCheckFailed);
+        }
+        padded.truncate(length);
+        Ok(padded)
+    }
+
+    /// The wrapping function W of SP 800-38F on at least two semiblocks
+    fn wrap_semiblocks(&self, iv: [u8; SEMIBLOCK_SIZE], key_data: &[u8]) -> Vec<u8> {
+        let n = key_data.len() / SEMIBLOCK_SIZE;
+        let mut a = iv;
+        let mut r = key_data.to_vec();
+        for j in 0..6 {
+            for (i, semiblock) in r.chunks_exact_mut(SEMIBLOCK_SIZE).enumerate() {
+                let mut block = [0u8; BLOCK_SIZE];
+                block[..SEMIBLOCK_SIZE].copy_from_slice(&a);
+                block[SEMIBLOCK_SIZE..].copy_from_slice(semiblock);
+                self.cipher.encrypt_block(&mut block);
+                let t = (n * j + i + 1) as u64;
+                a = (u64::from_be_bytes(block[..SEMIBLOCK_SIZE].try_into().unwrap()) ^ t)
+                    .to_be_bytes();
+                semiblock.copy_from_slice(&block[SEMIBLOCK_SIZE..]);
+            }
+        }
+        [&a[..], &r].concat()
+    }
+
+    /// The unwrapping function W^-1 of SP 800-38F, returning the recovered IV and key data
+    fn unwrap_semiblocks(&self, wrapped: &[u8]) -> ([u8; SEMIBLOCK_SIZE], Vec<u8>) {
+        let n = wrapped.len() / SEMIBLOCK_SIZE - 1;
+        let mut a: [u8; SEMIBLOCK_SIZE] = wrapped[..SEMIBLOCK_SIZE].try_into().unwrap();
+        let mut r = wrapped[SEMIBLOCK_SIZE..].to_vec();
+        for j in (0..6).rev() {
+            for (i, semiblock) in r.chunks_exact_mut(SEMIBLOCK_SIZE).enumerate().rev() {
+                let t = (n * j + i + 1) as u64;
+                let mut block = [0u8; BLOCK_SIZE];
+                block[..SEMIBLOCK_SIZE].copy_from_slice(&(u64::from_be_bytes(a) ^ t).to_be_bytes());
+                block[SEMIBLOCK_SIZE..].copy_from_slice(semiblock);
+                self.cipher.decrypt_block(&mut block);
+                a = block[..SEMIBLOCK_SIZE].try_into().unwrap();
+                semiblock.copy_from_slice(&block[SEMIBLOCK_SIZE..]);
+            }
+        }
+        (a, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes::Key;
+    use crate::aes_kw::{AesKw, KeyWrapError};
+    use crate::test_util::hex;
+
+    const KEY_DATA: &str = "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f";
+
+    fn kek(len: u8) -> Vec<u8> {
+        (0..len).collect()
+    }
+
+    fn check(kek: &[u8], key_data: &[u8], wrapped: &str) {
+        let kw = AesKw::new(kek).unwrap();
+        assert_eq!(kw.wrap_key(key_data).unwrap(), hex(wrapped));
+        assert_eq!(kw.unwrap_key(&hex(wrapped)).unwrap(), key_data);
+    }
+
+    fn check_padded(kek: &[u8], key_data: &[u8], wrapped: &str) {
+        let kw = AesKw::new(kek).unwrap();
+        assert_eq!(kw.wrap_key_padded(key_data).unwrap(), hex(wrapped));
+        assert_eq!(kw.unwrap_key_padded(&hex(wrapped)).unwrap(), key_data);
+    }
+
+    // RFC 3394 section 4
+    #[test]
+    fn rfc3394_vectors() {
+        let key_data = hex(KEY_DATA);
+        check(
+            &kek(16),
+            &key_data[..16],
+            "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5",
+        );
+        check(
+            &kek(24),
+            &key_data[..16],
+            "96778b25ae6ca435f92b5b97c050aed2468ab8a17ad84e5d",
+        );
+        check(
+            &kek(32),
+            &key_data[..16],
+            "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7",
+        );
+        check(
+            &kek(24),
+            &key_data[..24],
+            "031d33264e15d33268f24ec260743edce1c6c7ddee725a936ba814915c6762d2",
+        );
+        check(
+            &kek(32),
+            &key_data[..24],
+            "a8f9bc1612c68b3ff6e6f4fbe30e71e4769c8b80a32cb8958cd5d17d6b254da1",
+        );
+        check(
+            &kek(32),
+            &key_data,
+            "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21",
+        );
+    }
+
+    // RFC 5649 section 6 for the 192 bit key, and Python's cryptography for the other sizes
+    #[test]
+    fn rfc5649_vectors() {
+        let kek_192 = hex("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8");
+        check_padded(
+            &kek_192,
+            &hex("c37b7e6492584340bed12207808941155068f738"),
+            "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+        );
+        check_padded(
+            &kek_192,
+            &hex("466f7250617369"),
+            "afbeb0f07dfbf5419200f2ccb50bb24f",
+        );
+        check_padded(&kek(16), &[1], "354adcce4f3b9a3ecc942d83cf9f216f");
+        check_padded(
+            &kek(16),
+            &hex("001122334455667788"),
+            "b4bd457489f2aabdbebf0db46e64e195af069b81a9f3d20d",
+        );
+        check_padded(&kek(32), &[1], "57bd956ca41f9470203c090c613f5bc5");
+        check_padded(
+            &kek(32),
+            &hex("001122334455667788"),
+            "6216054b046d66cd763f4fc3f08152c18d1cb013d3739d4c",
+        );
+    }
+
+    #[test]
+    fn integrity_failures() {
+        let kw = AesKw::new(&kek(16)).unwrap();
+        let mut wrapped = kw.wrap_key(&hex(KEY_DATA)).unwrap();
+        wrapped[10] ^= 1;
+        assert_eq!(
+            kw.unwrap_key(&wrapped),
+            Err(KeyWrapError::IntegrityCheckFailed)
+        );
+
+        let other = AesKw::new(&kek(32)).unwrap();
+        for wrapped in [
+            kw.wrap_key_padded(&[7; 5]).unwrap(),
+            kw.wrap_key_padded(&[7; 21]).unwrap(),
+        ] {
+            assert_eq!(
+                other.unwrap_key_padded(&wrapped),
+                Err(KeyWrapError::IntegrityCheckFailed)
+            );
+        }
+
+        // a KW wrapped key does not pass as KWP, nor the other way round
+        let wrapped = kw.wrap_key(&[7; 24]).unwrap();
+        assert_eq!(
+            kw.unwrap_key_padded(&wrapped),
+            Err(KeyWrapError::IntegrityCheckFailed)
+        );
+        let wrapped = kw.wrap_key_padded(&[7; 24]).unwrap();
+        assert_eq!(
+            kw.unwrap_key(&wrapped),
+            Err(KeyWrapError::IntegrityCheckFailed)
+        );
+    }
+
+    #[test]
+    fn invalid_lengths() {
+        assert!(matches!(
+            AesKw::new(&[0; 20]),
+            Err(KeyWrapError::InvalidKeyLength)
+        ));
+        let kw = AesKw::new(&kek(16)).unwrap();
+        assert_eq!(kw.wrap_key(&[0; 8]), Err(KeyWrapError::InvalidInputLength));
+        assert_eq!(kw.wrap_key(&[0; 20]), Err(KeyWrapError::InvalidInputLength));
+        assert_eq!(
+            kw.unwrap_key(&[0; 16]),
+            Err(KeyWrapError::InvalidInputLength)
+        );
+        assert_eq!(
+            kw.wrap_key_padded(&[]),
+            Err(KeyWrapError::InvalidInputLength)
+        );
+        assert_eq!(
+            kw.unwrap_key_padded(&[0; 20]),
+            Err(KeyWrapError::InvalidInputLength)
+        );
+    }
+
+    #[test]
+    fn wrap_aes_key() {
+        let kek = Key { bytes: [3; 16] };
+        let key = Key { bytes: [9; 16] };
+        let kw = AesKw::with_key(&kek);
+        let wrapped = kw.wrap_aes_key(&key);
+        assert_eq!(wrapped.len(), 24);
+        assert_eq!(kw.unwrap_aes_key(&wrapped).unwrap().bytes, key.bytes);
+        assert_eq!(
+            kw.unwrap_aes_key(&kw.wrap_key(&[9; 32]).unwrap()).err(),
+            Some(KeyWrapError::InvalidInputLength)
+        );
+    }
+}
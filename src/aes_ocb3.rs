@@ -0,0 +1,266 @@
+/**
+* This is an implementation of the OCB3 authenticated encryption mode of AES from RFC 7253.
+* Each block is encrypted once between two whitenings with an offset that moves by one of the
+* L values per block, and a checksum of the plaintext blocks is encrypted to make the tag,
+* so encryption and authentication are done in a single pass.
+* The nonce can be 1 to 15 bytes and the tag 1 to 16 bytes.
+*/
+use crate::aead::{Aead, AeadError};
+use crate::aes::{Aes, BLOCK_SIZE, double_block};
+use crate::constant_time;
+
+/// The block zero padded after a single one bit, used for the final partial block
+fn pad_partial(data: &[u8]) -> u128 {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..data.len()].copy_from_slice(data);
+    block[data.len()] = 0x80;
+    u128::from_be_bytes(block)
+}
+
+/// AES-OCB3 with a key of `KEY_SIZE` bytes, a nonce of `NONCE_SIZE` bytes and a tag of `TAG_SIZE` bytes
+#[derive(Clone)]
+pub struct AesOcb3<const KEY_SIZE: usize, const NONCE_SIZE: usize, const TAG_SIZE: usize> {
+    cipher: Aes,
+    ///L_*, the encryption of the zero block
+    l_star: u128,
+    ///L_$ = double(L_*)
+    l_dollar: u128,
+    ///L_0 = double(L_$)
+    l_0: u128,
+}
+
+pub type Aes128Ocb3 = AesOcb3<16, 12, 16>;
+pub type Aes256Ocb3 = AesOcb3<32, 12, 16>;
+
+impl<const KEY_SIZE: usize, const NONCE_SIZE: usize, const TAG_SIZE: usize>
+    AesOcb3<KEY_SIZE, NONCE_SIZE, TAG_SIZE>
+{
+    fn encipher(&self, block: u128) -> u128 {
+        let mut bytes = block.to_be_bytes();
+        self.cipher.encrypt_block(&mut bytes);
+        u128::from_be_bytes(bytes)
+    }
+
+    fn decipher(&self, block: u128) -> u128 {
+        let mut bytes = block.to_be_bytes();
+        self.cipher.decrypt_block(&mut bytes);
+        u128::from_be_bytes(bytes)
+    }
+
+    /// L_ntz(i), the value the offset moves by for block `i` counting from 1
+    fn l(&self, i: u64) -> u128 {
+        (0..i.trailing_zeros()).fold(self.l_0, |l, _| double_block(l))
+    }
+
+    /// Offset_0, derived from the nonce and tag length
+    fn initial_offset(&self, nonce: &[u8]) -> u128 {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[0] = ((TAG_SIZE * 8 % 128) << 1) as u8;
+        block[BLOCK_SIZE - NONCE_SIZE - 1] |= 1;
+        block[BLOCK_SIZE - NONCE_SIZE..].copy_from_slice(nonce);
+        let block = u128::from_be_bytes(block);
+
+        let bottom = (block & 0x3f) as u32;
+        let ktop = self.encipher(block & !0x3f);
+        let stretch = ((ktop >> 64) ^ (ktop >> 56)) as u64;
+        if bottom == 0 {
+            ktop
+        } else {
+            (ktop << bottom) | (stretch >> (64 - bottom)) as u128
+        }
+    }
+
+    /// HASH(K, A) of the associated data
+    fn hash(&self, aad: &[u8]) -> u128 {
+        let mut sum = 0;
+        let mut offset = 0;
+        let mut blocks = aad.chunks_exact(BLOCK_SIZE);
+        for (i, block) in (1..).zip(&mut blocks) {
+            offset ^= self.l(i);
+            sum ^= self.encipher(u128::from_be_bytes(block.try_into().unwrap()) ^ offset);
+        }
+        let rest = blocks.remainder();
+        if !rest.is_empty() {
+            offset ^= self.l_star;
+            sum ^= self.encipher(pad_partial(rest) ^ offset);
+        }
+        sum
+    }
+
+    /// Encrypt or decrypt `data` in place and return the full 16 byte tag
+    fn process(&self, nonce: &[u8], aad: &[u8], data: &mut [u8], encrypt: bool) -> u128 {
+        let mut offset = self.initial_offset(nonce);
+        let mut checksum = 0;
+
+        let mut blocks = data.chunks_exact_mut(BLOCK_SIZE);
+        for (i, block) in (1..).zip(&mut blocks) {
+            offset ^= self.l(i);
+            let input = u128::from_be_bytes((&*block).try_into().unwrap());
+            let output = if encrypt {
+                checksum ^= input;
+                self.encipher(input ^ offset) ^ offset
+            } else {
+                let plain = self.decipher(input ^ offset) ^ offset;
+                checksum ^= plain;
+                plain
+            };
+            block.copy_from_slice(&output.to_be_bytes());
+        }
+
+        let rest = blocks.into_remainder();
+        if !rest.is_empty() {
+            offset ^= self.l_star;
+            let pad = self.encipher(offset).to_be_bytes();
+            if encrypt {
+                checksum ^= pad_partial(rest);
+            }
+            for (byte, pad_byte) in rest.iter_mut().zip(pad) {
+                *byte ^= pad_byte;
+            }
+            if !encrypt {
+                checksum ^= pad_partial(rest);
+            }
+        }
+
+        self.encipher(checksum ^ offset ^ self.l_dollar) ^ self.hash(aad)
+    }
+}
+
+impl<const KEY_SIZE: usize, const NONCE_SIZE: usize, const TAG_SIZE: usize> Aead
+    for AesOcb3<KEY_SIZE, NONCE_SIZE, TAG_SIZE>
+{
+    const KEY_SIZE: usize = KEY_SIZE;
+    const NONCE_SIZE: usize = NONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    /// Fails on a wrong key length, or if the nonce or tag size is not one OCB allows
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        let valid_nonce = (1..=15).contains(&NONCE_SIZE);
+        let valid_tag = (1..=16).contains(&TAG_SIZE);
+        if key.len() != KEY_SIZE || !valid_nonce || !valid_tag {
+            return Err(AeadError);
+        }
+        let mut ocb = AesOcb3 {
+            cipher: Aes::new(key).map_err(|_| AeadError)?,
+            l_star: 0,
+            l_dollar: 0,
+            l_0: 0,
+        };
+        ocb.l_star = ocb.encipher(0);
+        ocb.l_dollar = double_block(ocb.l_star);
+        ocb.l_0 = double_block(ocb.l_dollar);
+        Ok(ocb)
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        if nonce.len() != NONCE_SIZE {
+            return Err(AeadError);
+        }
+        let tag = self.process(nonce, aad, data, true);
+        Ok(tag.to_be_bytes()[..TAG_SIZE].to_vec())
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        if nonce.len() != NONCE_SIZE {
+            return Err(AeadError);
+        }
+        let expected = self.process(nonce, aad, data, false);
+        if !constant_time::eq(&expected.to_be_bytes()[..TAG_SIZE], tag) {
+            // encrypt the buffer again, unauthenticated plaintext is never handed back
+            self.process(nonce, aad, data, true);
+            return Err(AeadError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::Aead;
+    use crate::aes_ocb3::{Aes128Ocb3, AesOcb3};
+    use crate::test_util::{check_aead, check_failed_open_leaves_ciphertext};
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f";
+
+    // sample results from RFC 7253 appendix A
+    #[test]
+    fn rfc7253_samples() {
+        check_aead::<Aes128Ocb3>(
+            KEY,
+            "bbaa99887766554433221100",
+            "",
+            "",
+            "785407bfffc8ad9edcc5520ac9111ee6",
+        );
+        check_aead::<Aes128Ocb3>(
+            KEY,
+            "bbaa99887766554433221101",
+            "0001020304050607",
+            "0001020304050607",
+            "6820b3657b6f615a5725bda0d3b4eb3a257c9af1f8f03009",
+        );
+        check_aead::<Aes128Ocb3>(
+            KEY,
+            "bbaa99887766554433221103",
+            "",
+            "0001020304050607",
+            "45dd69f8f5aae72414054cd1f35d82760b2cd00d2f99bfa9",
+        );
+        check_aead::<Aes128Ocb3>(
+            KEY,
+            "bbaa9988776655443322110d",
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "d5ca91748410c1751ff8a2f618255b68f4bb10ed8cd6da54\
+             eb1dfad7ac789284a8433dcc5c564a3e",
+        );
+        check_aead::<Aes128Ocb3>(
+            KEY,
+            "bbaa99887766554433221110",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021222324252627",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021222324252627",
+            "f6b1cfe767ccee4e3c72e608909408c86b924832c4c9ddae9f6c7069651aa65f\
+             b2a449351c9d60f06af7658756258fa4432d7841fd86e447",
+        );
+    }
+
+    #[test]
+    fn rfc7253_96_bit_tag() {
+        check_aead::<AesOcb3<16, 12, 12>>(
+            "0f0e0d0c0b0a09080706050403020100",
+            "bbaa9988776655443322110d",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021222324252627",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f2021222324252627",
+            "1792a4e31e0755fb03e31b22116e6c2ddf9efd6e33d536f1a0124b0a55bae884\
+             ed93481529c76b6ad0c515f4d1cdd4fdac4f02aa",
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(AesOcb3::<16, 0, 16>::new(&[0; 16]).is_err());
+        assert!(AesOcb3::<16, 16, 16>::new(&[0; 16]).is_err());
+        assert!(AesOcb3::<16, 12, 17>::new(&[0; 16]).is_err());
+        assert!(Aes128Ocb3::new(&[0; 24]).is_err());
+    }
+
+    #[test]
+    fn failed_open_leaves_ciphertext() {
+        check_failed_open_leaves_ciphertext::<Aes128Ocb3>(
+            &[1; 16],
+            &[2; 12],
+            b"thirty-three bytes of sensor data",
+        );
+    }
+}
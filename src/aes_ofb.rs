@@ -0,0 +1,129 @@
+/**
+* This is an implementation of the OFB mode of AES from NIST SP 800-38A.
+* The keystream is the IV encrypted over and over, each output block being the input to the next,
+* so it does not depend on the data and a bit flipped in the ciphertext flips the same plaintext bit.
+* Unlike counter mode the keystream cannot jump ahead, seeking backwards starts again from the IV.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE};
+use crate::stream_cipher::StreamCipher;
+
+#[derive(Clone)]
+pub struct AesOfb {
+    cipher: Aes,
+    iv: [u8; BLOCK_SIZE],
+    keystream: [u8; BLOCK_SIZE],
+    ///The block index of `keystream`, if it has been generated
+    buffered_block: Option<u64>,
+    position: u64,
+}
+
+impl AesOfb {
+    pub fn new(key: &[u8], iv: &[u8; BLOCK_SIZE]) -> Result<AesOfb, AesError> {
+        Ok(AesOfb {
+            cipher: Aes::new(key)?,
+            iv: *iv,
+            keystream: [0; BLOCK_SIZE],
+            buffered_block: None,
+            position: 0,
+        })
+    }
+
+    /// Move `keystream` to block `block`, from the IV if it is behind the buffered block
+    fn generate_block(&mut self, block: u64) {
+        let mut next = match self.buffered_block {
+            Some(buffered) if buffered <= block => buffered + 1,
+            _ => {
+                self.keystream = self.iv;
+                0
+            }
+        };
+        while next <= block {
+            self.cipher.encrypt_block(&mut self.keystream);
+            next += 1;
+        }
+        self.buffered_block = Some(block);
+    }
+}
+
+impl StreamCipher for AesOfb {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        let mut done = 0;
+        while done < data.len() {
+            let block = self.position / BLOCK_SIZE as u64;
+            if self.buffered_block != Some(block) {
+                self.generate_block(block);
+            }
+
+            let offset = (self.position % BLOCK_SIZE as u64) as usize;
+            let len = (data.len() - done).min(BLOCK_SIZE - offset);
+            for (byte, key_byte) in data[done..done + len]
+                .iter_mut()
+                .zip(&self.keystream[offset..])
+            {
+                *byte ^= key_byte;
+            }
+            self.position += len as u64;
+            done += len;
+        }
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes_ofb::AesOfb;
+    use crate::stream_cipher::StreamCipher;
+    use crate::test_util::hex;
+
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+    const IV: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    fn check(key: &str, ciphertext: &str) {
+        let mut ofb = AesOfb::new(&hex(key), &IV).unwrap();
+        let mut data = hex(PLAINTEXT);
+        ofb.apply_keystream(&mut data);
+        assert_eq!(data, hex(ciphertext));
+        ofb.seek(0);
+        ofb.apply_keystream(&mut data);
+        assert_eq!(data, hex(PLAINTEXT));
+    }
+
+    // SP 800-38A F.4.1 and F.4.5
+    #[test]
+    fn sp800_38a_vectors() {
+        check(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+             9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e",
+        );
+        check(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            "dc7e84bfda79164b7ecd8486985d38604febdc6740d20b3ac88f6ad82a4fb08d\
+             71ab47a086e86eedf39d1c5bba97c4080126141d67f37be8538f5a8be740e484",
+        );
+    }
+
+    #[test]
+    fn seek_forwards_and_back() {
+        let mut ofb = AesOfb::new(&[9; 32], &IV).unwrap();
+        let mut whole = [0u8; 100];
+        ofb.apply_keystream(&mut whole);
+
+        for start in [70, 3, 50, 0, 99] {
+            ofb.seek(start);
+            let mut part = [0u8; 20];
+            let end = (start as usize + 20).min(100);
+            ofb.apply_keystream(&mut part[..end - start as usize]);
+            assert_eq!(part[..end - start as usize], whole[start as usize..end]);
+            assert_eq!(ofb.position(), end as u64);
+        }
+    }
+}
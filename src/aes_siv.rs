@@ -0,0 +1,300 @@
+/**
+* This is an implementation of the deterministic authenticated encryption mode AES-SIV from RFC 5297.
+* S2V chains the CMAC of every associated data component and of the plaintext into a synthetic IV,
+* which is both the tag and, with two bits cleared, the initial counter block for AES-CTR.
+* The key is twice an AES key, the first half keys CMAC and the second half the encryption.
+* Sealing the same input twice gives the same output, so a repeated nonce only reveals that the
+* messages were equal. Sealed messages are laid out as in the RFC, the tag first.
+*/
+use crate::aead::{Aead, AeadError};
+use crate::aes::{Aes, BLOCK_SIZE, double_block};
+use crate::aes_ctr::AesCtr;
+use crate::cmac::{Cmac, pad};
+use crate::constant_time;
+use crate::stream_cipher::StreamCipher;
+
+pub const TAG_SIZE: usize = BLOCK_SIZE;
+///Most associated data components, including the nonce, S2V can take
+pub const MAX_COMPONENTS: usize = 126;
+
+///Clears the top bit of the last two 32 bit words of the IV, so 32 and 64 bit counters never carry
+const COUNTER_MASK: u128 = !((1 << 63) | (1 << 31));
+
+/// AES-SIV with a key of `KEY_SIZE` bytes, 32, 48 or 64
+#[derive(Clone)]
+pub struct AesSiv<const KEY_SIZE: usize> {
+    cmac: Cmac,
+    cipher: Aes,
+}
+
+pub type Aes128Siv = AesSiv<32>;
+pub type Aes192Siv = AesSiv<48>;
+pub type Aes256Siv = AesSiv<64>;
+
+impl<const KEY_SIZE: usize> AesSiv<KEY_SIZE> {
+    fn mac(&self, data: &[u8]) -> u128 {
+        let mut cmac = self.cmac.clone();
+        cmac.update(data);
+        u128::from_be_bytes(cmac.finalize())
+    }
+
+    /// The synthetic IV of the associated data `components` and `plaintext`
+    fn s2v(&self, components: &[&[u8]], plaintext: &[u8]) -> u128 {
+        let mut d = self.mac(&[0; BLOCK_SIZE]);
+        for component in components {
+            d = double_block(d) ^ self.mac(component);
+        }
+
+        if plaintext.len() >= BLOCK_SIZE {
+            // xor d into the last 16 bytes
+            let mut t = plaintext.to_vec();
+            let tail = t.len() - BLOCK_SIZE;
+            let mixed = u128::from_be_bytes(t[tail..].try_into().unwrap()) ^ d;
+            t[tail..].copy_from_slice(&mixed.to_be_bytes());
+            self.mac(&t)
+        } else {
+            self.mac(&(double_block(d) ^ pad(plaintext)).to_be_bytes())
+        }
+    }
+
+    fn apply_keystream(&self, iv: u128, data: &mut [u8]) {
+        let counter = (iv & COUNTER_MASK).to_be_bytes();
+        AesCtr::with_cipher(self.cipher.clone(), &counter).apply_keystream(data);
+    }
+
+    /// Encrypt `data` in place under any number of associated data components and return the tag
+    pub fn seal_in_place_components(
+        &self,
+        components: &[&[u8]],
+        data: &mut [u8],
+    ) -> Result<[u8; TAG_SIZE], AeadError> {
+        if components.len() > MAX_COMPONENTS {
+            return Err(AeadError);
+        }
+        let iv = self.s2v(components, data);
+        self.apply_keystream(iv, data);
+        Ok(iv.to_be_bytes())
+    }
+
+    /// Check `tag` and decrypt `data` in place, `data` is left untouched if the check fails
+    pub fn open_in_place_components(
+        &self,
+        components: &[&[u8]],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        if components.len() > MAX_COMPONENTS || tag.len() != TAG_SIZE {
+            return Err(AeadError);
+        }
+        let iv = u128::from_be_bytes(tag.try_into().unwrap());
+        self.apply_keystream(iv, data);
+        if !constant_time::eq(&self.s2v(components, data).to_be_bytes(), tag) {
+            self.apply_keystream(iv, data);
+            return Err(AeadError);
+        }
+        Ok(())
+    }
+
+    /// Encrypt `plaintext`, returning the tag followed by the ciphertext
+    pub fn seal_components(
+        &self,
+        components: &[&[u8]],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let mut data = plaintext.to_vec();
+        let tag = self.seal_in_place_components(components, &mut data)?;
+        Ok([&tag[..], &data].concat())
+    }
+
+    /// Check the tag at the start of `sealed` and return the decrypted rest
+    pub fn open_components(
+        &self,
+        components: &[&[u8]],
+        sealed: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        if sealed.len() < TAG_SIZE {
+            return Err(AeadError);
+        }
+        let (tag, data) = sealed.split_at(TAG_SIZE);
+        let mut data = data.to_vec();
+        self.open_in_place_components(components, &mut data, tag)?;
+        Ok(data)
+    }
+}
+
+/// The AEAD_AES_SIV_CMAC construction of RFC 5297 section 6, the components are the associated
+/// data then the nonce
+impl<const KEY_SIZE: usize> Aead for AesSiv<KEY_SIZE> {
+    const KEY_SIZE: usize = KEY_SIZE;
+    const NONCE_SIZE: usize = 16;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        if key.len() != KEY_SIZE || ![32, 48, 64].contains(&KEY_SIZE) {
+            return Err(AeadError);
+        }
+        let (mac_key, cipher_key) = key.split_at(KEY_SIZE / 2);
+        Ok(AesSiv {
+            cmac: Cmac::with_cipher(Aes::new(mac_key).map_err(|_| AeadError)?),
+            cipher: Aes::new(cipher_key).map_err(|_| AeadError)?,
+        })
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        if nonce.len() != Self::NONCE_SIZE {
+            return Err(AeadError);
+        }
+        Ok(self.seal_in_place_components(&[aad, nonce], data)?.to_vec())
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        if nonce.len() != Self::NONCE_SIZE {
+            return Err(AeadError);
+        }
+        self.open_in_place_components(&[aad, nonce], data, tag)
+    }
+
+    /// Encrypt `buffer` in place and put the tag in front of it
+    fn seal_in_place(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), AeadError> {
+        let tag = self.seal_in_place_detached(nonce, aad, buffer)?;
+        buffer.splice(0..0, tag);
+        Ok(())
+    }
+
+    /// Check and remove the tag at the start of `buffer`, then decrypt the rest in place
+    fn open_in_place(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), AeadError> {
+        if buffer.len() < TAG_SIZE {
+            return Err(AeadError);
+        }
+        let (tag, data) = buffer.split_at_mut(TAG_SIZE);
+        self.open_in_place_detached(nonce, aad, data, tag)?;
+        buffer.drain(..TAG_SIZE);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::{Aead, AeadError};
+    use crate::aes_siv::{Aes128Siv, Aes192Siv, Aes256Siv};
+    use crate::test_util::hex;
+
+    #[test]
+    fn rfc5297_deterministic() {
+        let siv = Aes128Siv::new(&hex(
+            "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+        ))
+        .unwrap();
+        let aad = hex("101112131415161718191a1b1c1d1e1f2021222324252627");
+        let plaintext = hex("112233445566778899aabbccddee");
+        let test_result = siv.seal_components(&[&aad], &plaintext).unwrap();
+        assert_eq!(
+            test_result,
+            hex("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c")
+        );
+        let opened = siv.open_components(&[&aad], &test_result).unwrap();
+        assert_eq!(opened, plaintext);
+    }
+
+    #[test]
+    fn rfc5297_nonce_based() {
+        let siv = Aes128Siv::new(&hex(
+            "7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f",
+        ))
+        .unwrap();
+        let ad1 =
+            hex("00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100");
+        let ad2 = hex("102030405060708090a0");
+        let nonce = hex("09f911029d74e35bd84156c5635688c0");
+        let plaintext = hex(
+            "7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553",
+        );
+        let test_result = siv
+            .seal_components(&[&ad1, &ad2, &nonce], &plaintext)
+            .unwrap();
+        assert_eq!(
+            test_result,
+            hex(
+                "7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17\
+                 dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d"
+            )
+        );
+        let opened = siv
+            .open_components(&[&ad1, &ad2, &nonce], &test_result)
+            .unwrap();
+        assert_eq!(opened, plaintext);
+        assert_eq!(
+            siv.open_components(&[&ad2, &ad1, &nonce], &test_result),
+            Err(AeadError)
+        );
+    }
+
+    // checked against the AESSIV implementation of the python cryptography package
+    #[test]
+    fn other_key_sizes_and_components() {
+        let siv = Aes256Siv::new(&[0; 64]).unwrap();
+        let test_result = siv.seal_components(&[b"a", b"b"], b"").unwrap();
+        assert_eq!(test_result, hex("4bd7e4f37c08f5aa723809cc5cd85023"));
+
+        let siv = Aes192Siv::new(&[0; 48]).unwrap();
+        let test_result = siv.seal_components(&[], b"0123456789abcdefXYZ").unwrap();
+        assert_eq!(
+            test_result,
+            hex("4814ad42f369f884b7888716e32493255eef00b9643bc866a3de10a86ba0b19ba6a708")
+        );
+    }
+
+    // AEAD_AES_SIV_CMAC_512 as the python cryptography package computes it
+    #[test]
+    fn aead_interface_puts_the_tag_first() {
+        let key: Vec<u8> = (0..64).collect();
+        let siv = Aes256Siv::new(&key).unwrap();
+        let test_result = siv.seal(&[1; 16], b"header", b"payload").unwrap();
+        assert_eq!(
+            test_result,
+            hex("928f259b7e9cffbe7ac23492695b713fe94cf225ecaeb0")
+        );
+        assert_eq!(
+            siv.open(&[1; 16], b"header", &test_result).unwrap(),
+            b"payload"
+        );
+        assert_eq!(siv.open(&[2; 16], b"header", &test_result), Err(AeadError));
+
+        let mut buffer = b"payload".to_vec();
+        siv.seal_in_place(&[1; 16], b"header", &mut buffer).unwrap();
+        assert_eq!(buffer, test_result);
+        siv.open_in_place(&[1; 16], b"header", &mut buffer).unwrap();
+        assert_eq!(buffer, b"payload");
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        assert!(Aes128Siv::new(&[0; 16]).is_err());
+        let siv = Aes128Siv::new(&[0; 32]).unwrap();
+        let components = vec![&b""[..]; 127];
+        assert!(siv.seal_components(&components, b"data").is_err());
+        assert!(siv.seal_components(&components[1..], b"data").is_ok());
+        assert!(siv.open_components(&[], &[0; 15]).is_err());
+    }
+}
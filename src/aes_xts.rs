@@ -0,0 +1,244 @@
+/**
+* This is an implementation of the XTS mode of AES from IEEE 1619 and NIST SP 800-38E, for
+* encrypting the sectors of a storage device in place.
+* The key is two AES keys, the second encrypts the tweak (the sector number as a 16 byte little
+* endian integer) and the first encrypts each block between two whitenings with the tweak, which is
+* multiplied by x in GF(2^128) from one block to the next.
+* A sector that is not a multiple of 16 bytes is handled with ciphertext stealing, so the
+* ciphertext is always as long as the plaintext. Sectors must be at least 16 bytes.
+*/
+use crate::aes::{Aes, BLOCK_SIZE, double_block};
+
+///Longest data unit SP 800-38E allows, 2^20 blocks
+pub const MAX_SECTOR_SIZE: usize = BLOCK_SIZE << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XtsError {
+    ///The key is not 32 or 64 bytes
+    InvalidKeyLength,
+    ///The two halves of the key are equal, which FIPS does not allow
+    EqualKeyHalves,
+    ///The sector is shorter than a block or longer than `MAX_SECTOR_SIZE`
+    InvalidSectorSize,
+}
+
+impl std::fmt::Display for XtsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XtsError::InvalidKeyLength => write!(f, "XTS keys are 32 or 64 bytes"),
+            XtsError::EqualKeyHalves => write!(f, "the two halves of the XTS key are equal"),
+            XtsError::InvalidSectorSize => {
+                write!(
+                    f,
+                    "XTS sectors are 16 bytes to {MAX_SECTOR_SIZE} bytes long"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for XtsError {}
+
+/// Multiply the tweak by x, with the block read as a little endian integer as IEEE 1619 does
+fn next_tweak(tweak: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+    double_block(u128::from_le_bytes(tweak)).to_le_bytes()
+}
+
+fn xor_into(block: &mut [u8; BLOCK_SIZE], other: &[u8; BLOCK_SIZE]) {
+    for (byte, other_byte) in block.iter_mut().zip(other) {
+        *byte ^= other_byte;
+    }
+}
+
+/// AES-XTS with a 32 byte (AES-128) or 64 byte (AES-256) key
+#[derive(Clone)]
+pub struct AesXts {
+    data_cipher: Aes,
+    tweak_cipher: Aes,
+}
+
+impl AesXts {
+    pub fn new(key: &[u8]) -> Result<AesXts, XtsError> {
+        if key.len() != 32 && key.len() != 64 {
+            return Err(XtsError::InvalidKeyLength);
+        }
+        let (data_key, tweak_key) = key.split_at(key.len() / 2);
+        if data_key == tweak_key {
+            return Err(XtsError::EqualKeyHalves);
+        }
+        Ok(AesXts {
+            data_cipher: Aes::new(data_key).map_err(|_| XtsError::InvalidKeyLength)?,
+            tweak_cipher: Aes::new(tweak_key).map_err(|_| XtsError::InvalidKeyLength)?,
+        })
+    }
+
+    /// Encrypt sector number `sector` in place
+    pub fn encrypt_sector(&self, sector: u128, data: &mut [u8]) -> Result<(), XtsError> {
+        self.encrypt_with_tweak(&sector.to_le_bytes(), data)
+    }
+
+    /// Decrypt sector number `sector` in place
+    pub fn decrypt_sector(&self, sector: u128, data: &mut [u8]) -> Result<(), XtsError> {
+        self.decrypt_with_tweak(&sector.to_le_bytes(), data)
+    }
+
+    /// Encrypt a data unit in place under a raw 16 byte tweak
+    pub fn encrypt_with_tweak(
+        &self,
+        tweak: &[u8; BLOCK_SIZE],
+        data: &mut [u8],
+    ) -> Result<(), XtsError> {
+        self.process(tweak, data, true)
+    }
+
+    /// Decrypt a data unit in place under a raw 16 byte tweak
+    pub fn decrypt_with_tweak(
+        &self,
+        tweak: &[u8; BLOCK_SIZE],
+        data: &mut [u8],
+    ) -> Result<(), XtsError> {
+        self.process(tweak, data, false)
+    }
+
+    /// Encrypt or decrypt one block between two whitenings with the tweak
+    fn crypt_block(&self, block: &mut [u8; BLOCK_SIZE], tweak: &[u8; BLOCK_SIZE], encrypt: bool) {
+        xor_into(block, tweak);
+        if encrypt {
+            self.data_cipher.encrypt_block(block);
+        } else {
+            self.data_cipher.decrypt_block(block);
+        }
+        xor_into(block, tweak);
+    }
+
+    fn process(
+        &self,
+        tweak: &[u8; BLOCK_SIZE],
+        data: &mut [u8],
+        encrypt: bool,
+    ) -> Result<(), XtsError> {
+        if data.len() < BLOCK_SIZE || data.len() > MAX_SECTOR_SIZE {
+            return Err(XtsError::InvalidSectorSize);
+        }
+        let mut tweak = *tweak;
+        self.tweak_cipher.encrypt_block(&mut tweak);
+
+        let partial = data.len() % BLOCK_SIZE;
+        // with stealing the last full block is done together with the partial one
+        let plain_blocks = data.len() / BLOCK_SIZE - if partial > 0 { 1 } else { 0 };
+        for chunk in data[..plain_blocks * BLOCK_SIZE].chunks_exact_mut(BLOCK_SIZE) {
+            let block: &mut [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+            self.crypt_block(block, &tweak, encrypt);
+            tweak = next_tweak(tweak);
+        }
+        if partial == 0 {
+            return Ok(());
+        }
+
+        // decryption undoes the last full block with the tweak the partial block was given
+        let (first_tweak, second_tweak) = if encrypt {
+            (tweak, next_tweak(tweak))
+        } else {
+            (next_tweak(tweak), tweak)
+        };
+        let (last_full, tail) = data[plain_blocks * BLOCK_SIZE..].split_at_mut(BLOCK_SIZE);
+        let last_full: &mut [u8; BLOCK_SIZE] = last_full.try_into().unwrap();
+        self.crypt_block(last_full, &first_tweak, encrypt);
+
+        // swap the tail with the front of the processed block and process the result again
+        let mut stolen = *last_full;
+        stolen[..partial].copy_from_slice(tail);
+        tail.copy_from_slice(&last_full[..partial]);
+        self.crypt_block(&mut stolen, &second_tweak, encrypt);
+        *last_full = stolen;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes_xts::{AesXts, XtsError};
+    use crate::test_util::hex;
+
+    // IEEE P1619/D16 test vectors
+    const STEALING_KEY: &str = "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0";
+
+    fn check(key: &str, sector: u128, plaintext: &[u8], ciphertext: &[u8]) {
+        let xts = AesXts::new(&hex(key)).unwrap();
+        let mut data = plaintext.to_vec();
+        xts.encrypt_sector(sector, &mut data).unwrap();
+        assert_eq!(data, ciphertext);
+        xts.decrypt_sector(sector, &mut data).unwrap();
+        assert_eq!(data, plaintext);
+    }
+
+    #[test]
+    fn ieee1619_vector_2() {
+        check(
+            "1111111111111111111111111111111122222222222222222222222222222222",
+            0x3333333333,
+            &[0x44; 32],
+            &hex("c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0"),
+        );
+    }
+
+    #[test]
+    fn ieee1619_vector_10_aes256() {
+        let xts = AesXts::new(&hex(
+            "2718281828459045235360287471352662497757247093699959574966967627\
+             3141592653589793238462643383279502884197169399375105820974944592",
+        ))
+        .unwrap();
+        let plaintext: Vec<u8> = (0..512).map(|i| i as u8).collect();
+        let mut data = plaintext.clone();
+        xts.encrypt_sector(0xff, &mut data).unwrap();
+        assert_eq!(
+            data[..32],
+            hex("1c3b3a102f770386e4836c99e370cf9bea00803f5e482357a4ae12d414a3e63b")
+        );
+        assert_eq!(
+            data[480..],
+            hex("773dad38014bd2092fa755c824bb5e54c4f36ffda9fcea70b9c6e693e148c151")
+        );
+        xts.decrypt_sector(0xff, &mut data).unwrap();
+        assert_eq!(data, plaintext);
+    }
+
+    #[test]
+    fn ieee1619_ciphertext_stealing() {
+        let plaintext: Vec<u8> = (0..20).collect();
+        let expected = [
+            "6c1625db4671522d3d7599601de7ca09ed",
+            "d069444b7a7e0cab09e24447d24deb1fedbf",
+            "e5df1351c0544ba1350b3363cd8ef4beedbf9d",
+            "9d84c813f719aa2c7be3f66171c7c5c2edbf9dac",
+        ];
+        for (len, ciphertext) in (17..).zip(expected) {
+            check(
+                STEALING_KEY,
+                0x123456789a,
+                &plaintext[..len],
+                &hex(ciphertext),
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_keys_and_sectors() {
+        assert!(matches!(
+            AesXts::new(&[0; 48]),
+            Err(XtsError::InvalidKeyLength)
+        ));
+        assert!(matches!(
+            AesXts::new(&[7; 64]),
+            Err(XtsError::EqualKeyHalves)
+        ));
+
+        let xts = AesXts::new(&hex(STEALING_KEY)).unwrap();
+        assert_eq!(
+            xts.encrypt_sector(0, &mut [0; 15]),
+            Err(XtsError::InvalidSectorSize)
+        );
+        assert!(xts.encrypt_sector(0, &mut [0; 16]).is_ok());
+    }
+}
@@ -0,0 +1,637 @@
+/**
+* This is an implementation of the Argon2 memory hard password hash from RFC 9106, version 0x13,
+* in its three variants: Argon2d (data dependent memory access), Argon2i (data independent access)
+* and Argon2id (independent for the first half pass, dependent afterwards, the recommended one).
+* Hashes can be stored and checked as PHC strings such as `$argon2id$v=19$m=65536,t=3,p=4$...`.
+*/
+use crate::blake2::Blake2b;
+use crate::constant_time;
+use crate::phc::{PhcError, PhcString};
+
+///Argon2 version 1.3
+pub const VERSION: u32 = 0x13;
+
+///Memory blocks are 1 KiB, held as 128 words
+const BLOCK_WORDS: usize = 128;
+const BLOCK_BYTES: usize = 8 * BLOCK_WORDS;
+///Each lane is split into this many segments, lanes synchronise at segment boundaries
+const SYNC_POINTS: usize = 4;
+
+///Limits on the inputs from RFC 9106
+const MIN_OUTPUT_LEN: usize = 4;
+const MIN_SALT_LEN: usize = 8;
+///Most lanes accepted. RFC 9106 allows up to 2^24 - 1, far more than any machine runs threads
+pub const MAX_PARALLELISM: u32 = 255;
+///Largest memory size accepted, 4 GiB. RFC 9106 allows up to 2^32 - 1 KiB, but a stored hash asking
+///for more than this is far more likely to be tampered with than legitimate.
+pub const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+///Most passes over memory accepted. RFC 9106 allows up to 2^32 - 1, which would keep a single
+///verification busy indefinitely; recommended settings use a handful.
+pub const MAX_ITERATIONS: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Argon2d,
+    Argon2i,
+    Argon2id,
+}
+
+impl Variant {
+    /// The type number y hashed into H0
+    fn type_number(self) -> u32 {
+        match self {
+            Variant::Argon2d => 0,
+            Variant::Argon2i => 1,
+            Variant::Argon2id => 2,
+        }
+    }
+
+    /// The identifier used in PHC strings
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Argon2d => "argon2d",
+            Variant::Argon2i => "argon2i",
+            Variant::Argon2id => "argon2id",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Variant> {
+        match name {
+            "argon2d" => Some(Variant::Argon2d),
+            "argon2i" => Some(Variant::Argon2i),
+            "argon2id" => Some(Variant::Argon2id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Argon2Error {
+    ///Memory must be at least 8 KiB per lane
+    MemoryTooLow,
+    ///Memory must be at most `MAX_MEMORY_KIB`
+    MemoryTooHigh,
+    ///Iterations must be between 1 and `MAX_ITERATIONS`
+    InvalidIterations,
+    ///Parallelism must be between 1 and `MAX_PARALLELISM`
+    InvalidParallelism,
+    ///The tag must be at least 4 bytes
+    OutputTooShort,
+    ///The salt must be at least 8 bytes
+    SaltTooShort,
+    ///A stored hash could not be parsed
+    InvalidHashString,
+}
+
+impl std::fmt::Display for Argon2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Argon2Error::MemoryTooLow => write!(f, "Argon2 memory must be at least 8 KiB per lane"),
+            Argon2Error::MemoryTooHigh => write!(f, "Argon2 memory must be at most 4 GiB"),
+            Argon2Error::InvalidIterations => {
+                write!(f, "Argon2 iterations must be between 1 and 64")
+            }
+            Argon2Error::InvalidParallelism => write!(f, "invalid Argon2 parallelism"),
+            Argon2Error::OutputTooShort => write!(f, "Argon2 output must be at least 4 bytes"),
+            Argon2Error::SaltTooShort => write!(f, "Argon2 salt must be at least 8 bytes"),
+            Argon2Error::InvalidHashString => write!(f, "malformed Argon2 hash string"),
+        }
+    }
+}
+
+impl std::error::Error for Argon2Error {}
+
+impl From<PhcError> for Argon2Error {
+    fn from(_: PhcError) -> Argon2Error {
+        Argon2Error::InvalidHashString
+    }
+}
+
+/// Cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    ///Memory size m in KiB
+    pub memory_kib: u32,
+    ///Number of passes t over memory
+    pub iterations: u32,
+    ///Number of lanes p
+    pub parallelism: u32,
+    ///Tag length T in bytes
+    pub output_len: usize,
+}
+
+impl Params {
+    /// Parameters with the default 32 byte output
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Params {
+        Params {
+            memory_kib,
+            iterations,
+            parallelism,
+            output_len: 32,
+        }
+    }
+
+    fn validate(&self) -> Result<(), Argon2Error> {
+        if self.parallelism == 0 || self.parallelism > MAX_PARALLELISM {
+            return Err(Argon2Error::InvalidParallelism);
+        }
+        if (self.memory_kib as u64) < 8 * self.parallelism as u64 {
+            return Err(Argon2Error::MemoryTooLow);
+        }
+        if self.memory_kib > MAX_MEMORY_KIB {
+            return Err(Argon2Error::MemoryTooHigh);
+        }
+        if self.iterations == 0 || self.iterations > MAX_ITERATIONS {
+            return Err(Argon2Error::InvalidIterations);
+        }
+        if self.output_len < MIN_OUTPUT_LEN {
+            return Err(Argon2Error::OutputTooShort);
+        }
+        Ok(())
+    }
+}
+
+type Block = [u64; BLOCK_WORDS];
+
+/// The variable length hash H' built on BLAKE2b
+fn variable_hash(output: &mut [u8], inputs: &[&[u8]]) {
+    let len_prefix = (output.len() as u32).to_le_bytes();
+    if output.len() <= 64 {
+        let mut state = Blake2b::init_size(output.len()).unwrap();
+        state.update(&len_prefix);
+        for input in inputs {
+            state.update(input);
+        }
+        output.copy_from_slice(&state.finalize());
+        return;
+    }
+
+    // chain 64 byte hashes, keeping the first half of each, and finish with a shorter one
+    let mut state = Blake2b::init_size(64).unwrap();
+    state.update(&len_prefix);
+    for input in inputs {
+        state.update(input);
+    }
+    let mut v = state.finalize();
+    let full_blocks = output.len().div_ceil(32) - 2;
+    output[..32].copy_from_slice(&v[..32]);
+    for i in 1..full_blocks {
+        let mut state = Blake2b::init_size(64).unwrap();
+        state.update(&v);
+        v = state.finalize();
+        output[32 * i..32 * (i + 1)].copy_from_slice(&v[..32]);
+    }
+    let rest = output.len() - 32 * full_blocks;
+    let mut state = Blake2b::init_size(rest).unwrap();
+    state.update(&v);
+    output[32 * full_blocks..].copy_from_slice(&state.finalize());
+}
+
+/// The BLAKE2b based mixing function with the added multiplication
+fn gb(v: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+    let mul = |x: u64, y: u64| {
+        2u64.wrapping_mul(x & 0xffffffff)
+            .wrapping_mul(y & 0xffffffff)
+    };
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(mul(v[a], v[b]));
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]).wrapping_add(mul(v[c], v[d]));
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(mul(v[a], v[b]));
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]).wrapping_add(mul(v[c], v[d]));
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The permutation P over the 16 words at the given positions
+fn permute(v: &mut Block, i: [usize; 16]) {
+    gb(v, i[0], i[4], i[8], i[12]);
+    gb(v, i[1], i[5], i[9], i[13]);
+    gb(v, i[2], i[6], i[10], i[14]);
+    gb(v, i[3], i[7], i[11], i[15]);
+    gb(v, i[0], i[5], i[10], i[15]);
+    gb(v, i[1], i[6], i[11], i[12]);
+    gb(v, i[2], i[7], i[8], i[13]);
+    gb(v, i[3], i[4], i[9], i[14]);
+}
+
+/// The compression function G(X, Y)
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r = [0u64; BLOCK_WORDS];
+    for i in 0..BLOCK_WORDS {
+        r[i] = x[i] ^ y[i];
+    }
+    let mut z = r;
+
+    // P on each row of sixteen consecutive words
+    for row in 0..8 {
+        permute(&mut z, core::array::from_fn(|k| 16 * row + k));
+    }
+    // then on each column, made of word pairs from each row
+    for column in 0..8 {
+        permute(
+            &mut z,
+            core::array::from_fn(|k| 16 * (k / 2) + 2 * column + (k % 2)),
+        );
+    }
+
+    for i in 0..BLOCK_WORDS {
+        z[i] ^= r[i];
+    }
+    z
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block = [0u64; BLOCK_WORDS];
+    for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    block
+}
+
+/// Compute a raw Argon2 tag. `secret` and `associated_data` may be empty.
+pub fn hash(
+    variant: Variant,
+    params: &Params,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    associated_data: &[u8],
+) -> Result<Vec<u8>, Argon2Error> {
+    params.validate()?;
+    if salt.len() < MIN_SALT_LEN {
+        return Err(Argon2Error::SaltTooShort);
+    }
+
+    let lanes = params.parallelism as usize;
+    let passes = params.iterations as usize;
+    // round memory down to a multiple of 4 blocks per lane
+    let memory_blocks = (params.memory_kib as usize / (SYNC_POINTS * lanes)) * SYNC_POINTS * lanes;
+    let lane_length = memory_blocks / lanes;
+    let segment_length = lane_length / SYNC_POINTS;
+
+    let mut h0 = Blake2b::init_size(64).unwrap();
+    for value in [
+        params.parallelism,
+        params.output_len as u32,
+        params.memory_kib,
+        params.iterations,
+        VERSION,
+        variant.type_number(),
+    ] {
+        h0.update(&value.to_le_bytes());
+    }
+    for input in [password, salt, secret, associated_data] {
+        h0.update(&(input.len() as u32).to_le_bytes());
+        h0.update(input);
+    }
+    let h0 = h0.finalize();
+
+    let mut memory: Vec<Block> = vec![[0u64; BLOCK_WORDS]; memory_blocks];
+    for lane in 0..lanes {
+        for column in 0..2 {
+            let mut bytes = [0u8; BLOCK_BYTES];
+            variable_hash(
+                &mut bytes,
+                &[
+                    &h0,
+                    &(column as u32).to_le_bytes(),
+                    &(lane as u32).to_le_bytes(),
+                ],
+            );
+            memory[lane * lane_length + column] = block_from_bytes(&bytes);
+        }
+    }
+
+    let zero_block = [0u64; BLOCK_WORDS];
+    for pass in 0..passes {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                let data_independent = match variant {
+                    Variant::Argon2i => true,
+                    Variant::Argon2d => false,
+                    Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+                };
+
+                // inputs to the address generator for data independent addressing
+                let mut address_input = [0u64; BLOCK_WORDS];
+                address_input[..6].copy_from_slice(&[
+                    pass as u64,
+                    lane as u64,
+                    slice as u64,
+                    memory_blocks as u64,
+                    passes as u64,
+                    variant.type_number() as u64,
+                ]);
+                let mut addresses = [0u64; BLOCK_WORDS];
+                let next_addresses = |address_input: &mut Block, addresses: &mut Block| {
+                    address_input[6] += 1;
+                    *addresses = compress(&zero_block, &compress(&zero_block, address_input));
+                };
+
+                let start_index = if pass == 0 && slice == 0 { 2 } else { 0 };
+                if data_independent && start_index != 0 {
+                    next_addresses(&mut address_input, &mut addresses);
+                }
+
+                for index in start_index..segment_length {
+                    let column = slice * segment_length + index;
+                    let current = lane * lane_length + column;
+                    let previous = if column == 0 {
+                        current + lane_length - 1
+                    } else {
+                        current - 1
+                    };
+
+                    let pseudo_random = if data_independent {
+                        if index % BLOCK_WORDS == 0 {
+                            next_addresses(&mut address_input, &mut addresses);
+                        }
+                        addresses[index % BLOCK_WORDS]
+                    } else {
+                        memory[previous][0]
+                    };
+                    let j1 = pseudo_random & 0xffffffff;
+                    let j2 = pseudo_random >> 32;
+
+                    let ref_lane = if pass == 0 && slice == 0 {
+                        lane
+                    } else {
+                        (j2 % lanes as u64) as usize
+                    };
+
+                    // the blocks this one may reference, never the one just computed
+                    let reference_area = if ref_lane == lane {
+                        if pass == 0 {
+                            column - 1
+                        } else {
+                            lane_length - segment_length + index - 1
+                        }
+                    } else {
+                        let completed = if pass == 0 {
+                            slice * segment_length
+                        } else {
+                            lane_length - segment_length
+                        };
+                        if index == 0 { completed - 1 } else { completed }
+                    };
+
+                    let x = (j1 * j1) >> 32;
+                    let y = (reference_area as u64 * x) >> 32;
+                    let relative = reference_area as u64 - 1 - y;
+                    let start = if pass == 0 || slice == SYNC_POINTS - 1 {
+                        0
+                    } else {
+                        (slice + 1) * segment_length
+                    };
+                    let ref_column = (start + relative as usize) % lane_length;
+                    let reference = ref_lane * lane_length + ref_column;
+
+                    let new_block = compress(&memory[previous], &memory[reference]);
+                    if pass == 0 {
+                        memory[current] = new_block;
+                    } else {
+                        for (word, new_word) in memory[current].iter_mut().zip(new_block) {
+                            *word ^= new_word;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut final_block = memory[lane_length - 1];
+    for lane in 1..lanes {
+        for (word, lane_word) in final_block
+            .iter_mut()
+            .zip(memory[lane * lane_length + lane_length - 1])
+        {
+            *word ^= lane_word;
+        }
+    }
+    let mut final_bytes = [0u8; BLOCK_BYTES];
+    for (chunk, word) in final_bytes.chunks_exact_mut(8).zip(final_block) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+
+    let mut tag = vec![0u8; params.output_len];
+    variable_hash(&mut tag, &[&final_bytes]);
+    Ok(tag)
+}
+
+/// Hash a password and encode the result, with its salt and parameters, as a PHC string
+pub fn hash_password(
+    variant: Variant,
+    params: &Params,
+    password: &[u8],
+    salt: &[u8],
+) -> Result<String, Argon2Error> {
+    let tag = hash(variant, params, password, salt, &[], &[])?;
+    let phc = PhcString {
+        algorithm: variant.name().to_string(),
+        version: Some(VERSION),
+        params: vec![
+            ("m".to_string(), params.memory_kib.to_string()),
+            ("t".to_string(), params.iterations.to_string()),
+            ("p".to_string(), params.parallelism.to_string()),
+        ],
+        salt: Some(salt.to_vec()),
+        hash: Some(tag),
+    };
+    Ok(phc.to_string())
+}
+
+/// Check a password against a PHC string produced by `hash_password`, comparing in constant time.
+/// A string without `v=` is version 0x10, which is not implemented, so it is rejected, as are
+/// stored hashes shorter than 4 bytes. The cost parameters come from the string and are held to
+/// the same limits as `hash`: `MAX_MEMORY_KIB`, `MAX_ITERATIONS` and `MAX_PARALLELISM` bound both
+/// the memory and the time a tampered string can make a verification take.
+pub fn verify_password(password: &[u8], encoded: &str) -> Result<bool, Argon2Error> {
+    let phc = PhcString::parse(encoded)?;
+    let variant = Variant::from_name(&phc.algorithm).ok_or(Argon2Error::InvalidHashString)?;
+    if phc.version != Some(VERSION) {
+        return Err(Argon2Error::InvalidHashString);
+    }
+
+    let number = |name: &str| -> Result<u32, Argon2Error> {
+        phc.param(name)
+            .and_then(|value| value.parse().ok())
+            .ok_or(Argon2Error::InvalidHashString)
+    };
+    let (salt, expected) = match (&phc.salt, &phc.hash) {
+        (Some(salt), Some(hash)) if hash.len() >= MIN_OUTPUT_LEN => (salt, hash),
+        _ => return Err(Argon2Error::InvalidHashString),
+    };
+    let params = Params {
+        memory_kib: number("m")?,
+        iterations: number("t")?,
+        parallelism: number("p")?,
+        output_len: expected.len(),
+    };
+
+    let tag = hash(variant, &params, password, salt, &[], &[])?;
+    Ok(constant_time::eq(&tag, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::argon2::{self, Argon2Error, Params, Variant};
+    use crate::test_util::hex;
+
+    /// The inputs shared by the RFC 9106 test vectors
+    fn rfc9106_hash(variant: Variant) -> Vec<u8> {
+        argon2::hash(
+            variant,
+            &Params::new(32, 3, 4),
+            &[0x01; 32],
+            &[0x02; 16],
+            &[0x03; 8],
+            &[0x04; 12],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rfc9106_argon2d() {
+        let test_result = hex("512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb");
+        assert_eq!(rfc9106_hash(Variant::Argon2d), test_result);
+    }
+
+    #[test]
+    fn rfc9106_argon2i() {
+        let test_result = hex("c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8");
+        assert_eq!(rfc9106_hash(Variant::Argon2i), test_result);
+    }
+
+    #[test]
+    fn rfc9106_argon2id() {
+        let test_result = hex("0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659");
+        assert_eq!(rfc9106_hash(Variant::Argon2id), test_result);
+    }
+
+    #[test]
+    fn long_output() {
+        let params = Params {
+            output_len: 100,
+            ..Params::new(16, 1, 1)
+        };
+        let tag = argon2::hash(Variant::Argon2id, &params, b"pw", b"saltsalt", &[], &[]).unwrap();
+        assert_eq!(tag.len(), 100);
+    }
+
+    #[test]
+    fn phc_string_matches_reference() {
+        let encoded = argon2::hash_password(
+            Variant::Argon2id,
+            &Params::new(64, 2, 1),
+            b"password",
+            b"somesaltsomesalt",
+        )
+        .unwrap();
+        assert_eq!(
+            encoded,
+            "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHRzb21lc2FsdA$Gpj7qOY5RCXJvcMzqcdQqvgR3wcPX7SleI4c9NtXk6E"
+        );
+    }
+
+    #[test]
+    fn verify_password() {
+        let encoded = argon2::hash_password(
+            Variant::Argon2i,
+            &Params::new(32, 2, 2),
+            b"correct horse",
+            b"0123456789abcdef",
+        )
+        .unwrap();
+        assert_eq!(
+            argon2::verify_password(b"correct horse", &encoded),
+            Ok(true)
+        );
+        assert_eq!(
+            argon2::verify_password(b"battery staple", &encoded),
+            Ok(false)
+        );
+        assert_eq!(
+            argon2::verify_password(b"correct horse", "$argon2id$v=19$m=64"),
+            Err(Argon2Error::InvalidHashString)
+        );
+        assert_eq!(
+            argon2::verify_password(
+                b"correct horse",
+                "$bcrypt$v=19$m=64,t=2,p=1$c29tZXNhbHQ$AAAA"
+            ),
+            Err(Argon2Error::InvalidHashString)
+        );
+
+        let tag = "AAAAAAAAAAAAAAAAAAAAAA";
+        // no version field means version 0x10
+        assert_eq!(
+            argon2::verify_password(b"pw", &format!("$argon2id$m=64,t=2,p=1$c29tZXNhbHQ${tag}")),
+            Err(Argon2Error::InvalidHashString)
+        );
+        // a 3 byte stored hash
+        assert_eq!(
+            argon2::verify_password(b"pw", "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHQ$AAAA"),
+            Err(Argon2Error::InvalidHashString)
+        );
+        assert_eq!(
+            argon2::verify_password(
+                b"pw",
+                &format!("$argon2id$v=19$m=4294967295,t=2,p=1$c29tZXNhbHQ${tag}")
+            ),
+            Err(Argon2Error::MemoryTooHigh)
+        );
+        assert_eq!(
+            argon2::verify_password(
+                b"pw",
+                &format!("$argon2id$v=19$m=64,t=4294967295,p=1$c29tZXNhbHQ${tag}")
+            ),
+            Err(Argon2Error::InvalidIterations)
+        );
+        assert_eq!(
+            argon2::verify_password(
+                b"pw",
+                &format!("$argon2id$v=19$m=4096,t=2,p=256$c29tZXNhbHQ${tag}")
+            ),
+            Err(Argon2Error::InvalidParallelism)
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let hash = |params: Params, salt: &[u8]| {
+            argon2::hash(Variant::Argon2id, &params, b"pw", salt, &[], &[])
+        };
+        assert_eq!(
+            hash(Params::new(15, 1, 2), b"saltsalt"),
+            Err(Argon2Error::MemoryTooLow)
+        );
+        assert_eq!(
+            hash(Params::new(argon2::MAX_MEMORY_KIB + 1, 1, 1), b"saltsalt"),
+            Err(Argon2Error::MemoryTooHigh)
+        );
+        assert_eq!(
+            hash(Params::new(16, 0, 1), b"saltsalt"),
+            Err(Argon2Error::InvalidIterations)
+        );
+        assert_eq!(
+            hash(Params::new(16, argon2::MAX_ITERATIONS + 1, 1), b"saltsalt"),
+            Err(Argon2Error::InvalidIterations)
+        );
+        assert_eq!(
+            hash(Params::new(16, 1, 0), b"saltsalt"),
+            Err(Argon2Error::InvalidParallelism)
+        );
+        assert_eq!(
+            hash(
+                Params::new(4096, 1, argon2::MAX_PARALLELISM + 1),
+                b"saltsalt"
+            ),
+            Err(Argon2Error::InvalidParallelism)
+        );
+        assert_eq!(
+            hash(Params::new(16, 1, 1), b"salt"),
+            Err(Argon2Error::SaltTooShort)
+        );
+    }
+}
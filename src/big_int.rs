@@ -1,7 +1,15 @@
 //Library of functions to deal with unsigned integers n bytes long
 //Has the lsb at position 0
+//The arithmetic works a byte at a time and is not constant time, it is meant as a simple reference
+//for checking the optimised field and group code in tests, not for handling secrets.
+//mod_pow is the exception: for an odd modulus it uses Montgomery multiplication over 64 bit limbs
+//and a fixed 4 bit window, with the same operations for every exponent of a given byte length, so
+//it can raise to secret exponents for finite field Diffie-Hellman
 
-#[derive(Debug)]
+use std::cmp::Ordering;
+use std::ops;
+
+#[derive(Debug, Clone)]
 pub struct BigInt {
     size: usize,
     content: Vec<u8>,
@@ -18,11 +26,7 @@ impl BigInt {
     }
 
     pub fn copy(in_: &BigInt) -> BigInt {
-        BigInt {
-            size: in_.size.clone(),
-            content: in_.content.clone(),
-            error: in_.error.clone(),
-        }
+        in_.clone()
     }
 
     pub fn new(content_: Vec<u8>) -> BigInt {
@@ -33,10 +37,44 @@ impl BigInt {
         }
     }
 
+    pub fn from_u64(value: u64) -> BigInt {
+        BigInt::new(value.to_le_bytes().to_vec())
+    }
+
+    pub fn from_be_bytes(bytes: &[u8]) -> BigInt {
+        BigInt::new(bytes.iter().rev().copied().collect())
+    }
+
+    /// The value as `len` little endian bytes, panics if it does not fit
+    pub fn to_le_bytes(&self, len: usize) -> Vec<u8> {
+        let mut bytes = self.content.clone();
+        assert!(
+            bytes.iter().skip(len).all(|&byte| byte == 0),
+            "value does not fit"
+        );
+        bytes.resize(len, 0);
+        bytes
+    }
+
+    /// The value as `len` big endian bytes, panics if it does not fit
+    pub fn to_be_bytes(&self, len: usize) -> Vec<u8> {
+        let mut bytes = self.to_le_bytes(len);
+        bytes.reverse();
+        bytes
+    }
+
     pub fn set(&mut self, content_: Vec<u8>) {
-        for i in 0..std::cmp::min(content_.len(), self.size) {
-            self.content[i] = content_[i];
-        }
+        let len = content_.len().min(self.size);
+        self.content[..len].copy_from_slice(&content_[..len]);
+    }
+
+    /// True if a subtraction went below zero
+    pub fn error(&self) -> bool {
+        self.error
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.content.iter().all(|&byte| byte == 0)
     }
 
     pub fn print_all(&self) {
@@ -45,146 +83,414 @@ impl BigInt {
         for i in self.content.iter() {
             print!("{0:x}", i);
         }
-        print!("\n");
+        println!();
     }
 
+    /// Drop the high zero bytes, keeping at least one byte
     pub fn trim(&mut self) {
-        let temp = self.size - 1;
-        for i in temp..0 {
-            if self.content[i] == 0 {
-                self.content.pop();
-            } else {
+        while self.content.len() > 1 && self.content.last() == Some(&0) {
+            self.content.pop();
+        }
+        self.size = self.content.len();
+    }
+
+    /// Number of bits up to the highest set one, 0 for zero
+    pub fn bit_len(&self) -> usize {
+        match self.content.iter().rposition(|&byte| byte != 0) {
+            Some(i) => i * 8 + 8 - self.content[i].leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.content
+            .get(i / 8)
+            .is_some_and(|byte| (byte >> (i % 8)) & 1 == 1)
+    }
+
+    /// Quotient and remainder of the division by `divisor`, panics on division by zero
+    pub fn div_rem(&self, divisor: &BigInt) -> (BigInt, BigInt) {
+        assert!(!divisor.is_zero(), "division by zero");
+        let mut quotient = BigInt::init(self.size.max(1));
+        let mut remainder = BigInt::init(divisor.size + 1);
+        for i in (0..self.bit_len()).rev() {
+            // remainder = 2 * remainder + bit, which stays below 2 * divisor
+            let mut carry = self.bit(i) as u8;
+            for byte in remainder.content.iter_mut() {
+                let next = *byte >> 7;
+                *byte = (*byte << 1) | carry;
+                carry = next;
+            }
+            if remainder >= *divisor {
+                remainder = remainder - divisor.clone();
+                quotient.content[i / 8] |= 1 << (i % 8);
+            }
+        }
+        quotient.trim();
+        remainder.trim();
+        (quotient, remainder)
+    }
+
+    /// `self ^ exponent mod modulus`, by square and multiply
+    pub fn mod_pow(&self, exponent: &BigInt, modulus: &BigInt) -> BigInt {
+        if modulus.bit(0) && modulus.bit_len() > 1 {
+            return Montgomery::new(modulus).pow(self, exponent);
+        }
+        let base = self.clone() % modulus.clone();
+        let mut result = BigInt::from_u64(1) % modulus.clone();
+        for i in (0..exponent.bit_len()).rev() {
+            result = (result.clone() * result) % modulus.clone();
+            if exponent.bit(i) {
+                result = (result * base.clone()) % modulus.clone();
+            }
+        }
+        result
+    }
+
+    /// One Miller-Rabin round on an odd `self` > 3 with `base` in 2..self-2, false means composite
+    pub fn miller_rabin(&self, base: &BigInt) -> bool {
+        let one = BigInt::from_u64(1);
+        let minus_one = self.clone() - one.clone();
+        let trailing_zeros = (0..).find(|&i| minus_one.bit(i)).unwrap_or(0);
+        let mut odd = minus_one.clone();
+        for _ in 0..trailing_zeros {
+            odd = odd / BigInt::from_u64(2);
+        }
+
+        let mut x = base.mod_pow(&odd, self);
+        if x == one || x == minus_one {
+            return true;
+        }
+        for _ in 1..trailing_zeros {
+            x = (x.clone() * x) % self.clone();
+            if x == minus_one {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// `self mod divisor` for a small nonzero divisor, a byte at a time from the top
+    pub fn rem_u32(&self, divisor: u32) -> u32 {
+        assert!(divisor != 0, "division by zero");
+        self.content.iter().rev().fold(0u64, |remainder, &byte| {
+            ((remainder << 8) | byte as u64) % divisor as u64
+        }) as u32
+    }
+
+    /// Greatest common divisor, by the binary algorithm
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        if self.is_zero() {
+            return other.clone();
+        }
+        if other.is_zero() {
+            return self.clone();
+        }
+        let len = self.bit_len().max(other.bit_len()).div_ceil(64);
+        let mut a = self.limbs(len);
+        let mut b = other.limbs(len);
+        let shift = trailing_zeros(&a).min(trailing_zeros(&b));
+        let zeros = trailing_zeros(&a);
+        shift_right(&mut a, zeros);
+        loop {
+            let zeros = trailing_zeros(&b);
+            shift_right(&mut b, zeros);
+            if a.iter().rev().cmp(b.iter().rev()) == Ordering::Greater {
+                std::mem::swap(&mut a, &mut b);
+            }
+            let mut borrow = false;
+            for (b_j, &a_j) in b.iter_mut().zip(&a) {
+                let (value, borrow_1) = b_j.overflowing_sub(a_j);
+                let (value, borrow_2) = value.overflowing_sub(borrow as u64);
+                *b_j = value;
+                borrow = borrow_1 | borrow_2;
+            }
+            if b.iter().all(|&limb| limb == 0) {
                 break;
             }
         }
+        let mut power = BigInt::init(shift / 8 + 1);
+        power.content[shift / 8] = 1 << (shift % 8);
+        BigInt::from_limbs(&a) * power
+    }
+
+    /// The value as `len` little endian 64 bit limbs
+    fn limbs(&self, len: usize) -> Vec<u64> {
+        self.to_le_bytes(8 * len)
+            .chunks(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    fn from_limbs(limbs: &[u64]) -> BigInt {
+        let mut result = BigInt::new(limbs.iter().flat_map(|limb| limb.to_le_bytes()).collect());
+        result.trim();
+        result
     }
 }
-use std::ops;
 
-impl ops::Add for BigInt {
-    type Output = BigInt;
-    fn add(self, rhs: BigInt) -> BigInt {
-        let mut _size: usize = 0;
-        if ((self.content[self.size - 1] as u16) + (rhs.content[rhs.size - 1] + 1) as u16)
-            > (u8::MAX as u16)
-        {
-            _size = std::cmp::max(self.size, rhs.size) + 1;
+fn trailing_zeros(limbs: &[u64]) -> usize {
+    let i = limbs.iter().position(|&limb| limb != 0).unwrap_or(0);
+    64 * i + limbs[i].trailing_zeros() as usize
+}
+
+/// Shift right by `shift` bits in place
+fn shift_right(limbs: &mut [u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    for i in 0..limbs.len() {
+        let low = limbs.get(i + words).copied().unwrap_or(0);
+        let high = limbs.get(i + words + 1).copied().unwrap_or(0);
+        limbs[i] = if bits == 0 {
+            low
         } else {
-            _size = std::cmp::max(self.size, rhs.size);
+            (low >> bits) | (high << (64 - bits))
+        };
+    }
+}
+
+/// Montgomery arithmetic modulo an odd number, on values below it in little endian 64 bit limbs
+struct Montgomery {
+    modulus: BigInt,
+    limbs: Vec<u64>,
+    ///-modulus^-1 mod 2^64
+    inverse: u64,
+}
+
+impl Montgomery {
+    fn new(modulus: &BigInt) -> Montgomery {
+        let limbs = modulus.limbs(modulus.bit_len().div_ceil(64));
+        // Newton iteration, each step doubles the number of correct low bits
+        let mut inverse = 1u64;
+        for _ in 0..6 {
+            inverse = inverse.wrapping_mul(2u64.wrapping_sub(limbs[0].wrapping_mul(inverse)));
+        }
+        Montgomery {
+            modulus: modulus.clone(),
+            limbs,
+            inverse: inverse.wrapping_neg(),
         }
+    }
+
+    /// a b / R mod modulus with R = 2^(64 len), the final subtraction is masked
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let len = self.limbs.len();
+        let mut t = vec![0u64; len + 2];
+        for &b_i in b {
+            let mut carry = 0u64;
+            for (t_j, &a_j) in t.iter_mut().zip(a) {
+                let sum = *t_j as u128 + a_j as u128 * b_i as u128 + carry as u128;
+                *t_j = sum as u64;
+                carry = (sum >> 64) as u64;
+            }
+            let sum = t[len] as u128 + carry as u128;
+            t[len] = sum as u64;
+            t[len + 1] = (sum >> 64) as u64;
 
-        let mut _content: Vec<u8> = vec![0; _size];
-        let mut intermediate_sum: u16;
-        let mut remainder: u8 = 0;
-
-        for i in 0.._size {
-            if (i < self.size) && (i < rhs.size) {
-                intermediate_sum =
-                    (self.content[i] as u16) + (rhs.content[i] as u16) + (remainder as u16) as u16;
-                _content[i] = (intermediate_sum % ((u8::MAX as u16) + 1)) as u8;
-                remainder = (intermediate_sum / ((u8::MAX as u16) + 1)) as u8;
-            } else if i < rhs.size {
-                _content[i] = rhs.content[i];
-                remainder = 0;
-            } else if i < self.size {
-                _content[i] = self.content[i];
-                remainder = 0;
-            } else {
-                _content[i] = remainder;
+            let m = t[0].wrapping_mul(self.inverse);
+            let mut carry = ((t[0] as u128 + m as u128 * self.limbs[0] as u128) >> 64) as u64;
+            for j in 1..len {
+                let sum = t[j] as u128 + m as u128 * self.limbs[j] as u128 + carry as u128;
+                t[j - 1] = sum as u64;
+                carry = (sum >> 64) as u64;
             }
+            let sum = t[len] as u128 + carry as u128;
+            t[len - 1] = sum as u64;
+            t[len] = t[len + 1] + (sum >> 64) as u64;
         }
 
-        BigInt {
-            size: _size,
-            content: _content,
-            error: false,
+        // t < 2 modulus, keep t - modulus unless it borrows past the top limb
+        let mut difference = vec![0u64; len];
+        let mut borrow = 0u64;
+        for (j, d) in difference.iter_mut().enumerate() {
+            let (value, borrow_1) = t[j].overflowing_sub(self.limbs[j]);
+            let (value, borrow_2) = value.overflowing_sub(borrow);
+            *d = value;
+            borrow = (borrow_1 | borrow_2) as u64;
+        }
+        let keep_t = ((t[len] < borrow) as u64).wrapping_neg();
+        (0..len)
+            .map(|j| (t[j] & keep_t) | (difference[j] & !keep_t))
+            .collect()
+    }
+
+    /// R^2 mod modulus, by doubling 1 modulo the modulus 2 * 64 * len times
+    fn r2(&self) -> Vec<u64> {
+        let len = self.limbs.len();
+        let mut r2 = vec![0u64; len];
+        r2[0] = 1;
+        for _ in 0..128 * len {
+            let mut carry = 0u64;
+            for limb in r2.iter_mut() {
+                let next = *limb >> 63;
+                *limb = (*limb << 1) | carry;
+                carry = next;
+            }
+            let mut difference = vec![0u64; len];
+            let mut borrow = 0u64;
+            for (j, d) in difference.iter_mut().enumerate() {
+                let (value, borrow_1) = r2[j].overflowing_sub(self.limbs[j]);
+                let (value, borrow_2) = value.overflowing_sub(borrow);
+                *d = value;
+                borrow = (borrow_1 | borrow_2) as u64;
+            }
+            if carry >= borrow {
+                r2 = difference;
+            }
+        }
+        r2
+    }
+
+    /// `base ^ exponent`, 4 exponent bits at a time with every table entry read for each window
+    fn pow(&self, base: &BigInt, exponent: &BigInt) -> BigInt {
+        let len = self.limbs.len();
+        let r2 = self.r2();
+        let base = if *base < self.modulus {
+            base.clone()
+        } else {
+            base.clone() % self.modulus.clone()
+        };
+
+        let mut table = vec![self.mul(&BigInt::from_u64(1).limbs(len), &r2)];
+        let base = self.mul(&base.limbs(len), &r2);
+        for i in 1..16 {
+            table.push(self.mul(&table[i - 1], &base));
+        }
+
+        let mut result = table[0].clone();
+        for byte in exponent.content.iter().rev() {
+            for window in [byte >> 4, byte & 15] {
+                for _ in 0..4 {
+                    result = self.mul(&result, &result);
+                }
+                let mut entry = vec![0u64; len];
+                for (i, value) in table.iter().enumerate() {
+                    let mask = ((i as u8 == window) as u64).wrapping_neg();
+                    for (e, v) in entry.iter_mut().zip(value) {
+                        *e |= v & mask;
+                    }
+                }
+                result = self.mul(&result, &entry);
+            }
+        }
+        let mut one = vec![0u64; len];
+        one[0] = 1;
+        BigInt::from_limbs(&self.mul(&result, &one))
+    }
+}
+
+impl ops::Add for BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: BigInt) -> BigInt {
+        let size = self.size.max(rhs.size);
+        let mut content = Vec::with_capacity(size + 1);
+        let mut carry = 0u16;
+        for i in 0..size {
+            let sum = *self.content.get(i).unwrap_or(&0) as u16
+                + *rhs.content.get(i).unwrap_or(&0) as u16
+                + carry;
+            content.push(sum as u8);
+            carry = sum >> 8;
         }
+        if carry > 0 {
+            content.push(carry as u8);
+        }
+        BigInt::new(content)
     }
 }
 
 impl ops::Sub for BigInt {
     type Output = BigInt;
     fn sub(self, rhs: BigInt) -> BigInt {
-        let mut result: BigInt = BigInt::copy(&self);
-
+        let mut result = BigInt::copy(&self);
         if rhs > self {
             result.error = true;
             return result;
         }
 
-        let mut result: BigInt = BigInt::copy(&self);
-        let mut temp: i16;
-        let mut carry: bool = false;
-
+        let mut borrow = 0i16;
         for i in 0..self.size {
-            if carry {
-                temp = (self.content[i] as i16) - (rhs.content[i] as i16) - 1;
-            } else {
-                temp = (self.content[i] as i16) - (rhs.content[i] as i16);
-            }
-
-            if temp >= 0 {
-                result.content[i] = temp as u8;
-                carry = false;
-            } else {
+            let mut temp =
+                self.content[i] as i16 - *rhs.content.get(i).unwrap_or(&0) as i16 - borrow;
+            borrow = 0;
+            if temp < 0 {
                 temp += 256;
-                result.content[i] = temp as u8;
-                carry = true;
+                borrow = 1;
             }
+            result.content[i] = temp as u8;
         }
-        if carry {
-            result.error = true;
+        result
+    }
+}
+
+impl ops::Mul for BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: BigInt) -> BigInt {
+        let mut content = vec![0u32; self.size + rhs.size];
+        for (i, &a) in self.content.iter().enumerate() {
+            let mut carry = 0u32;
+            for (j, &b) in rhs.content.iter().enumerate() {
+                let sum = content[i + j] + a as u32 * b as u32 + carry;
+                content[i + j] = sum & 0xff;
+                carry = sum >> 8;
+            }
+            content[i + rhs.size] += carry;
         }
-        return result;
+        let mut result = BigInt::new(content.into_iter().map(|byte| byte as u8).collect());
+        result.trim();
+        result
+    }
+}
+
+impl ops::Div for BigInt {
+    type Output = BigInt;
+    fn div(self, rhs: BigInt) -> BigInt {
+        self.div_rem(&rhs).0
+    }
+}
+
+impl ops::Rem for BigInt {
+    type Output = BigInt;
+    fn rem(self, rhs: BigInt) -> BigInt {
+        self.div_rem(&rhs).1
     }
 }
 
 impl PartialEq for BigInt {
     fn eq(&self, other: &BigInt) -> bool {
-        if self.size != other.size {
-            return false;
-        }
-        if self.content != other.content {
-            return false;
-        } else {
-            return true;
-        }
+        self.cmp(other) == Ordering::Equal
     }
 }
 
+impl Eq for BigInt {}
+
 impl PartialOrd for BigInt {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.size > other.size {
-            for i in other.size..self.size {
-                if self.content[i] != 0 {
-                    return Some(std::cmp::Ordering::Greater);
-                }
-            }
-        } else if other.size > self.size {
-            for i in self.size..other.size {
-                if other.content[i] != 0 {
-                    return Some(std::cmp::Ordering::Less);
-                }
-            }
-        } else {
-            for i in 0..self.size {
-                if self.content[self.size - 1 - i] > other.content[self.size - 1 - i] {
-                    return Some(std::cmp::Ordering::Greater);
-                }
-                if self.content[self.size - 1 - i] < other.content[self.size - 1 - i] {
-                    return Some(std::cmp::Ordering::Less);
-                } else if i == self.size - 1 {
-                    return Some(std::cmp::Ordering::Equal);
-                }
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    /// Compare the values, high zero bytes do not count
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.size.max(other.size);
+        for i in (0..len).rev() {
+            let ordering = self
+                .content
+                .get(i)
+                .unwrap_or(&0)
+                .cmp(other.content.get(i).unwrap_or(&0));
+            if ordering != Ordering::Equal {
+                return ordering;
             }
         }
-        return None;
+        Ordering::Equal
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::big_int;
+    use crate::test_util::hex;
 
     #[test]
     fn test_copy_constructor() {
@@ -222,6 +528,14 @@ mod tests {
         assert_eq!(test_lhs + test_rhs, test_result);
     }
 
+    #[test]
+    fn sum_carry_into_longer_operand() {
+        let test_lhs = big_int::BigInt::new(vec![255, 255, 255]);
+        let test_rhs = big_int::BigInt::new(vec![1]);
+        let test_result = big_int::BigInt::new(vec![0, 0, 0, 1]);
+        assert_eq!(test_lhs + test_rhs, test_result);
+    }
+
     #[test]
     fn easy_sub() {
         let test_data1 = vec![255, 5];
@@ -242,6 +556,13 @@ mod tests {
         assert_eq!(test_lhs - test_rhs, test_result);
     }
 
+    #[test]
+    fn sub_underflow_sets_error() {
+        let test_lhs = big_int::BigInt::new(vec![1]);
+        let test_rhs = big_int::BigInt::new(vec![0, 1]);
+        assert!((test_lhs - test_rhs).error());
+    }
+
     #[test]
     fn greater_than() {
         let test_data1 = vec![255, 255, 3];
@@ -282,6 +603,14 @@ mod tests {
         assert_eq!(test_lhs < test_rhs, test_result);
     }
 
+    #[test]
+    fn size_diff_with_zero_high_bytes() {
+        let test_lhs = big_int::BigInt::new(vec![5, 1, 0, 0]);
+        let test_rhs = big_int::BigInt::new(vec![4, 1]);
+        assert!(test_lhs > test_rhs);
+        assert_eq!(test_lhs, big_int::BigInt::new(vec![5, 1]));
+    }
+
     #[test]
     fn equal_to() {
         let test_data1 = vec![255, 255, 2];
@@ -301,4 +630,92 @@ mod tests {
         let test_result = big_int::BigInt::new(vec![254, 255, 2, 1]);
         assert_eq!(test_lhs + test_rhs, test_result);
     }
+
+    #[test]
+    fn multiply() {
+        let test_lhs = big_int::BigInt::from_u64(0xffff_ffff_ffff_ffff);
+        let test_rhs = big_int::BigInt::from_u64(0xffff_ffff_ffff_ffff);
+        // (2^64 - 1)^2 = 2^128 - 2^65 + 1
+        let test_result = big_int::BigInt::from_be_bytes(&[
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0, 0, 0, 0, 0, 0, 0, 1,
+        ]);
+        assert_eq!(test_lhs * test_rhs, test_result);
+    }
+
+    #[test]
+    fn divide() {
+        let test_lhs = big_int::BigInt::from_be_bytes(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde]);
+        let test_rhs = big_int::BigInt::from_u64(0x1234);
+        let (quotient, remainder) = test_lhs.div_rem(&test_rhs);
+        assert_eq!(quotient, big_int::BigInt::from_u64(0x0100_04c0_1690));
+        assert_eq!(remainder, big_int::BigInt::from_u64(0x079e));
+        assert_eq!(quotient * test_rhs + remainder, test_lhs);
+    }
+
+    #[test]
+    fn modular_exponentiation() {
+        // Fermat: 3^(p - 1) = 1 mod p for the prime 2^61 - 1
+        let modulus = big_int::BigInt::from_u64((1 << 61) - 1);
+        let exponent = big_int::BigInt::from_u64((1 << 61) - 2);
+        let test_result = big_int::BigInt::from_u64(3).mod_pow(&exponent, &modulus);
+        assert_eq!(test_result, big_int::BigInt::from_u64(1));
+
+        let test_result = big_int::BigInt::from_u64(4).mod_pow(
+            &big_int::BigInt::from_u64(13),
+            &big_int::BigInt::from_u64(497),
+        );
+        assert_eq!(test_result, big_int::BigInt::from_u64(445));
+    }
+
+    #[test]
+    fn modular_exponentiation_even_and_large() {
+        // 3^200 mod 1000 = 1, on the plain square and multiply path
+        let test_result = big_int::BigInt::from_u64(3).mod_pow(
+            &big_int::BigInt::from_u64(200),
+            &big_int::BigInt::from_u64(1000),
+        );
+        assert_eq!(test_result, big_int::BigInt::from_u64(1));
+
+        // checked with python pow, the base is larger than the modulus
+        let modulus = big_int::BigInt::from_be_bytes(&[
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0x61,
+        ]);
+        let base = big_int::BigInt::from_be_bytes(&[0x12; 20]);
+        let exponent = big_int::BigInt::from_be_bytes(&[0, 0, 0x34, 0x56, 0x78, 0x9a]);
+        let test_result = base.mod_pow(&exponent, &modulus);
+        assert_eq!(
+            test_result,
+            big_int::BigInt::from_be_bytes(&hex("b990b8ca434136f83e7cdd18ac9077a1"))
+        );
+    }
+
+    #[test]
+    fn miller_rabin() {
+        let prime = big_int::BigInt::from_u64((1 << 61) - 1);
+        let composite = big_int::BigInt::from_u64(3215031751); // strong pseudoprime to 2, 3, 5, 7
+        for base in [2, 3, 5, 7, 11] {
+            assert!(prime.miller_rabin(&big_int::BigInt::from_u64(base)));
+        }
+        for base in [2, 3, 5, 7] {
+            assert!(composite.miller_rabin(&big_int::BigInt::from_u64(base)));
+        }
+        assert!(!composite.miller_rabin(&big_int::BigInt::from_u64(11)));
+    }
+
+    #[test]
+    fn gcd_and_small_remainders() {
+        let a = big_int::BigInt::from_u64(2u64.pow(10) * 3 * 5 * 7 * 1_000_003);
+        let b = big_int::BigInt::from_u64(2u64.pow(4) * 5 * 7 * 11 * 13);
+        assert_eq!(a.gcd(&b), big_int::BigInt::from_u64(2u64.pow(4) * 5 * 7));
+        assert_eq!(b.gcd(&a), big_int::BigInt::from_u64(2u64.pow(4) * 5 * 7));
+        assert_eq!(a.gcd(&big_int::BigInt::from_u64(0)), a,);
+        // (2^128 - 1) = 3 * 5 * 17 * 257 * 641 * 65537 * 274177 * 6700417 * 67280421310721
+        let large = big_int::BigInt::from_be_bytes(&[0xff; 16]);
+        let test_result = large.gcd(&big_int::BigInt::from_u64(641 * 6700417 * 19));
+        assert_eq!(test_result, big_int::BigInt::from_u64(641 * 6700417));
+
+        assert_eq!(large.rem_u32(65537), 0);
+        assert_eq!(large.rem_u32(65539), 6560);
+    }
 }
@@ -0,0 +1,353 @@
+/**
+* This is an implementation of the BIP-340 Schnorr signature scheme over secp256k1.
+* Public keys are x-only: a 32 byte x coordinate standing for the point with that x and an even y,
+* so a secret key whose point has an odd y is negated before signing. The nonce point R is made
+* even the same way, and a signature is R.x || s with s = k + e d mod n, where the challenge e is
+* a tagged hash of R.x, the public key and the message.
+* The nonce is a tagged hash of the secret key masked with auxiliary random data, the public key
+* and the message, so signing stays safe with a broken random generator and resists side channels
+* with a good one.
+*/
+use crate::ec::{
+    EcError, PublicKey, SECP256K1, SecretKey, is_zero_limbs, less_than, limbs_from_be_bytes,
+    limbs_to_be_bytes,
+};
+use crate::hash::Hash;
+use crate::rng::CryptoRng;
+use crate::sha2::Sha256;
+
+pub const SECRET_KEY_SIZE: usize = 32;
+pub const PUBLIC_KEY_SIZE: usize = 32;
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// SHA256(SHA256(tag) || SHA256(tag) || pieces), the domain separated hash of BIP-340
+pub fn tagged_hash(tag: &str, pieces: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hash = Sha256::init();
+    hash.update(&tag_hash);
+    hash.update(&tag_hash);
+    for piece in pieces {
+        hash.update(piece);
+    }
+    hash.finalize()
+        .try_into()
+        .expect("SHA-256 digests are 32 bytes")
+}
+
+/// The challenge e = H_challenge(R.x || P.x || m) mod n
+fn challenge(r: &[u8], public_key: &[u8; PUBLIC_KEY_SIZE], message: &[u8]) -> [u64; 4] {
+    let hash = tagged_hash("BIP0340/challenge", &[r, public_key, message]);
+    SECP256K1.n.reduce(&limbs_from_be_bytes(&hash))
+}
+
+/// d e mod n for canonical scalars
+fn mul_scalars(d: &[u64; 4], e: &[u64; 4]) -> [u64; 4] {
+    let n = &SECP256K1.n;
+    n.canonical(&n.mul(&n.montgomery(d), &n.montgomery(e)))
+}
+
+/// A public key, the x coordinate of a point with an even y
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XOnlyPublicKey {
+    key: PublicKey<4>,
+}
+
+impl XOnlyPublicKey {
+    /// Read an x coordinate, which must be below p and on the curve
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_SIZE]) -> Result<XOnlyPublicKey, EcError> {
+        let key = PublicKey::from_x(&SECP256K1, &limbs_from_be_bytes(bytes), false)
+            .ok_or(EcError::InvalidPoint)?;
+        Ok(XOnlyPublicKey { key })
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        limbs_to_be_bytes(&self.key.x())
+            .try_into()
+            .expect("secp256k1 coordinates are 32 bytes")
+    }
+
+    /// The full point, with an even y
+    pub fn public_key(&self) -> PublicKey<4> {
+        self.key
+    }
+
+    /// Check a signature R.x || s, R = sG - eP must have an even y and the x coordinate given
+    pub fn verify(&self, message: &[u8], signature: &[u8; SIGNATURE_SIZE]) -> Result<(), EcError> {
+        let curve = &SECP256K1;
+        let r = limbs_from_be_bytes(&signature[..32]);
+        let s = limbs_from_be_bytes(&signature[32..]);
+        if !less_than(&r, &curve.p.value) || !less_than(&s, &curve.n.value) {
+            return Err(EcError::InvalidSignature);
+        }
+        let e = challenge(&signature[..32], &self.to_bytes(), message);
+        let point = curve.add(
+            &curve.mul(&curve.generator(), &s),
+            &curve.mul(&self.key.point(), &curve.n.neg(&e)),
+        );
+        match PublicKey::from_point(curve, &point) {
+            Some(nonce_point) if !nonce_point.has_odd_y() && nonce_point.x() == r => Ok(()),
+            _ => Err(EcError::InvalidSignature),
+        }
+    }
+}
+
+/// A secret key with its x-only public key
+#[derive(Clone)]
+pub struct SigningKey {
+    secret: SecretKey<4>,
+    ///The secret scalar, negated when its point has an odd y so that it matches `public_key`
+    d: [u64; 4],
+    public_key: XOnlyPublicKey,
+}
+
+impl SigningKey {
+    /// Read a big endian scalar in 1..n-1
+    pub fn from_bytes(bytes: &[u8; SECRET_KEY_SIZE]) -> Result<SigningKey, EcError> {
+        Ok(SigningKey::from_secret(SecretKey::from_bytes(
+            &SECP256K1, bytes,
+        )?))
+    }
+
+    /// Generate a fresh random key
+    pub fn generate(rng: &mut impl CryptoRng) -> SigningKey {
+        SigningKey::from_secret(SecretKey::generate(&SECP256K1, rng))
+    }
+
+    fn from_secret(secret: SecretKey<4>) -> SigningKey {
+        let point = secret.public_key();
+        let d = if point.has_odd_y() {
+            SECP256K1.n.neg(&secret.scalar)
+        } else {
+            secret.scalar
+        };
+        let key = PublicKey::from_x(&SECP256K1, &point.x(), false)
+            .expect("the x coordinate of a public key is on the curve");
+        SigningKey {
+            secret,
+            d,
+            public_key: XOnlyPublicKey { key },
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_SIZE] {
+        self.secret
+            .to_bytes()
+            .try_into()
+            .expect("secp256k1 scalars are 32 bytes")
+    }
+
+    pub fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key
+    }
+
+    /// Sign with 32 bytes of auxiliary randomness drawn from `rng`
+    pub fn sign(&self, message: &[u8], rng: &mut impl CryptoRng) -> [u8; SIGNATURE_SIZE] {
+        self.sign_with_aux_rand(message, &rng.random_array())
+    }
+
+    /// Sign with the given auxiliary data, all zeros gives deterministic signatures
+    pub fn sign_with_aux_rand(&self, message: &[u8], aux_rand: &[u8; 32]) -> [u8; SIGNATURE_SIZE] {
+        let curve = &SECP256K1;
+        let n = &curve.n;
+        let public_key = self.public_key.to_bytes();
+
+        let mask = tagged_hash("BIP0340/aux", &[aux_rand]);
+        let t: Vec<u8> = limbs_to_be_bytes(&self.d)
+            .iter()
+            .zip(mask)
+            .map(|(d, m)| d ^ m)
+            .collect();
+        let nonce = tagged_hash("BIP0340/nonce", &[&t, &public_key, message]);
+        let k = n.reduce(&limbs_from_be_bytes(&nonce));
+        assert!(!is_zero_limbs(&k), "the nonce hash is a multiple of n");
+
+        let nonce_point = PublicKey::from_point(curve, &curve.mul(&curve.generator(), &k))
+            .expect("a nonce in 1..n-1 never gives the point at infinity");
+        let k = if nonce_point.has_odd_y() {
+            n.neg(&k)
+        } else {
+            k
+        };
+        let r = limbs_to_be_bytes(&nonce_point.x());
+        let e = challenge(&r, &public_key, message);
+        let s = n.add(&k, &mul_scalars(&e, &self.d));
+
+        let mut signature = [0u8; SIGNATURE_SIZE];
+        signature[..32].copy_from_slice(&r);
+        signature[32..].copy_from_slice(&limbs_to_be_bytes(&s));
+        signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bip340::{SigningKey, XOnlyPublicKey};
+    use crate::ec::EcError;
+    use crate::rng::SeededRng;
+    use crate::test_util::hex;
+
+    fn array<const N: usize>(s: &str) -> [u8; N] {
+        hex(s).try_into().unwrap()
+    }
+
+    fn check_sign(key: &str, public_key: &str, aux_rand: &str, message: &str, signature: &str) {
+        let key = SigningKey::from_bytes(&array(key)).unwrap();
+        assert_eq!(key.public_key().to_bytes().to_vec(), hex(public_key));
+        let test_result = key.sign_with_aux_rand(&hex(message), &array(aux_rand));
+        assert_eq!(test_result.to_vec(), hex(signature));
+        check_verify(public_key, message, signature, true);
+    }
+
+    fn check_verify(public_key: &str, message: &str, signature: &str, valid: bool) {
+        let public_key = XOnlyPublicKey::from_bytes(&array(public_key)).unwrap();
+        let test_result = public_key.verify(&hex(message), &array(signature));
+        assert_eq!(test_result.is_ok(), valid);
+    }
+
+    // BIP-340 test vectors 0 to 3
+    #[test]
+    fn bip340_signing() {
+        check_sign(
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215\
+             25F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+        );
+        check_sign(
+            "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE3341\
+             8906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+        );
+        check_sign(
+            "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9",
+            "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906",
+            "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+            "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1B\
+             AB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+        );
+        check_sign(
+            "0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710",
+            "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC\
+             97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+        );
+    }
+
+    // BIP-340 test vectors 15 to 18, messages that are not 32 bytes long
+    #[test]
+    fn bip340_message_lengths() {
+        const KEY: &str = "0340034003400340034003400340034003400340034003400340034003400340";
+        const PUBLIC_KEY: &str = "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117";
+        const AUX_RAND: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+        check_sign(
+            KEY,
+            PUBLIC_KEY,
+            AUX_RAND,
+            "",
+            "71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF\
+             6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63",
+        );
+        check_sign(
+            KEY,
+            PUBLIC_KEY,
+            AUX_RAND,
+            "11",
+            "08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303\
+             EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF",
+        );
+        check_sign(
+            KEY,
+            PUBLIC_KEY,
+            AUX_RAND,
+            "0102030405060708090A0B0C0D0E0F1011",
+            "5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370\
+             C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5",
+        );
+        check_sign(
+            KEY,
+            PUBLIC_KEY,
+            AUX_RAND,
+            &"99".repeat(100),
+            "403B12B0D8555A344175EA7EC746566303321E5DBFA8BE6F091635163ECA79A8\
+             585ED3E3170807E7C03B720FC54C7B23897FCBA0E9D0B4A06894CFD249F22367",
+        );
+    }
+
+    // BIP-340 test vectors 4 to 14
+    #[test]
+    fn bip340_verification() {
+        const PUBLIC_KEY: &str = "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659";
+        const MESSAGE: &str = "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89";
+        check_verify(
+            "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9",
+            "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+            "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C63\
+             76AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+            true,
+        );
+        for signature in [
+            // R has an odd y
+            "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A1460297556\
+             3CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+            // negated message
+            "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F\
+             28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD",
+            // negated s
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+             961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6",
+            // sG - eP is the point at infinity, with R.x = 0 or 1
+            "0000000000000000000000000000000000000000000000000000000000000000\
+             123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+            "0000000000000000000000000000000000000000000000000000000000000001\
+             7615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+            // R.x is not on the curve
+            "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D\
+             69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            // R.x is p, s is n
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F\
+             69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+             FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+        ] {
+            check_verify(PUBLIC_KEY, MESSAGE, signature, false);
+        }
+
+        // vector 5 is not on the curve, and vector 14 is above p
+        for public_key in [
+            "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+        ] {
+            assert_eq!(
+                XOnlyPublicKey::from_bytes(&array(public_key)),
+                Err(EcError::InvalidPoint)
+            );
+        }
+    }
+
+    #[test]
+    fn random_signatures() {
+        let mut rng = SeededRng::from_seed(b"bip340");
+        for i in 0..8u8 {
+            let key = SigningKey::generate(&mut rng);
+            let message = [i; 5];
+            let signature = key.sign(&message, &mut rng);
+            let public_key = XOnlyPublicKey::from_bytes(&key.public_key().to_bytes()).unwrap();
+            assert!(public_key.verify(&message, &signature).is_ok());
+            assert!(public_key.verify(&[i; 4], &signature).is_err());
+            let restored = SigningKey::from_bytes(&key.to_bytes()).unwrap();
+            assert_eq!(restored.public_key(), key.public_key());
+        }
+        assert_eq!(
+            SigningKey::from_bytes(&[0; 32]).map(|key| key.to_bytes()),
+            Err(EcError::InvalidSecretKey)
+        );
+    }
+}
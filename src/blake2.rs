@@ -0,0 +1,355 @@
+/**
+* This is an implementation of the BLAKE2b and BLAKE2s hash functions from RFC 7693.
+* Both support keyed hashing, a salt, a personalization string and any output length
+* up to their maximum (64 bytes for BLAKE2b, 32 bytes for BLAKE2s).
+* The two variants share one implementation that is instantiated for 64 and 32 bit words.
+*/
+use crate::hash::Hash;
+
+///Message word permutations used by each round
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2S_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+macro_rules! blake2_variant {
+    (
+        $(#[$doc:meta])*
+        $name:ident,
+        $word:ty,
+        $iv:expr,
+        $rounds:expr,
+        $rotations:expr,
+        $param_size:expr
+    ) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            state: [$word; 8],
+            buffer: [u8; Self::BLOCK_BYTES],
+            buffer_len: usize,
+            ///Number of message bytes compressed so far, used as the counter t
+            counter: u128,
+            output_size: usize,
+        }
+
+        impl $name {
+            const WORD_BYTES: usize = std::mem::size_of::<$word>();
+            const BLOCK_BYTES: usize = 16 * Self::WORD_BYTES;
+            ///Largest digest, key, and salt/personalization sizes
+            pub const MAX_OUTPUT_SIZE: usize = 8 * Self::WORD_BYTES;
+            pub const MAX_KEY_SIZE: usize = 8 * Self::WORD_BYTES;
+            pub const PARAM_SIZE: usize = $param_size;
+
+            /// Create a state with every parameter set.
+            ///
+            /// `salt` and `personalization` may be shorter than `PARAM_SIZE` and are padded with zeros.
+            /// Returns `None` if any of the lengths is out of range.
+            pub fn init_with(
+                output_size: usize,
+                key: &[u8],
+                salt: &[u8],
+                personalization: &[u8],
+            ) -> Option<$name> {
+                if output_size == 0
+                    || output_size > Self::MAX_OUTPUT_SIZE
+                    || key.len() > Self::MAX_KEY_SIZE
+                    || salt.len() > Self::PARAM_SIZE
+                    || personalization.len() > Self::PARAM_SIZE
+                {
+                    return None;
+                }
+
+                // the parameter block, fanout and depth are 1 for sequential hashing
+                let mut params = [0u8; 8 * Self::WORD_BYTES];
+                params[0] = output_size as u8;
+                params[1] = key.len() as u8;
+                params[2] = 1;
+                params[3] = 1;
+                let salt_offset = 8 * Self::WORD_BYTES - 2 * Self::PARAM_SIZE;
+                params[salt_offset..salt_offset + salt.len()].copy_from_slice(salt);
+                let personal_offset = salt_offset + Self::PARAM_SIZE;
+                params[personal_offset..personal_offset + personalization.len()]
+                    .copy_from_slice(personalization);
+
+                let mut state = $iv;
+                for (word, chunk) in state.iter_mut().zip(params.chunks_exact(Self::WORD_BYTES)) {
+                    *word ^= <$word>::from_le_bytes(chunk.try_into().unwrap());
+                }
+
+                let mut result = $name {
+                    state,
+                    buffer: [0; Self::BLOCK_BYTES],
+                    buffer_len: 0,
+                    counter: 0,
+                    output_size,
+                };
+
+                // a key is processed as a full first block of its own
+                if !key.is_empty() {
+                    let mut block = [0u8; Self::BLOCK_BYTES];
+                    block[..key.len()].copy_from_slice(key);
+                    result.update(&block);
+                }
+                Some(result)
+            }
+
+            /// Create an unkeyed state with the given output size in bytes
+            pub fn init_size(output_size: usize) -> Option<$name> {
+                Self::init_with(output_size, &[], &[], &[])
+            }
+
+            /// Create a keyed state (a MAC) with the given output size in bytes
+            pub fn init_keyed(output_size: usize, key: &[u8]) -> Option<$name> {
+                Self::init_with(output_size, key, &[], &[])
+            }
+
+            pub fn update(&mut self, mut data: &[u8]) {
+                // the last block has to be compressed with the final flag set, so a full
+                // buffer is only compressed once we know more data follows it
+                while !data.is_empty() {
+                    if self.buffer_len == Self::BLOCK_BYTES {
+                        self.counter += Self::BLOCK_BYTES as u128;
+                        let block = self.buffer;
+                        self.compress(&block, false);
+                        self.buffer_len = 0;
+                    }
+                    let take = std::cmp::min(Self::BLOCK_BYTES - self.buffer_len, data.len());
+                    self.buffer[self.buffer_len..self.buffer_len + take]
+                        .copy_from_slice(&data[..take]);
+                    self.buffer_len += take;
+                    data = &data[take..];
+                }
+            }
+
+            pub fn finalize(mut self) -> Vec<u8> {
+                self.counter += self.buffer_len as u128;
+                let mut block = self.buffer;
+                block[self.buffer_len..].fill(0);
+                self.compress(&block, true);
+
+                let mut output = Vec::with_capacity(Self::MAX_OUTPUT_SIZE);
+                for word in self.state {
+                    output.extend_from_slice(&word.to_le_bytes());
+                }
+                output.truncate(self.output_size);
+                output
+            }
+
+            fn compress(&mut self, block: &[u8; Self::BLOCK_BYTES], last: bool) {
+                let mut m = [0 as $word; 16];
+                for (word, chunk) in m.iter_mut().zip(block.chunks_exact(Self::WORD_BYTES)) {
+                    *word = <$word>::from_le_bytes(chunk.try_into().unwrap());
+                }
+
+                let mut v = [0 as $word; 16];
+                v[..8].copy_from_slice(&self.state);
+                v[8..].copy_from_slice(&$iv);
+                v[12] ^= self.counter as $word;
+                v[13] ^= (self.counter >> (8 * Self::WORD_BYTES)) as $word;
+                if last {
+                    v[14] = !v[14];
+                }
+
+                let [r1, r2, r3, r4]: [u32; 4] = $rotations;
+                let g = |v: &mut [$word; 16], a: usize, b: usize, c: usize, d: usize, x: $word, y: $word| {
+                    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+                    v[d] = (v[d] ^ v[a]).rotate_right(r1);
+                    v[c] = v[c].wrapping_add(v[d]);
+                    v[b] = (v[b] ^ v[c]).rotate_right(r2);
+                    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+                    v[d] = (v[d] ^ v[a]).rotate_right(r3);
+                    v[c] = v[c].wrapping_add(v[d]);
+                    v[b] = (v[b] ^ v[c]).rotate_right(r4);
+                };
+
+                for round in 0..$rounds {
+                    let s = &SIGMA[round % 10];
+                    g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+                    g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+                    g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+                    g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+                    g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+                    g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+                    g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+                    g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+                }
+
+                for i in 0..8 {
+                    self.state[i] ^= v[i] ^ v[i + 8];
+                }
+            }
+        }
+    };
+}
+
+blake2_variant!(
+    /// BLAKE2b, for 64 bit platforms, with outputs of 1 to 64 bytes
+    Blake2b,
+    u64,
+    BLAKE2B_IV,
+    12,
+    [32, 24, 16, 63],
+    16
+);
+blake2_variant!(
+    /// BLAKE2s, for 8 to 32 bit platforms, with outputs of 1 to 32 bytes
+    Blake2s,
+    u32,
+    BLAKE2S_IV,
+    10,
+    [16, 12, 8, 7],
+    8
+);
+
+/// Unkeyed BLAKE2b with the full 64 byte digest
+#[derive(Clone)]
+pub struct Blake2b512 {
+    inner: Blake2b,
+}
+
+impl Hash for Blake2b512 {
+    const BLOCK_SIZE: usize = 128;
+    const OUTPUT_SIZE: usize = 64;
+
+    fn init() -> Self {
+        Blake2b512 {
+            inner: Blake2b::init_size(Self::OUTPUT_SIZE).unwrap(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.inner.finalize()
+    }
+}
+
+/// Unkeyed BLAKE2s with the full 32 byte digest
+#[derive(Clone)]
+pub struct Blake2s256 {
+    inner: Blake2s,
+}
+
+impl Hash for Blake2s256 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 32;
+
+    fn init() -> Self {
+        Blake2s256 {
+            inner: Blake2s::init_size(Self::OUTPUT_SIZE).unwrap(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.inner.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blake2;
+    use crate::hash::Hash;
+    use crate::test_util::hex;
+
+    #[test]
+    fn blake2b_abc() {
+        let test_result = hex(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+        );
+        assert_eq!(blake2::Blake2b512::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn blake2s_abc() {
+        let test_result = hex("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982");
+        assert_eq!(blake2::Blake2s256::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn blake2b_exact_block_is_not_compressed_early() {
+        let test_result = hex(
+            "865939e120e6805438478841afb739ae4250cf372653078a065cdcfffca4caf7\
+             98e6d462b65d658fc165782640eded70963449ae1500fb0f24981d7727e22c41",
+        );
+        assert_eq!(blake2::Blake2b512::digest(&[0; 128]), test_result);
+    }
+
+    #[test]
+    fn blake2b_keyed() {
+        let test_result = hex(
+            "142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e9248\
+             4be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461",
+        );
+        let key: Vec<u8> = (0..64).collect();
+        let mut state = blake2::Blake2b::init_keyed(64, &key).unwrap();
+        for chunk in (0..255).collect::<Vec<u8>>().chunks(50) {
+            state.update(chunk);
+        }
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn blake2s_keyed() {
+        let test_result = hex("3fb735061abc519dfe979e54c1ee5bfad0a9d858b3315bad34bde999efd724dd");
+        let key: Vec<u8> = (0..32).collect();
+        let mut state = blake2::Blake2s::init_keyed(32, &key).unwrap();
+        state.update(&(0..255).collect::<Vec<u8>>());
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn blake2b_salt_and_personalization() {
+        let test_result = hex("72c728f4696779842ae3343b2bbb15710b04a1da");
+        let mut state =
+            blake2::Blake2b::init_with(20, b"", b"0123456789abcdef", b"my-app-v1").unwrap();
+        state.update(b"hello world");
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn blake2s_all_parameters() {
+        let test_result = hex("a1177a8ed2623b7ed362ba2987e33f1b");
+        let mut state = blake2::Blake2s::init_with(16, b"secret", b"saltsalt", b"ctx").unwrap();
+        state.update(b"hello world");
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(blake2::Blake2b::init_size(0).is_none());
+        assert!(blake2::Blake2b::init_size(65).is_none());
+        assert!(blake2::Blake2s::init_keyed(32, &[0; 33]).is_none());
+        assert!(blake2::Blake2s::init_with(32, b"", &[0; 9], b"").is_none());
+    }
+}
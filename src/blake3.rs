@@ -0,0 +1,669 @@
+/**
+* This is an implementation of the BLAKE3 hash function.
+* It supports the three BLAKE3 modes (hash, keyed hash and derive key) and extendable output.
+* The input is split into 1 KiB chunks that form the leaves of a binary tree, so large inputs
+* can be hashed in parallel: `update_parallel` spreads whole subtrees over threads using std only.
+*/
+use crate::hash::Hash;
+
+const OUT_LEN: usize = 32;
+pub const KEY_LEN: usize = 32;
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+
+///Domain flags mixed into every compression
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+///Permutation applied to the message words between rounds
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+///Subtrees smaller than this many chunks are not worth a thread of their own
+const MIN_PARALLEL_CHUNKS: usize = 16;
+
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn round(state: &mut [u32; 16], m: &[u32; 16]) {
+    // columns
+    g(state, 0, 4, 8, 12, m[0], m[1]);
+    g(state, 1, 5, 9, 13, m[2], m[3]);
+    g(state, 2, 6, 10, 14, m[4], m[5]);
+    g(state, 3, 7, 11, 15, m[6], m[7]);
+    // diagonals
+    g(state, 0, 5, 10, 15, m[8], m[9]);
+    g(state, 1, 6, 11, 12, m[10], m[11]);
+    g(state, 2, 7, 8, 13, m[12], m[13]);
+    g(state, 3, 4, 9, 14, m[14], m[15]);
+}
+
+/// The BLAKE3 compression function, returns the full 16 word state
+fn compress(
+    chaining_value: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [
+        chaining_value[0],
+        chaining_value[1],
+        chaining_value[2],
+        chaining_value[3],
+        chaining_value[4],
+        chaining_value[5],
+        chaining_value[6],
+        chaining_value[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut block = *block_words;
+
+    for i in 0..7 {
+        round(&mut state, &block);
+        if i < 6 {
+            block = MSG_PERMUTATION.map(|index| block[index]);
+        }
+    }
+
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+    state
+}
+
+fn first_8_words(compression_output: [u32; 16]) -> [u32; 8] {
+    compression_output[..8].try_into().unwrap()
+}
+
+fn words_from_le_bytes<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+/// The inputs to a compression that has not been done yet. Each chunk and parent node produces one,
+/// and it is either turned into a chaining value or, for the root, into output bytes.
+#[derive(Clone)]
+struct Output {
+    input_chaining_value: [u32; 8],
+    block_words: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8_words(compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            self.counter,
+            self.block_len,
+            self.flags,
+        ))
+    }
+
+    /// Fill `output` with the root output stream starting `position` bytes in
+    fn root_output_bytes(&self, position: u64, output: &mut [u8]) {
+        let mut block_counter = position / (2 * OUT_LEN as u64);
+        let mut skip = (position % (2 * OUT_LEN as u64)) as usize;
+        let mut written = 0;
+        while written < output.len() {
+            let words = compress(
+                &self.input_chaining_value,
+                &self.block_words,
+                block_counter,
+                self.block_len,
+                self.flags | ROOT,
+            );
+            let mut block = [0u8; 2 * OUT_LEN];
+            for (chunk, word) in block.chunks_exact_mut(4).zip(words) {
+                chunk.copy_from_slice(&word.to_le_bytes());
+            }
+            let take = std::cmp::min(block.len() - skip, output.len() - written);
+            output[written..written + take].copy_from_slice(&block[skip..skip + take]);
+            written += take;
+            skip = 0;
+            block_counter += 1;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ChunkState {
+    chaining_value: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn init(key_words: [u32; 8], chunk_counter: u64, flags: u32) -> ChunkState {
+        ChunkState {
+            chaining_value: key_words,
+            chunk_counter,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            // a full block is only compressed once more input arrives, the last one needs CHUNK_END
+            if self.block_len == BLOCK_LEN {
+                let block_words = words_from_le_bytes(&self.block);
+                self.chaining_value = first_8_words(compress(
+                    &self.chaining_value,
+                    &block_words,
+                    self.chunk_counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                ));
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+            }
+
+            let take = std::cmp::min(BLOCK_LEN - self.block_len, input.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&input[..take]);
+            self.block_len += take;
+            input = &input[take..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            input_chaining_value: self.chaining_value,
+            block_words: words_from_le_bytes(&self.block),
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+fn parent_output(
+    left_child_cv: [u32; 8],
+    right_child_cv: [u32; 8],
+    key_words: [u32; 8],
+    flags: u32,
+) -> Output {
+    let mut block_words = [0; 16];
+    block_words[..8].copy_from_slice(&left_child_cv);
+    block_words[8..].copy_from_slice(&right_child_cv);
+    Output {
+        input_chaining_value: key_words,
+        block_words,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT | flags,
+    }
+}
+
+/// Chaining value of a complete, non-root subtree whose length is a power of two chunks,
+/// splitting the two halves over threads while `threads` allows it
+fn subtree_chaining_value(
+    input: &[u8],
+    key_words: [u32; 8],
+    chunk_counter: u64,
+    flags: u32,
+    threads: usize,
+) -> [u32; 8] {
+    if input.len() <= CHUNK_LEN {
+        let mut chunk = ChunkState::init(key_words, chunk_counter, flags);
+        chunk.update(input);
+        return chunk.output().chaining_value();
+    }
+
+    let half = input.len() / 2;
+    let right_counter = chunk_counter + (half / CHUNK_LEN) as u64;
+    let (left, right) = if threads > 1 && input.len() >= MIN_PARALLEL_CHUNKS * CHUNK_LEN {
+        std::thread::scope(|scope| {
+            let right_handle = scope.spawn(|| {
+                subtree_chaining_value(&input[half..], key_words, right_counter, flags, threads / 2)
+            });
+            let left = subtree_chaining_value(
+                &input[..half],
+                key_words,
+                chunk_counter,
+                flags,
+                threads - threads / 2,
+            );
+            (left, right_handle.join().unwrap())
+        })
+    } else {
+        (
+            subtree_chaining_value(&input[..half], key_words, chunk_counter, flags, 1),
+            subtree_chaining_value(&input[half..], key_words, right_counter, flags, 1),
+        )
+    };
+    parent_output(left, right, key_words, flags).chaining_value()
+}
+
+/// An incremental BLAKE3 hasher
+#[derive(Clone)]
+pub struct Blake3 {
+    chunk_state: ChunkState,
+    key_words: [u32; 8],
+    ///Chaining values of completed subtrees that still wait for a right sibling
+    cv_stack: Vec<[u32; 8]>,
+    flags: u32,
+}
+
+impl Blake3 {
+    fn init_internal(key_words: [u32; 8], flags: u32) -> Blake3 {
+        Blake3 {
+            chunk_state: ChunkState::init(key_words, 0, flags),
+            key_words,
+            cv_stack: Vec::new(),
+            flags,
+        }
+    }
+
+    /// A hasher for the regular hash mode
+    pub fn init() -> Blake3 {
+        Self::init_internal(IV, 0)
+    }
+
+    /// A hasher for the keyed hash mode, a MAC with a 32 byte key
+    pub fn init_keyed(key: &[u8; KEY_LEN]) -> Blake3 {
+        Self::init_internal(words_from_le_bytes(key), KEYED_HASH)
+    }
+
+    /// A hasher for the key derivation mode. The context string should be hardcoded, globally
+    /// unique and application specific, the key material is then fed in with `update`.
+    pub fn init_derive_key(context: &str) -> Blake3 {
+        let mut context_hasher = Self::init_internal(IV, DERIVE_KEY_CONTEXT);
+        context_hasher.update(context.as_bytes());
+        let mut context_key = [0u8; KEY_LEN];
+        context_hasher.finalize_xof().squeeze(&mut context_key);
+        Self::init_internal(words_from_le_bytes(&context_key), DERIVE_KEY_MATERIAL)
+    }
+
+    /// Record the chaining value of a finished chunk or subtree that starts at `chunk_counter`.
+    /// Everything on the stack that is complete at that point gets merged first, and the new value
+    /// is left unmerged since it may turn out to be the root.
+    fn push_cv(&mut self, cv: [u32; 8], chunk_counter: u64) {
+        self.merge_cv_stack(chunk_counter);
+        self.cv_stack.push(cv);
+    }
+
+    /// Merge stack entries into parents until it holds one entry per complete subtree
+    /// in the first `total_chunks` chunks
+    fn merge_cv_stack(&mut self, total_chunks: u64) {
+        while self.cv_stack.len() > total_chunks.count_ones() as usize {
+            let right = self.cv_stack.pop().unwrap();
+            let left = self.cv_stack.pop().unwrap();
+            let parent = parent_output(left, right, self.key_words, self.flags).chaining_value();
+            self.cv_stack.push(parent);
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.update_parallel(input, 1);
+    }
+
+    /// Absorb `input`, hashing whole subtrees of it on up to `threads` threads.
+    /// The result is identical to calling `update`.
+    pub fn update_parallel(&mut self, mut input: &[u8], threads: usize) {
+        // finish the chunk in progress, it is only closed once more input follows it
+        if self.chunk_state.len() > 0 {
+            let take = std::cmp::min(CHUNK_LEN - self.chunk_state.len(), input.len());
+            self.chunk_state.update(&input[..take]);
+            input = &input[take..];
+            if input.is_empty() {
+                return;
+            }
+            let cv = self.chunk_state.output().chaining_value();
+            let counter = self.chunk_state.chunk_counter;
+            self.push_cv(cv, counter);
+            self.chunk_state = ChunkState::init(self.key_words, counter + 1, self.flags);
+        }
+
+        // hash the largest aligned power of two subtrees while more than a chunk remains
+        while input.len() > CHUNK_LEN {
+            let counter = self.chunk_state.chunk_counter;
+            let mut subtree_chunks = 1usize << (usize::BITS - 1 - input.len().leading_zeros());
+            subtree_chunks /= CHUNK_LEN;
+            while (subtree_chunks as u64 - 1) & counter != 0 {
+                subtree_chunks /= 2;
+            }
+            let subtree_len = subtree_chunks * CHUNK_LEN;
+            let subtree = &input[..subtree_len];
+
+            if subtree_chunks == 1 {
+                let cv =
+                    subtree_chaining_value(subtree, self.key_words, counter, self.flags, threads);
+                self.push_cv(cv, counter);
+            } else {
+                // push both halves rather than their parent, if this is the last of the input
+                // the parent is the root and needs the ROOT flag
+                let half = subtree_len / 2;
+                let right_counter = counter + (subtree_chunks / 2) as u64;
+                let (left, right) = std::thread::scope(|scope| {
+                    let right_handle = (threads > 1).then(|| {
+                        scope.spawn(|| {
+                            subtree_chaining_value(
+                                &subtree[half..],
+                                self.key_words,
+                                right_counter,
+                                self.flags,
+                                threads / 2,
+                            )
+                        })
+                    });
+                    let left = subtree_chaining_value(
+                        &subtree[..half],
+                        self.key_words,
+                        counter,
+                        self.flags,
+                        threads - threads / 2,
+                    );
+                    let right = match right_handle {
+                        Some(handle) => handle.join().unwrap(),
+                        None => subtree_chaining_value(
+                            &subtree[half..],
+                            self.key_words,
+                            right_counter,
+                            self.flags,
+                            1,
+                        ),
+                    };
+                    (left, right)
+                });
+                self.push_cv(left, counter);
+                self.push_cv(right, right_counter);
+            }
+
+            self.chunk_state =
+                ChunkState::init(self.key_words, counter + subtree_chunks as u64, self.flags);
+            input = &input[subtree_len..];
+        }
+
+        if !input.is_empty() {
+            self.chunk_state.update(input);
+            self.merge_cv_stack(self.chunk_state.chunk_counter);
+        }
+    }
+
+    fn root_output(&self) -> Output {
+        let (mut output, mut remaining) = if self.chunk_state.len() > 0 || self.cv_stack.is_empty()
+        {
+            (self.chunk_state.output(), self.cv_stack.len())
+        } else {
+            // the input ended on a subtree boundary, its two halves are on top of the stack
+            let len = self.cv_stack.len();
+            (
+                parent_output(
+                    self.cv_stack[len - 2],
+                    self.cv_stack[len - 1],
+                    self.key_words,
+                    self.flags,
+                ),
+                len - 2,
+            )
+        };
+        while remaining > 0 {
+            remaining -= 1;
+            output = parent_output(
+                self.cv_stack[remaining],
+                output.chaining_value(),
+                self.key_words,
+                self.flags,
+            );
+        }
+        output
+    }
+
+    /// The default 32 byte digest
+    pub fn finalize(&self) -> [u8; OUT_LEN] {
+        let mut output = [0; OUT_LEN];
+        self.root_output().root_output_bytes(0, &mut output);
+        output
+    }
+
+    /// Return a reader for the extendable output, the first 32 bytes are the digest
+    pub fn finalize_xof(&self) -> Blake3Reader {
+        Blake3Reader {
+            output: self.root_output(),
+            position: 0,
+        }
+    }
+}
+
+/// Reader for the BLAKE3 extendable output, it can be read sequentially or seeked
+pub struct Blake3Reader {
+    output: Output,
+    position: u64,
+}
+
+impl Blake3Reader {
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        self.output.root_output_bytes(self.position, output);
+        self.position += output.len() as u64;
+    }
+
+    /// Move to an absolute byte offset in the output stream
+    pub fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+}
+
+impl Hash for Blake3 {
+    const BLOCK_SIZE: usize = BLOCK_LEN;
+    const OUTPUT_SIZE: usize = OUT_LEN;
+
+    fn init() -> Self {
+        Blake3::init()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Blake3::update(self, data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Blake3::finalize(&self).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blake3;
+    use crate::test_util::hex;
+
+    const DERIVE_KEY_CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    /// The input used by the official test vectors, a repeating 0..251 byte pattern
+    fn test_input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn hash(input: &[u8]) -> Vec<u8> {
+        let mut state = blake3::Blake3::init();
+        state.update(input);
+        state.finalize().to_vec()
+    }
+
+    #[test]
+    fn empty() {
+        let test_result = hex("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+        assert_eq!(hash(b""), test_result);
+    }
+
+    #[test]
+    fn abc() {
+        let test_result = hex("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85");
+        assert_eq!(hash(b"abc"), test_result);
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let cases = [
+            (
+                1023,
+                "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11",
+            ),
+            (
+                1024,
+                "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7",
+            ),
+            (
+                1025,
+                "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+            ),
+            (
+                2048,
+                "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a",
+            ),
+            (
+                2049,
+                "5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b6879522563030",
+            ),
+            (
+                8193,
+                "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b",
+            ),
+            (
+                31744,
+                "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c47",
+            ),
+            (
+                102400,
+                "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085",
+            ),
+        ];
+        for (len, expected) in cases {
+            assert_eq!(hash(&test_input(len)), hex(expected), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn byte_at_a_time_matches_one_shot() {
+        let input = test_input(5000);
+        let mut state = blake3::Blake3::init();
+        for byte in input.iter() {
+            state.update(&[*byte]);
+        }
+        assert_eq!(state.finalize().to_vec(), hash(&input));
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let test_result = hex("d93c23eedaf165a7e0be908ba86f1a7a520d568d2d13cde787c8580c5c72cc54");
+        let input = test_input(100000);
+        for threads in [1, 2, 3, 8] {
+            let mut state = blake3::Blake3::init();
+            state.update(&input[..1500]);
+            state.update_parallel(&input[1500..], threads);
+            assert_eq!(
+                state.finalize().to_vec(),
+                test_result,
+                "{} threads",
+                threads
+            );
+        }
+
+        let mut state = blake3::Blake3::init();
+        state.update_parallel(&test_input(32768 + 5), 4);
+        assert_eq!(
+            state.finalize().to_vec(),
+            hex("19c945c8775a72749bebdc07c0c1014f3c10dc01ba11865033b1a7a0d645b916")
+        );
+    }
+
+    #[test]
+    fn extended_output_and_seek() {
+        let test_result = hex(
+            "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444\
+             f4c4a22b4b399155358a994e52bf255de60035742ec71bd08ac275a1b51cc6bf\
+             e332b0ef84b409108cda080e6269ed4b3e2c3f7d722aa4cdc98d16deb554e562\
+             7be8f955c98e1d5f9565a9194cad0c4285f93700062d9595adb992ae68ff1280\
+             0ab67a",
+        );
+        let mut state = blake3::Blake3::init();
+        state.update(&test_input(1025));
+        let mut reader = state.finalize_xof();
+        let mut output = [0u8; 131];
+        for chunk in output.chunks_mut(10) {
+            reader.squeeze(chunk);
+        }
+        assert_eq!(output.to_vec(), test_result);
+
+        let mut tail = [0u8; 31];
+        reader.seek(100);
+        reader.squeeze(&mut tail);
+        assert_eq!(tail.to_vec(), test_result[100..]);
+    }
+
+    #[test]
+    fn keyed_hash() {
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let mut state = blake3::Blake3::init_keyed(&key);
+        assert_eq!(
+            state.finalize().to_vec(),
+            hex("73492b19995d71cdb1e9d74decc09809eb732f1b00bc95c27cb15f9dd4d6478f")
+        );
+        state.update(&test_input(2049));
+        assert_eq!(
+            state.finalize().to_vec(),
+            hex("5442eec85e3fd173dcff07c39cd8cff9689f17224471e655618ed728cf03b056")
+        );
+    }
+
+    #[test]
+    fn derive_key() {
+        let mut state = blake3::Blake3::init_derive_key(DERIVE_KEY_CONTEXT);
+        assert_eq!(
+            state.finalize().to_vec(),
+            hex("2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d")
+        );
+        state.update(&test_input(1025));
+        assert_eq!(
+            state.finalize().to_vec(),
+            hex("effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb")
+        );
+    }
+}
@@ -0,0 +1,307 @@
+/**
+* This is an implementation of the ChaCha20 stream cipher from RFC 8439, with a 256 bit key,
+* a 96 bit nonce and a 32 bit block counter. HChaCha20 derives a subkey from a key and the first
+* 128 bits of a nonce, which XChaCha20 uses to extend the nonce to 192 bits so nonces can be random.
+*/
+use crate::stream_cipher::StreamCipher;
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const XNONCE_SIZE: usize = 24;
+const BLOCK_SIZE: usize = 64;
+
+///"expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+/// The 20 rounds, as 10 pairs of a column round and a diagonal round
+fn rounds(state: &[u32; 16]) -> [u32; 16] {
+    let mut x = *state;
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    x
+}
+
+/// The initial state: constants, key, then the 16 bytes of counter and nonce
+fn initial_state(key: &[u8; KEY_SIZE], input: &[u8; 16]) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&CONSTANTS);
+    for (word, bytes) in state[4..12].iter_mut().zip(key.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    for (word, bytes) in state[12..].iter_mut().zip(input.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    state
+}
+
+/// The ChaCha20 block function, 64 bytes of keystream for one counter value
+pub fn chacha20_block(key: &[u8; KEY_SIZE], counter: u32, nonce: &[u8; NONCE_SIZE]) -> [u8; 64] {
+    let mut input = [0u8; 16];
+    input[..4].copy_from_slice(&counter.to_le_bytes());
+    input[4..].copy_from_slice(nonce);
+    let state = initial_state(key, &input);
+    serialize_block(&state)
+}
+
+fn serialize_block(state: &[u32; 16]) -> [u8; 64] {
+    let mixed = rounds(state);
+    let mut output = [0u8; BLOCK_SIZE];
+    for ((bytes, word), initial) in output.chunks_exact_mut(4).zip(mixed).zip(state) {
+        bytes.copy_from_slice(&word.wrapping_add(*initial).to_le_bytes());
+    }
+    output
+}
+
+/// HChaCha20, the first and last rows of the rounds without the final addition
+pub fn hchacha20(key: &[u8; KEY_SIZE], nonce: &[u8; 16]) -> [u8; KEY_SIZE] {
+    let mixed = rounds(&initial_state(key, nonce));
+    let mut output = [0u8; KEY_SIZE];
+    for (bytes, word) in output
+        .chunks_exact_mut(4)
+        .zip(mixed[..4].iter().chain(&mixed[12..]))
+    {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    output
+}
+
+#[derive(Clone)]
+pub struct ChaCha20 {
+    state: [u32; 16],
+    keystream: [u8; BLOCK_SIZE],
+    ///The block counter of `keystream`, if it has been generated
+    buffered_block: Option<u64>,
+    position: u64,
+}
+
+impl ChaCha20 {
+    /// Start the keystream at block counter 0
+    pub fn new(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE]) -> ChaCha20 {
+        let mut input = [0u8; 16];
+        input[4..].copy_from_slice(nonce);
+        ChaCha20 {
+            state: initial_state(key, &input),
+            keystream: [0; BLOCK_SIZE],
+            buffered_block: None,
+            position: 0,
+        }
+    }
+}
+
+impl StreamCipher for ChaCha20 {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        let mut done = 0;
+        while done < data.len() {
+            let block = self.position / BLOCK_SIZE as u64;
+            if self.buffered_block != Some(block) {
+                assert!(
+                    block <= u32::MAX as u64,
+                    "ChaCha20 keystream exhausted, the block counter is 32 bits"
+                );
+                self.state[12] = block as u32;
+                self.keystream = serialize_block(&self.state);
+                self.buffered_block = Some(block);
+            }
+
+            let offset = (self.position % BLOCK_SIZE as u64) as usize;
+            let len = (data.len() - done).min(BLOCK_SIZE - offset);
+            for (byte, key_byte) in data[done..done + len]
+                .iter_mut()
+                .zip(&self.keystream[offset..])
+            {
+                *byte ^= key_byte;
+            }
+            self.position += len as u64;
+            done += len;
+        }
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+/// ChaCha20 with a 192 bit nonce, keyed with the HChaCha20 subkey of the first 16 nonce bytes
+#[derive(Clone)]
+pub struct XChaCha20 {
+    inner: ChaCha20,
+}
+
+impl XChaCha20 {
+    pub fn new(key: &[u8; KEY_SIZE], nonce: &[u8; XNONCE_SIZE]) -> XChaCha20 {
+        let subkey = hchacha20(key, nonce[..16].try_into().unwrap());
+        let mut inner_nonce = [0u8; NONCE_SIZE];
+        inner_nonce[4..].copy_from_slice(&nonce[16..]);
+        XChaCha20 {
+            inner: ChaCha20::new(&subkey, &inner_nonce),
+        }
+    }
+}
+
+impl StreamCipher for XChaCha20 {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        self.inner.apply_keystream(data)
+    }
+
+    fn seek(&mut self, position: u64) {
+        self.inner.seek(position)
+    }
+
+    fn position(&self) -> u64 {
+        self.inner.position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chacha20::{self, ChaCha20, XChaCha20};
+    use crate::stream_cipher::StreamCipher;
+    use crate::test_util::hex;
+
+    fn rfc_key() -> [u8; 32] {
+        hex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")
+            .try_into()
+            .unwrap()
+    }
+
+    const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+    #[test]
+    fn rfc8439_block_function() {
+        let nonce = hex("000000090000004a00000000").try_into().unwrap();
+        let test_result = hex(
+            "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e\
+             d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e",
+        );
+        assert_eq!(
+            chacha20::chacha20_block(&rfc_key(), 1, &nonce).to_vec(),
+            test_result
+        );
+    }
+
+    #[test]
+    fn rfc8439_encryption() {
+        let nonce = hex("000000000000004a00000000").try_into().unwrap();
+        let mut cipher = ChaCha20::new(&rfc_key(), &nonce);
+        cipher.seek(64);
+        let mut data = SUNSCREEN.to_vec();
+        cipher.apply_keystream(&mut data);
+        let test_result = hex(
+            "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b\
+             f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8\
+             07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736\
+             5af90bbf74a35be6b40b8eedf2785e42874d",
+        );
+        assert_eq!(data, test_result);
+        assert_eq!(cipher.position(), 64 + SUNSCREEN.len() as u64);
+
+        // decrypting is the same operation
+        cipher.seek(64);
+        cipher.apply_keystream(&mut data);
+        assert_eq!(data, SUNSCREEN);
+    }
+
+    #[test]
+    fn pieces_match_one_pass() {
+        let nonce = [7u8; 12];
+        let mut whole = vec![0u8; 300];
+        ChaCha20::new(&rfc_key(), &nonce).apply_keystream(&mut whole);
+
+        let mut pieces = vec![0u8; 300];
+        let mut cipher = ChaCha20::new(&rfc_key(), &nonce);
+        let mut start = 0;
+        for len in [1, 63, 64, 65, 0, 107] {
+            cipher.apply_keystream(&mut pieces[start..start + len]);
+            start += len;
+        }
+        assert_eq!(pieces, whole);
+
+        let mut tail = vec![0u8; 100];
+        cipher.seek(200);
+        cipher.apply_keystream(&mut tail);
+        assert_eq!(tail, whole[200..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn counter_overflow_panics() {
+        let mut cipher = ChaCha20::new(&rfc_key(), &[0; 12]);
+        cipher.seek((u32::MAX as u64) * 64 + 60);
+        cipher.apply_keystream(&mut [0u8; 8]);
+    }
+
+    #[test]
+    fn hchacha20_vectors() {
+        // draft-irtf-cfrg-xchacha section 2.2.1
+        let nonce = hex("000000090000004a0000000031415927").try_into().unwrap();
+        assert_eq!(
+            chacha20::hchacha20(&rfc_key(), &nonce).to_vec(),
+            hex("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc")
+        );
+
+        // libsodium test vector
+        let key = hex("24f11cce8a1b3d61e441561a696c1c1b7e173d084fd4812425435a8896a013dc");
+        let nonce = hex("d9660c5900ae19ddad28d6e06e45fe5e").try_into().unwrap();
+        assert_eq!(
+            chacha20::hchacha20(&key.try_into().unwrap(), &nonce).to_vec(),
+            hex("5966b3eec3bff1189f831f06afe4d4e3be97fa9235ec8c20d08acfbbb4e851e3")
+        );
+    }
+
+    #[test]
+    fn xchacha20_keystream() {
+        // libsodium test vectors
+        let cases = [
+            (
+                "79c99798ac67300bbb2704c95c341e3245f3dcb21761b98e52ff45b24f304fc4",
+                "b33ffd3096479bcfbc9aee49417688a0a2554f8d95389419",
+                "c6e9758160083ac604ef90e712ce6e75d7797590744e0cf060f013739c",
+            ),
+            (
+                "5f5763ff9a30c95da5c9f2a8dfd7cc6efd9dfb431812c075aa3e4f32e04f53e4",
+                "a5fa890efa3b9a034d377926ce0e08ee6d7faccaee41b771",
+                "8a1a5ba898bdbcff602b1036e469a18a5e45789d0e8d9837d81a2388a52b0b6a\
+                 0f51891528f424c4a7f492a8dd7bce8bac19fbdbe1fb379ac0",
+            ),
+            (
+                "9d23bd4149cb979ccf3c5c94dd217e9808cb0e50cd0f67812235eaaf601d6232",
+                "c047548266b7c370d33566a2425cbf30d82d1eaf5294109e",
+                "a21209096594de8c5667b1d13ad93f744106d054df210e4782cd396fec692d35\
+                 15a20bf351eec011a92c367888bc464c32f0807acd6c203a247e0db854148468\
+                 e9f96bee4cf718d68d5f637cbd5a376457788e6fae90fc31097cfc",
+            ),
+        ];
+        for (key, nonce, keystream) in cases {
+            let mut cipher = XChaCha20::new(
+                &hex(key).try_into().unwrap(),
+                &hex(nonce).try_into().unwrap(),
+            );
+            let mut output = vec![0u8; hex(keystream).len()];
+            cipher.apply_keystream(&mut output);
+            assert_eq!(output, hex(keystream));
+        }
+    }
+}
@@ -0,0 +1,256 @@
+/**
+* This is an implementation of the ChaCha20-Poly1305 AEAD from RFC 8439, and of XChaCha20-Poly1305
+* (draft-irtf-cfrg-xchacha) which takes a 192 bit nonce that is safe to choose at random.
+* The Poly1305 key is the first 32 bytes of keystream block 0, the data is encrypted from block 1,
+* and the tag covers the padded associated data, the padded ciphertext and both lengths.
+*/
+use crate::aead::{Aead, AeadError};
+use crate::chacha20::{self, ChaCha20, XChaCha20};
+use crate::constant_time;
+use crate::poly1305::{self, Poly1305};
+use crate::stream_cipher::StreamCipher;
+
+pub const TAG_SIZE: usize = poly1305::TAG_SIZE;
+///Longest message that can be sealed under one nonce, the rest of the 32 bit block counter
+pub const MAX_PLAINTEXT_SIZE: u64 = (1 << 38) - 64;
+
+/// The tag of the ciphertext `data`, with the cipher positioned at the start of its keystream
+fn compute_tag(cipher: &mut impl StreamCipher, aad: &[u8], data: &[u8]) -> [u8; TAG_SIZE] {
+    let mut poly_key = [0u8; poly1305::KEY_SIZE];
+    cipher.seek(0);
+    cipher.apply_keystream(&mut poly_key);
+
+    let mut poly = Poly1305::new(&poly_key);
+    poly.update_padded(aad);
+    poly.update_padded(data);
+    poly.update(&(aad.len() as u64).to_le_bytes());
+    poly.update(&(data.len() as u64).to_le_bytes());
+    poly.finalize()
+}
+
+fn seal_in_place(
+    mut cipher: impl StreamCipher,
+    aad: &[u8],
+    data: &mut [u8],
+) -> Result<Vec<u8>, AeadError> {
+    if data.len() as u64 > MAX_PLAINTEXT_SIZE {
+        return Err(AeadError);
+    }
+    cipher.seek(64);
+    cipher.apply_keystream(data);
+    Ok(compute_tag(&mut cipher, aad, data).to_vec())
+}
+
+fn open_in_place(
+    mut cipher: impl StreamCipher,
+    aad: &[u8],
+    data: &mut [u8],
+    tag: &[u8],
+) -> Result<(), AeadError> {
+    if data.len() as u64 > MAX_PLAINTEXT_SIZE {
+        return Err(AeadError);
+    }
+    if !constant_time::eq(&compute_tag(&mut cipher, aad, data), tag) {
+        return Err(AeadError);
+    }
+    cipher.seek(64);
+    cipher.apply_keystream(data);
+    Ok(())
+}
+
+/// ChaCha20-Poly1305 with a 96 bit nonce, which must never repeat under one key
+#[derive(Clone)]
+pub struct ChaCha20Poly1305 {
+    key: [u8; chacha20::KEY_SIZE],
+}
+
+impl ChaCha20Poly1305 {
+    fn cipher(&self, nonce: &[u8]) -> Result<ChaCha20, AeadError> {
+        let nonce = nonce.try_into().map_err(|_| AeadError)?;
+        Ok(ChaCha20::new(&self.key, nonce))
+    }
+}
+
+impl Aead for ChaCha20Poly1305 {
+    const KEY_SIZE: usize = chacha20::KEY_SIZE;
+    const NONCE_SIZE: usize = chacha20::NONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        Ok(ChaCha20Poly1305 {
+            key: key.try_into().map_err(|_| AeadError)?,
+        })
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        seal_in_place(self.cipher(nonce)?, aad, data)
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        open_in_place(self.cipher(nonce)?, aad, data, tag)
+    }
+}
+
+/// XChaCha20-Poly1305 with a 192 bit nonce, long enough to be generated at random
+#[derive(Clone)]
+pub struct XChaCha20Poly1305 {
+    key: [u8; chacha20::KEY_SIZE],
+}
+
+impl XChaCha20Poly1305 {
+    fn cipher(&self, nonce: &[u8]) -> Result<XChaCha20, AeadError> {
+        let nonce = nonce.try_into().map_err(|_| AeadError)?;
+        Ok(XChaCha20::new(&self.key, nonce))
+    }
+}
+
+impl Aead for XChaCha20Poly1305 {
+    const KEY_SIZE: usize = chacha20::KEY_SIZE;
+    const NONCE_SIZE: usize = chacha20::XNONCE_SIZE;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn new(key: &[u8]) -> Result<Self, AeadError> {
+        Ok(XChaCha20Poly1305 {
+            key: key.try_into().map_err(|_| AeadError)?,
+        })
+    }
+
+    fn seal_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        seal_in_place(self.cipher(nonce)?, aad, data)
+    }
+
+    fn open_in_place_detached(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), AeadError> {
+        open_in_place(self.cipher(nonce)?, aad, data, tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::{Aead, AeadError};
+    use crate::chacha20_poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+    use crate::test_util::hex;
+
+    const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you \
+                               only one tip for the future, sunscreen would be it.";
+
+    fn key() -> [u8; 32] {
+        std::array::from_fn(|i| 0x80 + i as u8)
+    }
+
+    #[test]
+    fn rfc8439_section_2_8_2() {
+        let aead = ChaCha20Poly1305::new(&key()).unwrap();
+        let nonce = hex("070000004041424344454647");
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+        let test_result = aead.seal(&nonce, &aad, SUNSCREEN).unwrap();
+        let expected = hex(
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+             3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+             92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+             3ff4def08e4b7a9de576d26586cec64b6116\
+             1ae10b594f09e26a7e902ecbd0600691",
+        );
+        assert_eq!(test_result, expected);
+        assert_eq!(aead.open(&nonce, &aad, &test_result).unwrap(), SUNSCREEN);
+    }
+
+    #[test]
+    fn rfc8439_appendix_a_5_decryption() {
+        let key = hex("1c9240a5eb55d38af333888604f6b5f0473917c1402b80099dca5cbc207075c0");
+        let aead = ChaCha20Poly1305::new(&key).unwrap();
+        let nonce = hex("000000000102030405060708");
+        let aad = hex("f33388860000000000004e91");
+        let mut data = hex(
+            "64a0861575861af460f062c79be643bd5e805cfd345cf389f108670ac76c8cb2\
+             4c6cfc18755d43eea09ee94e382d26b0bdb7b73c321b0100d4f03b7f355894cf\
+             332f830e710b97ce98c8a84abd0b948114ad176e008d33bd60f982b1ff37c855\
+             9797a06ef4f0ef61c186324e2b3506383606907b6a7c02b0f9f6157b53c867e4\
+             b9166c767b804d46a59b5216cde7a4e99040c5a40433225ee282a1b0a06c523e\
+             af4534d7f83fa1155b0047718cbc546a0d072b04b3564eea1b422273f548271a\
+             0bb2316053fa76991955ebd63159434ecebb4e466dae5a1073a6727627097a10\
+             49e617d91d361094fa68f0ff77987130305beaba2eda04df997b714d6c6f2c29\
+             a6ad5cb4022b02709b",
+        );
+        let tag = hex("eead9d67890cbb22392336fea1851f38");
+        aead.open_in_place_detached(&nonce, &aad, &mut data, &tag)
+            .unwrap();
+        let plaintext = String::from_utf8(data).unwrap();
+        assert!(plaintext.starts_with("Internet-Drafts are draft documents valid for a maximum"));
+        assert!(plaintext.ends_with("as /\u{201c}work in progress./\u{201d}"));
+    }
+
+    #[test]
+    fn xchacha20_poly1305_draft_a_3_1() {
+        let aead = XChaCha20Poly1305::new(&key()).unwrap();
+        let nonce = hex("404142434445464748494a4b4c4d4e4f5051525354555657");
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+        let mut data = SUNSCREEN.to_vec();
+        let tag = aead
+            .seal_in_place_detached(&nonce, &aad, &mut data)
+            .unwrap();
+        let expected = hex(
+            "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb\
+             731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b452\
+             2f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff9\
+             21f9664c97637da9768812f615c68b13b52e",
+        );
+        assert_eq!(data, expected);
+        assert_eq!(tag, hex("c0875924c1c7987947deafd8780acf49"));
+        assert_eq!(
+            aead.open(&nonce, &aad, &aead.seal(&nonce, &aad, SUNSCREEN).unwrap())
+                .unwrap(),
+            SUNSCREEN
+        );
+    }
+
+    #[test]
+    fn tampering_is_rejected() {
+        let aead = ChaCha20Poly1305::new(&[9; 32]).unwrap();
+        let nonce = [1; 12];
+        let sealed = aead.seal(&nonce, b"header", b"payload").unwrap();
+
+        for i in 0..sealed.len() {
+            let mut corrupted = sealed.clone();
+            corrupted[i] ^= 0x40;
+            assert_eq!(aead.open(&nonce, b"header", &corrupted), Err(AeadError));
+        }
+        assert!(aead.open(&nonce, b"Header", &sealed).is_err());
+        assert!(aead.open(&[2; 12], b"header", &sealed).is_err());
+        assert!(aead.open(&nonce, b"header", &sealed[..15]).is_err());
+
+        let mut data = sealed[..7].to_vec();
+        let result = aead.open_in_place_detached(&nonce, b"", &mut data, &[0; 16]);
+        assert!(result.is_err());
+        assert_eq!(data, sealed[..7]);
+    }
+
+    #[test]
+    fn empty_message() {
+        let aead = XChaCha20Poly1305::new(&[5; 32]).unwrap();
+        let sealed = aead.seal(&[6; 24], b"", b"").unwrap();
+        assert_eq!(sealed.len(), 16);
+        assert_eq!(aead.open(&[6; 24], b"", &sealed).unwrap(), b"");
+    }
+}
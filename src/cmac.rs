@@ -0,0 +1,192 @@
+/**
+* This is an implementation of AES-CMAC (OMAC1) from RFC 4493 and NIST SP 800-38B.
+* The message is CBC-MAC'd with a zero IV, after the last block is xored with one of two subkeys
+* derived from the encryption of the zero block: K1 when the last block is whole, K2 when it is
+* padded with a one bit and zeros. Any AES key size can be used.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE, double_block};
+use crate::constant_time;
+
+pub const TAG_SIZE: usize = BLOCK_SIZE;
+///Shortest truncated tag accepted, 64 bits as SP 800-38B recommends
+pub const MIN_TRUNCATED_SIZE: usize = 8;
+
+/// `data` followed by a one bit and zeros up to a whole block
+pub(crate) fn pad(data: &[u8]) -> u128 {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..data.len()].copy_from_slice(data);
+    block[data.len()] = 0x80;
+    u128::from_be_bytes(block)
+}
+
+/// A streaming AES-CMAC computation
+#[derive(Clone)]
+pub struct Cmac {
+    cipher: Aes,
+    k1: u128,
+    k2: u128,
+    state: u128,
+    ///The last block is held back until more data shows it is not the final one
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+}
+
+impl Cmac {
+    pub fn new(key: &[u8]) -> Result<Cmac, AesError> {
+        Ok(Cmac::with_cipher(Aes::new(key)?))
+    }
+
+    /// CMAC over an already expanded key, deriving the subkeys from it
+    pub fn with_cipher(cipher: Aes) -> Cmac {
+        let mut l = [0u8; BLOCK_SIZE];
+        cipher.encrypt_block(&mut l);
+        let k1 = double_block(u128::from_be_bytes(l));
+        Cmac {
+            cipher,
+            k1,
+            k2: double_block(k1),
+            state: 0,
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+        }
+    }
+
+    /// The subkeys K1 and K2
+    pub fn subkeys(&self) -> ([u8; BLOCK_SIZE], [u8; BLOCK_SIZE]) {
+        (self.k1.to_be_bytes(), self.k2.to_be_bytes())
+    }
+
+    fn encipher(&self, block: u128) -> u128 {
+        let mut bytes = block.to_be_bytes();
+        self.cipher.encrypt_block(&mut bytes);
+        u128::from_be_bytes(bytes)
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.buffer_len == BLOCK_SIZE {
+                self.state = self.encipher(self.state ^ u128::from_be_bytes(self.buffer));
+                self.buffer_len = 0;
+            }
+            let take = data.len().min(BLOCK_SIZE - self.buffer_len);
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+        }
+    }
+
+    pub fn finalize(self) -> [u8; TAG_SIZE] {
+        let last = if self.buffer_len == BLOCK_SIZE {
+            u128::from_be_bytes(self.buffer) ^ self.k1
+        } else {
+            pad(&self.buffer[..self.buffer_len]) ^ self.k2
+        };
+        self.encipher(self.state ^ last).to_be_bytes()
+    }
+
+    /// Check a full length tag in constant time
+    #[must_use]
+    pub fn verify(self, tag: &[u8]) -> bool {
+        constant_time::eq(&self.finalize(), tag)
+    }
+
+    /// Check a tag truncated to its leftmost bytes in constant time, the tag length itself is not
+    /// secret. Tags shorter than `MIN_TRUNCATED_SIZE` are always rejected.
+    #[must_use]
+    pub fn verify_truncated(self, tag: &[u8]) -> bool {
+        if tag.len() < MIN_TRUNCATED_SIZE || tag.len() > TAG_SIZE {
+            return false;
+        }
+        constant_time::eq(&self.finalize()[..tag.len()], tag)
+    }
+
+    /// Compute the tag of a complete message in one call
+    pub fn mac(key: &[u8], data: &[u8]) -> Result<[u8; TAG_SIZE], AesError> {
+        let mut cmac = Cmac::new(key)?;
+        cmac.update(data);
+        Ok(cmac.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes::AesError;
+    use crate::cmac::Cmac;
+    use crate::test_util::hex;
+
+    const MESSAGE: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                           30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn check(key: &str, tags: [&str; 4]) {
+        let message = hex(MESSAGE);
+        for (len, tag) in [0, 16, 40, 64].into_iter().zip(tags) {
+            let test_result = Cmac::mac(&hex(key), &message[..len]).unwrap();
+            assert_eq!(test_result.to_vec(), hex(tag), "{len} bytes");
+        }
+    }
+
+    // RFC 4493 section 4
+    #[test]
+    fn rfc4493_examples() {
+        let cmac = Cmac::new(&hex("2b7e151628aed2a6abf7158809cf4f3c")).unwrap();
+        let (k1, k2) = cmac.subkeys();
+        assert_eq!(k1.to_vec(), hex("fbeed618357133667c85e08f7236a8de"));
+        assert_eq!(k2.to_vec(), hex("f7ddac306ae266ccf90bc11ee46d513b"));
+
+        check(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            [
+                "bb1d6929e95937287fa37d129b756746",
+                "070a16b46b4d4144f79bdd9dd04a287c",
+                "dfa66747de9ae63030ca32611497c827",
+                "51f0bebf7e3b9d92fc49741779363cfe",
+            ],
+        );
+    }
+
+    // SP 800-38B appendix D.2 and D.3
+    #[test]
+    fn sp800_38b_aes192_and_aes256() {
+        check(
+            "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+            [
+                "d17ddf46adaacde531cac483de7a9367",
+                "9e99a7bf31e710900662f65e617c5184",
+                "8a1de5be2eb31aad089a82e6ee908b0e",
+                "a1d5df0eed790f794d77589659f39a11",
+            ],
+        );
+        check(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            [
+                "028962f61b7bf89efc6b551f4667d983",
+                "28a7023f452e8f82bd4bf28d8c37c35c",
+                "aaf3d8f1de5640c232f5b169b9c911e6",
+                "e1992190549f6ed5696a2c056c315410",
+            ],
+        );
+    }
+
+    #[test]
+    fn pieces_and_verify() {
+        let key = hex("2b7e151628aed2a6abf7158809cf4f3c");
+        let message = hex(MESSAGE);
+        let tag = Cmac::mac(&key, &message).unwrap();
+        for split in [0, 1, 15, 16, 17, 32, 63, 64] {
+            let mut cmac = Cmac::new(&key).unwrap();
+            cmac.update(&message[..split]);
+            cmac.update(&message[split..]);
+            assert_eq!(cmac.finalize(), tag);
+        }
+
+        let mut cmac = Cmac::new(&key).unwrap();
+        cmac.update(&message);
+        assert!(cmac.clone().verify(&tag));
+        assert!(cmac.clone().verify_truncated(&tag[..8]));
+        assert!(!cmac.clone().verify_truncated(&tag[..4]));
+        let mut bad_tag = tag;
+        bad_tag[15] ^= 1;
+        assert!(!cmac.verify(&bad_tag));
+        assert_eq!(Cmac::new(&[0; 17]).err(), Some(AesError::InvalidKeyLength));
+    }
+}
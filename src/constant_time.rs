@@ -0,0 +1,31 @@
+//Helpers for comparing secret data without leaking where it differs through timing
+
+/// Compare two byte strings in time that depends only on their lengths
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut difference: u8 = 0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        difference |= x ^ y;
+    }
+    // keep the optimiser from turning the loop into an early exit
+    std::hint::black_box(difference) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constant_time;
+
+    #[test]
+    fn equal() {
+        assert!(constant_time::eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(constant_time::eq(&[], &[]));
+    }
+
+    #[test]
+    fn not_equal() {
+        assert!(!constant_time::eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!constant_time::eq(&[1, 2, 3], &[1, 2]));
+    }
+}
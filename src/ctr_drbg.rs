@@ -0,0 +1,504 @@
+/**
+* This is an implementation of CTR_DRBG from NIST SP 800-90A using AES-128, AES-192 or AES-256.
+* The internal state is an AES key and a 16 byte counter block V. Output is AES in counter mode
+* and the key and V are replaced after every request, so earlier output cannot be recovered.
+* Without the derivation function the entropy input must be full entropy of exactly the seed length.
+*/
+use crate::aes::{Aes, BLOCK_SIZE};
+use crate::drbg::{DrbgError, MAX_INPUT_SIZE, MAX_REQUEST_SIZE, RESEED_INTERVAL};
+use crate::rng::CryptoRng;
+
+///Largest seed length, the AES-256 key plus one block
+const MAX_SEED_SIZE: usize = 32 + BLOCK_SIZE;
+
+#[derive(Clone)]
+pub struct CtrDrbg {
+    cipher: Aes,
+    v: [u8; BLOCK_SIZE],
+    key_size: usize,
+    derivation_function: bool,
+    reseed_counter: u64,
+}
+
+/// Block_Cipher_df, compresses `input` into `output.len()` bytes with AES-CBC-MAC under a fixed key
+fn block_cipher_df(key_size: usize, input: &[&[u8]], output: &mut [u8]) {
+    let input_len: usize = input.iter().map(|part| part.len()).sum();
+
+    // S = L || N || input || 0x80, zero padded to a whole number of blocks
+    let mut s = Vec::with_capacity(input_len + 2 * BLOCK_SIZE);
+    s.extend_from_slice(&(input_len as u32).to_be_bytes());
+    s.extend_from_slice(&(output.len() as u32).to_be_bytes());
+    for part in input {
+        s.extend_from_slice(part);
+    }
+    s.push(0x80);
+    s.resize(s.len().next_multiple_of(BLOCK_SIZE), 0);
+
+    let df_key: Vec<u8> = (0..key_size as u8).collect();
+    let bcc_cipher = Aes::new(&df_key).unwrap();
+    let mut temp = [0u8; MAX_SEED_SIZE];
+    for (i, chunk) in temp[..key_size + BLOCK_SIZE]
+        .chunks_mut(BLOCK_SIZE)
+        .enumerate()
+    {
+        // BCC over IV || S where the IV is the 32 bit block index followed by zeros
+        let mut chaining = [0u8; BLOCK_SIZE];
+        chaining[..4].copy_from_slice(&(i as u32).to_be_bytes());
+        bcc_cipher.encrypt_block(&mut chaining);
+        for block in s.chunks_exact(BLOCK_SIZE) {
+            for (c, b) in chaining.iter_mut().zip(block) {
+                *c ^= b;
+            }
+            bcc_cipher.encrypt_block(&mut chaining);
+        }
+        chunk.copy_from_slice(&chaining);
+    }
+
+    let cipher = Aes::new(&temp[..key_size]).unwrap();
+    let mut x: [u8; BLOCK_SIZE] = temp[key_size..key_size + BLOCK_SIZE].try_into().unwrap();
+    for chunk in output.chunks_mut(BLOCK_SIZE) {
+        cipher.encrypt_block(&mut x);
+        chunk.copy_from_slice(&x[..chunk.len()]);
+    }
+}
+
+impl CtrDrbg {
+    /// Instantiate with a 16, 24 or 32 byte AES key. The `nonce` is only used with the derivation function.
+    pub fn instantiate(
+        key_size: usize,
+        derivation_function: bool,
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> Result<CtrDrbg, DrbgError> {
+        let cipher = Aes::new(&vec![0u8; key_size]).map_err(|_| DrbgError::InvalidParameters)?;
+        let mut drbg = CtrDrbg {
+            cipher,
+            v: [0; BLOCK_SIZE],
+            key_size,
+            derivation_function,
+            reseed_counter: 1,
+        };
+        drbg.check_entropy(entropy)?;
+        drbg.check_input(nonce)?;
+        drbg.check_input(personalization)?;
+
+        let seed_material = if derivation_function {
+            drbg.seed_material(&[entropy, nonce, personalization])
+        } else {
+            drbg.seed_material(&[entropy, personalization])
+        };
+        drbg.update(&seed_material);
+        Ok(drbg)
+    }
+
+    /// Instantiate with the derivation function, taking the entropy and nonce from `rng`
+    pub fn from_rng(
+        rng: &mut impl CryptoRng,
+        key_size: usize,
+        personalization: &[u8],
+    ) -> Result<CtrDrbg, DrbgError> {
+        let entropy = rng.random_vec(key_size);
+        let nonce = rng.random_vec(key_size / 2);
+        CtrDrbg::instantiate(key_size, true, &entropy, &nonce, personalization)
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        self.check_entropy(entropy)?;
+        self.check_input(additional_input)?;
+
+        let seed_material = self.seed_material(&[entropy, additional_input]);
+        self.update(&seed_material);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Reseed with entropy taken from `rng`
+    pub fn reseed_from_rng(
+        &mut self,
+        rng: &mut impl CryptoRng,
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        let entropy = rng.random_vec(self.entropy_size());
+        self.reseed(&entropy, additional_input)
+    }
+
+    /// Fill `output` with random bytes, mixing in the optional `additional_input`
+    pub fn generate(
+        &mut self,
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if output.len() > MAX_REQUEST_SIZE {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        self.check_input(additional_input)?;
+
+        let additional = if additional_input.is_empty() {
+            vec![0u8; self.seed_size()]
+        } else {
+            let additional = self.seed_material(&[additional_input]);
+            self.update(&additional);
+            additional
+        };
+
+        for chunk in output.chunks_mut(BLOCK_SIZE) {
+            let mut block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            block.fill(0);
+        }
+
+        self.update(&additional);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// Reseed with `entropy` and `additional_input`, then generate, as prediction resistance requires
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        output: &mut [u8],
+        entropy: &[u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        self.reseed(entropy, additional_input)?;
+        self.generate(output, &[])
+    }
+
+    /// The number of requests since the last (re)seed, plus one
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn seed_size(&self) -> usize {
+        self.key_size + BLOCK_SIZE
+    }
+
+    /// The entropy input length requested when reseeding from an rng
+    fn entropy_size(&self) -> usize {
+        if self.derivation_function {
+            self.key_size
+        } else {
+            self.seed_size()
+        }
+    }
+
+    fn check_entropy(&self, entropy: &[u8]) -> Result<(), DrbgError> {
+        let valid = if self.derivation_function {
+            entropy.len() >= self.key_size && entropy.len() as u64 <= MAX_INPUT_SIZE
+        } else {
+            entropy.len() == self.seed_size()
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(DrbgError::InvalidEntropyLength)
+        }
+    }
+
+    fn check_input(&self, input: &[u8]) -> Result<(), DrbgError> {
+        let limit = if self.derivation_function {
+            MAX_INPUT_SIZE
+        } else {
+            self.seed_size() as u64
+        };
+        if input.len() as u64 > limit {
+            Err(DrbgError::InputTooLong)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// With the derivation function the inputs are compressed to the seed length.
+    /// Without it the inputs are zero padded to the seed length and xored together.
+    fn seed_material(&self, input: &[&[u8]]) -> Vec<u8> {
+        let mut seed = vec![0u8; self.seed_size()];
+        if self.derivation_function {
+            block_cipher_df(self.key_size, input, &mut seed);
+        } else {
+            for part in input {
+                for (s, p) in seed.iter_mut().zip(part.iter()) {
+                    *s ^= p;
+                }
+            }
+        }
+        seed
+    }
+
+    /// Increment V and encrypt it
+    fn next_block(&mut self) -> [u8; BLOCK_SIZE] {
+        self.v = u128::from_be_bytes(self.v).wrapping_add(1).to_be_bytes();
+        let mut block = self.v;
+        self.cipher.encrypt_block(&mut block);
+        block
+    }
+
+    /// CTR_DRBG_Update, replaces the key and V with fresh keystream xored with `provided_data`
+    fn update(&mut self, provided_data: &[u8]) {
+        let mut temp = [0u8; MAX_SEED_SIZE];
+        let seed_size = self.seed_size();
+        for chunk in temp[..seed_size].chunks_mut(BLOCK_SIZE) {
+            chunk.copy_from_slice(&self.next_block());
+        }
+        for (t, p) in temp.iter_mut().zip(provided_data) {
+            *t ^= p;
+        }
+        self.cipher = Aes::new(&temp[..self.key_size]).unwrap();
+        self.v.copy_from_slice(&temp[self.key_size..seed_size]);
+        temp.fill(0);
+    }
+}
+
+impl CryptoRng for CtrDrbg {
+    /// Panics once the reseed interval has passed, call `reseed` before then
+    fn fill_bytes(&mut self, output: &mut [u8]) {
+        for chunk in output.chunks_mut(MAX_REQUEST_SIZE) {
+            self.generate(chunk, &[])
+                .expect("CTR_DRBG must be reseeded");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctr_drbg::CtrDrbg;
+    use crate::drbg::DrbgError;
+    use crate::rng::{CryptoRng, SeededRng};
+    use crate::test_util::hex;
+
+    /// Consecutive bytes, the pattern used for the inputs of the generated AES-128 no df test
+    fn byte_run(first: u8, len: u8) -> Vec<u8> {
+        (first..first + len).collect()
+    }
+
+    #[test]
+    fn cavp_aes256_no_df_reseed() {
+        // CAVP CTR_DRBG.rsp [AES-256 no df] [PredictionResistance = False] COUNT = 0
+        let mut drbg = CtrDrbg::instantiate(
+            32,
+            false,
+            &hex(
+                "e4bc23c5089a19d86f4119cb3fa08c0a4991e0a1def17e101e4c14d9c323460a\
+                 7c2fb58e0b086c6c57b55f56cae25bad",
+            ),
+            &[],
+            &[],
+        )
+        .unwrap();
+        drbg.reseed(
+            &hex(
+                "fd85a836bba85019881e8c6bad23c9061adc75477659acaea8e4a01dfe07a183\
+                 2dad1c136f59d70f8653a5dc118663d6",
+            ),
+            &[],
+        )
+        .unwrap();
+
+        let mut output = [0u8; 64];
+        drbg.generate(&mut output, &[]).unwrap();
+        drbg.generate(&mut output, &[]).unwrap();
+        let test_result = hex(
+            "b2cb8905c05e5950ca31895096be29ea3d5a3b82b269495554eb80fe07de43e1\
+             93b9e7c3ece73b80e062b1c1f68202fbb1c52a040ea2478864295282234aaada",
+        );
+        assert_eq!(output.to_vec(), test_result);
+        assert_eq!(drbg.reseed_counter(), 3);
+    }
+
+    #[test]
+    fn cavp_aes256_df_additional_input() {
+        // CAVP CTR_DRBG.rsp [AES-256 use df] [PredictionResistance = False]
+        // [EntropyInputLen = 256] [NonceLen = 128] [PersonalizationStringLen = 256]
+        // [AdditionalInputLen = 256] COUNT = 0, reseeded before the first generate as the file does
+        let nonce = hex("a94da55afdc50ce51c9a3b8a4c448440");
+        let personalization =
+            hex("8b52a24a93c34ea71e1ca705eb829ba65de4d4e07fa3d86b37845ff1c7d5f6d2");
+        let mut drbg = CtrDrbg::instantiate(
+            32,
+            true,
+            &hex("a53e371017439193591e475087aaddd5c1c386cdca0ddb68e002d80fdc401a47"),
+            &nonce,
+            &personalization,
+        )
+        .unwrap();
+
+        drbg.reseed(
+            &hex("dd40e5987b2716731568d276bf0c6715757903d3dede914642ddd467c879c81e"),
+            &hex("7fd81fbd2ab51c115d834e99f65ca54020ed388ed59ee07593fe125e5d73fb75"),
+        )
+        .unwrap();
+
+        let mut output = [0u8; 16];
+        drbg.generate(
+            &mut output,
+            &hex("20f422edf85ca16a01cfbe5f8d6c947fae12a857db2aa9bfc7b36581808d0d46"),
+        )
+        .unwrap();
+        drbg.generate(
+            &mut output,
+            &hex("cd2cff14693e4c9efdfe260de986004930bab1c65057772a62392c3b74ebc90d"),
+        )
+        .unwrap();
+        assert_eq!(output.to_vec(), hex("b3f19a17590194922a9fc4e30043715d"));
+    }
+
+    #[test]
+    fn cavp_aes256_df_prediction_resistance() {
+        // CAVP CTR_DRBG.rsp [AES-256 use df] [PredictionResistance = True]
+        // [EntropyInputLen = 256] [NonceLen = 128] [PersonalizationStringLen = 256]
+        // [AdditionalInputLen = 256] COUNT = 0
+        let mut drbg = CtrDrbg::instantiate(
+            32,
+            true,
+            &hex("6168fc1af0b5956b85099b743f1378493b85ec93133ba94f96ab2ce4c88fdd6a"),
+            &hex("add2bbbab76589c3216c55332b36ffa4"),
+            &hex("6ecae72072d3845a32d34b2472c4632b9d12240c23268e8316370bd1064f686d"),
+        )
+        .unwrap();
+
+        let mut output = [0u8; 16];
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("0b23afdff162d7d34397f87704a84220bdf60fc1172f9f54bb561786680ebaa9"),
+            &hex("7e084abbe3217cc923d2f8b07398ba847423ab068ae222d37bce9bd24a76b8de"),
+        )
+        .unwrap();
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("bf6c592a0d440fae9a5e0373d8a6e1cf25613824869e53e8a4df56f406079c0f"),
+            &hex("946bc99fab8dc5ec71881d008c8968e4c8077736176d7978c7064e99042829c3"),
+        )
+        .unwrap();
+        assert_eq!(output.to_vec(), hex("224ab4b8b6ee7db19ec9f9a0d9e29700"));
+    }
+
+    #[test]
+    fn cavp_aes128_df_reseed() {
+        // CAVP CTR_DRBG.rsp (CAVS 14.3) [AES-128 use df] [PredictionResistance = False]
+        // [EntropyInputLen = 128] [NonceLen = 64] [PersonalizationStringLen = 0]
+        // [AdditionalInputLen = 0] COUNT = 0
+        let mut drbg = CtrDrbg::instantiate(
+            16,
+            true,
+            &hex("0f65da13dca407999d4773c2b4a11d85"),
+            &hex("5209e5b4ed82a234"),
+            &[],
+        )
+        .unwrap();
+        drbg.reseed(&hex("1dea0a12c52bf64339dd291c80d8ca89"), &[])
+            .unwrap();
+
+        let mut output = [0u8; 64];
+        drbg.generate(&mut output, &[]).unwrap();
+        drbg.generate(&mut output, &[]).unwrap();
+        let test_result = hex(
+            "2859cc468a76b08661ffd23b28547ffd0997ad526a0f51261b99ed3a37bd407b\
+             f418dbe6c6c3e26ed0ddefcb7474d899bd99f3655427519fc5b4057bcaf306d4",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn cavp_aes128_df_prediction_resistance() {
+        // CAVP CTR_DRBG.rsp (CAVS 14.3) [AES-128 use df] [PredictionResistance = True]
+        // [EntropyInputLen = 128] [NonceLen = 64] [PersonalizationStringLen = 128]
+        // [AdditionalInputLen = 128] COUNT = 0
+        let mut drbg = CtrDrbg::instantiate(
+            16,
+            true,
+            &hex("92898f31fa1cff6d182f260643dff818"),
+            &hex("c2a4d972c3b9b697"),
+            &hex("ea65ee60264e7eb60e8268c4373c5c0b"),
+        )
+        .unwrap();
+
+        let mut output = [0u8; 64];
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("20728a06f86f8dd441e272b7c42ce810"),
+            &hex("1a40fae3cc6c7ca0f8daba59236dad1d"),
+        )
+        .unwrap();
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("3db0f094f305503317863e2208f7a501"),
+            &hex("9f72766cc746e5ed2e532012bc59318c"),
+        )
+        .unwrap();
+        let test_result = hex(
+            "5a3539870f4d22a40924ee71c96fac720ad6f08882d0832873ec3f93d8ab4523\
+             f07eac45145e939fb1d676433db6e80888f6da89087742fe1af43fc423c51f68",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn aes128_no_df() {
+        // generated with OpenSSL's CTR-DRBG
+        let mut drbg =
+            CtrDrbg::instantiate(16, false, &byte_run(0x00, 32), &[], &byte_run(0x40, 32)).unwrap();
+        let mut output = [0u8; 64];
+        drbg.generate(&mut output, &byte_run(0x60, 32)).unwrap();
+        assert_eq!(
+            output.to_vec(),
+            hex(
+                "cbca7021e6e00f5ce8f499ca8e566b4805094a9591deda27471910063a6da790\
+                 bb26af644dff74ef86be7d457d176b7df463ebc4f2456a6628274cc51569216e"
+            )
+        );
+
+        drbg.reseed(&byte_run(0x80, 32), &byte_run(0xa0, 32))
+            .unwrap();
+        drbg.generate(&mut output, &byte_run(0xc0, 32)).unwrap();
+        assert_eq!(
+            output.to_vec(),
+            hex(
+                "33221583ecb5d89d04be0377872b4f9e6ff24c2f383140eee347a026f90a880b\
+                 e00c2c30908e0ed509673684f7acd08bb32f9e4c19906429516c00331be8af5a"
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        assert_eq!(
+            CtrDrbg::instantiate(20, true, &[0; 32], &[], &[]).err(),
+            Some(DrbgError::InvalidParameters)
+        );
+        assert_eq!(
+            CtrDrbg::instantiate(16, false, &[0; 31], &[], &[]).err(),
+            Some(DrbgError::InvalidEntropyLength)
+        );
+        assert_eq!(
+            CtrDrbg::instantiate(32, true, &[0; 31], &[0; 16], &[]).err(),
+            Some(DrbgError::InvalidEntropyLength)
+        );
+        assert_eq!(
+            CtrDrbg::instantiate(16, false, &[0; 32], &[], &[0; 33]).err(),
+            Some(DrbgError::InputTooLong)
+        );
+
+        let mut drbg = CtrDrbg::instantiate(16, true, &[0; 16], &[0; 8], &[]).unwrap();
+        let mut output = vec![0u8; (1 << 16) + 1];
+        assert_eq!(
+            drbg.generate(&mut output, &[]),
+            Err(DrbgError::RequestTooLarge)
+        );
+    }
+
+    #[test]
+    fn usable_as_rng() {
+        let mut seed = SeededRng::from_seed(b"ctr_drbg");
+        let mut first = CtrDrbg::from_rng(&mut seed, 32, b"app").unwrap();
+        let mut second = first.clone();
+        let output = first.random_vec(100_000);
+        assert_eq!(output, second.random_vec(100_000));
+        assert_eq!(first.reseed_counter(), 3);
+
+        first.reseed_from_rng(&mut seed, &[]).unwrap();
+        assert_eq!(first.reseed_counter(), 1);
+        assert_ne!(first.random_vec(32), second.random_vec(32));
+    }
+}
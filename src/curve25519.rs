@@ -0,0 +1,306 @@
+/**
+* This is an implementation of arithmetic in GF(2^255 - 19), the field Curve25519 and Ed25519 are
+* defined over.
+* Elements are held in five 51 bit limbs, products are accumulated in u128 and the part above
+* 2^255 is folded back in multiplied by 19, as 2^255 = 19 mod p. Limbs are only partly reduced
+* between operations, `to_bytes` gives the canonical encoding.
+* Every operation takes the same time whatever the values, choices are made with masks.
+*/
+use crate::constant_time;
+use std::ops;
+
+const MASK_51: u64 = (1 << 51) - 1;
+
+///p - 2, the exponent of inversion, little endian
+const P_MINUS_2: [u8; 32] = {
+    let mut exponent = [0xff; 32];
+    exponent[0] = 0xeb;
+    exponent[31] = 0x7f;
+    exponent
+};
+///(p - 5) / 8 = 2^252 - 3, little endian
+const P_MINUS_5_DIV_8: [u8; 32] = {
+    let mut exponent = [0xff; 32];
+    exponent[0] = 0xfd;
+    exponent[31] = 0x0f;
+    exponent
+};
+
+/// An element of GF(2^255 - 19)
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FieldElement(pub(crate) [u64; 5]);
+
+///A square root of -1, 2^((p - 1) / 4)
+pub(crate) const SQRT_M1: FieldElement = FieldElement([
+    0x61b274a0ea0b0,
+    0xd5a5fc8f189d,
+    0x7ef5e9cbd0c60,
+    0x78595a6804c9e,
+    0x2b8324804fc1d,
+]);
+
+impl FieldElement {
+    pub(crate) const ZERO: FieldElement = FieldElement([0; 5]);
+    pub(crate) const ONE: FieldElement = FieldElement([1, 0, 0, 0, 0]);
+
+    /// Read a little endian encoding, the top bit is ignored as RFC 7748 requires
+    pub(crate) fn from_bytes(bytes: &[u8; 32]) -> FieldElement {
+        let load = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        FieldElement([
+            load(0) & MASK_51,
+            (load(6) >> 3) & MASK_51,
+            (load(12) >> 6) & MASK_51,
+            (load(19) >> 1) & MASK_51,
+            (load(24) >> 12) & MASK_51,
+        ])
+    }
+
+    /// The canonical little endian encoding, fully reduced mod p
+    pub(crate) fn to_bytes(self) -> [u8; 32] {
+        let mut limbs = Self::carry(Self::carry(self.0).0).0;
+        // q is 1 exactly when the value is at least p
+        let mut q = (limbs[0] + 19) >> 51;
+        for limb in &limbs[1..] {
+            q = (limb + q) >> 51;
+        }
+        limbs[0] += 19 * q;
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= MASK_51;
+        }
+        limbs[4] &= MASK_51;
+
+        let mut bytes = [0u8; 32];
+        let mut accumulator = 0u128;
+        let mut bits = 0;
+        let mut i = 0;
+        for limb in limbs {
+            accumulator |= (limb as u128) << bits;
+            bits += 51;
+            while bits >= 8 {
+                bytes[i] = accumulator as u8;
+                accumulator >>= 8;
+                bits -= 8;
+                i += 1;
+            }
+        }
+        bytes[i] = accumulator as u8;
+        bytes
+    }
+
+    /// Carry every limb into the next, the top limb wraps around times 19
+    fn carry(mut limbs: [u64; 5]) -> FieldElement {
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= MASK_51;
+        }
+        let top = limbs[4] >> 51;
+        limbs[4] &= MASK_51;
+        limbs[0] += top * 19;
+        FieldElement(limbs)
+    }
+
+    pub(crate) fn square(&self) -> FieldElement {
+        *self * *self
+    }
+
+    /// `self^exponent` for a public little endian exponent
+    fn pow(&self, exponent: &[u8; 32]) -> FieldElement {
+        let mut result = FieldElement::ONE;
+        for i in (0..256).rev() {
+            result = result.square();
+            if (exponent[i / 8] >> (i % 8)) & 1 == 1 {
+                result = result * *self;
+            }
+        }
+        result
+    }
+
+    /// The inverse, zero maps to zero
+    pub(crate) fn invert(&self) -> FieldElement {
+        self.pow(&P_MINUS_2)
+    }
+
+    /// Returns whether `u / v` is a square, and its square root if it is
+    pub(crate) fn sqrt_ratio(u: &FieldElement, v: &FieldElement) -> (bool, FieldElement) {
+        // r = u v^3 (u v^7)^((p - 5) / 8), then r^2 v is u, -u, or neither
+        let v3 = v.square() * *v;
+        let v7 = v3.square() * *v;
+        let r = *u * v3 * (*u * v7).pow(&P_MINUS_5_DIV_8);
+        let check = *v * r.square();
+
+        let correct_sign = check.ct_eq(u);
+        let flipped_sign = check.ct_eq(&-*u);
+        let r = FieldElement::select(&r, &(r * SQRT_M1), flipped_sign);
+        (correct_sign | flipped_sign, r)
+    }
+
+    pub(crate) fn ct_eq(&self, other: &FieldElement) -> bool {
+        constant_time::eq(&self.to_bytes(), &other.to_bytes())
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.ct_eq(&FieldElement::ZERO)
+    }
+
+    /// Whether the canonical value is odd, which RFC 8032 calls negative
+    pub(crate) fn is_negative(&self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    /// `b` if `choice` is set, otherwise `a`
+    pub(crate) fn select(a: &FieldElement, b: &FieldElement, choice: bool) -> FieldElement {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        let mut limbs = a.0;
+        for (limb, b_limb) in limbs.iter_mut().zip(b.0) {
+            *limb ^= (*limb ^ b_limb) & mask;
+        }
+        FieldElement(limbs)
+    }
+
+    /// Exchange `a` and `b` if `choice` is set
+    pub(crate) fn swap(a: &mut FieldElement, b: &mut FieldElement, choice: bool) {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        for (a_limb, b_limb) in a.0.iter_mut().zip(b.0.iter_mut()) {
+            let difference = (*a_limb ^ *b_limb) & mask;
+            *a_limb ^= difference;
+            *b_limb ^= difference;
+        }
+    }
+
+    /// Carry 128 bit column sums down to 51 bit limbs
+    fn reduce_wide(mut wide: [u128; 5]) -> FieldElement {
+        for i in 0..4 {
+            wide[i + 1] += wide[i] >> 51;
+            wide[i] &= MASK_51 as u128;
+        }
+        let low = wide[0] + (wide[4] >> 51) * 19;
+        let mut limbs = [0u64; 5];
+        limbs[0] = low as u64 & MASK_51;
+        limbs[1] = wide[1] as u64 + (low >> 51) as u64;
+        limbs[2] = wide[2] as u64;
+        limbs[3] = wide[3] as u64;
+        limbs[4] = wide[4] as u64 & MASK_51;
+        FieldElement(limbs)
+    }
+}
+
+impl ops::Add for FieldElement {
+    type Output = FieldElement;
+    fn add(self, rhs: FieldElement) -> FieldElement {
+        let mut limbs = self.0;
+        for (limb, rhs_limb) in limbs.iter_mut().zip(rhs.0) {
+            *limb += rhs_limb;
+        }
+        FieldElement::carry(limbs)
+    }
+}
+
+impl ops::Sub for FieldElement {
+    type Output = FieldElement;
+    fn sub(self, rhs: FieldElement) -> FieldElement {
+        // add 4p first so no limb goes negative
+        let four_p = [
+            4 * (MASK_51 - 18),
+            4 * MASK_51,
+            4 * MASK_51,
+            4 * MASK_51,
+            4 * MASK_51,
+        ];
+        let mut limbs = self.0;
+        for ((limb, rhs_limb), p_limb) in limbs.iter_mut().zip(rhs.0).zip(four_p) {
+            *limb = *limb + p_limb - rhs_limb;
+        }
+        FieldElement::carry(limbs)
+    }
+}
+
+impl ops::Neg for FieldElement {
+    type Output = FieldElement;
+    fn neg(self) -> FieldElement {
+        FieldElement::ZERO - self
+    }
+}
+
+impl ops::Mul for FieldElement {
+    type Output = FieldElement;
+    fn mul(self, rhs: FieldElement) -> FieldElement {
+        let [a0, a1, a2, a3, a4] = self.0.map(|limb| limb as u128);
+        let [b0, b1, b2, b3, b4] = rhs.0.map(|limb| limb as u128);
+        // the products that land at 2^255 and above, folded back times 19
+        let (b1_19, b2_19, b3_19, b4_19) = (b1 * 19, b2 * 19, b3 * 19, b4 * 19);
+        FieldElement::reduce_wide([
+            a0 * b0 + a1 * b4_19 + a2 * b3_19 + a3 * b2_19 + a4 * b1_19,
+            a0 * b1 + a1 * b0 + a2 * b4_19 + a3 * b3_19 + a4 * b2_19,
+            a0 * b2 + a1 * b1 + a2 * b0 + a3 * b4_19 + a4 * b3_19,
+            a0 * b3 + a1 * b2 + a2 * b1 + a3 * b0 + a4 * b4_19,
+            a0 * b4 + a1 * b3 + a2 * b2 + a3 * b1 + a4 * b0,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::big_int::BigInt;
+    use crate::curve25519::{FieldElement, SQRT_M1};
+    use crate::rng::{CryptoRng, SeededRng};
+
+    fn p() -> BigInt {
+        BigInt::from_be_bytes(&[0xff; 32]) / BigInt::from_u64(2) - BigInt::from_u64(18)
+    }
+
+    fn to_big(element: &FieldElement) -> BigInt {
+        BigInt::new(element.to_bytes().to_vec())
+    }
+
+    // Sums, differences, products and inverses of random elements against the same operations mod p
+    #[test]
+    fn matches_big_int() {
+        let mut rng = SeededRng::from_seed(b"curve25519 field");
+        for _ in 0..4 {
+            let mut a_bytes: [u8; 32] = rng.random_array();
+            let mut b_bytes: [u8; 32] = rng.random_array();
+            // the top bit is not part of the value
+            a_bytes[31] &= 0x7f;
+            b_bytes[31] &= 0x7f;
+            let a = FieldElement::from_bytes(&a_bytes);
+            let b = FieldElement::from_bytes(&b_bytes);
+            let big_a = BigInt::new(a_bytes.to_vec()) % p();
+            let big_b = BigInt::new(b_bytes.to_vec()) % p();
+
+            assert_eq!(to_big(&(a + b)), (big_a.clone() + big_b.clone()) % p());
+            assert_eq!(
+                to_big(&(a - b)),
+                (big_a.clone() + p() - big_b.clone()) % p()
+            );
+            assert_eq!(to_big(&(a * b)), (big_a.clone() * big_b.clone()) % p());
+            let p_minus_2 = p() - BigInt::from_u64(2);
+            assert_eq!(to_big(&a.invert()), big_a.mod_pow(&p_minus_2, &p()));
+        }
+    }
+
+    #[test]
+    fn canonical_encoding() {
+        // p itself and p + 1 encode as 0 and 1
+        let mut p_bytes = [0xff; 32];
+        p_bytes[0] = 0xed;
+        p_bytes[31] = 0x7f;
+        assert_eq!(FieldElement::from_bytes(&p_bytes).to_bytes(), [0; 32]);
+        p_bytes[0] = 0xee;
+        assert!(FieldElement::from_bytes(&p_bytes).ct_eq(&FieldElement::ONE));
+        assert!((SQRT_M1.square() + FieldElement::ONE).is_zero());
+    }
+
+    #[test]
+    fn square_roots() {
+        let four = FieldElement([4, 0, 0, 0, 0]);
+        let (is_square, root) = FieldElement::sqrt_ratio(&four, &FieldElement::ONE);
+        assert!(is_square);
+        assert!(root.square().ct_eq(&four));
+
+        // 2 is not a square mod p
+        let two = FieldElement([2, 0, 0, 0, 0]);
+        let (is_square, _) = FieldElement::sqrt_ratio(&two, &FieldElement::ONE);
+        assert!(!is_square);
+    }
+}
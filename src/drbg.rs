@@ -0,0 +1,54 @@
+/*
+* These are the pieces shared by the deterministic random bit generators of NIST SP 800-90A.
+* Each DRBG is instantiated from entropy, a nonce and an optional personalization string,
+* and must be reseeded with fresh entropy after `RESEED_INTERVAL` requests.
+* Prediction resistance is a reseed with fresh entropy immediately before a request.
+*/
+
+///Number of generate requests allowed between reseeds
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+///Largest number of bytes returned by a single generate request (2^19 bits)
+pub const MAX_REQUEST_SIZE: usize = 1 << 16;
+
+///Largest entropy input, personalization string or additional input (2^35 bits)
+pub const MAX_INPUT_SIZE: u64 = 1 << 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrbgError {
+    ///The underlying cipher or hash does not support the requested parameters
+    InvalidParameters,
+    ///The entropy input is shorter than the security strength, or the wrong length
+    InvalidEntropyLength,
+    ///A personalization string or additional input is too long
+    InputTooLong,
+    ///More than `MAX_REQUEST_SIZE` bytes were requested at once
+    RequestTooLarge,
+    ///The reseed counter has passed `RESEED_INTERVAL`
+    ReseedRequired,
+}
+
+impl std::fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrbgError::InvalidParameters => write!(f, "unsupported DRBG parameters"),
+            DrbgError::InvalidEntropyLength => write!(f, "invalid DRBG entropy input length"),
+            DrbgError::InputTooLong => write!(f, "DRBG input is too long"),
+            DrbgError::RequestTooLarge => write!(f, "DRBG request is too large"),
+            DrbgError::ReseedRequired => write!(f, "DRBG must be reseeded"),
+        }
+    }
+}
+
+impl std::error::Error for DrbgError {}
+
+/// The security strength in bytes that a hash with an `output_size` byte output supports,
+/// from SP 800-57. Hash_DRBG and HMAC_DRBG support at most 256 bits.
+pub fn hash_security_strength(output_size: usize) -> usize {
+    match output_size {
+        32.. => 32,
+        24.. => 24,
+        16.. => 16,
+        _ => 14,
+    }
+}
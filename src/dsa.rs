@@ -0,0 +1,1111 @@
+/**
+* This is an implementation of DSA from FIPS 186-4, with the provable domain parameters of
+* appendix A.1.2, the verifiable canonical generator of appendix A.2.3 and the deterministic
+* nonces of RFC 6979.
+* p and q come from the Shawe-Taylor construction of appendix C.6: each prime is grown from one
+* about half its size and proven prime by Pocklington's criterion, so no probabilistic test is
+* involved and anyone holding the seeds can regenerate and check them. Candidates with a small
+* factor are dropped by trial division before the Pocklington test, which could not pass for them
+* anyway, so the primes, seeds and counters are exactly those of the standard.
+* Signing takes its nonce from HMAC_DRBG as in RFC 6979 section 3.2, over the hash used for the
+* message, which is truncated to the length of q. The arithmetic is `BigInt`, and the secret
+* exponentiations go through `mod_pow`, which does the same work for every exponent of a length.
+*/
+use crate::big_int::BigInt;
+use crate::hash::Hash;
+use crate::hmac_drbg::HmacDrbg;
+use crate::rng::CryptoRng;
+
+///Primes below this are tried as divisors before the Pocklington test
+const TRIAL_DIVISION_BOUND: u32 = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DsaError {
+    ///p and q do not have one of the allowed sizes, q does not divide p - 1 or g does not have
+    ///order q, or the parameters do not match their seeds
+    InvalidParameters,
+    ///The first seed is shorter than N bits or below 2^(N-1)
+    InvalidSeed,
+    ///The hash output is shorter than q, too short for parameter generation
+    HashTooShort,
+    ///The counter limit was reached, generation must restart from another seed
+    GenerationFailed,
+    ///The public key is out of range or outside the subgroup of order q
+    InvalidPublicKey,
+    ///The secret key is not in 1..q-1
+    InvalidSecretKey,
+    ///L = 1024, N = 160 parameters can only verify legacy signatures, not be generated or sign
+    LegacySize,
+    InvalidSignature,
+}
+
+impl std::fmt::Display for DsaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DsaError::InvalidParameters => write!(f, "invalid domain parameters"),
+            DsaError::InvalidSeed => write!(f, "invalid domain parameter seed"),
+            DsaError::HashTooShort => write!(f, "the hash is shorter than q"),
+            DsaError::GenerationFailed => write!(f, "parameter generation failed for this seed"),
+            DsaError::InvalidPublicKey => write!(f, "invalid public key"),
+            DsaError::InvalidSecretKey => write!(f, "invalid secret key"),
+            DsaError::LegacySize => write!(f, "1024 bit DSA is only allowed for verification"),
+            DsaError::InvalidSignature => write!(f, "invalid signature"),
+        }
+    }
+}
+
+impl std::error::Error for DsaError {}
+
+/// The (L, N) bit lengths of p and q allowed by FIPS 186-4 section 4.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterSize {
+    ///Only for verifying legacy signatures
+    L1024N160,
+    L2048N224,
+    L2048N256,
+    L3072N256,
+}
+
+impl ParameterSize {
+    /// (L, N)
+    pub fn lengths(self) -> (usize, usize) {
+        match self {
+            ParameterSize::L1024N160 => (1024, 160),
+            ParameterSize::L2048N224 => (2048, 224),
+            ParameterSize::L2048N256 => (2048, 256),
+            ParameterSize::L3072N256 => (3072, 256),
+        }
+    }
+
+    fn from_lengths(l: usize, n: usize) -> Option<ParameterSize> {
+        [
+            ParameterSize::L1024N160,
+            ParameterSize::L2048N224,
+            ParameterSize::L2048N256,
+            ParameterSize::L3072N256,
+        ]
+        .into_iter()
+        .find(|size| size.lengths() == (l, n))
+    }
+}
+
+fn power_of_two(bits: usize) -> BigInt {
+    let mut power = BigInt::init(bits / 8 + 1);
+    power.set([vec![0; bits / 8], vec![1 << (bits % 8)]].concat());
+    power
+}
+
+fn ceil_div(a: BigInt, b: BigInt) -> BigInt {
+    (a + b.clone() - BigInt::from_u64(1)) / b
+}
+
+/// seed + offset as an integer of the seed's length, wrapping around
+fn seed_plus(seed: &[u8], offset: u64) -> Vec<u8> {
+    let mut result = seed.to_vec();
+    let mut carry = offset as u128;
+    for byte in result.iter_mut().rev() {
+        carry += *byte as u128;
+        *byte = carry as u8;
+        carry >>= 8;
+    }
+    result
+}
+
+/// The sum of Hash(seed + i) 2^(i outlen) for i in 0..count
+fn hash_sum<H: Hash>(seed: &[u8], count: usize) -> BigInt {
+    let bytes: Vec<u8> = (0..count)
+        .rev()
+        .flat_map(|i| H::digest(&seed_plus(seed, i as u64)))
+        .collect();
+    BigInt::from_be_bytes(&bytes)
+}
+
+fn is_small_prime(c: u64) -> bool {
+    c >= 2
+        && (2..)
+            .take_while(|d| d * d <= c)
+            .all(|d| !c.is_multiple_of(d))
+}
+
+/// A prime with the seed and counter reached when it was found
+struct PrimeResult {
+    prime: BigInt,
+    seed: Vec<u8>,
+    counter: u32,
+}
+
+/// The Shawe-Taylor random prime routine of FIPS 186-4 appendix C.6
+fn st_random_prime<H: Hash>(length: usize, input_seed: &[u8]) -> Option<PrimeResult> {
+    if length < 2 {
+        return None;
+    }
+    if length >= 33 {
+        let c0 = st_random_prime::<H>(length.div_ceil(2) + 1, input_seed)?;
+        return pocklington::<H>(length, &BigInt::from_u64(1), c0, 4 * length);
+    }
+
+    let mut seed = input_seed.to_vec();
+    let mut counter = 0;
+    loop {
+        let c: Vec<u8> = H::digest(&seed)
+            .iter()
+            .zip(H::digest(&seed_plus(&seed, 1)))
+            .map(|(a, b)| a ^ b)
+            .collect();
+        let low = u64::from_be_bytes(c[c.len() - 8..].try_into().unwrap());
+        let top = 1u64 << (length - 1);
+        let c = (top + low % top) | 1;
+        counter += 1;
+        seed = seed_plus(&seed, 2);
+        if is_small_prime(c) {
+            return Some(PrimeResult {
+                prime: BigInt::from_u64(c),
+                seed,
+                counter,
+            });
+        }
+        if counter as usize > 4 * length {
+            return None;
+        }
+    }
+}
+
+/// The search shared by appendix C.6 and A.1.2.1.2: the first c = 2 t m c0 + 1 of `length` bits
+/// from a hashed starting t, proven prime by Pocklington's criterion with the prime c0 > sqrt(c).
+/// It fails once `max_tries` candidates have been rejected
+fn pocklington<H: Hash>(
+    length: usize,
+    m: &BigInt,
+    c0: PrimeResult,
+    max_tries: usize,
+) -> Option<PrimeResult> {
+    let one = BigInt::from_u64(1);
+    let iterations = length.div_ceil(8 * H::OUTPUT_SIZE) - 1;
+    let PrimeResult {
+        prime: c0,
+        mut seed,
+        mut counter,
+    } = c0;
+    let old_counter = counter;
+    let small_primes: Vec<u32> = (3..TRIAL_DIVISION_BOUND)
+        .filter(|&d| is_small_prime(d as u64))
+        .collect();
+
+    let top = power_of_two(length - 1);
+    let x = hash_sum::<H>(&seed, iterations + 1);
+    seed = seed_plus(&seed, iterations as u64 + 1);
+    let x = top.clone() + x % top.clone();
+    let step = BigInt::from_u64(2) * m.clone() * c0.clone();
+    let mut t = ceil_div(x, step.clone());
+    loop {
+        if step.clone() * t.clone() + one.clone() > power_of_two(length) {
+            t = ceil_div(top.clone(), step.clone());
+        }
+        let c = step.clone() * t.clone() + one.clone();
+        counter += 1;
+        let a_seed = seed;
+        seed = seed_plus(&a_seed, iterations as u64 + 1);
+
+        if small_primes.iter().all(|&d| c.rem_u32(d) != 0) {
+            let a = hash_sum::<H>(&a_seed, iterations + 1);
+            let a = a % (c.clone() - BigInt::from_u64(3)) + BigInt::from_u64(2);
+            let z = a.mod_pow(&(BigInt::from_u64(2) * t.clone() * m.clone()), &c);
+            if z.mod_pow(&c0, &c) == one && (z.clone() - one.clone()).gcd(&c) == one {
+                return Some(PrimeResult {
+                    prime: c,
+                    seed,
+                    counter,
+                });
+            }
+        }
+        if (counter - old_counter) as usize >= max_tries {
+            return None;
+        }
+        t = t + one.clone();
+    }
+}
+
+/// The verifiable canonical generator of appendix A.2.3
+fn canonical_generator<H: Hash>(
+    p: &BigInt,
+    q: &BigInt,
+    domain_parameter_seed: &[u8],
+    index: u8,
+) -> Option<BigInt> {
+    let e = (p.clone() - BigInt::from_u64(1)) / q.clone();
+    (1..=u16::MAX).find_map(|count| {
+        let u = [
+            domain_parameter_seed,
+            b"ggen",
+            &[index],
+            &count.to_be_bytes(),
+        ]
+        .concat();
+        let g = BigInt::from_be_bytes(&H::digest(&u)).mod_pow(&e, p);
+        (g >= BigInt::from_u64(2)).then_some(g)
+    })
+}
+
+fn to_bytes(value: &BigInt, bits: usize) -> Vec<u8> {
+    value.to_be_bytes(bits.div_ceil(8))
+}
+
+/// The domain parameters p, q and g
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameters {
+    p: BigInt,
+    q: BigInt,
+    g: BigInt,
+}
+
+impl Parameters {
+    /// Parameters from big endian p, q and g. The sizes, q | p - 1 and the order of g are
+    /// checked, but not that p and q are prime, use `ProvableParameters::validate` for that
+    pub fn new(p: &[u8], q: &[u8], g: &[u8]) -> Result<Parameters, DsaError> {
+        let p = BigInt::from_be_bytes(p);
+        let q = BigInt::from_be_bytes(q);
+        let g = BigInt::from_be_bytes(g);
+        let one = BigInt::from_u64(1);
+        if ParameterSize::from_lengths(p.bit_len(), q.bit_len()).is_none()
+            || !((p.clone() - one.clone()) % q.clone()).is_zero()
+            || g <= one
+            || g >= p
+            || g.mod_pow(&q, &p) != one
+        {
+            return Err(DsaError::InvalidParameters);
+        }
+        Ok(Parameters { p, q, g })
+    }
+
+    /// Generate provable parameters from a random first seed of N bits, with generator index 1.
+    /// L1024N160 is refused with `LegacySize`
+    pub fn generate<H: Hash>(
+        size: ParameterSize,
+        rng: &mut impl CryptoRng,
+    ) -> Result<ProvableParameters, DsaError> {
+        if size == ParameterSize::L1024N160 {
+            return Err(DsaError::LegacySize);
+        }
+        let (_, n) = size.lengths();
+        loop {
+            let mut first_seed = rng.random_vec(n / 8);
+            first_seed[0] |= 0x80;
+            match Parameters::from_seed::<H>(size, &first_seed, 1) {
+                Err(DsaError::GenerationFailed) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// The provable primes of appendix A.1.2.1.2 and the canonical generator with `index`, all
+    /// derived from `first_seed` with `H`
+    pub fn from_seed<H: Hash>(
+        size: ParameterSize,
+        first_seed: &[u8],
+        index: u8,
+    ) -> Result<ProvableParameters, DsaError> {
+        let (l, n) = size.lengths();
+        if 8 * H::OUTPUT_SIZE < n {
+            return Err(DsaError::HashTooShort);
+        }
+        if 8 * first_seed.len() < n || BigInt::from_be_bytes(first_seed) < power_of_two(n - 1) {
+            return Err(DsaError::InvalidSeed);
+        }
+
+        let q = st_random_prime::<H>(n, first_seed).ok_or(DsaError::GenerationFailed)?;
+        let p0 = st_random_prime::<H>(l / 2 + 1, &q.seed).ok_or(DsaError::GenerationFailed)?;
+        let p = pocklington::<H>(l, &q.prime, p0, 4 * l + 1).ok_or(DsaError::GenerationFailed)?;
+
+        let domain_parameter_seed = [first_seed, &p.seed, &q.seed].concat();
+        let g = canonical_generator::<H>(&p.prime, &q.prime, &domain_parameter_seed, index)
+            .ok_or(DsaError::GenerationFailed)?;
+        Ok(ProvableParameters {
+            parameters: Parameters {
+                p: p.prime,
+                q: q.prime,
+                g,
+            },
+            first_seed: first_seed.to_vec(),
+            p_seed: p.seed,
+            q_seed: q.seed,
+            p_gen_counter: p.counter,
+            q_gen_counter: q.counter,
+            index,
+        })
+    }
+
+    /// (L, N)
+    pub fn size(&self) -> ParameterSize {
+        ParameterSize::from_lengths(self.p.bit_len(), self.q.bit_len())
+            .expect("the sizes are checked on construction")
+    }
+
+    pub fn p(&self) -> Vec<u8> {
+        to_bytes(&self.p, self.p.bit_len())
+    }
+
+    pub fn q(&self) -> Vec<u8> {
+        to_bytes(&self.q, self.q.bit_len())
+    }
+
+    pub fn g(&self) -> Vec<u8> {
+        to_bytes(&self.g, self.p.bit_len())
+    }
+}
+
+/// Domain parameters with the seeds and counters that prove how they were made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvableParameters {
+    pub parameters: Parameters,
+    ///The seed generation started from, at least N bits long
+    pub first_seed: Vec<u8>,
+    pub p_seed: Vec<u8>,
+    pub q_seed: Vec<u8>,
+    pub p_gen_counter: u32,
+    pub q_gen_counter: u32,
+    ///Index of the canonical generator g
+    pub index: u8,
+}
+
+impl ProvableParameters {
+    /// Regenerate the parameters from the first seed with `H` and compare, as in appendix
+    /// A.1.2.2 and A.2.4
+    pub fn validate<H: Hash>(&self) -> Result<(), DsaError> {
+        let size =
+            ParameterSize::from_lengths(self.parameters.p.bit_len(), self.parameters.q.bit_len())
+                .ok_or(DsaError::InvalidParameters)?;
+        match Parameters::from_seed::<H>(size, &self.first_seed, self.index) {
+            Ok(regenerated) if regenerated == *self => Ok(()),
+            _ => Err(DsaError::InvalidParameters),
+        }
+    }
+}
+
+/// A DSA signature (r, s)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    r: BigInt,
+    s: BigInt,
+    ///Length in bytes of each half, the length of q
+    size: usize,
+}
+
+impl Signature {
+    /// Read r || s, each big endian and exactly the length of q, so there is one encoding per
+    /// signature
+    pub fn from_bytes(parameters: &Parameters, bytes: &[u8]) -> Result<Signature, DsaError> {
+        let size = parameters.q.bit_len().div_ceil(8);
+        if bytes.len() != 2 * size {
+            return Err(DsaError::InvalidSignature);
+        }
+        Ok(Signature {
+            r: BigInt::from_be_bytes(&bytes[..size]),
+            s: BigInt::from_be_bytes(&bytes[size..]),
+            size,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.r.to_be_bytes(self.size), self.s.to_be_bytes(self.size)].concat()
+    }
+}
+
+/// The leftmost bits of a digest up to the length of q, as an integer
+fn digest_to_int(digest: &[u8], q: &BigInt) -> BigInt {
+    let len = q.bit_len().div_ceil(8);
+    BigInt::from_be_bytes(&digest[..digest.len().min(len)])
+}
+
+/// a^-1 mod q for the prime q
+fn invert(a: &BigInt, q: &BigInt) -> BigInt {
+    a.mod_pow(&(q.clone() - BigInt::from_u64(2)), q)
+}
+
+/// A secret key x in 1..q-1
+#[derive(Clone)]
+pub struct SecretKey<'a> {
+    parameters: &'a Parameters,
+    x: BigInt,
+}
+
+impl<'a> SecretKey<'a> {
+    /// Read a big endian x
+    pub fn from_bytes(parameters: &'a Parameters, bytes: &[u8]) -> Result<SecretKey<'a>, DsaError> {
+        let x = BigInt::from_be_bytes(bytes);
+        if x.is_zero() || x >= parameters.q {
+            return Err(DsaError::InvalidSecretKey);
+        }
+        Ok(SecretKey { parameters, x })
+    }
+
+    /// Generate a fresh random key, drawing again whenever the value is out of range
+    pub fn generate(parameters: &'a Parameters, rng: &mut impl CryptoRng) -> SecretKey<'a> {
+        let len = parameters.q.bit_len().div_ceil(8);
+        loop {
+            if let Ok(key) = SecretKey::from_bytes(parameters, &rng.random_vec(len)) {
+                return key;
+            }
+        }
+    }
+
+    /// x as big endian bytes, the length of q
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(&self.x, self.parameters.q.bit_len())
+    }
+
+    pub fn parameters(&self) -> &'a Parameters {
+        self.parameters
+    }
+
+    pub fn public_key(&self) -> PublicKey<'a> {
+        let parameters = self.parameters;
+        PublicKey {
+            parameters,
+            y: parameters.g.mod_pow(&self.x, &parameters.p),
+        }
+    }
+
+    /// Hash the message with `H` and sign it
+    pub fn sign<H: Hash>(&self, message: &[u8]) -> Result<Signature, DsaError> {
+        self.sign_prehashed::<H>(&H::digest(message))
+    }
+
+    /// Sign a digest computed elsewhere, `H` derives the nonce and should be the hash used.
+    /// Keys with L1024N160 parameters are refused with `LegacySize`
+    pub fn sign_prehashed<H: Hash>(&self, digest: &[u8]) -> Result<Signature, DsaError> {
+        if self.parameters.size() == ParameterSize::L1024N160 {
+            return Err(DsaError::LegacySize);
+        }
+        let Parameters { p, q, g } = self.parameters;
+        let bits = q.bit_len();
+        let z = digest_to_int(digest, q);
+        let mut nonces = HmacDrbg::<H>::rfc6979(
+            &to_bytes(&self.x, bits),
+            &to_bytes(&(z.clone() % q.clone()), bits),
+        );
+        loop {
+            let mut k_bytes = vec![0u8; bits.div_ceil(8)];
+            nonces
+                .generate(&mut k_bytes, &[])
+                .expect("a signature takes few nonces");
+            let k = BigInt::from_be_bytes(&k_bytes);
+            if k.is_zero() || k >= *q {
+                continue;
+            }
+            let r = g.mod_pow(&k, p) % q.clone();
+            let s = (invert(&k, q) * (z.clone() + self.x.clone() * r.clone())) % q.clone();
+            if !r.is_zero() && !s.is_zero() {
+                return Ok(Signature {
+                    r,
+                    s,
+                    size: bits.div_ceil(8),
+                });
+            }
+        }
+    }
+}
+
+/// A public key y = g^x mod p
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey<'a> {
+    parameters: &'a Parameters,
+    y: BigInt,
+}
+
+impl<'a> PublicKey<'a> {
+    /// Read a big endian y, which must lie in 2..p-2 and in the subgroup of order q
+    pub fn from_bytes(parameters: &'a Parameters, bytes: &[u8]) -> Result<PublicKey<'a>, DsaError> {
+        let y = BigInt::from_be_bytes(bytes);
+        let one = BigInt::from_u64(1);
+        if y <= one
+            || y >= parameters.p.clone() - one.clone()
+            || y.mod_pow(&parameters.q, &parameters.p) != one
+        {
+            return Err(DsaError::InvalidPublicKey);
+        }
+        Ok(PublicKey { parameters, y })
+    }
+
+    /// y as big endian bytes, the length of p
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(&self.y, self.parameters.p.bit_len())
+    }
+
+    pub fn parameters(&self) -> &'a Parameters {
+        self.parameters
+    }
+
+    /// Hash the message with `H` and check the signature
+    pub fn verify<H: Hash>(&self, message: &[u8], signature: &Signature) -> Result<(), DsaError> {
+        self.verify_prehashed(&H::digest(message), signature)
+    }
+
+    pub fn verify_prehashed(&self, digest: &[u8], signature: &Signature) -> Result<(), DsaError> {
+        let Parameters { p, q, g } = self.parameters;
+        let Signature { r, s, size } = signature;
+        if *size != q.bit_len().div_ceil(8) || r.is_zero() || *r >= *q || s.is_zero() || *s >= *q {
+            return Err(DsaError::InvalidSignature);
+        }
+        let w = invert(s, q);
+        let u1 = (digest_to_int(digest, q) * w.clone()) % q.clone();
+        let u2 = (r.clone() * w) % q.clone();
+        let v = (g.mod_pow(&u1, p) * self.y.mod_pow(&u2, p)) % p.clone() % q.clone();
+        if v != *r {
+            return Err(DsaError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::big_int::BigInt;
+    use crate::dsa::{
+        DsaError, ParameterSize, Parameters, ProvableParameters, PublicKey, SecretKey, Signature,
+    };
+    use crate::hash::Hash;
+    use crate::rng::SeededRng;
+    use crate::sha2::{Sha224, Sha256, Sha384, Sha512};
+    use crate::test_util::hex;
+
+    // RFC 6979 appendix A.2.1
+    fn parameters_1024() -> Parameters {
+        Parameters::new(
+            &hex(
+                "86F5CA03DCFEB225063FF830A0C769B9DD9D6153AD91D7CE27F787C43278B447\
+                 E6533B86B18BED6E8A48B784A14C252C5BE0DBF60B86D6385BD2F12FB763ED88\
+                 73ABFD3F5BA2E0A8C0A59082EAC056935E529DAF7C610467899C77ADEDFC846C\
+                 881870B7B19B2B58F9BE0521A17002E3BDD6B86685EE90B3D9A1B02B782B1779",
+            ),
+            &hex("996F967F6C8E388D9E28D01E205FBA957A5698B1"),
+            &hex(
+                "07B0F92546150B62514BB771E2A0C0CE387F03BDA6C56B505209FF25FD3C133D\
+                 89BBCD97E904E09114D9A7DEFDEADFC9078EA544D2E401AEECC40BB9FBBF78FD\
+                 87995A10A1C27CB7789B594BA7EFB5C4326A9FE59A070E136DB77175464ADCA4\
+                 17BE5DCE2F40D10A46A3A3943F26AB7FD9C0398FF8C76EE0A56826A8A88F1DBD",
+            ),
+        )
+        .unwrap()
+    }
+
+    // RFC 6979 appendix A.2.2
+    fn parameters_2048() -> Parameters {
+        Parameters::new(
+            &hex(
+                "9DB6FB5951B66BB6FE1E140F1D2CE5502374161FD6538DF1648218642F0B5C48\
+                 C8F7A41AADFA187324B87674FA1822B00F1ECF8136943D7C55757264E5A1A44F\
+                 FE012E9936E00C1D3E9310B01C7D179805D3058B2A9F4BB6F9716BFE6117C6B5\
+                 B3CC4D9BE341104AD4A80AD6C94E005F4B993E14F091EB51743BF33050C38DE2\
+                 35567E1B34C3D6A5C0CEAA1A0F368213C3D19843D0B4B09DCB9FC72D39C8DE41\
+                 F1BF14D4BB4563CA28371621CAD3324B6A2D392145BEBFAC748805236F5CA2FE\
+                 92B871CD8F9C36D3292B5509CA8CAA77A2ADFC7BFD77DDA6F71125A7456FEA15\
+                 3E433256A2261C6A06ED3693797E7995FAD5AABBCFBE3EDA2741E375404AE25B",
+            ),
+            &hex("F2C3119374CE76C9356990B465374A17F23F9ED35089BD969F61C6DDE9998C1F"),
+            &hex(
+                "5C7FF6B06F8F143FE8288433493E4769C4D988ACE5BE25A0E24809670716C613\
+                 D7B0CEE6932F8FAA7C44D2CB24523DA53FBE4F6EC3595892D1AA58C4328A06C4\
+                 6A15662E7EAA703A1DECF8BBB2D05DBE2EB956C142A338661D10461C0D135472\
+                 085057F3494309FFA73C611F78B32ADBB5740C361C9F35BE90997DB2014E2EF5\
+                 AA61782F52ABEB8BD6432C4DD097BC5423B285DAFB60DC364E8161F4A2A35ACA\
+                 3A10B1C4D203CC76A470A33AFDCBDD92959859ABD8B56E1725252D78EAC66E71\
+                 BA9AE3F1DD2487199874393CD4D832186800654760E1E34C09E4D155179F9EC0\
+                 DC4473F996BDCE6EED1CABED8B6F116F7AD9CF505DF0F998E34AB27514B0FFE7",
+            ),
+        )
+        .unwrap()
+    }
+
+    fn check<H: Hash>(key: &SecretKey, message: &[u8], r: &str, s: &str) {
+        let test_result = key.sign::<H>(message).unwrap();
+        assert_eq!(test_result.to_bytes(), [hex(r), hex(s)].concat());
+        assert!(key.public_key().verify::<H>(message, &test_result).is_ok());
+    }
+
+    /// 1024 bit keys may no longer sign, but their signatures still verify
+    fn check_legacy<H: Hash>(key: &SecretKey, message: &[u8], r: &str, s: &str) {
+        assert_eq!(key.sign::<H>(message), Err(DsaError::LegacySize));
+        let signature =
+            Signature::from_bytes(key.parameters(), &[hex(r), hex(s)].concat()).unwrap();
+        assert!(key.public_key().verify::<H>(message, &signature).is_ok());
+    }
+
+    // RFC 6979 appendix A.2.1, SHA-384 and SHA-512 digests are truncated to 160 bits
+    #[test]
+    fn rfc6979_dsa_1024() {
+        let parameters = parameters_1024();
+        let key = SecretKey::from_bytes(
+            &parameters,
+            &hex("411602CB19A6CCC34494D79D98EF1E7ED5AF25F7"),
+        )
+        .unwrap();
+        assert_eq!(
+            key.public_key().to_bytes(),
+            hex(
+                "5DF5E01DED31D0297E274E1691C192FE5868FEF9E19A84776454B100CF16F653\
+                 92195A38B90523E2542EE61871C0440CB87C322FC4B4D2EC5E1E7EC766E1BE8D\
+                 4CE935437DC11C3C8FD426338933EBFE739CB3465F4D3668C5E473508253B1E6\
+                 82F65CBDC4FAE93C2EA212390E54905A86E2223170B44EAA7DA5DD9FFCFB7F3B"
+            )
+        );
+        check_legacy::<Sha224>(
+            &key,
+            b"sample",
+            "4BC3B686AEA70145856814A6F1BB53346F02101E",
+            "410697B92295D994D21EDD2F4ADA85566F6F94C1",
+        );
+        check_legacy::<Sha224>(
+            &key,
+            b"test",
+            "6868E9964E36C1689F6037F91F28D5F2C30610F2",
+            "49CEC3ACDC83018C5BD2674ECAAD35B8CD22940F",
+        );
+        check_legacy::<Sha256>(
+            &key,
+            b"sample",
+            "81F2F5850BE5BC123C43F71A3033E9384611C545",
+            "4CDD914B65EB6C66A8AAAD27299BEE6B035F5E89",
+        );
+        check_legacy::<Sha256>(
+            &key,
+            b"test",
+            "22518C127299B0F6FDC9872B282B9E70D0790812",
+            "6837EC18F150D55DE95B5E29BE7AF5D01E4FE160",
+        );
+        check_legacy::<Sha384>(
+            &key,
+            b"sample",
+            "07F2108557EE0E3921BC1774F1CA9B410B4CE65A",
+            "54DF70456C86FAC10FAB47C1949AB83F2C6F7595",
+        );
+        check_legacy::<Sha384>(
+            &key,
+            b"test",
+            "854CF929B58D73C3CBFDC421E8D5430CD6DB5E66",
+            "91D0E0F53E22F898D158380676A871A157CDA622",
+        );
+        check_legacy::<Sha512>(
+            &key,
+            b"sample",
+            "16C3491F9B8C3FBBDD5E7A7B667057F0D8EE8E1B",
+            "02C36A127A7B89EDBB72E4FFBC71DABC7D4FC69C",
+        );
+        check_legacy::<Sha512>(
+            &key,
+            b"test",
+            "8EA47E475BA8AC6F2D821DA3BD212D11A3DEB9A0",
+            "7C670C7AD72B6C050C109E1790008097125433E8",
+        );
+    }
+
+    // RFC 6979 appendix A.2.2, SHA-224 digests are shorter than q
+    #[test]
+    fn rfc6979_dsa_2048() {
+        let parameters = parameters_2048();
+        let key = SecretKey::from_bytes(
+            &parameters,
+            &hex("69C7548C21D0DFEA6B9A51C9EAD4E27C33D3B3F180316E5BCAB92C933F0E4DBC"),
+        )
+        .unwrap();
+        check::<Sha224>(
+            &key,
+            b"sample",
+            "DC9F4DEADA8D8FF588E98FED0AB690FFCE858DC8C79376450EB6B76C24537E2C",
+            "A65A9C3BC7BABE286B195D5DA68616DA8D47FA0097F36DD19F517327DC848CEC",
+        );
+        check::<Sha256>(
+            &key,
+            b"sample",
+            "EACE8BDBBE353C432A795D9EC556C6D021F7A03F42C36E9BC87E4AC7932CC809",
+            "7081E175455F9247B812B74583E9E94F9EA79BD640DC962533B0680793A38D53",
+        );
+        check::<Sha256>(
+            &key,
+            b"test",
+            "8190012A1969F9957D56FCCAAD223186F423398D58EF5B3CEFD5A4146A4476F0",
+            "7452A53F7075D417B4B013B278D1BB8BBD21863F5E7B1CEE679CF2188E1AB19E",
+        );
+        check::<Sha512>(
+            &key,
+            b"test",
+            "89EC4BB1400ECCFF8E7D9AA515CD1DE7803F2DAFF09693EE7FD1353E90A68307",
+            "C9F0BDABCC0D880BB137A994CC7F3980CE91CC10FAF529FC46565B15CEA854E1",
+        );
+    }
+
+    // checked against a direct python transcription of appendix A.1.2.1.2, C.6 and A.2.3, with
+    // p and q confirmed prime by sympy
+    #[test]
+    fn provable_parameters_1024() {
+        let first_seed = hex("d5f3a6a0b54e8c9d2c1e7f4b3a291807f6e5d4c3");
+        let test_result =
+            Parameters::from_seed::<Sha256>(ParameterSize::L1024N160, &first_seed, 1).unwrap();
+        let parameters = &test_result.parameters;
+        assert_eq!(
+            parameters.p(),
+            hex(
+                "b72524f93a396839d174b51d9f8a2f5b238ef9f28f91fb7d1c6a0aacd97a8d01\
+                 fca0093b269177ce4f08dbea116c0e94ae018c288912cd023165f3188d9db068\
+                 00f01193cb42abea45e30b0c298fd3da4bba8fcfa2e316cfaa2d60ffc03aa7ad\
+                 7193cce3471918b073f5d13a822a46ff4d45fb47fdf3fcfcf4c2e9ec138abdf5"
+            )
+        );
+        assert_eq!(
+            parameters.q(),
+            hex("cd2a0db4b30520601250076e7940ed7ed07924fb")
+        );
+        assert_eq!(
+            parameters.g(),
+            hex(
+                "703b02b1fa78fb5957d2f940c70c95eccb9eb223d734a04ee8d290d7ca71164b\
+                 ddbaa21a3e3ab3e05a809172a0c55265ffb5d98640daca17c8db92eec7128113\
+                 9367032618815c88b960ad79859797c54e34b21fa8f98e6176ad8da02170cb21\
+                 a294af4eb6b943db7aad58d1b0a3c5cc42caa51692726641778a34206c50f890"
+            )
+        );
+        assert_eq!(
+            test_result.p_seed,
+            hex("d5f3a6a0b54e8c9d2c1e7f4b3a291807f6e5e18f")
+        );
+        assert_eq!(
+            test_result.q_seed,
+            hex("d5f3a6a0b54e8c9d2c1e7f4b3a291807f6e5d519")
+        );
+        assert_eq!(
+            (test_result.p_gen_counter, test_result.q_gen_counter),
+            (901, 66)
+        );
+        assert_eq!(parameters.size(), ParameterSize::L1024N160);
+        assert!(test_result.validate::<Sha256>().is_ok());
+
+        let tampered = ProvableParameters {
+            p_gen_counter: 900,
+            ..test_result.clone()
+        };
+        assert_eq!(
+            tampered.validate::<Sha256>(),
+            Err(DsaError::InvalidParameters)
+        );
+        let tampered = ProvableParameters {
+            index: 2,
+            ..test_result
+        };
+        assert_eq!(
+            tampered.validate::<Sha256>(),
+            Err(DsaError::InvalidParameters)
+        );
+    }
+
+    /// Verify (r, s) over `message` with the public key y of the parameters p, q and g
+    fn verify_with<H: Hash>(
+        p: &str,
+        q: &str,
+        g: &str,
+        y: &str,
+        message: &str,
+        r: &str,
+        s: &str,
+    ) -> Result<(), DsaError> {
+        let parameters = Parameters::new(&hex(p), &hex(q), &hex(g)).unwrap();
+        let public_key = PublicKey::from_bytes(&parameters, &hex(y)).unwrap();
+        let signature = Signature::from_bytes(&parameters, &[hex(r), hex(s)].concat()).unwrap();
+        public_key.verify::<H>(&hex(message), &signature)
+    }
+
+    // ACVP DSA sigVer, L = 3072, N = 256, SHA2-512, as carried in OpenSSL's test/acvp_test.inc:
+    // the second case changes the first message byte and is expected to fail
+    #[test]
+    fn acvp_sigver_3072_256() {
+        let p = "d2902e38a532bb63e0c320d926062106853a4ce31383ca438c9c76c06560277e\
+                 7ca0839f6591f9165fe8600cc6912035e7f183e6f88cbb4cfff54d098e8372cb\
+                 225fd085a9603c4aa6dd731fcfd0d742b87261db91e3bb5c2141fd97d0817253\
+                 77e0159ec0d06ab47ff863391a2563844dba2c299428ce5b9ac314ad9d821d8f\
+                 f3e96065280b0e486bcc059d3b1f1d0aa7f822b0e152b0258fea2528c96f44cd\
+                 a41613e8d0db436eceec0ba83e5310a2520ebb6363842c12932998af388f0b86\
+                 16990e39a84a0bcdaa668f4c15b7a5bb22778de805352daa8d83debc153dc295\
+                 0e478541ade3b170761b629e968b18d7e3b5f86e856761547c850891f4463f01\
+                 9948183c0dc72deca4111d4f7fbf3ae89c1cae9e30321f81ef14fe5cc25cd06a\
+                 7c18889fc4977d4b3b01eb59581c006b3ed68080860639880d231ed65e1f923b\
+                 ec500ba0834f10deaf7b19bcbd72e642fed7ef22d3836b30a3950d3e619ebc0e\
+                 147e61053dba4eef31755d101ebabdba894d3a5b03b1ae27472d03b18a741bf3";
+        let q = "ac718d81052fab72b9969498b5192be27806aa32fcb9d2fd26c4506f81d804ab";
+        let g = "3d0b463913ff67a88ce88a46469ae670a1f548f584f893579a4f2cd426491c83\
+                 64140b5bef6f6f9114c54de886475cfcaebfd832e296b961703f2429fa415d8e\
+                 d0b0f126d57ce61748e5040e5814eeba64e9f16a7c17ab7b28cf697ddc54caf2\
+                 4c2217ddc31a02e28ee6a4fb84272be814f33dac590cab690e73df82c1ded7d9\
+                 a7ca8f4bce8a05bd07c829bb46292a4fa712199101a0ae16efc1c54b03f053dc\
+                 fc1cc473b7bf53eb1963ca30535412900e43c766ff29fca4e8f64b763ba2656b\
+                 9efaba5b549434f6d1202af73972a3dbd18fa64d1bb12dc57fc52c7e6dd9c8c2\
+                 19c0c4c277d94c6377590c5bfe69efbf5847693e49a71b986ce5a7428b0e6805\
+                 488039f4029ee29f1cda24c5b8ec03ea7a00dfcf58d0e7b7f3e736dd1f65f92d\
+                 6fc3e472fdba588db5df613d3bb5f308e7215a7dff021e0e4eb50d3d33f4a76d\
+                 f796c29685339c58725c97730edc5c6b3d68f7f00fcc01bb47013cb0524870b8\
+                 0c0f04b58f7050127c9dd1c12bfe95318f2dfaacae24dd13da76c234b94a3ec3";
+        let y = "91781cba8a2ff6ec9bd4732c1fc0fe79cfac0c3c0d81853dcd672b77994a5148\
+                 5803c968e6192628dc869f8fcedd1bcddd634ece764dd50d717304039c35d056\
+                 9825a706f26ba99f9ab12abdb77162990647772257fa242121b078267eecb6eb\
+                 82155368aeac5bdca67f6b26e65922551c3bfdd590a96fb3e6998e2672a102aa\
+                 37f68968f15a6d54ad9df303be3f9d853825b8dfb84321caf8dc12401eef3740\
+                 ce0e028863982e9389b043ac0e624c3814ac0ca303108eb460109bcc16a7b81d\
+                 73812612a89afe17bb2d335e8ca480bf8437ca0f502379202a8ed11f9f89984f\
+                 f5b60fb93cfc6c00bc762fb4fd22133726cd9baf4c8916d07344f97160a23efe\
+                 24fefcfe9091ed92570afaeb2199e39aff5c7485c26d8390ee84051a00ac87a7\
+                 7887ca70fcb0f43b617cd009632b5ec2fe1541b39ffc19e34d3c6f89eb8a43ec\
+                 8efbeccd992e4b0299acc662aac50fa30bbbcd510c19a77a436caa26282ac99d\
+                 97ae8374dac40398945828bc321dd4f26f8992d4809bde6bc56fdb7a031cf555";
+        let r = "2a240fa704f1e0603b07deb65f012081dd64220f9f2e6733b756de17d0ed9d30";
+        let s = "1593816ec2076e066abf62bf93a6cb6eba1e7257270e859e8c429a416327744f";
+        let message = "32e96447ed3bf0c0cac290f5106099824d1344fa92d1fd502680ea7b7dc5f0b7";
+        assert_eq!(verify_with::<Sha512>(p, q, g, y, message, r, s), Ok(()));
+        let message = "31e96447ed3bf0c0cac290f5106099824d1344fa92d1fd502680ea7b7dc5f0b7";
+        assert_eq!(
+            verify_with::<Sha512>(p, q, g, y, message, r, s),
+            Err(DsaError::InvalidSignature)
+        );
+    }
+
+    // CAVP 186-3 SigGen.txt [mod = L=2048, N=256, SHA-256] first entry: y is recomputed from x and the
+    // signature verifies, then fails once r or the message is altered
+    #[test]
+    fn cavp_siggen_2048_256() {
+        let p = "a8adb6c0b4cf9588012e5deff1a871d383e0e2a85b5e8e03d814fe13a059705e\
+                 663230a377bf7323a8fa117100200bfd5adf857393b0bbd67906c081e585410e\
+                 38480ead51684dac3a38f7b64c9eb109f19739a4517cd7d5d6291e8af20a3fbf\
+                 17336c7bf80ee718ee087e322ee41047dabefbcc34d10b66b644ddb3160a28c0\
+                 639563d71993a26543eadb7718f317bf5d9577a6156561b082a10029cd44012b\
+                 18de6844509fe058ba87980792285f2750969fe89c2cd6498db3545638d5379d\
+                 125dccf64e06c1af33a6190841d223da1513333a7c9d78462abaab31b9f96d5f\
+                 34445ceb6309f2f6d2c8dde06441e87980d303ef9a1ff007e8be2f0be06cc15f";
+        let q = "e71f8567447f42e75f5ef85ca20fe557ab0343d37ed09edc3f6e68604d6b9dfb";
+        let g = "5ba24de9607b8998e66ce6c4f812a314c6935842f7ab54cd82b19fa104abfb5d\
+                 84579a623b2574b37d22ccae9b3e415e48f5c0f9bcbdff8071d63b9bb956e547\
+                 af3a8df99e5d3061979652ff96b765cb3ee493643544c75dbe5bb39834531952\
+                 a0fb4b0378b3fcbb4c8b5800a5330392a2a04e700bb6ed7e0b85795ea38b1b96\
+                 2741b3f33b9dde2f4ec1354f09e2eb78e95f037a5804b6171659f88715ce1a9b\
+                 0cc90c27f35ef2f10ff0c7c7a2bb0154d9b8ebe76a3d764aa879af372f4240de\
+                 8347937e5a90cec9f41ff2f26b8da9a94a225d1a913717d73f10397d2183f1ba\
+                 3b7b45a68f1ff1893caf69a827802f7b6a48d51da6fbefb64fd9a6c5b75c4561";
+        let x = "446969025446247f84fdea74d02d7dd13672b2deb7c085be11111441955a377b";
+        let y = "5a55dceddd1134ee5f11ed85deb4d634a3643f5f36dc3a70689256469a0b651a\
+                 d22880f14ab85719434f9c0e407e60ea420e2a0cd29422c4899c416359dbb1e5\
+                 92456f2b3cce233259c117542fd05f31ea25b015d9121c890b90e0bad033be13\
+                 68d229985aac7226d1c8c2eab325ef3b2cd59d3b9f7de7dbc94af1a9339eb430\
+                 ca36c26c46ecfa6c5481711496f624e188ad7540ef5df26f8efacb820bd17a1f\
+                 618acb50c9bc197d4cb7ccac45d824a3bf795c234b556b06aeb9291734532520\
+                 84003f69fe98045fe74002ba658f93475622f76791d9b2623d1b5fff2cc16844\
+                 746efd2d30a6a8134bfc4c8cc80a46107901fb973c28fc553130f3286c1489da";
+        let message = "4e3a28bcf90d1d2e75f075d9fbe55b36c5529b17bc3a9ccaba6935c9e2054825\
+                 5b3dfae0f91db030c12f2c344b3a29c4151c5b209f5e319fdf1c23b190f64f1f\
+                 e5b330cb7c8fa952f9d90f13aff1cb11d63181da9efc6f7e15bfed4862d1a62c\
+                 7dcf3ba8bf1ff304b102b1ec3f1497dddf09712cf323f5610a9d10c3d9132659";
+        let r = "633055e055f237c38999d81c397848c38cce80a55b649d9e7905c298e2a51447";
+        let s = "2bbf68317660ec1e4b154915027b0bc00ee19cfc0bf75d01930504f2ce10a8b0";
+        let parameters = Parameters::new(&hex(p), &hex(q), &hex(g)).unwrap();
+        let key = SecretKey::from_bytes(&parameters, &hex(x)).unwrap();
+        assert_eq!(key.public_key().to_bytes(), hex(y));
+        assert_eq!(verify_with::<Sha256>(p, q, g, y, message, r, s), Ok(()));
+
+        let mut altered_r = hex(r);
+        altered_r[31] ^= 1;
+        let signature = Signature::from_bytes(&parameters, &[altered_r, hex(s)].concat()).unwrap();
+        assert_eq!(
+            key.public_key().verify::<Sha256>(&hex(message), &signature),
+            Err(DsaError::InvalidSignature)
+        );
+        let mut altered_message = hex(message);
+        altered_message[0] ^= 1;
+        let signature =
+            Signature::from_bytes(key.parameters(), &[hex(r), hex(s)].concat()).unwrap();
+        assert_eq!(
+            key.public_key()
+                .verify::<Sha256>(&altered_message, &signature),
+            Err(DsaError::InvalidSignature)
+        );
+    }
+
+    // CAVP 186-3 SigGen.txt [mod = L=3072, N=256, SHA-256] first entry: y is recomputed from x and the
+    // signature verifies, then fails once r or the message is altered
+    #[test]
+    fn cavp_siggen_3072_256() {
+        let p = "c7b86d7044218e367453d210e76433e4e27a983db1c560bb9755a8fb7d819912\
+                 c56cfe002ab1ff3f72165b943c0b28ed46039a07de507d7a29f738603decd127\
+                 0380a41f971f2592661a64ba2f351d9a69e51a888a05156b7fe1563c4b77ee93\
+                 a44949138438a2ab8bdcfc49b4e78d1cde766e54984760057d76cd740c94a4dd\
+                 25a46aa77b18e9d707d6738497d4eac364f4792d9766a16a0e234807e96b8c64\
+                 d404bbdb876e39b5799ef53fe6cb9bab62ef19fdcc2bdd905beda13b9ef7ac35\
+                 f1f557cb0dc458c019e2bc19a9f5dfc1e4eca9e6d466564124304a31f038605a\
+                 3e342da01be1c2b545610edd2c1397a3c8396588c6329efeb4e165af5b368a39\
+                 a88e4888e39f40bb3de4eb1416672f999fead37aef1ca9643ff32cdbc0fcebe6\
+                 28d7e46d281a989d43dd21432151af68be3f6d56acfbdb6c97d87fcb5e6291bf\
+                 8b4ee1275ae0eb4383cc753903c8d29f4adb6a547e405decdff288c5f6c7aa30\
+                 dcb12f84d392493a70933317c0f5e6552601fae18f17e6e5bb6bf396d32d8ab9";
+        let q = "876fa09e1dc62b236ce1c3155ba48b0ccfda29f3ac5a97f7ffa1bd87b68d2a4b";
+        let g = "110afebb12c7f862b6de03d47fdbc3326e0d4d31b12a8ca95b2dee2123bcc667\
+                 d4f72c1e7209767d2721f95fbd9a4d03236d54174fbfaff2c4ff7deae4738b20\
+                 d9f37bf0a1134c288b420af0b5792e47a92513c0413f346a4edbab2c45bdca13\
+                 f5341c2b55b8ba54932b9217b5a859e553f14bb8c120fbb9d99909dff5ea68e1\
+                 4b379964fd3f3861e5ba5cc970c4a180eef54428703961021e7bd68cb637927b\
+                 8cbee6805fa27285bfee4d1ef70e02c1a18a7cd78bef1dd9cdad45dde9cd6907\
+                 55050fc4662937ee1d6f4db12807ccc95bc435f11b71e7086048b1dab5913c60\
+                 55012de82e43a4e50cf93feff5dcab814abc224c5e0025bd868c3fc592041bba\
+                 04747c10af513fc36e4d91c63ee5253422cf4063398d77c52fcb011427cbfcfa\
+                 67b1b2c2d1aa4a3da72645cb1c767036054e2f31f88665a54461c885fb3219d5\
+                 ad8748a01158f6c7c0df5a8c908ba8c3e536822428886c7b500bbc15b49df746\
+                 b9de5a78fe3b4f6991d0110c3cbff458039dc36261cf46af4bc2515368f4abb7";
+        let x = "3470832055dade94e14cd8777171d18e5d06f66aeff4c61471e4eba74ee56164";
+        let y = "456a105c713566234838bc070b8a751a0b57767cb75e99114a1a46641e11da1f\
+                 a9f22914d808ad7148612c1ea55d25301781e9ae0c9ae36a69d87ba039ec7cd8\
+                 64c3ad094873e6e56709fd10d966853d611b1cff15d37fdee424506c184d62c7\
+                 033358be78c2250943b6f6d043d63b317de56e5ad8d1fd97dd355abe96452f8e\
+                 435485fb3b907b51900aa3f24418df50b4fcdafbf6137548c39373b8bc4ba3da\
+                 bb4746ebd17b87fcd6a2f197c107b18ec5b465e6e4cb430d9c0ce78da5988441\
+                 054a370792b730da9aba41a3169af26176f74e6f7c0c9c9b55b62bbe7ce38d46\
+                 95d48157e660c2acb63f482f55418150e5fee43ace84c540c3ba7662ae80835c\
+                 1a2d51890ea96ba206427c41ef8c38aa07d2a365e7e58380d8f4782e22ac2101\
+                 af732ee22758337b253637838e16f50f56d313d07981880d685557f7d79a6db8\
+                 23c61f1bb3dbc5d50421a4843a6f29690e78aa0f0cff304231818b81fc4a243f\
+                 c00f09a54c466d6a8c73d32a55e1abd5ec8b4e1afa32a79b01df85a81f3f5cfe";
+        let message = "cb06e02234263c22b80e832d6dc5a1bee5ea8af3bc2da752441c04027f176158\
+                 bfe68372bd67f84d489c0d49b07d4025962976be60437be1a2d01d3be0992afa\
+                 5abe0980e26a9da4ae72f827b423665195cc4eed6fe85c335b32d9c03c945a86\
+                 e7fa99373f0a30c6eca938b3afb6dff67adb8bece6f8cfec4b6a12ea281e2323";
+        let r = "53bae6c6f336e2eb311c1e92d95fc449a929444ef81ec4279660b200d59433de";
+        let s = "49f3a74e953e77a7941af3aefeef4ed499be209976a0edb3fa5e7cb961b0c112";
+        let parameters = Parameters::new(&hex(p), &hex(q), &hex(g)).unwrap();
+        let key = SecretKey::from_bytes(&parameters, &hex(x)).unwrap();
+        assert_eq!(key.public_key().to_bytes(), hex(y));
+        assert_eq!(verify_with::<Sha256>(p, q, g, y, message, r, s), Ok(()));
+
+        let mut altered_r = hex(r);
+        altered_r[31] ^= 1;
+        let signature = Signature::from_bytes(&parameters, &[altered_r, hex(s)].concat()).unwrap();
+        assert_eq!(
+            key.public_key().verify::<Sha256>(&hex(message), &signature),
+            Err(DsaError::InvalidSignature)
+        );
+        let mut altered_message = hex(message);
+        altered_message[0] ^= 1;
+        let signature =
+            Signature::from_bytes(key.parameters(), &[hex(r), hex(s)].concat()).unwrap();
+        assert_eq!(
+            key.public_key()
+                .verify::<Sha256>(&altered_message, &signature),
+            Err(DsaError::InvalidSignature)
+        );
+    }
+
+    // No CAVP PQGGen vectors for provable primes are available to this tree, so the L2048N256
+    // primes are checked with Miller-Rabin, which shares nothing with the Pocklington proofs
+    // that generation relies on
+    #[test]
+    fn provable_parameters_2048_256() {
+        let first_seed = hex("c3a5f1e2d4b6978a0b1c2d3e4f5a6b7c8d9eafb0c1d2e3f405162738495a6b7c");
+        let test_result =
+            Parameters::from_seed::<Sha256>(ParameterSize::L2048N256, &first_seed, 1).unwrap();
+        let parameters = &test_result.parameters;
+        assert_eq!(parameters.size(), ParameterSize::L2048N256);
+        for prime in [&parameters.p, &parameters.q] {
+            for base in [2, 3] {
+                assert!(prime.miller_rabin(&BigInt::from_u64(base)));
+            }
+        }
+        assert_eq!(
+            Parameters::new(&parameters.p(), &parameters.q(), &parameters.g()).as_ref(),
+            Ok(parameters)
+        );
+    }
+
+    #[test]
+    fn generated_parameters_sign_and_verify() {
+        let mut rng = SeededRng::from_seed(b"dsa");
+        let provable = Parameters::generate::<Sha256>(ParameterSize::L2048N224, &mut rng).unwrap();
+        let parameters = &provable.parameters;
+        assert_eq!(parameters.size(), ParameterSize::L2048N224);
+        assert_eq!(provable.first_seed.len(), 28);
+
+        let key = SecretKey::generate(parameters, &mut rng);
+        let public_key = PublicKey::from_bytes(parameters, &key.public_key().to_bytes()).unwrap();
+        let signature = key.sign::<Sha256>(b"message").unwrap();
+        assert!(public_key.verify::<Sha256>(b"message", &signature).is_ok());
+        assert_eq!(
+            public_key.verify::<Sha256>(b"massage", &signature),
+            Err(DsaError::InvalidSignature)
+        );
+        let restored = SecretKey::from_bytes(parameters, &key.to_bytes()).unwrap();
+        assert_eq!(restored.sign::<Sha256>(b"message"), Ok(signature));
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        let parameters = parameters_2048();
+        let mut rng = SeededRng::from_seed(b"dsa inputs");
+        let key = SecretKey::generate(&parameters, &mut rng);
+        let public_key = key.public_key();
+        let signature = key.sign::<Sha256>(b"message").unwrap().to_bytes();
+        let q = parameters.q();
+        for bad in [
+            [vec![0; 32], signature[32..].to_vec()].concat(),
+            [signature[..32].to_vec(), q.clone()].concat(),
+            [q.clone(), signature[32..].to_vec()].concat(),
+        ] {
+            assert_eq!(
+                public_key.verify::<Sha256>(
+                    b"message",
+                    &Signature::from_bytes(&parameters, &bad).unwrap()
+                ),
+                Err(DsaError::InvalidSignature)
+            );
+        }
+        // r || s must be exactly twice the length of q, so zero padding is not a second encoding
+        for bad in [&signature[1..], &[&[0], &signature[..]].concat()] {
+            assert_eq!(
+                Signature::from_bytes(&parameters, bad),
+                Err(DsaError::InvalidSignature)
+            );
+        }
+        // a signature read for parameters with a shorter q
+        let legacy = Signature::from_bytes(&parameters_1024(), &signature[..40]).unwrap();
+        assert_eq!(
+            public_key.verify::<Sha256>(b"message", &legacy),
+            Err(DsaError::InvalidSignature)
+        );
+
+        // 1 and p - 1 are out of range, and g + 1 is not in the subgroup
+        let mut p_minus_one = parameters.p();
+        *p_minus_one.last_mut().unwrap() -= 1;
+        let mut g_plus_one = parameters.g();
+        *g_plus_one.last_mut().unwrap() += 1;
+        for bad in [vec![1], p_minus_one, g_plus_one.clone()] {
+            assert_eq!(
+                PublicKey::from_bytes(&parameters, &bad),
+                Err(DsaError::InvalidPublicKey)
+            );
+        }
+        assert_eq!(
+            SecretKey::from_bytes(&parameters, &q).map(|key| key.to_bytes()),
+            Err(DsaError::InvalidSecretKey)
+        );
+
+        // g must have order q, and q must be 224 or 256 bits for a 2048 bit p
+        assert_eq!(
+            Parameters::new(&parameters.p(), &q, &g_plus_one),
+            Err(DsaError::InvalidParameters)
+        );
+        assert_eq!(
+            Parameters::new(&parameters.p(), &q[1..], &parameters.g()),
+            Err(DsaError::InvalidParameters)
+        );
+        assert_eq!(
+            Parameters::from_seed::<Sha224>(ParameterSize::L2048N256, &[0xff; 32], 1),
+            Err(DsaError::HashTooShort)
+        );
+        assert_eq!(
+            Parameters::from_seed::<Sha256>(ParameterSize::L1024N160, &[0x7f; 20], 1),
+            Err(DsaError::InvalidSeed)
+        );
+        assert_eq!(
+            Parameters::generate::<Sha256>(ParameterSize::L1024N160, &mut rng),
+            Err(DsaError::LegacySize)
+        );
+    }
+}
@@ -0,0 +1,843 @@
+/**
+* This is an implementation of arithmetic on the short Weierstrass curves y^2 = x^3 + ax + b that
+* ECDSA and ECDH run on, with the NIST curves P-256 and P-384 from FIPS 186-5 and secp256k1 from
+* SEC 2.
+* Field elements and scalars are little endian 64 bit limbs in Montgomery form, multiplied with
+* the CIOS method and brought below the modulus with a masked subtraction. The limb routines are
+* const fn so the curve constants are converted to Montgomery form at compile time.
+* Points are in Jacobian coordinates (X : Y : Z) with x = X/Z^2 and y = Y/Z^3, Z = 0 being the
+* point at infinity. The addition formula fails for equal inputs and the point at infinity, so
+* those results are computed too and chosen with masks. Scalar multiplication doubles and adds for
+* every bit of the scalar and selects with masks, taking the same time for every secret scalar.
+* Public keys use the SEC1 compressed and uncompressed encodings and are checked to be on the curve.
+*/
+use crate::rng::CryptoRng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcError {
+    ///The encoding is not a point on the curve
+    InvalidPoint,
+    ///The secret key is not in 1..n-1 or has the wrong length
+    InvalidSecretKey,
+    ///The keys belong to different curves
+    CurveMismatch,
+    ///The signature does not match the message and key
+    InvalidSignature,
+}
+
+impl std::fmt::Display for EcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EcError::InvalidPoint => write!(f, "the encoding is not a point on the curve"),
+            EcError::InvalidSecretKey => write!(f, "the secret key is out of range"),
+            EcError::CurveMismatch => write!(f, "the keys belong to different curves"),
+            EcError::InvalidSignature => write!(f, "the signature is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for EcError {}
+
+/// a + b, and the carry out
+const fn add_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], u64) {
+    let mut sum = [0u64; N];
+    let mut carry = 0u64;
+    let mut i = 0;
+    while i < N {
+        let s = a[i] as u128 + b[i] as u128 + carry as u128;
+        sum[i] = s as u64;
+        carry = (s >> 64) as u64;
+        i += 1;
+    }
+    (sum, carry)
+}
+
+/// a - b, and the borrow out
+const fn sub_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], u64) {
+    let mut difference = [0u64; N];
+    let mut borrow = 0u64;
+    let mut i = 0;
+    while i < N {
+        let d = (a[i] as u128)
+            .wrapping_sub(b[i] as u128)
+            .wrapping_sub(borrow as u128);
+        difference[i] = d as u64;
+        borrow = (d >> 127) as u64;
+        i += 1;
+    }
+    (difference, borrow)
+}
+
+/// `a` when `mask` is all zeros, `b` when it is all ones
+const fn select_limbs<const N: usize>(a: &[u64; N], b: &[u64; N], mask: u64) -> [u64; N] {
+    let mut result = [0u64; N];
+    let mut i = 0;
+    while i < N {
+        result[i] = a[i] ^ ((a[i] ^ b[i]) & mask);
+        i += 1;
+    }
+    result
+}
+
+/// Subtract `modulus` from `carry` * 2^(64 N) + `limbs` if that does not go negative
+const fn reduce_once<const N: usize>(limbs: &[u64; N], carry: u64, modulus: &[u64; N]) -> [u64; N] {
+    let (difference, borrow) = sub_limbs(limbs, modulus);
+    select_limbs(&difference, limbs, (borrow & !carry).wrapping_neg())
+}
+
+/// Whether every limb is zero, without branching on the limbs
+pub(crate) fn is_zero_limbs<const N: usize>(a: &[u64; N]) -> bool {
+    let folded = a.iter().fold(0, |acc, limb| acc | limb);
+    (folded | folded.wrapping_neg()) >> 63 == 0
+}
+
+/// Read 8 N big endian bytes
+pub(crate) fn limbs_from_be_bytes<const N: usize>(bytes: &[u8]) -> [u64; N] {
+    let mut limbs = [0u64; N];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.rchunks_exact(8)) {
+        *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    limbs
+}
+
+pub(crate) fn limbs_to_be_bytes<const N: usize>(limbs: &[u64; N]) -> Vec<u8> {
+    limbs
+        .iter()
+        .rev()
+        .flat_map(|limb| limb.to_be_bytes())
+        .collect()
+}
+
+/// Whether a < b
+pub(crate) fn less_than<const N: usize>(a: &[u64; N], b: &[u64; N]) -> bool {
+    sub_limbs(a, b).1 == 1
+}
+
+/// An odd modulus with the constants Montgomery multiplication needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Modulus<const N: usize> {
+    pub(crate) value: [u64; N],
+    ///-value^-1 mod 2^64
+    inverse: u64,
+    ///R = 2^(64 N) mod value, one in Montgomery form
+    pub(crate) one: [u64; N],
+    ///R^2 mod value, which converts into Montgomery form
+    r2: [u64; N],
+}
+
+impl<const N: usize> Modulus<N> {
+    pub(crate) const fn new(value: [u64; N]) -> Modulus<N> {
+        // value is its own inverse mod 8, and each Newton step doubles the correct bits
+        let mut inverse = value[0];
+        let mut i = 0;
+        while i < 5 {
+            inverse = inverse.wrapping_mul(2u64.wrapping_sub(value[0].wrapping_mul(inverse)));
+            i += 1;
+        }
+
+        // R and R^2 by doubling from 1
+        let mut r = [0u64; N];
+        r[0] = 1;
+        let mut one = r;
+        let mut bit = 0;
+        while bit < 128 * N {
+            let (doubled, carry) = add_limbs(&r, &r);
+            r = reduce_once(&doubled, carry, &value);
+            bit += 1;
+            if bit == 64 * N {
+                one = r;
+            }
+        }
+        Modulus {
+            value,
+            inverse: inverse.wrapping_neg(),
+            one,
+            r2: r,
+        }
+    }
+
+    /// a * b / R mod value, for a and b below value
+    pub(crate) const fn mul(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let mut t = [0u64; N];
+        let mut t_high = 0u64;
+        let mut i = 0;
+        while i < N {
+            // t += a * b[i]
+            let mut carry = 0u64;
+            let mut j = 0;
+            while j < N {
+                let s = t[j] as u128 + a[j] as u128 * b[i] as u128 + carry as u128;
+                t[j] = s as u64;
+                carry = (s >> 64) as u64;
+                j += 1;
+            }
+            let s = t_high as u128 + carry as u128;
+            let (t_n, t_n1) = (s as u64, (s >> 64) as u64);
+
+            // add the multiple of value that clears the low limb, then drop it
+            let m = t[0].wrapping_mul(self.inverse);
+            let s = t[0] as u128 + m as u128 * self.value[0] as u128;
+            let mut carry = (s >> 64) as u64;
+            j = 1;
+            while j < N {
+                let s = t[j] as u128 + m as u128 * self.value[j] as u128 + carry as u128;
+                t[j - 1] = s as u64;
+                carry = (s >> 64) as u64;
+                j += 1;
+            }
+            let s = t_n as u128 + carry as u128;
+            t[N - 1] = s as u64;
+            t_high = t_n1 + (s >> 64) as u64;
+            i += 1;
+        }
+        reduce_once(&t, t_high, &self.value)
+    }
+
+    /// Convert into Montgomery form
+    pub(crate) const fn montgomery(&self, a: &[u64; N]) -> [u64; N] {
+        self.mul(a, &self.r2)
+    }
+
+    /// Convert out of Montgomery form
+    pub(crate) fn canonical(&self, a: &[u64; N]) -> [u64; N] {
+        let mut one = [0u64; N];
+        one[0] = 1;
+        self.mul(a, &one)
+    }
+
+    /// Reduce a value below 2 * value, such as a coordinate mod a slightly smaller order
+    pub(crate) fn reduce(&self, a: &[u64; N]) -> [u64; N] {
+        reduce_once(a, 0, &self.value)
+    }
+
+    pub(crate) fn add(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let (sum, carry) = add_limbs(a, b);
+        reduce_once(&sum, carry, &self.value)
+    }
+
+    pub(crate) fn sub(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let (difference, borrow) = sub_limbs(a, b);
+        let (wrapped, _) = add_limbs(&difference, &self.value);
+        select_limbs(&difference, &wrapped, borrow.wrapping_neg())
+    }
+
+    pub(crate) fn neg(&self, a: &[u64; N]) -> [u64; N] {
+        self.sub(&[0; N], a)
+    }
+
+    pub(crate) fn square(&self, a: &[u64; N]) -> [u64; N] {
+        self.mul(a, a)
+    }
+
+    /// `a^exponent` in Montgomery form, for a public exponent
+    pub(crate) fn pow(&self, a: &[u64; N], exponent: &[u64; N]) -> [u64; N] {
+        let mut result = self.one;
+        for i in (0..64 * N).rev() {
+            result = self.square(&result);
+            if (exponent[i / 64] >> (i % 64)) & 1 == 1 {
+                result = self.mul(&result, a);
+            }
+        }
+        result
+    }
+
+    /// The inverse by Fermat's little theorem, the modulus must be prime
+    pub(crate) fn invert(&self, a: &[u64; N]) -> [u64; N] {
+        let mut two = [0u64; N];
+        two[0] = 2;
+        self.pow(a, &sub_limbs(&self.value, &two).0)
+    }
+
+    /// A square root, for a prime modulus that is 3 mod 4
+    pub(crate) fn sqrt(&self, a: &[u64; N]) -> Option<[u64; N]> {
+        // (value + 1) / 4 = (value >> 2) + 1
+        let exponent: [u64; N] = std::array::from_fn(|i| {
+            (self.value[i] >> 2) | self.value.get(i + 1).map_or(0, |next| next << 62)
+        });
+        let mut one = [0u64; N];
+        one[0] = 1;
+        let root = self.pow(a, &add_limbs(&exponent, &one).0);
+        (self.square(&root) == *a).then_some(root)
+    }
+}
+
+/// A short Weierstrass curve with a base point of prime order n
+#[derive(Debug, PartialEq, Eq)]
+pub struct Curve<const N: usize> {
+    pub(crate) p: Modulus<N>,
+    pub(crate) n: Modulus<N>,
+    ///a, b and the base point in Montgomery form
+    a: [u64; N],
+    b: [u64; N],
+    gx: [u64; N],
+    gy: [u64; N],
+}
+
+impl<const N: usize> Curve<N> {
+    /// The curve from little endian limbs of its parameters, the order must fill all 64 N bits
+    pub(crate) const fn new(
+        p: [u64; N],
+        n: [u64; N],
+        a: [u64; N],
+        b: [u64; N],
+        gx: [u64; N],
+        gy: [u64; N],
+    ) -> Curve<N> {
+        let p = Modulus::new(p);
+        Curve {
+            p,
+            n: Modulus::new(n),
+            a: p.montgomery(&a),
+            b: p.montgomery(&b),
+            gx: p.montgomery(&gx),
+            gy: p.montgomery(&gy),
+        }
+    }
+
+    /// Size in bytes of a field element or a scalar
+    pub const fn field_size(&self) -> usize {
+        8 * N
+    }
+
+    pub(crate) fn generator(&self) -> ProjectivePoint<N> {
+        ProjectivePoint {
+            x: self.gx,
+            y: self.gy,
+            z: self.p.one,
+        }
+    }
+
+    pub(crate) fn identity(&self) -> ProjectivePoint<N> {
+        ProjectivePoint {
+            x: self.p.one,
+            y: self.p.one,
+            z: [0; N],
+        }
+    }
+
+    /// x^3 + ax + b
+    fn curve_rhs(&self, x: &[u64; N]) -> [u64; N] {
+        let f = &self.p;
+        let x3 = f.mul(&f.square(x), x);
+        f.add(&f.add(&x3, &f.mul(&self.a, x)), &self.b)
+    }
+
+    pub(crate) fn double(&self, point: &ProjectivePoint<N>) -> ProjectivePoint<N> {
+        let f = &self.p;
+        let (x, y, z) = (&point.x, &point.y, &point.z);
+        let xx = f.square(x);
+        let yy = f.square(y);
+        let yyyy = f.square(&yy);
+        let zz = f.square(z);
+        // S = 2((X + YY)^2 - XX - YYYY), M = 3 XX + a ZZ^2
+        let s = f.sub(&f.sub(&f.square(&f.add(x, &yy)), &xx), &yyyy);
+        let s = f.add(&s, &s);
+        let m = f.add(&f.add(&xx, &xx), &xx);
+        let m = f.add(&m, &f.mul(&self.a, &f.square(&zz)));
+
+        let x3 = f.sub(&f.square(&m), &f.add(&s, &s));
+        let yyyy8 = f.add(&yyyy, &yyyy);
+        let yyyy8 = f.add(&yyyy8, &yyyy8);
+        let yyyy8 = f.add(&yyyy8, &yyyy8);
+        let y3 = f.sub(&f.mul(&m, &f.sub(&s, &x3)), &yyyy8);
+        let z3 = f.sub(&f.sub(&f.square(&f.add(y, z)), &yy), &zz);
+        ProjectivePoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// The sum of any two points, the special cases are chosen in constant time
+    pub(crate) fn add(
+        &self,
+        p1: &ProjectivePoint<N>,
+        p2: &ProjectivePoint<N>,
+    ) -> ProjectivePoint<N> {
+        let f = &self.p;
+        let z1z1 = f.square(&p1.z);
+        let z2z2 = f.square(&p2.z);
+        let u1 = f.mul(&p1.x, &z2z2);
+        let u2 = f.mul(&p2.x, &z1z1);
+        let s1 = f.mul(&f.mul(&p1.y, &p2.z), &z2z2);
+        let s2 = f.mul(&f.mul(&p2.y, &p1.z), &z1z1);
+        let h = f.sub(&u2, &u1);
+        let h2 = f.add(&h, &h);
+        let i = f.square(&h2);
+        let j = f.mul(&h, &i);
+        let r = f.sub(&s2, &s1);
+        let r = f.add(&r, &r);
+        let v = f.mul(&u1, &i);
+
+        let x3 = f.sub(&f.sub(&f.square(&r), &j), &f.add(&v, &v));
+        let s1j = f.mul(&s1, &j);
+        let y3 = f.sub(&f.mul(&r, &f.sub(&v, &x3)), &f.add(&s1j, &s1j));
+        let z1_plus_z2 = f.add(&p1.z, &p2.z);
+        let z3 = f.mul(&f.sub(&f.sub(&f.square(&z1_plus_z2), &z1z1), &z2z2), &h);
+        let sum = ProjectivePoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        };
+
+        // equal points give h = r = 0 and need doubling, opposite points give z3 = 0 by themselves
+        let sum = ProjectivePoint::select(
+            &sum,
+            &self.double(p1),
+            is_zero_limbs(&h) & is_zero_limbs(&r),
+        );
+        let sum = ProjectivePoint::select(&sum, p2, is_zero_limbs(&p1.z));
+        ProjectivePoint::select(&sum, p1, is_zero_limbs(&p2.z))
+    }
+
+    /// `[scalar]point` for scalar limbs below 2^(64 N), in constant time
+    pub(crate) fn mul(&self, point: &ProjectivePoint<N>, scalar: &[u64; N]) -> ProjectivePoint<N> {
+        let mut result = self.identity();
+        for i in (0..64 * N).rev() {
+            result = self.double(&result);
+            let sum = self.add(&result, point);
+            let bit = (scalar[i / 64] >> (i % 64)) & 1 == 1;
+            result = ProjectivePoint::select(&result, &sum, bit);
+        }
+        result
+    }
+
+    /// The affine coordinates in Montgomery form, None for the point at infinity
+    pub(crate) fn to_affine(&self, point: &ProjectivePoint<N>) -> Option<([u64; N], [u64; N])> {
+        if is_zero_limbs(&point.z) {
+            return None;
+        }
+        let f = &self.p;
+        let z_inverse = f.invert(&point.z);
+        let z_inverse2 = f.square(&z_inverse);
+        let x = f.mul(&point.x, &z_inverse2);
+        let y = f.mul(&point.y, &f.mul(&z_inverse2, &z_inverse));
+        Some((x, y))
+    }
+}
+
+/// A point in Jacobian coordinates, in Montgomery form
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ProjectivePoint<const N: usize> {
+    x: [u64; N],
+    y: [u64; N],
+    z: [u64; N],
+}
+
+impl<const N: usize> ProjectivePoint<N> {
+    /// `b` if `choice` is set, otherwise `a`
+    fn select(a: &ProjectivePoint<N>, b: &ProjectivePoint<N>, choice: bool) -> ProjectivePoint<N> {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        ProjectivePoint {
+            x: select_limbs(&a.x, &b.x, mask),
+            y: select_limbs(&a.y, &b.y, mask),
+            z: select_limbs(&a.z, &b.z, mask),
+        }
+    }
+}
+
+///NIST P-256, also called secp256r1 and prime256v1
+pub static P256: Curve<4> = Curve::new(
+    [
+        0xffffffffffffffff,
+        0x00000000ffffffff,
+        0x0000000000000000,
+        0xffffffff00000001,
+    ],
+    [
+        0xf3b9cac2fc632551,
+        0xbce6faada7179e84,
+        0xffffffffffffffff,
+        0xffffffff00000000,
+    ],
+    [
+        0xfffffffffffffffc,
+        0x00000000ffffffff,
+        0x0000000000000000,
+        0xffffffff00000001,
+    ],
+    [
+        0x3bce3c3e27d2604b,
+        0x651d06b0cc53b0f6,
+        0xb3ebbd55769886bc,
+        0x5ac635d8aa3a93e7,
+    ],
+    [
+        0xf4a13945d898c296,
+        0x77037d812deb33a0,
+        0xf8bce6e563a440f2,
+        0x6b17d1f2e12c4247,
+    ],
+    [
+        0xcbb6406837bf51f5,
+        0x2bce33576b315ece,
+        0x8ee7eb4a7c0f9e16,
+        0x4fe342e2fe1a7f9b,
+    ],
+);
+
+///NIST P-384, also called secp384r1
+pub static P384: Curve<6> = Curve::new(
+    [
+        0x00000000ffffffff,
+        0xffffffff00000000,
+        0xfffffffffffffffe,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ],
+    [
+        0xecec196accc52973,
+        0x581a0db248b0a77a,
+        0xc7634d81f4372ddf,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ],
+    [
+        0x00000000fffffffc,
+        0xffffffff00000000,
+        0xfffffffffffffffe,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ],
+    [
+        0x2a85c8edd3ec2aef,
+        0xc656398d8a2ed19d,
+        0x0314088f5013875a,
+        0x181d9c6efe814112,
+        0x988e056be3f82d19,
+        0xb3312fa7e23ee7e4,
+    ],
+    [
+        0x3a545e3872760ab7,
+        0x5502f25dbf55296c,
+        0x59f741e082542a38,
+        0x6e1d3b628ba79b98,
+        0x8eb1c71ef320ad74,
+        0xaa87ca22be8b0537,
+    ],
+    [
+        0x7a431d7c90ea0e5f,
+        0x0a60b1ce1d7e819d,
+        0xe9da3113b5f0b8c0,
+        0xf8f41dbd289a147c,
+        0x5d9e98bf9292dc29,
+        0x3617de4a96262c6f,
+    ],
+);
+
+///secp256k1 from SEC 2, the curve of Bitcoin
+pub static SECP256K1: Curve<4> = Curve::new(
+    [
+        0xfffffffefffffc2f,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ],
+    [
+        0xbfd25e8cd0364141,
+        0xbaaedce6af48a03b,
+        0xfffffffffffffffe,
+        0xffffffffffffffff,
+    ],
+    [0; 4],
+    [7, 0, 0, 0],
+    [
+        0x59f2815b16f81798,
+        0x029bfcdb2dce28d9,
+        0x55a06295ce870b07,
+        0x79be667ef9dcbbac,
+    ],
+    [
+        0x9c47d08ffb10d4b8,
+        0xfd17b448a6855419,
+        0x5da4fbfc0e1108a8,
+        0x483ada7726a3c465,
+    ],
+);
+
+/// A secret key, a scalar in 1..n-1
+#[derive(Clone)]
+pub struct SecretKey<const N: usize> {
+    pub(crate) curve: &'static Curve<N>,
+    pub(crate) scalar: [u64; N],
+}
+
+impl<const N: usize> SecretKey<N> {
+    /// Read a big endian scalar of exactly `curve.field_size()` bytes
+    pub fn from_bytes(curve: &'static Curve<N>, bytes: &[u8]) -> Result<SecretKey<N>, EcError> {
+        if bytes.len() != curve.field_size() {
+            return Err(EcError::InvalidSecretKey);
+        }
+        let scalar = limbs_from_be_bytes(bytes);
+        if is_zero_limbs(&scalar) || !less_than(&scalar, &curve.n.value) {
+            return Err(EcError::InvalidSecretKey);
+        }
+        Ok(SecretKey { curve, scalar })
+    }
+
+    /// Generate a fresh random key, drawing again whenever the value is out of range
+    pub fn generate(curve: &'static Curve<N>, rng: &mut impl CryptoRng) -> SecretKey<N> {
+        loop {
+            if let Ok(key) = SecretKey::from_bytes(curve, &rng.random_vec(curve.field_size())) {
+                return key;
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        limbs_to_be_bytes(&self.scalar)
+    }
+
+    pub fn curve(&self) -> &'static Curve<N> {
+        self.curve
+    }
+
+    pub fn public_key(&self) -> PublicKey<N> {
+        let point = self.curve.mul(&self.curve.generator(), &self.scalar);
+        PublicKey::from_point(self.curve, &point)
+            .expect("a scalar in 1..n-1 never gives the point at infinity")
+    }
+}
+
+/// A public key, a point on the curve other than the point at infinity
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey<const N: usize> {
+    pub(crate) curve: &'static Curve<N>,
+    ///Affine coordinates in Montgomery form
+    x: [u64; N],
+    y: [u64; N],
+}
+
+impl<const N: usize> PublicKey<N> {
+    /// Read a SEC1 encoded point, 0x04 || x || y uncompressed or 0x02 / 0x03 || x compressed
+    pub fn from_sec1(curve: &'static Curve<N>, bytes: &[u8]) -> Result<PublicKey<N>, EcError> {
+        let size = curve.field_size();
+        match (bytes.first(), bytes.len()) {
+            (Some(0x04), len) if len == 1 + 2 * size => {
+                let f = &curve.p;
+                let x = limbs_from_be_bytes(&bytes[1..1 + size]);
+                let y = limbs_from_be_bytes(&bytes[1 + size..]);
+                if !less_than(&x, &f.value) || !less_than(&y, &f.value) {
+                    return Err(EcError::InvalidPoint);
+                }
+                let (x, y) = (f.montgomery(&x), f.montgomery(&y));
+                if f.square(&y) != curve.curve_rhs(&x) {
+                    return Err(EcError::InvalidPoint);
+                }
+                Ok(PublicKey { curve, x, y })
+            }
+            (Some(prefix @ (0x02 | 0x03)), len) if len == 1 + size => {
+                PublicKey::from_x(curve, &limbs_from_be_bytes(&bytes[1..]), *prefix == 0x03)
+                    .ok_or(EcError::InvalidPoint)
+            }
+            _ => Err(EcError::InvalidPoint),
+        }
+    }
+
+    /// The point with x coordinate `x` and y of the given parity, if x is below p and on the curve
+    pub(crate) fn from_x(
+        curve: &'static Curve<N>,
+        x: &[u64; N],
+        odd: bool,
+    ) -> Option<PublicKey<N>> {
+        let f = &curve.p;
+        if !less_than(x, &f.value) {
+            return None;
+        }
+        let x = f.montgomery(x);
+        let y = f.sqrt(&curve.curve_rhs(&x))?;
+        let y = if (f.canonical(&y)[0] & 1 == 1) == odd {
+            y
+        } else {
+            f.neg(&y)
+        };
+        Some(PublicKey { curve, x, y })
+    }
+
+    /// The point as a public key, None for the point at infinity
+    pub(crate) fn from_point(
+        curve: &'static Curve<N>,
+        point: &ProjectivePoint<N>,
+    ) -> Option<PublicKey<N>> {
+        let (x, y) = curve.to_affine(point)?;
+        Some(PublicKey { curve, x, y })
+    }
+
+    /// The SEC1 encoding, compressed to the x coordinate and the parity of y if asked
+    pub fn to_sec1(&self, compressed: bool) -> Vec<u8> {
+        let x = limbs_to_be_bytes(&self.x());
+        if compressed {
+            [&[0x02 | self.has_odd_y() as u8], x.as_slice()].concat()
+        } else {
+            let y = self.curve.p.canonical(&self.y);
+            [&[0x04], x.as_slice(), &limbs_to_be_bytes(&y)].concat()
+        }
+    }
+
+    pub fn curve(&self) -> &'static Curve<N> {
+        self.curve
+    }
+
+    /// The canonical x coordinate
+    pub(crate) fn x(&self) -> [u64; N] {
+        self.curve.p.canonical(&self.x)
+    }
+
+    pub(crate) fn has_odd_y(&self) -> bool {
+        self.curve.p.canonical(&self.y)[0] & 1 == 1
+    }
+
+    pub(crate) fn point(&self) -> ProjectivePoint<N> {
+        ProjectivePoint {
+            x: self.x,
+            y: self.y,
+            z: self.curve.p.one,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::big_int::BigInt;
+    use crate::ec::{
+        Curve, EcError, P256, P384, PublicKey, SECP256K1, SecretKey, is_zero_limbs,
+        limbs_from_be_bytes, limbs_to_be_bytes,
+    };
+    use crate::rng::{CryptoRng, SeededRng};
+    use crate::test_util::hex;
+
+    fn check_field<const N: usize>(curve: &Curve<N>) {
+        let mut rng = SeededRng::from_seed(b"ec field");
+        for modulus in [&curve.p, &curve.n] {
+            let big_m = BigInt::from_be_bytes(&limbs_to_be_bytes(&modulus.value));
+            for _ in 0..4 {
+                let a = modulus.reduce(&limbs_from_be_bytes(&rng.random_vec(8 * N)));
+                let b = modulus.reduce(&limbs_from_be_bytes(&rng.random_vec(8 * N)));
+                let big = |limbs: &[u64; N]| BigInt::from_be_bytes(&limbs_to_be_bytes(limbs));
+                let (big_a, big_b) = (big(&a) % big_m.clone(), big(&b) % big_m.clone());
+                let (a, b) = (modulus.montgomery(&a), modulus.montgomery(&b));
+                let back = |limbs: [u64; N]| big(&modulus.canonical(&limbs));
+
+                assert_eq!(
+                    back(modulus.mul(&a, &b)),
+                    (big_a.clone() * big_b.clone()) % big_m.clone()
+                );
+                assert_eq!(
+                    back(modulus.add(&a, &b)),
+                    (big_a.clone() + big_b.clone()) % big_m.clone()
+                );
+                assert_eq!(
+                    back(modulus.sub(&a, &b)),
+                    (big_a.clone() + big_m.clone() - big_b.clone()) % big_m.clone()
+                );
+                assert_eq!(modulus.mul(&a, &modulus.invert(&a)), modulus.one);
+            }
+        }
+    }
+
+    // Montgomery products, sums, differences and inverses mod p and mod n of both curves
+    #[test]
+    fn field_matches_big_int() {
+        check_field(&P256);
+        check_field(&P384);
+    }
+
+    fn check_group<const N: usize>(curve: &Curve<N>) {
+        let g = curve.generator();
+        assert!(is_zero_limbs(&curve.mul(&g, &curve.n.value).z));
+        // [n - 1]G is -G, and adding G lands at infinity through the opposite points case
+        let mut n_minus_1 = curve.n.value;
+        n_minus_1[0] -= 1;
+        let minus_g = curve.mul(&g, &n_minus_1);
+        assert!(is_zero_limbs(&curve.add(&minus_g, &g).z));
+        // adding a point to itself goes through the doubling case
+        let doubled = curve.to_affine(&curve.double(&g));
+        assert_eq!(curve.to_affine(&curve.add(&g, &g)), doubled);
+    }
+
+    #[test]
+    fn generator_order() {
+        check_group(&P256);
+        check_group(&P384);
+        check_group(&SECP256K1);
+    }
+
+    // public keys checked against the python cryptography package
+    #[test]
+    fn sec1_encoding() {
+        let key = SecretKey::from_bytes(
+            &P256,
+            &hex("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721"),
+        )
+        .unwrap();
+        let public_key = key.public_key();
+        let uncompressed = public_key.to_sec1(false);
+        assert_eq!(
+            uncompressed,
+            hex(
+                "0460fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6\
+                 7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"
+            )
+        );
+        let compressed = public_key.to_sec1(true);
+        assert_eq!(
+            compressed,
+            hex("0360fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6")
+        );
+        assert_eq!(PublicKey::from_sec1(&P256, &uncompressed), Ok(public_key));
+        assert_eq!(PublicKey::from_sec1(&P256, &compressed), Ok(public_key));
+
+        let mut rng = SeededRng::from_seed(b"ec sec1");
+        for _ in 0..4 {
+            let public_key = SecretKey::generate(&P384, &mut rng).public_key();
+            for compressed in [false, true] {
+                let encoded = public_key.to_sec1(compressed);
+                assert_eq!(PublicKey::from_sec1(&P384, &encoded), Ok(public_key));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        let key = SecretKey::from_bytes(&P256, &[1; 32]).unwrap();
+        let uncompressed = key.public_key().to_sec1(false);
+
+        let mut off_curve = uncompressed.clone();
+        off_curve[64] ^= 1;
+        let mut x_too_big = vec![0x02];
+        x_too_big.extend(hex(
+            "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+        ));
+        for bytes in [
+            off_curve,
+            x_too_big,
+            vec![0x00],
+            uncompressed[..64].to_vec(),
+            [&[0x05], &uncompressed[1..]].concat(),
+        ] {
+            assert_eq!(
+                PublicKey::from_sec1(&P256, &bytes),
+                Err(EcError::InvalidPoint)
+            );
+        }
+        // a key for the other curve
+        assert!(PublicKey::from_sec1(&P384, &uncompressed).is_err());
+
+        // x = 1 has no y on P-256
+        let mut no_root = vec![0x02];
+        no_root.extend([0; 31]);
+        no_root.push(1);
+        assert!(PublicKey::from_sec1(&P256, &no_root).is_err());
+
+        let n = limbs_to_be_bytes(&P256.n.value);
+        for bytes in [vec![0; 32], n, vec![1; 31]] {
+            assert!(matches!(
+                SecretKey::from_bytes(&P256, &bytes),
+                Err(EcError::InvalidSecretKey)
+            ));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+/**
+* This is an implementation of elliptic curve Diffie-Hellman from SEC1 section 3.3.1 and
+* NIST SP 800-56A, over the curves of `ec`.
+* The shared secret is the x coordinate of the secret scalar times the peer point. Peer keys are
+* checked to be on the curve when they are decoded, and as the curves here have cofactor 1 no
+* further validation is needed. The scalar multiplication takes the same time for every secret.
+*/
+use crate::ec::{EcError, PublicKey, SecretKey, limbs_to_be_bytes};
+
+impl<const N: usize> SecretKey<N> {
+    /// The shared x coordinate with `peer`, it should be passed through a KDF before use
+    pub fn diffie_hellman(&self, peer: &PublicKey<N>) -> Result<Vec<u8>, EcError> {
+        if self.curve != peer.curve {
+            return Err(EcError::CurveMismatch);
+        }
+        let point = self.curve.mul(&peer.point(), &self.scalar);
+        let (x, _) = self.curve.to_affine(&point).ok_or(EcError::InvalidPoint)?;
+        Ok(limbs_to_be_bytes(&self.curve.p.canonical(&x)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ec::{EcError, P256, P384, PublicKey, SECP256K1, SecretKey};
+    use crate::rng::SeededRng;
+    use crate::test_util::hex;
+
+    // checked against ECDH of the python cryptography package
+    #[test]
+    fn matches_python() {
+        let key = SecretKey::from_bytes(
+            &P256,
+            &hex("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721"),
+        )
+        .unwrap();
+        let peer = PublicKey::from_sec1(
+            &P256,
+            &hex("039fad84aeae08bbef7f010014d82cef6a09de2b0cf871b5ce0c4f1d13a59a5934"),
+        )
+        .unwrap();
+        let test_result = key.diffie_hellman(&peer).unwrap();
+        assert_eq!(
+            test_result,
+            hex("20a7d258a87ab30d8f7a82031af7464cd68b2edf63b01c7d416a3353771bda44")
+        );
+
+        let key = SecretKey::from_bytes(
+            &P384,
+            &hex(
+                "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d8\
+                  96d5724e4c70a825f872c9ea60d2edf5",
+            ),
+        )
+        .unwrap();
+        let peer = PublicKey::from_sec1(
+            &P384,
+            &hex(
+                "02fbf96095915eb587390f850ec95b35f618ae121c72a70808ad8f9f4c261b60\
+                  2b63541743e395f7c778e52bb28c18dc83",
+            ),
+        )
+        .unwrap();
+        let test_result = key.diffie_hellman(&peer).unwrap();
+        assert_eq!(
+            test_result,
+            hex(
+                "c53bab691ea15d22fc2c65bfcacd5be8fc94546c51fc2ce15252d09d1c72e798\
+                 c1365f0c35f8afb7f7e6f747a10d137f"
+            )
+        );
+    }
+
+    #[test]
+    fn both_sides_agree() {
+        let mut rng = SeededRng::from_seed(b"ecdh");
+        let alice = SecretKey::generate(&P256, &mut rng);
+        let bob = SecretKey::generate(&P256, &mut rng);
+        assert_eq!(
+            alice.diffie_hellman(&bob.public_key()),
+            bob.diffie_hellman(&alice.public_key())
+        );
+    }
+
+    #[test]
+    fn rejects_other_curves() {
+        let mut rng = SeededRng::from_seed(b"ecdh curves");
+        let key = SecretKey::generate(&P256, &mut rng);
+        let peer = SecretKey::generate(&SECP256K1, &mut rng).public_key();
+        assert_eq!(key.diffie_hellman(&peer), Err(EcError::CurveMismatch));
+    }
+}
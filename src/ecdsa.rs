@@ -0,0 +1,422 @@
+/**
+* This is an implementation of ECDSA from FIPS 186-5 over the curves of `ec`, with the
+* deterministic nonces of RFC 6979.
+* The nonce comes from HMAC_DRBG instantiated with the secret key as entropy and the reduced
+* message hash as nonce, which is the generation procedure of RFC 6979 section 3.2: a signature
+* depends only on the key and the message, so a weak random generator cannot leak the key.
+* The hash is truncated to the bit length of the order, which fills every limb for the curves
+* here. Signatures are the fixed size concatenation r || s.
+* (r, n - s) is as valid as (r, s), so protocols such as Bitcoin only accept the low S form, at
+* most (n - 1) / 2. Recoverable signatures are always low S and carry a recovery id: bit 0 is the
+* parity of y of the point R and bit 1 is set when its x coordinate was reduced mod n, which is
+* enough to rebuild the public key from the signature and the message.
+*/
+use crate::ec::{
+    Curve, EcError, PublicKey, SecretKey, is_zero_limbs, less_than, limbs_from_be_bytes,
+    limbs_to_be_bytes,
+};
+use crate::hash::Hash;
+use crate::hmac_drbg::HmacDrbg;
+
+/// An ECDSA signature (r, s)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature<const N: usize> {
+    pub(crate) r: [u64; N],
+    pub(crate) s: [u64; N],
+}
+
+impl<const N: usize> Signature<N> {
+    /// Read r || s, each big endian and the size of a field element
+    pub fn from_bytes(bytes: &[u8]) -> Result<Signature<N>, EcError> {
+        if bytes.len() != 16 * N {
+            return Err(EcError::InvalidSignature);
+        }
+        Ok(Signature {
+            r: limbs_from_be_bytes(&bytes[..8 * N]),
+            s: limbs_from_be_bytes(&bytes[8 * N..]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [limbs_to_be_bytes(&self.r), limbs_to_be_bytes(&self.s)].concat()
+    }
+
+    /// Whether s is at most (n - 1) / 2
+    pub fn is_low_s(&self, curve: &Curve<N>) -> bool {
+        !less_than(&half_order(curve), &self.s)
+    }
+
+    /// The low S form of the signature, replacing s by n - s when it is high
+    pub fn normalize_s(&self, curve: &Curve<N>) -> Signature<N> {
+        let negated = curve.n.neg(&self.s);
+        Signature {
+            r: self.r,
+            s: if self.is_low_s(curve) {
+                self.s
+            } else {
+                negated
+            },
+        }
+    }
+}
+
+/// (n - 1) / 2, the largest low S value
+fn half_order<const N: usize>(curve: &Curve<N>) -> [u64; N] {
+    let n = &curve.n.value;
+    std::array::from_fn(|i| (n[i] >> 1) | n.get(i + 1).map_or(0, |next| next << 63))
+}
+
+/// a + b and whether it overflowed
+fn add_with_carry<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], bool) {
+    let mut sum = [0u64; N];
+    let mut carry = false;
+    for i in 0..N {
+        let (s, carry_1) = a[i].overflowing_add(b[i]);
+        let (s, carry_2) = s.overflowing_add(carry as u64);
+        sum[i] = s;
+        carry = carry_1 | carry_2;
+    }
+    (sum, carry)
+}
+
+/// bits2int of RFC 6979, the leftmost 64 N bits of the digest
+pub(crate) fn digest_to_limbs<const N: usize>(digest: &[u8]) -> [u64; N] {
+    let mut bytes = vec![0u8; 8 * N];
+    let len = digest.len().min(8 * N);
+    bytes[8 * N - len..].copy_from_slice(&digest[..len]);
+    limbs_from_be_bytes(&bytes)
+}
+
+impl<const N: usize> SecretKey<N> {
+    /// Hash the message with `H` and sign the digest
+    pub fn sign<H: Hash>(&self, message: &[u8]) -> Signature<N> {
+        self.sign_prehashed::<H>(&H::digest(message))
+    }
+
+    /// Sign a digest computed elsewhere, `H` derives the nonces and should be the hash that made it
+    pub fn sign_prehashed<H: Hash>(&self, digest: &[u8]) -> Signature<N> {
+        self.sign_prehashed_recoverable::<H>(digest).0
+    }
+
+    /// Hash the message with `H` and make a low S signature with its recovery id
+    pub fn sign_recoverable<H: Hash>(&self, message: &[u8]) -> (Signature<N>, u8) {
+        let (signature, recovery_id) = self.sign_prehashed_recoverable::<H>(&H::digest(message));
+        let low_s = signature.normalize_s(self.curve);
+        // negating s is the same as negating R, which flips the parity of its y
+        (low_s, recovery_id ^ (low_s != signature) as u8)
+    }
+
+    /// The signature over a digest with the recovery id of R, before any S normalization
+    fn sign_prehashed_recoverable<H: Hash>(&self, digest: &[u8]) -> (Signature<N>, u8) {
+        let n = &self.curve.n;
+        let e = n.reduce(&digest_to_limbs(digest));
+        let mut nonces = HmacDrbg::<H>::rfc6979(&self.to_bytes(), &limbs_to_be_bytes(&e));
+        loop {
+            let mut k_bytes = vec![0u8; 8 * N];
+            nonces
+                .generate(&mut k_bytes, &[])
+                .expect("a signature takes few nonces");
+            let k = limbs_from_be_bytes(&k_bytes);
+            if is_zero_limbs(&k) || !less_than(&k, &n.value) {
+                continue;
+            }
+            if let Some(signed) = self.sign_with_nonce(&e, &k) {
+                return signed;
+            }
+        }
+    }
+
+    /// r = (kG).x mod n and s = k^-1 (e + r d) mod n with the recovery id, None if r or s is zero
+    fn sign_with_nonce(&self, e: &[u64; N], k: &[u64; N]) -> Option<(Signature<N>, u8)> {
+        let curve = self.curve;
+        let n = &curve.n;
+        let point = PublicKey::from_point(curve, &curve.mul(&curve.generator(), k))?;
+        let x = point.x();
+        let r = n.reduce(&x);
+        let recovery_id = point.has_odd_y() as u8 | ((r != x) as u8) << 1;
+
+        let k_inverse = n.invert(&n.montgomery(k));
+        let rd = n.mul(&n.montgomery(&r), &n.montgomery(&self.scalar));
+        let s = n.add(&n.montgomery(e), &rd);
+        let s = n.canonical(&n.mul(&k_inverse, &s));
+        (!is_zero_limbs(&r) && !is_zero_limbs(&s)).then_some((Signature { r, s }, recovery_id))
+    }
+}
+
+impl<const N: usize> PublicKey<N> {
+    /// Hash the message with `H` and verify the signature over the digest
+    pub fn verify<H: Hash>(&self, message: &[u8], signature: &Signature<N>) -> Result<(), EcError> {
+        self.verify_prehashed(&H::digest(message), signature)
+    }
+
+    pub fn verify_prehashed(&self, digest: &[u8], signature: &Signature<N>) -> Result<(), EcError> {
+        let curve = self.curve;
+        let n = &curve.n;
+        let in_range = |value: &[u64; N]| !is_zero_limbs(value) && less_than(value, &n.value);
+        if !in_range(&signature.r) || !in_range(&signature.s) {
+            return Err(EcError::InvalidSignature);
+        }
+
+        // u1 = e / s and u2 = r / s, then (u1 G + u2 Q).x mod n must be r
+        let e = n.reduce(&digest_to_limbs(digest));
+        let w = n.invert(&n.montgomery(&signature.s));
+        let u1 = n.canonical(&n.mul(&n.montgomery(&e), &w));
+        let u2 = n.canonical(&n.mul(&n.montgomery(&signature.r), &w));
+        let point = curve.add(
+            &curve.mul(&curve.generator(), &u1),
+            &curve.mul(&self.point(), &u2),
+        );
+        let (x, _) = curve.to_affine(&point).ok_or(EcError::InvalidSignature)?;
+        if n.reduce(&curve.p.canonical(&x)) != signature.r {
+            return Err(EcError::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Rebuild the public key from a message hashed with `H`, its signature and the recovery id
+    pub fn recover<H: Hash>(
+        curve: &'static Curve<N>,
+        message: &[u8],
+        signature: &Signature<N>,
+        recovery_id: u8,
+    ) -> Result<PublicKey<N>, EcError> {
+        PublicKey::recover_prehashed(curve, &H::digest(message), signature, recovery_id)
+    }
+
+    pub fn recover_prehashed(
+        curve: &'static Curve<N>,
+        digest: &[u8],
+        signature: &Signature<N>,
+        recovery_id: u8,
+    ) -> Result<PublicKey<N>, EcError> {
+        let n = &curve.n;
+        let in_range = |value: &[u64; N]| !is_zero_limbs(value) && less_than(value, &n.value);
+        if !in_range(&signature.r) || !in_range(&signature.s) || recovery_id > 3 {
+            return Err(EcError::InvalidSignature);
+        }
+
+        // R from its x coordinate, r or r + n, and the parity of y
+        let mut x = signature.r;
+        if recovery_id & 2 != 0 {
+            let (sum, carry) = add_with_carry(&x, &n.value);
+            if carry {
+                return Err(EcError::InvalidSignature);
+            }
+            x = sum;
+        }
+        let r_point =
+            PublicKey::from_x(curve, &x, recovery_id & 1 == 1).ok_or(EcError::InvalidSignature)?;
+
+        // Q = r^-1 (s R - e G)
+        let e = n.reduce(&digest_to_limbs(digest));
+        let r_inverse = n.invert(&n.montgomery(&signature.r));
+        let u1 = n.canonical(&n.neg(&n.mul(&n.montgomery(&e), &r_inverse)));
+        let u2 = n.canonical(&n.mul(&n.montgomery(&signature.s), &r_inverse));
+        let point = curve.add(
+            &curve.mul(&curve.generator(), &u1),
+            &curve.mul(&r_point.point(), &u2),
+        );
+        PublicKey::from_point(curve, &point).ok_or(EcError::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ec::{
+        Curve, EcError, P256, P384, PublicKey, SECP256K1, SecretKey, limbs_to_be_bytes,
+    };
+    use crate::ecdsa::Signature;
+    use crate::hash::Hash;
+    use crate::rng::SeededRng;
+    use crate::sha2::{Sha256, Sha384, Sha512};
+    use crate::test_util::hex;
+
+    fn check<H: Hash, const N: usize>(
+        curve: &'static Curve<N>,
+        key: &str,
+        message: &[u8],
+        r: &str,
+        s: &str,
+    ) {
+        let key = SecretKey::from_bytes(curve, &hex(key)).unwrap();
+        let test_result = key.sign::<H>(message);
+        assert_eq!(test_result.to_bytes(), [hex(r), hex(s)].concat());
+        assert!(key.public_key().verify::<H>(message, &test_result).is_ok());
+    }
+
+    const P256_KEY: &str = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+    const P384_KEY: &str = "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d8\
+                            96d5724e4c70a825f872c9ea60d2edf5";
+
+    // RFC 6979 appendix A.2.5, SHA-512 digests are truncated to the order
+    #[test]
+    fn rfc6979_p256() {
+        check::<Sha256, 4>(
+            &P256,
+            P256_KEY,
+            b"sample",
+            "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+            "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+        );
+        check::<Sha256, 4>(
+            &P256,
+            P256_KEY,
+            b"test",
+            "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+            "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+        );
+        check::<Sha512, 4>(
+            &P256,
+            P256_KEY,
+            b"sample",
+            "8496a60b5e9b47c825488827e0495b0e3fa109ec4568fd3f8d1097678eb97f00",
+            "2362ab1adbe2b8adf9cb9edab740ea6049c028114f2460f96554f61fae3302fe",
+        );
+    }
+
+    // RFC 6979 appendix A.2.6, SHA-256 digests are shorter than the order
+    #[test]
+    fn rfc6979_p384() {
+        check::<Sha384, 6>(
+            &P384,
+            P384_KEY,
+            b"sample",
+            "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c\
+             81a648152e44acf96e36dd1e80fabe46",
+            "99ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94f\
+             a329c145786e679e7b82c71a38628ac8",
+        );
+        check::<Sha384, 6>(
+            &P384,
+            P384_KEY,
+            b"test",
+            "8203b63d3c853e8d77227fb377bcf7b7b772e97892a80f36ab775d509d7a5feb\
+             0542a7f0812998da8f1dd3ca3cf023db",
+            "ddd0760448d42d8a43af45af836fce4de8be06b485e9b61b827c2f13173923e0\
+             6a739f040649a667bf3b828246baa5a5",
+        );
+        check::<Sha256, 6>(
+            &P384,
+            P384_KEY,
+            b"sample",
+            "21b13d1e013c7fa1392d03c5f99af8b30c570c6f98d4ea8e354b63a21d3daa33\
+             bde1e888e63355d92fa2b3c36d8fb2cd",
+            "f3aa443fb107745bf4bd77cb3891674632068a10ca67e3d45db2266fa7d1feeb\
+             efdc63eccd1ac42ec0cb8668a4fa0ab0",
+        );
+    }
+
+    #[test]
+    fn rejects_bad_signatures() {
+        let mut rng = SeededRng::from_seed(b"ecdsa");
+        let key = SecretKey::generate(&P256, &mut rng);
+        let public_key = key.public_key();
+        let signature = key.sign::<Sha256>(b"message");
+        assert_eq!(
+            public_key.verify::<Sha256>(b"massage", &signature),
+            Err(EcError::InvalidSignature)
+        );
+        let other_key = SecretKey::generate(&P256, &mut rng).public_key();
+        assert!(other_key.verify::<Sha256>(b"message", &signature).is_err());
+
+        let bytes = signature.to_bytes();
+        let mut flipped = bytes.clone();
+        flipped[40] ^= 0x10;
+        let n = limbs_to_be_bytes(&P256.n.value);
+        for bad in [
+            flipped,
+            [vec![0; 32], bytes[32..].to_vec()].concat(),
+            [bytes[..32].to_vec(), n].concat(),
+        ] {
+            let bad = Signature::from_bytes(&bad).unwrap();
+            assert_eq!(
+                public_key.verify::<Sha256>(b"message", &bad),
+                Err(EcError::InvalidSignature)
+            );
+        }
+        assert_eq!(
+            Signature::<4>::from_bytes(&bytes[1..]),
+            Err(EcError::InvalidSignature)
+        );
+    }
+
+    fn check_recoverable(key: &str, message: &[u8], r: &str, s: &str, recovery_id: u8) {
+        let key = SecretKey::from_bytes(&SECP256K1, &hex(key)).unwrap();
+        let (signature, test_result) = key.sign_recoverable::<Sha256>(message);
+        assert_eq!(signature.to_bytes(), [hex(r), hex(s)].concat());
+        assert_eq!(test_result, recovery_id);
+        assert!(signature.is_low_s(&SECP256K1));
+        let recovered =
+            PublicKey::recover::<Sha256>(&SECP256K1, message, &signature, recovery_id).unwrap();
+        assert_eq!(recovered, key.public_key());
+        assert!(recovered.verify::<Sha256>(message, &signature).is_ok());
+    }
+
+    // checked against the deterministic ECDSA of the python cryptography package, the first
+    // signature has a high S that is negated, which flips the recovery id
+    #[test]
+    fn secp256k1_recoverable() {
+        let key = SecretKey::from_bytes(&SECP256K1, &[[0; 31].as_slice(), &[1]].concat()).unwrap();
+        let high_s = key.sign::<Sha256>(b"Satoshi Nakamoto");
+        assert_eq!(
+            high_s.to_bytes(),
+            hex(
+                "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+                 dbbd3162d46e9f9bef7feb87c16dc13b4f6568a87f4e83f728e2443ba586675c"
+            )
+        );
+        assert!(!high_s.is_low_s(&SECP256K1));
+        let low_s = high_s.normalize_s(&SECP256K1);
+        assert!(
+            key.public_key()
+                .verify::<Sha256>(b"Satoshi Nakamoto", &low_s)
+                .is_ok()
+        );
+        assert_eq!(low_s.normalize_s(&SECP256K1), low_s);
+
+        check_recoverable(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            b"Satoshi Nakamoto",
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8",
+            "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+            1,
+        );
+        check_recoverable(
+            P256_KEY,
+            b"sample",
+            "432310e32cb80eb6503a26ce83cc165c783b870845fb8aad6d970889fcd7a6c8",
+            "530128b6b81c548874a6305d93ed071ca6e05074d85863d4056ce89b02bfab69",
+            0,
+        );
+        check_recoverable(
+            P256_KEY,
+            b"test",
+            "f2adcea7139057be6409855ee96d008e0e5b5f532333ec17448e26a36f47bcb2",
+            "570c9d342779b40f513c0d75cbf93e3f3de7b01f6593f17bfc2ee87151414d64",
+            0,
+        );
+    }
+
+    #[test]
+    fn recovery_round_trip() {
+        let mut rng = SeededRng::from_seed(b"recovery");
+        for curve in [&P256, &SECP256K1] {
+            for i in 0..8u8 {
+                let key = SecretKey::generate(curve, &mut rng);
+                let message = [i; 3];
+                let (signature, recovery_id) = key.sign_recoverable::<Sha256>(&message);
+                let test_result =
+                    PublicKey::recover::<Sha256>(curve, &message, &signature, recovery_id);
+                assert_eq!(test_result, Ok(key.public_key()));
+                // the other parity gives a different key, and ids above 3 are rejected
+                let other =
+                    PublicKey::recover::<Sha256>(curve, &message, &signature, recovery_id ^ 1);
+                assert_ne!(other, Ok(key.public_key()));
+                assert_eq!(
+                    PublicKey::recover::<Sha256>(curve, &message, &signature, 4),
+                    Err(EcError::InvalidSignature)
+                );
+            }
+        }
+    }
+}
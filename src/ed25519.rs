@@ -0,0 +1,744 @@
+/**
+* This is an implementation of the Ed25519 signature scheme from RFC 8032, with the Ed25519ctx and
+* Ed25519ph variants and batch verification.
+* Points of the twisted Edwards curve -x^2 + y^2 = 1 + d x^2 y^2 are held in extended coordinates
+* (X : Y : Z : T) with x = X/Z, y = Y/Z and xy = T/Z, and added with the complete formulas of
+* RFC 8032 section 5.1.4, so one formula covers doubling and the identity. Multiplication by a
+* secret scalar is a fixed run of doublings and additions with constant time selection.
+* Verification checks the cofactored equation [8][S]B = [8]R + [8][k]A and rejects S >= L, so single
+* and batch verification accept exactly the same signatures.
+*/
+use crate::curve25519::FieldElement;
+use crate::hash::Hash;
+use crate::rng::CryptoRng;
+use crate::sha2::Sha512;
+
+pub const SECRET_KEY_SIZE: usize = 32;
+pub const PUBLIC_KEY_SIZE: usize = 32;
+pub const SIGNATURE_SIZE: usize = 64;
+///Longest context Ed25519ctx and Ed25519ph take
+pub const MAX_CONTEXT_SIZE: usize = 255;
+
+///The curve constant d = -121665 / 121666
+const D: FieldElement = FieldElement([
+    0x34dca135978a3,
+    0x1a8283b156ebd,
+    0x5e7a26001c029,
+    0x739c663a03cbb,
+    0x52036cee2b6ff,
+]);
+///2d, used by the addition formula
+const D2: FieldElement = FieldElement([
+    0x69b9426b2f159,
+    0x35050762add7a,
+    0x3cf44c0038052,
+    0x6738cc7407977,
+    0x2406d9dc56dff,
+]);
+///The base point B, with y = 4/5 and x positive
+const BASE_POINT: EdwardsPoint = EdwardsPoint {
+    x: FieldElement([
+        0x62d608f25d51a,
+        0x412a4b4f6592a,
+        0x75b7171a4b31d,
+        0x1ff60527118fe,
+        0x216936d3cd6e5,
+    ]),
+    y: FieldElement([
+        0x6666666666658,
+        0x4cccccccccccc,
+        0x1999999999999,
+        0x3333333333333,
+        0x6666666666666,
+    ]),
+    z: FieldElement::ONE,
+    t: FieldElement([
+        0x68ab3a5b7dda3,
+        0xeea2a5eadbb,
+        0x2af8df483c27e,
+        0x332b375274732,
+        0x67875f0fd78b7,
+    ]),
+};
+///The group order L = 2^252 + 27742317777372353535851937790883648493, little endian 64 bit limbs
+const L: [u64; 4] = [
+    0x5812631a5cf5d3ed,
+    0x14def9dea2f79cd6,
+    0,
+    0x1000000000000000,
+];
+///The prefix of dom2 that separates Ed25519ctx and Ed25519ph from plain Ed25519
+const DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ed25519Error {
+    ///The public key is not the encoding of a curve point
+    InvalidPublicKey,
+    ///The signature does not match the message and key
+    InvalidSignature,
+    ///The context is longer than 255 bytes, or empty for Ed25519ctx
+    InvalidContext,
+}
+
+impl std::fmt::Display for Ed25519Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ed25519Error::InvalidPublicKey => write!(f, "the public key is not a curve point"),
+            Ed25519Error::InvalidSignature => write!(f, "the signature is invalid"),
+            Ed25519Error::InvalidContext => {
+                write!(f, "contexts are 1 to {MAX_CONTEXT_SIZE} bytes long")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Ed25519Error {}
+
+/// An integer mod L
+#[derive(Clone, Copy)]
+struct Scalar([u64; 4]);
+
+impl Scalar {
+    const ZERO: Scalar = Scalar([0; 4]);
+
+    /// Reduce a little endian integer of up to 64 bytes mod L
+    fn from_bytes_wide(bytes: &[u8]) -> Scalar {
+        let mut wide = [0u64; 8];
+        for (i, byte) in bytes.iter().enumerate() {
+            wide[i / 8] |= (*byte as u64) << (8 * (i % 8));
+        }
+        Scalar::reduce(&wide)
+    }
+
+    /// The scalar a signature carries, which must already be below L
+    fn from_canonical_bytes(bytes: &[u8; 32]) -> Option<Scalar> {
+        let scalar = Scalar::from_bytes_wide(bytes);
+        if scalar.to_bytes() == *bytes {
+            Some(scalar)
+        } else {
+            None
+        }
+    }
+
+    /// Shift the bits in from the top one at a time, subtracting L whenever the value reaches it
+    fn reduce(wide: &[u64; 8]) -> Scalar {
+        let mut r = [0u64; 4];
+        for i in (0..512).rev() {
+            let bit = (wide[i / 64] >> (i % 64)) & 1;
+            // r < L < 2^253, so 2r + 1 fits
+            for j in (1..4).rev() {
+                r[j] = (r[j] << 1) | (r[j - 1] >> 63);
+            }
+            r[0] = (r[0] << 1) | bit;
+
+            let mut reduced = [0u64; 4];
+            let mut borrow = 0u64;
+            for j in 0..4 {
+                let (difference, borrow_1) = r[j].overflowing_sub(L[j]);
+                let (difference, borrow_2) = difference.overflowing_sub(borrow);
+                reduced[j] = difference;
+                borrow = (borrow_1 | borrow_2) as u64;
+            }
+            // keep r when subtracting went negative
+            let mask = borrow.wrapping_sub(1);
+            for j in 0..4 {
+                r[j] = (reduced[j] & mask) | (r[j] & !mask);
+            }
+        }
+        Scalar(r)
+    }
+
+    /// a * b + c mod L
+    fn mul_add(a: &Scalar, b: &Scalar, c: &Scalar) -> Scalar {
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let sum = wide[i + j] as u128 + a.0[i] as u128 * b.0[j] as u128 + carry;
+                wide[i + j] = sum as u64;
+                carry = sum >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+        let mut carry = 0u128;
+        for (i, limb) in wide.iter_mut().enumerate() {
+            let sum = *limb as u128 + *c.0.get(i).unwrap_or(&0) as u128 + carry;
+            *limb = sum as u64;
+            carry = sum >> 64;
+        }
+        Scalar::reduce(&wide)
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// A point on the curve in extended coordinates
+#[derive(Clone, Copy)]
+struct EdwardsPoint {
+    x: FieldElement,
+    y: FieldElement,
+    z: FieldElement,
+    t: FieldElement,
+}
+
+impl EdwardsPoint {
+    const IDENTITY: EdwardsPoint = EdwardsPoint {
+        x: FieldElement::ZERO,
+        y: FieldElement::ONE,
+        z: FieldElement::ONE,
+        t: FieldElement::ZERO,
+    };
+
+    fn add(&self, other: &EdwardsPoint) -> EdwardsPoint {
+        let a = (self.y - self.x) * (other.y - other.x);
+        let b = (self.y + self.x) * (other.y + other.x);
+        let c = self.t * D2 * other.t;
+        let d = (self.z + self.z) * other.z;
+        let (e, f, g, h) = (b - a, d - c, d + c, b + a);
+        EdwardsPoint {
+            x: e * f,
+            y: g * h,
+            z: f * g,
+            t: e * h,
+        }
+    }
+
+    fn double(&self) -> EdwardsPoint {
+        let a = self.x.square();
+        let b = self.y.square();
+        let c = self.z.square() + self.z.square();
+        let h = a + b;
+        let e = h - (self.x + self.y).square();
+        let g = a - b;
+        let f = c + g;
+        EdwardsPoint {
+            x: e * f,
+            y: g * h,
+            z: f * g,
+            t: e * h,
+        }
+    }
+
+    fn neg(&self) -> EdwardsPoint {
+        EdwardsPoint {
+            x: -self.x,
+            y: self.y,
+            z: self.z,
+            t: -self.t,
+        }
+    }
+
+    /// `b` if `choice` is set, otherwise `a`
+    fn select(a: &EdwardsPoint, b: &EdwardsPoint, choice: bool) -> EdwardsPoint {
+        EdwardsPoint {
+            x: FieldElement::select(&a.x, &b.x, choice),
+            y: FieldElement::select(&a.y, &b.y, choice),
+            z: FieldElement::select(&a.z, &b.z, choice),
+            t: FieldElement::select(&a.t, &b.t, choice),
+        }
+    }
+
+    /// `[scalar]self` for a 256 bit little endian scalar, in constant time
+    fn mul(&self, scalar: &[u8; 32]) -> EdwardsPoint {
+        let mut result = EdwardsPoint::IDENTITY;
+        for i in (0..256).rev() {
+            result = result.double();
+            let sum = result.add(self);
+            let bit = (scalar[i / 8] >> (i % 8)) & 1 == 1;
+            result = EdwardsPoint::select(&result, &sum, bit);
+        }
+        result
+    }
+
+    fn mul_by_cofactor(&self) -> EdwardsPoint {
+        self.double().double().double()
+    }
+
+    fn is_identity(&self) -> bool {
+        self.x.is_zero() && (self.y - self.z).is_zero()
+    }
+
+    /// The encoding of RFC 8032 section 5.1.2, y with the sign of x in the top bit
+    fn compress(&self) -> [u8; 32] {
+        let z_inverse = self.z.invert();
+        let x = self.x * z_inverse;
+        let mut bytes = (self.y * z_inverse).to_bytes();
+        bytes[31] |= (x.is_negative() as u8) << 7;
+        bytes
+    }
+
+    /// The decoding of RFC 8032 section 5.1.3, rejecting y >= p and points off the curve
+    fn decompress(bytes: &[u8; 32]) -> Option<EdwardsPoint> {
+        let sign = bytes[31] >> 7 == 1;
+        let y = FieldElement::from_bytes(bytes);
+        let mut y_bytes = *bytes;
+        y_bytes[31] &= 0x7f;
+        if y.to_bytes() != y_bytes {
+            return None;
+        }
+
+        // x^2 = (y^2 - 1) / (d y^2 + 1)
+        let y2 = y.square();
+        let (is_square, x) =
+            FieldElement::sqrt_ratio(&(y2 - FieldElement::ONE), &(D * y2 + FieldElement::ONE));
+        if !is_square || (x.is_zero() && sign) {
+            return None;
+        }
+        let x = FieldElement::select(&x, &-x, x.is_negative() != sign);
+        Some(EdwardsPoint {
+            x,
+            y,
+            z: FieldElement::ONE,
+            t: x * y,
+        })
+    }
+}
+
+/// dom2(phflag, context), empty for plain Ed25519
+fn dom2(prehashed: bool, context: &[u8]) -> Vec<u8> {
+    [
+        DOM2_PREFIX,
+        &[prehashed as u8, context.len() as u8],
+        context,
+    ]
+    .concat()
+}
+
+/// SHA-512 of the pieces reduced mod L
+fn hash_to_scalar(pieces: &[&[u8]]) -> Scalar {
+    let mut hash = Sha512::init();
+    for piece in pieces {
+        hash.update(piece);
+    }
+    Scalar::from_bytes_wide(&hash.finalize())
+}
+
+/// An Ed25519 public key
+#[derive(Clone, Copy)]
+pub struct VerifyingKey {
+    bytes: [u8; PUBLIC_KEY_SIZE],
+    point: EdwardsPoint,
+}
+
+impl VerifyingKey {
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_SIZE]) -> Result<VerifyingKey, Ed25519Error> {
+        Ok(VerifyingKey {
+            bytes: *bytes,
+            point: EdwardsPoint::decompress(bytes).ok_or(Ed25519Error::InvalidPublicKey)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.bytes
+    }
+
+    /// Verify a plain Ed25519 signature
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<(), Ed25519Error> {
+        self.verify_with(&[], message, signature)
+    }
+
+    /// Verify an Ed25519ctx signature made under `context`
+    pub fn verify_ctx(
+        &self,
+        message: &[u8],
+        context: &[u8],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<(), Ed25519Error> {
+        if context.is_empty() || context.len() > MAX_CONTEXT_SIZE {
+            return Err(Ed25519Error::InvalidContext);
+        }
+        self.verify_with(&dom2(false, context), message, signature)
+    }
+
+    /// Verify an Ed25519ph signature, `prehash` is the SHA-512 state the message was fed into
+    pub fn verify_prehashed(
+        &self,
+        prehash: Sha512,
+        context: &[u8],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<(), Ed25519Error> {
+        if context.len() > MAX_CONTEXT_SIZE {
+            return Err(Ed25519Error::InvalidContext);
+        }
+        self.verify_with(&dom2(true, context), &prehash.finalize(), signature)
+    }
+
+    /// Decode R and S and compute k = H(dom || R || A || M)
+    fn parse_signature(
+        &self,
+        dom: &[u8],
+        message: &[u8],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<(EdwardsPoint, Scalar, Scalar), Ed25519Error> {
+        let r_bytes: &[u8; 32] = signature[..32].try_into().unwrap();
+        let r = EdwardsPoint::decompress(r_bytes).ok_or(Ed25519Error::InvalidSignature)?;
+        let s = Scalar::from_canonical_bytes(signature[32..].try_into().unwrap())
+            .ok_or(Ed25519Error::InvalidSignature)?;
+        let k = hash_to_scalar(&[dom, r_bytes, &self.bytes, message]);
+        Ok((r, k, s))
+    }
+
+    fn verify_with(
+        &self,
+        dom: &[u8],
+        message: &[u8],
+        signature: &[u8; SIGNATURE_SIZE],
+    ) -> Result<(), Ed25519Error> {
+        let (r, k, s) = self.parse_signature(dom, message, signature)?;
+        // [8]([S]B - [k]A - R) must be the identity
+        let check = BASE_POINT
+            .mul(&s.to_bytes())
+            .add(&self.point.mul(&k.to_bytes()).neg())
+            .add(&r.neg());
+        if !check.mul_by_cofactor().is_identity() {
+            return Err(Ed25519Error::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// An Ed25519 secret key, kept in the expanded form signing uses
+#[derive(Clone)]
+pub struct SigningKey {
+    seed: [u8; SECRET_KEY_SIZE],
+    ///The clamped secret scalar s
+    scalar: [u8; 32],
+    ///The second half of the hashed seed, which keys the nonce
+    prefix: [u8; 32],
+    verifying_key: VerifyingKey,
+}
+
+impl SigningKey {
+    /// Expand a 32 byte secret key as RFC 8032 section 5.1.5 describes
+    pub fn from_seed(seed: &[u8; SECRET_KEY_SIZE]) -> SigningKey {
+        let hash = Sha512::digest(seed);
+        let mut scalar: [u8; 32] = hash[..32].try_into().unwrap();
+        scalar[0] &= 248;
+        scalar[31] &= 127;
+        scalar[31] |= 64;
+        let point = BASE_POINT.mul(&scalar);
+        SigningKey {
+            seed: *seed,
+            scalar,
+            prefix: hash[32..].try_into().unwrap(),
+            verifying_key: VerifyingKey {
+                bytes: point.compress(),
+                point,
+            },
+        }
+    }
+
+    /// Generate a fresh random key
+    pub fn generate(rng: &mut impl CryptoRng) -> SigningKey {
+        SigningKey::from_seed(&rng.random_array())
+    }
+
+    pub fn to_bytes(&self) -> [u8; SECRET_KEY_SIZE] {
+        self.seed
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.verifying_key
+    }
+
+    /// Sign with plain Ed25519
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_SIZE] {
+        self.sign_with(&[], message)
+    }
+
+    /// Sign with Ed25519ctx, the context must be 1 to 255 bytes
+    pub fn sign_ctx(
+        &self,
+        message: &[u8],
+        context: &[u8],
+    ) -> Result<[u8; SIGNATURE_SIZE], Ed25519Error> {
+        if context.is_empty() || context.len() > MAX_CONTEXT_SIZE {
+            return Err(Ed25519Error::InvalidContext);
+        }
+        Ok(self.sign_with(&dom2(false, context), message))
+    }
+
+    /// Sign with Ed25519ph, `prehash` is the SHA-512 state the message was fed into
+    pub fn sign_prehashed(
+        &self,
+        prehash: Sha512,
+        context: &[u8],
+    ) -> Result<[u8; SIGNATURE_SIZE], Ed25519Error> {
+        if context.len() > MAX_CONTEXT_SIZE {
+            return Err(Ed25519Error::InvalidContext);
+        }
+        Ok(self.sign_with(&dom2(true, context), &prehash.finalize()))
+    }
+
+    /// The signing procedure of RFC 8032 section 5.1.6
+    fn sign_with(&self, dom: &[u8], message: &[u8]) -> [u8; SIGNATURE_SIZE] {
+        let r = hash_to_scalar(&[dom, &self.prefix, message]);
+        let r_bytes = BASE_POINT.mul(&r.to_bytes()).compress();
+        let k = hash_to_scalar(&[dom, &r_bytes, &self.verifying_key.bytes, message]);
+        let s = Scalar::mul_add(&k, &Scalar::from_bytes_wide(&self.scalar), &r);
+
+        let mut signature = [0u8; SIGNATURE_SIZE];
+        signature[..32].copy_from_slice(&r_bytes);
+        signature[32..].copy_from_slice(&s.to_bytes());
+        signature
+    }
+}
+
+/// Verify several plain Ed25519 signatures at once.
+/// Each equation is scaled by a random 128 bit factor and the sum is checked, so one bad signature
+/// fails the whole batch without saying which; verify one by one to find it.
+pub fn verify_batch(
+    items: &[(&VerifyingKey, &[u8], &[u8; SIGNATURE_SIZE])],
+    rng: &mut impl CryptoRng,
+) -> Result<(), Ed25519Error> {
+    let mut s_sum = Scalar::ZERO;
+    let mut sum = EdwardsPoint::IDENTITY;
+    for (key, message, signature) in items {
+        let (r, k, s) = key.parse_signature(&[], message, signature)?;
+        let z = Scalar::from_bytes_wide(&rng.random_array::<16>());
+        s_sum = Scalar::mul_add(&z, &s, &s_sum);
+        let zk = Scalar::mul_add(&z, &k, &Scalar::ZERO);
+        sum = sum
+            .add(&r.mul(&z.to_bytes()))
+            .add(&key.point.mul(&zk.to_bytes()));
+    }
+    let check = BASE_POINT.mul(&s_sum.to_bytes()).add(&sum.neg());
+    if !check.mul_by_cofactor().is_identity() {
+        return Err(Ed25519Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::big_int::BigInt;
+    use crate::ed25519::{
+        BASE_POINT, Ed25519Error, L, Scalar, SigningKey, VerifyingKey, verify_batch,
+    };
+    use crate::hash::Hash;
+    use crate::rng::{CryptoRng, SeededRng};
+    use crate::sha2::Sha512;
+    use crate::test_util::hex;
+
+    fn check(seed: &str, public_key: &str, message: &str, signature: &str) {
+        let key = SigningKey::from_seed(&hex(seed).try_into().unwrap());
+        assert_eq!(key.verifying_key().to_bytes().to_vec(), hex(public_key));
+        let test_result = key.sign(&hex(message));
+        assert_eq!(test_result.to_vec(), hex(signature));
+        assert!(
+            key.verifying_key()
+                .verify(&hex(message), &test_result)
+                .is_ok()
+        );
+    }
+
+    // RFC 8032 section 7.1 tests 1 to 3
+    #[test]
+    fn rfc8032_ed25519() {
+        check(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bac\
+             c61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        );
+        check(
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e\
+             458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        );
+        check(
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290\
+             ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        );
+    }
+
+    // RFC 8032 section 7.2, also produced by openssl pkeyutl with instance:Ed25519ctx
+    #[test]
+    fn rfc8032_ed25519ctx() {
+        let key = SigningKey::from_seed(
+            &hex("0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6")
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(
+            key.verifying_key().to_bytes().to_vec(),
+            hex("dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292")
+        );
+        let message = hex("f726936d19c800494e3fdaff20b276a8");
+        for (context, signature) in [
+            (
+                b"foo",
+                "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a\
+                 8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d",
+            ),
+            (
+                b"bar",
+                "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3\
+                 216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d",
+            ),
+        ] {
+            let test_result = key.sign_ctx(&message, context).unwrap();
+            assert_eq!(test_result.to_vec(), hex(signature));
+            let verifying_key = key.verifying_key();
+            assert!(
+                verifying_key
+                    .verify_ctx(&message, context, &test_result)
+                    .is_ok()
+            );
+            assert_eq!(
+                verifying_key.verify(&message, &test_result),
+                Err(Ed25519Error::InvalidSignature)
+            );
+        }
+    }
+
+    // RFC 8032 section 7.3
+    #[test]
+    fn rfc8032_ed25519ph() {
+        let key = SigningKey::from_seed(
+            &hex("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42")
+                .try_into()
+                .unwrap(),
+        );
+        let mut prehash = Sha512::init();
+        prehash.update(b"a");
+        prehash.update(b"bc");
+        let test_result = key.sign_prehashed(prehash.clone(), b"").unwrap();
+        assert_eq!(
+            test_result.to_vec(),
+            hex(
+                "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+                 31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406"
+            )
+        );
+        let verifying_key = key.verifying_key();
+        assert!(
+            verifying_key
+                .verify_prehashed(prehash, b"", &test_result)
+                .is_ok()
+        );
+        assert!(verifying_key.verify(b"abc", &test_result).is_err());
+    }
+
+    #[test]
+    fn rejects_forgeries() {
+        let key = SigningKey::from_seed(&[7; 32]);
+        let verifying_key = key.verifying_key();
+        let signature = key.sign(b"event 1");
+        assert_eq!(
+            verifying_key.verify(b"event 2", &signature),
+            Err(Ed25519Error::InvalidSignature)
+        );
+
+        let mut corrupted = signature;
+        corrupted[5] ^= 1;
+        assert!(verifying_key.verify(b"event 1", &corrupted).is_err());
+
+        // S + L is the same scalar but must not be accepted
+        let mut malleated = signature;
+        let mut carry = 0u128;
+        for (i, limb) in L.iter().enumerate() {
+            let chunk = &mut malleated[32 + 8 * i..40 + 8 * i];
+            let sum = u64::from_le_bytes(chunk.try_into().unwrap()) as u128 + *limb as u128 + carry;
+            chunk.copy_from_slice(&(sum as u64).to_le_bytes());
+            carry = sum >> 64;
+        }
+        assert_eq!(
+            verifying_key.verify(b"event 1", &malleated),
+            Err(Ed25519Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn invalid_keys_and_contexts() {
+        // y = 2 is not on the curve, and y = p is not canonical
+        let mut not_on_curve = [0u8; 32];
+        not_on_curve[0] = 2;
+        assert!(matches!(
+            VerifyingKey::from_bytes(&not_on_curve),
+            Err(Ed25519Error::InvalidPublicKey)
+        ));
+        let mut p = [0xff; 32];
+        p[0] = 0xed;
+        p[31] = 0x7f;
+        assert!(VerifyingKey::from_bytes(&p).is_err());
+
+        let key = SigningKey::from_seed(&[1; 32]);
+        assert_eq!(key.sign_ctx(b"", b""), Err(Ed25519Error::InvalidContext));
+        assert_eq!(
+            key.sign_ctx(b"", &[0; 256]),
+            Err(Ed25519Error::InvalidContext)
+        );
+        assert!(key.sign_ctx(b"", &[0; 255]).is_ok());
+    }
+
+    #[test]
+    fn batch_verification() {
+        let mut rng = SeededRng::from_seed(b"ed25519 batch");
+        let keys: Vec<SigningKey> = (0..4).map(|_| SigningKey::generate(&mut rng)).collect();
+        let verifying_keys: Vec<VerifyingKey> =
+            keys.iter().map(|key| key.verifying_key()).collect();
+        let messages: Vec<Vec<u8>> = (0..4).map(|i| vec![i as u8; i * 10]).collect();
+        let signatures: Vec<[u8; 64]> = keys
+            .iter()
+            .zip(&messages)
+            .map(|(key, message)| key.sign(message))
+            .collect();
+
+        let items: Vec<(&VerifyingKey, &[u8], &[u8; 64])> = (0..4)
+            .map(|i| (&verifying_keys[i], messages[i].as_slice(), &signatures[i]))
+            .collect();
+        assert!(verify_batch(&items, &mut rng).is_ok());
+        assert!(verify_batch(&[], &mut rng).is_ok());
+
+        let mut bad_signature = signatures[2];
+        bad_signature[40] ^= 1;
+        let mut items = items;
+        items[2].2 = &bad_signature;
+        assert_eq!(
+            verify_batch(&items, &mut rng),
+            Err(Ed25519Error::InvalidSignature)
+        );
+    }
+
+    // Reduction of 64 byte values and mul_add against the same operations mod L
+    #[test]
+    fn scalars_match_big_int() {
+        let order = BigInt::new(Scalar(L).to_bytes().to_vec());
+        let mut rng = SeededRng::from_seed(b"ed25519 scalars");
+        for _ in 0..4 {
+            let wide = rng.random_vec(64);
+            let a = Scalar::from_bytes_wide(&wide);
+            let expected = BigInt::new(wide) % order.clone();
+            assert_eq!(a.to_bytes().to_vec(), expected.to_le_bytes(32));
+
+            let b = Scalar::from_bytes_wide(&rng.random_vec(32));
+            let c = Scalar::from_bytes_wide(&rng.random_vec(32));
+            let big = |s: &Scalar| BigInt::new(s.to_bytes().to_vec());
+            let expected = (big(&a) * big(&b) + big(&c)) % order.clone();
+            assert_eq!(
+                Scalar::mul_add(&a, &b, &c).to_bytes().to_vec(),
+                expected.to_le_bytes(32)
+            );
+        }
+
+        // [L]B is the identity
+        assert!(BASE_POINT.mul(&Scalar(L).to_bytes()).is_identity());
+    }
+}
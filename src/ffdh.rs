@@ -0,0 +1,668 @@
+/**
+* This is an implementation of finite field Diffie-Hellman from NIST SP 800-56A, over the
+* ffdhe groups of RFC 7919 and the MODP groups of RFC 3526, or custom parameters.
+* All the named groups are safe primes p = 2q + 1 with generator 2, which generates the subgroup of
+* prime order q. A peer value must lie in 2..p-2 and satisfy y^q = 1 mod p, so it cannot confine
+* the shared secret to a small subgroup. Secret exponents for the named groups are short, at least
+* twice the estimated strength of the group as RFC 7919 section 5.2 allows, which keeps the large
+* groups usable. Custom groups are only accepted once p and q pass Miller-Rabin and g has order q.
+* The exponentiation is `BigInt::mod_pow`, which does the same work for every exponent of a given
+* length.
+*/
+use crate::big_int::BigInt;
+use crate::hash::Hash;
+use crate::rng::CryptoRng;
+use crate::sha2::Sha256;
+
+///Smallest prime accepted for a custom group, the size of the smallest RFC 3526 group
+pub const MIN_PRIME_BITS: usize = 1536;
+///Smallest subgroup order accepted for a custom group
+pub const MIN_SUBGROUP_BITS: usize = 224;
+///Miller-Rabin rounds when checking custom parameters, a composite passes with probability
+///at most 4^-40
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+///The prime of RFC 7919 appendix A.1
+const FFDHE2048: &str = "\
+    FFFFFFFF FFFFFFFF ADF85458 A2BB4A9A AFDC5620 273D3CF1 D8B9C583 CE2D3695 \
+    A9E13641 146433FB CC939DCE 249B3EF9 7D2FE363 630C75D8 F681B202 AEC4617A \
+    D3DF1ED5 D5FD6561 2433F51F 5F066ED0 85636555 3DED1AF3 B557135E 7F57C935 \
+    984F0C70 E0E68B77 E2A689DA F3EFE872 1DF158A1 36ADE735 30ACCA4F 483A797A \
+    BC0AB182 B324FB61 D108A94B B2C8E3FB B96ADAB7 60D7F468 1D4F42A3 DE394DF4 \
+    AE56EDE7 6372BB19 0B07A7C8 EE0A6D70 9E02FCE1 CDF7E2EC C03404CD 28342F61 \
+    9172FE9C E98583FF 8E4F1232 EEF28183 C3FE3B1B 4C6FAD73 3BB5FCBC 2EC22005 \
+    C58EF183 7D1683B2 C6F34A26 C1B2EFFA 886B4238 61285C97 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 7919 appendix A.2
+const FFDHE3072: &str = "\
+    FFFFFFFF FFFFFFFF ADF85458 A2BB4A9A AFDC5620 273D3CF1 D8B9C583 CE2D3695 \
+    A9E13641 146433FB CC939DCE 249B3EF9 7D2FE363 630C75D8 F681B202 AEC4617A \
+    D3DF1ED5 D5FD6561 2433F51F 5F066ED0 85636555 3DED1AF3 B557135E 7F57C935 \
+    984F0C70 E0E68B77 E2A689DA F3EFE872 1DF158A1 36ADE735 30ACCA4F 483A797A \
+    BC0AB182 B324FB61 D108A94B B2C8E3FB B96ADAB7 60D7F468 1D4F42A3 DE394DF4 \
+    AE56EDE7 6372BB19 0B07A7C8 EE0A6D70 9E02FCE1 CDF7E2EC C03404CD 28342F61 \
+    9172FE9C E98583FF 8E4F1232 EEF28183 C3FE3B1B 4C6FAD73 3BB5FCBC 2EC22005 \
+    C58EF183 7D1683B2 C6F34A26 C1B2EFFA 886B4238 611FCFDC DE355B3B 6519035B \
+    BC34F4DE F99C0238 61B46FC9 D6E6C907 7AD91D26 91F7F7EE 598CB0FA C186D91C \
+    AEFE1309 85139270 B4130C93 BC437944 F4FD4452 E2D74DD3 64F2E21E 71F54BFF \
+    5CAE82AB 9C9DF69E E86D2BC5 22363A0D ABC52197 9B0DEADA 1DBF9A42 D5C4484E \
+    0ABCD06B FA53DDEF 3C1B20EE 3FD59D7C 25E41D2B 66C62E37 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 7919 appendix A.3
+const FFDHE4096: &str = "\
+    FFFFFFFF FFFFFFFF ADF85458 A2BB4A9A AFDC5620 273D3CF1 D8B9C583 CE2D3695 \
+    A9E13641 146433FB CC939DCE 249B3EF9 7D2FE363 630C75D8 F681B202 AEC4617A \
+    D3DF1ED5 D5FD6561 2433F51F 5F066ED0 85636555 3DED1AF3 B557135E 7F57C935 \
+    984F0C70 E0E68B77 E2A689DA F3EFE872 1DF158A1 36ADE735 30ACCA4F 483A797A \
+    BC0AB182 B324FB61 D108A94B B2C8E3FB B96ADAB7 60D7F468 1D4F42A3 DE394DF4 \
+    AE56EDE7 6372BB19 0B07A7C8 EE0A6D70 9E02FCE1 CDF7E2EC C03404CD 28342F61 \
+    9172FE9C E98583FF 8E4F1232 EEF28183 C3FE3B1B 4C6FAD73 3BB5FCBC 2EC22005 \
+    C58EF183 7D1683B2 C6F34A26 C1B2EFFA 886B4238 611FCFDC DE355B3B 6519035B \
+    BC34F4DE F99C0238 61B46FC9 D6E6C907 7AD91D26 91F7F7EE 598CB0FA C186D91C \
+    AEFE1309 85139270 B4130C93 BC437944 F4FD4452 E2D74DD3 64F2E21E 71F54BFF \
+    5CAE82AB 9C9DF69E E86D2BC5 22363A0D ABC52197 9B0DEADA 1DBF9A42 D5C4484E \
+    0ABCD06B FA53DDEF 3C1B20EE 3FD59D7C 25E41D2B 669E1EF1 6E6F52C3 164DF4FB \
+    7930E9E4 E58857B6 AC7D5F42 D69F6D18 7763CF1D 55034004 87F55BA5 7E31CC7A \
+    7135C886 EFB4318A ED6A1E01 2D9E6832 A907600A 918130C4 6DC778F9 71AD0038 \
+    092999A3 33CB8B7A 1A1DB93D 7140003C 2A4ECEA9 F98D0ACC 0A8291CD CEC97DCF \
+    8EC9B55A 7F88A46B 4DB5A851 F44182E1 C68A007E 5E655F6A FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 7919 appendix A.4
+const FFDHE6144: &str = "\
+    FFFFFFFF FFFFFFFF ADF85458 A2BB4A9A AFDC5620 273D3CF1 D8B9C583 CE2D3695 \
+    A9E13641 146433FB CC939DCE 249B3EF9 7D2FE363 630C75D8 F681B202 AEC4617A \
+    D3DF1ED5 D5FD6561 2433F51F 5F066ED0 85636555 3DED1AF3 B557135E 7F57C935 \
+    984F0C70 E0E68B77 E2A689DA F3EFE872 1DF158A1 36ADE735 30ACCA4F 483A797A \
+    BC0AB182 B324FB61 D108A94B B2C8E3FB B96ADAB7 60D7F468 1D4F42A3 DE394DF4 \
+    AE56EDE7 6372BB19 0B07A7C8 EE0A6D70 9E02FCE1 CDF7E2EC C03404CD 28342F61 \
+    9172FE9C E98583FF 8E4F1232 EEF28183 C3FE3B1B 4C6FAD73 3BB5FCBC 2EC22005 \
+    C58EF183 7D1683B2 C6F34A26 C1B2EFFA 886B4238 611FCFDC DE355B3B 6519035B \
+    BC34F4DE F99C0238 61B46FC9 D6E6C907 7AD91D26 91F7F7EE 598CB0FA C186D91C \
+    AEFE1309 85139270 B4130C93 BC437944 F4FD4452 E2D74DD3 64F2E21E 71F54BFF \
+    5CAE82AB 9C9DF69E E86D2BC5 22363A0D ABC52197 9B0DEADA 1DBF9A42 D5C4484E \
+    0ABCD06B FA53DDEF 3C1B20EE 3FD59D7C 25E41D2B 669E1EF1 6E6F52C3 164DF4FB \
+    7930E9E4 E58857B6 AC7D5F42 D69F6D18 7763CF1D 55034004 87F55BA5 7E31CC7A \
+    7135C886 EFB4318A ED6A1E01 2D9E6832 A907600A 918130C4 6DC778F9 71AD0038 \
+    092999A3 33CB8B7A 1A1DB93D 7140003C 2A4ECEA9 F98D0ACC 0A8291CD CEC97DCF \
+    8EC9B55A 7F88A46B 4DB5A851 F44182E1 C68A007E 5E0DD902 0BFD64B6 45036C7A \
+    4E677D2C 38532A3A 23BA4442 CAF53EA6 3BB45432 9B7624C8 917BDD64 B1C0FD4C \
+    B38E8C33 4C701C3A CDAD0657 FCCFEC71 9B1F5C3E 4E46041F 388147FB 4CFDB477 \
+    A52471F7 A9A96910 B855322E DB6340D8 A00EF092 350511E3 0ABEC1FF F9E3A26E \
+    7FB29F8C 183023C3 587E38DA 0077D9B4 763E4E4B 94B2BBC1 94C6651E 77CAF992 \
+    EEAAC023 2A281BF6 B3A739C1 22611682 0AE8DB58 47A67CBE F9C9091B 462D538C \
+    D72B0374 6AE77F5E 62292C31 1562A846 505DC82D B854338A E49F5235 C95B9117 \
+    8CCF2DD5 CACEF403 EC9D1810 C6272B04 5B3B71F9 DC6B80D6 3FDD4A8E 9ADB1E69 \
+    62A69526 D43161C1 A41D570D 7938DAD4 A40E329C D0E40E65 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 7919 appendix A.5
+const FFDHE8192: &str = "\
+    FFFFFFFF FFFFFFFF ADF85458 A2BB4A9A AFDC5620 273D3CF1 D8B9C583 CE2D3695 \
+    A9E13641 146433FB CC939DCE 249B3EF9 7D2FE363 630C75D8 F681B202 AEC4617A \
+    D3DF1ED5 D5FD6561 2433F51F 5F066ED0 85636555 3DED1AF3 B557135E 7F57C935 \
+    984F0C70 E0E68B77 E2A689DA F3EFE872 1DF158A1 36ADE735 30ACCA4F 483A797A \
+    BC0AB182 B324FB61 D108A94B B2C8E3FB B96ADAB7 60D7F468 1D4F42A3 DE394DF4 \
+    AE56EDE7 6372BB19 0B07A7C8 EE0A6D70 9E02FCE1 CDF7E2EC C03404CD 28342F61 \
+    9172FE9C E98583FF 8E4F1232 EEF28183 C3FE3B1B 4C6FAD73 3BB5FCBC 2EC22005 \
+    C58EF183 7D1683B2 C6F34A26 C1B2EFFA 886B4238 611FCFDC DE355B3B 6519035B \
+    BC34F4DE F99C0238 61B46FC9 D6E6C907 7AD91D26 91F7F7EE 598CB0FA C186D91C \
+    AEFE1309 85139270 B4130C93 BC437944 F4FD4452 E2D74DD3 64F2E21E 71F54BFF \
+    5CAE82AB 9C9DF69E E86D2BC5 22363A0D ABC52197 9B0DEADA 1DBF9A42 D5C4484E \
+    0ABCD06B FA53DDEF 3C1B20EE 3FD59D7C 25E41D2B 669E1EF1 6E6F52C3 164DF4FB \
+    7930E9E4 E58857B6 AC7D5F42 D69F6D18 7763CF1D 55034004 87F55BA5 7E31CC7A \
+    7135C886 EFB4318A ED6A1E01 2D9E6832 A907600A 918130C4 6DC778F9 71AD0038 \
+    092999A3 33CB8B7A 1A1DB93D 7140003C 2A4ECEA9 F98D0ACC 0A8291CD CEC97DCF \
+    8EC9B55A 7F88A46B 4DB5A851 F44182E1 C68A007E 5E0DD902 0BFD64B6 45036C7A \
+    4E677D2C 38532A3A 23BA4442 CAF53EA6 3BB45432 9B7624C8 917BDD64 B1C0FD4C \
+    B38E8C33 4C701C3A CDAD0657 FCCFEC71 9B1F5C3E 4E46041F 388147FB 4CFDB477 \
+    A52471F7 A9A96910 B855322E DB6340D8 A00EF092 350511E3 0ABEC1FF F9E3A26E \
+    7FB29F8C 183023C3 587E38DA 0077D9B4 763E4E4B 94B2BBC1 94C6651E 77CAF992 \
+    EEAAC023 2A281BF6 B3A739C1 22611682 0AE8DB58 47A67CBE F9C9091B 462D538C \
+    D72B0374 6AE77F5E 62292C31 1562A846 505DC82D B854338A E49F5235 C95B9117 \
+    8CCF2DD5 CACEF403 EC9D1810 C6272B04 5B3B71F9 DC6B80D6 3FDD4A8E 9ADB1E69 \
+    62A69526 D43161C1 A41D570D 7938DAD4 A40E329C CFF46AAA 36AD004C F600C838 \
+    1E425A31 D951AE64 FDB23FCE C9509D43 687FEB69 EDD1CC5E 0B8CC3BD F64B10EF \
+    86B63142 A3AB8829 555B2F74 7C932665 CB2C0F1C C01BD702 29388839 D2AF05E4 \
+    54504AC7 8B758282 2846C0BA 35C35F5C 59160CC0 46FD8251 541FC68C 9C86B022 \
+    BB709987 6A460E74 51A8A931 09703FEE 1C217E6C 3826E52C 51AA691E 0E423CFC \
+    99E9E316 50C1217B 624816CD AD9A95F9 D5B80194 88D9C0A0 A1FE3075 A577E231 \
+    83F81D4A 3F2FA457 1EFC8CE0 BA8A4FE8 B6855DFE 72B0A66E DED2FBAB FBE58A30 \
+    FAFABE1C 5D71A87E 2F741EF8 C1FE86FE A6BBFDE5 30677F0D 97D11D49 F7A8443D \
+    0822E506 A9F4614E 011E2A94 838FF88C D68C8BB7 C5C6424C FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 3526 section 2 (group 5)
+const MODP1536: &str = "\
+    FFFFFFFF FFFFFFFF C90FDAA2 2168C234 C4C6628B 80DC1CD1 29024E08 8A67CC74 \
+    020BBEA6 3B139B22 514A0879 8E3404DD EF9519B3 CD3A431B 302B0A6D F25F1437 \
+    4FE1356D 6D51C245 E485B576 625E7EC6 F44C42E9 A637ED6B 0BFF5CB6 F406B7ED \
+    EE386BFB 5A899FA5 AE9F2411 7C4B1FE6 49286651 ECE45B3D C2007CB8 A163BF05 \
+    98DA4836 1C55D39A 69163FA8 FD24CF5F 83655D23 DCA3AD96 1C62F356 208552BB \
+    9ED52907 7096966D 670C354E 4ABC9804 F1746C08 CA237327 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 3526 section 3 (group 14)
+const MODP2048: &str = "\
+    FFFFFFFF FFFFFFFF C90FDAA2 2168C234 C4C6628B 80DC1CD1 29024E08 8A67CC74 \
+    020BBEA6 3B139B22 514A0879 8E3404DD EF9519B3 CD3A431B 302B0A6D F25F1437 \
+    4FE1356D 6D51C245 E485B576 625E7EC6 F44C42E9 A637ED6B 0BFF5CB6 F406B7ED \
+    EE386BFB 5A899FA5 AE9F2411 7C4B1FE6 49286651 ECE45B3D C2007CB8 A163BF05 \
+    98DA4836 1C55D39A 69163FA8 FD24CF5F 83655D23 DCA3AD96 1C62F356 208552BB \
+    9ED52907 7096966D 670C354E 4ABC9804 F1746C08 CA18217C 32905E46 2E36CE3B \
+    E39E772C 180E8603 9B2783A2 EC07A28F B5C55DF0 6F4C52C9 DE2BCBF6 95581718 \
+    3995497C EA956AE5 15D22618 98FA0510 15728E5A 8AACAA68 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 3526 section 4 (group 15)
+const MODP3072: &str = "\
+    FFFFFFFF FFFFFFFF C90FDAA2 2168C234 C4C6628B 80DC1CD1 29024E08 8A67CC74 \
+    020BBEA6 3B139B22 514A0879 8E3404DD EF9519B3 CD3A431B 302B0A6D F25F1437 \
+    4FE1356D 6D51C245 E485B576 625E7EC6 F44C42E9 A637ED6B 0BFF5CB6 F406B7ED \
+    EE386BFB 5A899FA5 AE9F2411 7C4B1FE6 49286651 ECE45B3D C2007CB8 A163BF05 \
+    98DA4836 1C55D39A 69163FA8 FD24CF5F 83655D23 DCA3AD96 1C62F356 208552BB \
+    9ED52907 7096966D 670C354E 4ABC9804 F1746C08 CA18217C 32905E46 2E36CE3B \
+    E39E772C 180E8603 9B2783A2 EC07A28F B5C55DF0 6F4C52C9 DE2BCBF6 95581718 \
+    3995497C EA956AE5 15D22618 98FA0510 15728E5A 8AAAC42D AD33170D 04507A33 \
+    A85521AB DF1CBA64 ECFB8504 58DBEF0A 8AEA7157 5D060C7D B3970F85 A6E1E4C7 \
+    ABF5AE8C DB0933D7 1E8C94E0 4A25619D CEE3D226 1AD2EE6B F12FFA06 D98A0864 \
+    D8760273 3EC86A64 521F2B18 177B200C BBE11757 7A615D6C 770988C0 BAD946E2 \
+    08E24FA0 74E5AB31 43DB5BFC E0FD108E 4B82D120 A93AD2CA FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 3526 section 5 (group 16)
+const MODP4096: &str = "\
+    FFFFFFFF FFFFFFFF C90FDAA2 2168C234 C4C6628B 80DC1CD1 29024E08 8A67CC74 \
+    020BBEA6 3B139B22 514A0879 8E3404DD EF9519B3 CD3A431B 302B0A6D F25F1437 \
+    4FE1356D 6D51C245 E485B576 625E7EC6 F44C42E9 A637ED6B 0BFF5CB6 F406B7ED \
+    EE386BFB 5A899FA5 AE9F2411 7C4B1FE6 49286651 ECE45B3D C2007CB8 A163BF05 \
+    98DA4836 1C55D39A 69163FA8 FD24CF5F 83655D23 DCA3AD96 1C62F356 208552BB \
+    9ED52907 7096966D 670C354E 4ABC9804 F1746C08 CA18217C 32905E46 2E36CE3B \
+    E39E772C 180E8603 9B2783A2 EC07A28F B5C55DF0 6F4C52C9 DE2BCBF6 95581718 \
+    3995497C EA956AE5 15D22618 98FA0510 15728E5A 8AAAC42D AD33170D 04507A33 \
+    A85521AB DF1CBA64 ECFB8504 58DBEF0A 8AEA7157 5D060C7D B3970F85 A6E1E4C7 \
+    ABF5AE8C DB0933D7 1E8C94E0 4A25619D CEE3D226 1AD2EE6B F12FFA06 D98A0864 \
+    D8760273 3EC86A64 521F2B18 177B200C BBE11757 7A615D6C 770988C0 BAD946E2 \
+    08E24FA0 74E5AB31 43DB5BFC E0FD108E 4B82D120 A9210801 1A723C12 A787E6D7 \
+    88719A10 BDBA5B26 99C32718 6AF4E23C 1A946834 B6150BDA 2583E9CA 2AD44CE8 \
+    DBBBC2DB 04DE8EF9 2E8EFC14 1FBECAA6 287C5947 4E6BC05D 99B2964F A090C3A2 \
+    233BA186 515BE7ED 1F612970 CEE2D7AF B81BDD76 2170481C D0069127 D5B05AA9 \
+    93B4EA98 8D8FDDC1 86FFB7DC 90A6C08F 4DF435C9 34063199 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 3526 section 6 (group 17)
+const MODP6144: &str = "\
+    FFFFFFFF FFFFFFFF C90FDAA2 2168C234 C4C6628B 80DC1CD1 29024E08 8A67CC74 \
+    020BBEA6 3B139B22 514A0879 8E3404DD EF9519B3 CD3A431B 302B0A6D F25F1437 \
+    4FE1356D 6D51C245 E485B576 625E7EC6 F44C42E9 A637ED6B 0BFF5CB6 F406B7ED \
+    EE386BFB 5A899FA5 AE9F2411 7C4B1FE6 49286651 ECE45B3D C2007CB8 A163BF05 \
+    98DA4836 1C55D39A 69163FA8 FD24CF5F 83655D23 DCA3AD96 1C62F356 208552BB \
+    9ED52907 7096966D 670C354E 4ABC9804 F1746C08 CA18217C 32905E46 2E36CE3B \
+    E39E772C 180E8603 9B2783A2 EC07A28F B5C55DF0 6F4C52C9 DE2BCBF6 95581718 \
+    3995497C EA956AE5 15D22618 98FA0510 15728E5A 8AAAC42D AD33170D 04507A33 \
+    A85521AB DF1CBA64 ECFB8504 58DBEF0A 8AEA7157 5D060C7D B3970F85 A6E1E4C7 \
+    ABF5AE8C DB0933D7 1E8C94E0 4A25619D CEE3D226 1AD2EE6B F12FFA06 D98A0864 \
+    D8760273 3EC86A64 521F2B18 177B200C BBE11757 7A615D6C 770988C0 BAD946E2 \
+    08E24FA0 74E5AB31 43DB5BFC E0FD108E 4B82D120 A9210801 1A723C12 A787E6D7 \
+    88719A10 BDBA5B26 99C32718 6AF4E23C 1A946834 B6150BDA 2583E9CA 2AD44CE8 \
+    DBBBC2DB 04DE8EF9 2E8EFC14 1FBECAA6 287C5947 4E6BC05D 99B2964F A090C3A2 \
+    233BA186 515BE7ED 1F612970 CEE2D7AF B81BDD76 2170481C D0069127 D5B05AA9 \
+    93B4EA98 8D8FDDC1 86FFB7DC 90A6C08F 4DF435C9 34028492 36C3FAB4 D27C7026 \
+    C1D4DCB2 602646DE C9751E76 3DBA37BD F8FF9406 AD9E530E E5DB382F 413001AE \
+    B06A53ED 9027D831 179727B0 865A8918 DA3EDBEB CF9B14ED 44CE6CBA CED4BB1B \
+    DB7F1447 E6CC254B 33205151 2BD7AF42 6FB8F401 378CD2BF 5983CA01 C64B92EC \
+    F032EA15 D1721D03 F482D7CE 6E74FEF6 D55E702F 46980C82 B5A84031 900B1C9E \
+    59E7C97F BEC7E8F3 23A97A7E 36CC88BE 0F1D45B7 FF585AC5 4BD407B2 2B4154AA \
+    CC8F6D7E BF48E1D8 14CC5ED2 0F8037E0 A79715EE F29BE328 06A1D58B B7C5DA76 \
+    F550AA3D 8A1FBFF0 EB19CCB1 A313D55C DA56C9EC 2EF29632 387FE8D7 6E3C0468 \
+    043E8F66 3F4860EE 12BF2D5B 0B7474D6 E694F91E 6DCC4024 FFFFFFFF FFFFFFFF";
+
+///The prime of RFC 3526 section 7 (group 18)
+const MODP8192: &str = "\
+    FFFFFFFF FFFFFFFF C90FDAA2 2168C234 C4C6628B 80DC1CD1 29024E08 8A67CC74 \
+    020BBEA6 3B139B22 514A0879 8E3404DD EF9519B3 CD3A431B 302B0A6D F25F1437 \
+    4FE1356D 6D51C245 E485B576 625E7EC6 F44C42E9 A637ED6B 0BFF5CB6 F406B7ED \
+    EE386BFB 5A899FA5 AE9F2411 7C4B1FE6 49286651 ECE45B3D C2007CB8 A163BF05 \
+    98DA4836 1C55D39A 69163FA8 FD24CF5F 83655D23 DCA3AD96 1C62F356 208552BB \
+    9ED52907 7096966D 670C354E 4ABC9804 F1746C08 CA18217C 32905E46 2E36CE3B \
+    E39E772C 180E8603 9B2783A2 EC07A28F B5C55DF0 6F4C52C9 DE2BCBF6 95581718 \
+    3995497C EA956AE5 15D22618 98FA0510 15728E5A 8AAAC42D AD33170D 04507A33 \
+    A85521AB DF1CBA64 ECFB8504 58DBEF0A 8AEA7157 5D060C7D B3970F85 A6E1E4C7 \
+    ABF5AE8C DB0933D7 1E8C94E0 4A25619D CEE3D226 1AD2EE6B F12FFA06 D98A0864 \
+    D8760273 3EC86A64 521F2B18 177B200C BBE11757 7A615D6C 770988C0 BAD946E2 \
+    08E24FA0 74E5AB31 43DB5BFC E0FD108E 4B82D120 A9210801 1A723C12 A787E6D7 \
+    88719A10 BDBA5B26 99C32718 6AF4E23C 1A946834 B6150BDA 2583E9CA 2AD44CE8 \
+    DBBBC2DB 04DE8EF9 2E8EFC14 1FBECAA6 287C5947 4E6BC05D 99B2964F A090C3A2 \
+    233BA186 515BE7ED 1F612970 CEE2D7AF B81BDD76 2170481C D0069127 D5B05AA9 \
+    93B4EA98 8D8FDDC1 86FFB7DC 90A6C08F 4DF435C9 34028492 36C3FAB4 D27C7026 \
+    C1D4DCB2 602646DE C9751E76 3DBA37BD F8FF9406 AD9E530E E5DB382F 413001AE \
+    B06A53ED 9027D831 179727B0 865A8918 DA3EDBEB CF9B14ED 44CE6CBA CED4BB1B \
+    DB7F1447 E6CC254B 33205151 2BD7AF42 6FB8F401 378CD2BF 5983CA01 C64B92EC \
+    F032EA15 D1721D03 F482D7CE 6E74FEF6 D55E702F 46980C82 B5A84031 900B1C9E \
+    59E7C97F BEC7E8F3 23A97A7E 36CC88BE 0F1D45B7 FF585AC5 4BD407B2 2B4154AA \
+    CC8F6D7E BF48E1D8 14CC5ED2 0F8037E0 A79715EE F29BE328 06A1D58B B7C5DA76 \
+    F550AA3D 8A1FBFF0 EB19CCB1 A313D55C DA56C9EC 2EF29632 387FE8D7 6E3C0468 \
+    043E8F66 3F4860EE 12BF2D5B 0B7474D6 E694F91E 6DBE1159 74A3926F 12FEE5E4 \
+    38777CB6 A932DF8C D8BEC4D0 73B931BA 3BC832B6 8D9DD300 741FA7BF 8AFC47ED \
+    2576F693 6BA42466 3AAB639C 5AE4F568 3423B474 2BF1C978 238F16CB E39D652D \
+    E3FDB8BE FC848AD9 22222E04 A4037C07 13EB57A8 1A23F0C7 3473FC64 6CEA306B \
+    4BCBC886 2F8385DD FA9D4B7F A2C087E8 79683303 ED5BDD3A 062B3CF5 B3A278A6 \
+    6D2A13F8 3F44F82D DF310EE0 74AB6A36 4597E899 A0255DC1 64F31CC5 0846851D \
+    F9AB4819 5DED7EA1 B1D510BD 7EE74D73 FAF36BC3 1ECFA268 359046F4 EB879F92 \
+    4009438B 481C6CD7 889A002E D5EE382B C9190DA6 FC026E47 9558E447 5677E9AA \
+    9E3050E2 765694DF C81F56E8 80B96E71 60C980DD 98EDD3DF FFFFFFFF FFFFFFFF";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfdhError {
+    ///The custom parameters are too small, not prime or the generator has the wrong order
+    InvalidGroup,
+    ///The peer value is out of range or outside the subgroup of order q
+    InvalidPublicKey,
+    ///The secret exponent is not in 1..q-1
+    InvalidSecretKey,
+    ///The keys belong to different groups
+    GroupMismatch,
+}
+
+impl std::fmt::Display for FfdhError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FfdhError::InvalidGroup => write!(f, "invalid group parameters"),
+            FfdhError::InvalidPublicKey => write!(f, "invalid public key"),
+            FfdhError::InvalidSecretKey => write!(f, "invalid secret key"),
+            FfdhError::GroupMismatch => write!(f, "the keys are in different groups"),
+        }
+    }
+}
+
+impl std::error::Error for FfdhError {}
+
+/// The groups of RFC 7919 and RFC 3526
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedGroup {
+    Ffdhe2048,
+    Ffdhe3072,
+    Ffdhe4096,
+    Ffdhe6144,
+    Ffdhe8192,
+    Modp1536,
+    Modp2048,
+    Modp3072,
+    Modp4096,
+    Modp6144,
+    Modp8192,
+}
+
+/// A prime p, the prime order q of the subgroup used and its generator g
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    p: BigInt,
+    q: BigInt,
+    g: BigInt,
+    ///Length of the secret exponents, at most the length of q
+    exponent_bits: usize,
+}
+
+fn from_hex(digits: &str) -> BigInt {
+    let nibbles: Vec<u8> = digits
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|c| (c as char).to_digit(16).expect("invalid hex digit") as u8)
+        .collect();
+    let bytes: Vec<u8> = nibbles
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect();
+    BigInt::from_be_bytes(&bytes)
+}
+
+/// Miller-Rabin with bases derived from a hash of `n`, so they cannot be chosen for in advance
+fn is_probable_prime(n: &BigInt) -> bool {
+    let three = BigInt::from_u64(3);
+    if *n <= three || (n.clone() % BigInt::from_u64(2)).is_zero() {
+        return *n == BigInt::from_u64(2) || *n == three;
+    }
+    let size = n.bit_len().div_ceil(8);
+    let bytes = n.to_be_bytes(size);
+    (0..MILLER_RABIN_ROUNDS as u32).all(|round| {
+        let mut hash = Sha256::init();
+        hash.update(&round.to_be_bytes());
+        hash.update(&bytes);
+        let base = BigInt::from_be_bytes(&hash.finalize()) % (n.clone() - three.clone())
+            + BigInt::from_u64(2);
+        n.miller_rabin(&base)
+    })
+}
+
+impl Group {
+    /// One of the groups of RFC 7919 or RFC 3526
+    pub fn named(name: NamedGroup) -> Group {
+        // secret exponent lengths from RFC 7919 appendix A, and twice the upper strength
+        // estimates of RFC 3526 section 8
+        let (p, exponent_bits) = match name {
+            NamedGroup::Ffdhe2048 => (FFDHE2048, 225),
+            NamedGroup::Ffdhe3072 => (FFDHE3072, 275),
+            NamedGroup::Ffdhe4096 => (FFDHE4096, 325),
+            NamedGroup::Ffdhe6144 => (FFDHE6144, 375),
+            NamedGroup::Ffdhe8192 => (FFDHE8192, 400),
+            NamedGroup::Modp1536 => (MODP1536, 240),
+            NamedGroup::Modp2048 => (MODP2048, 320),
+            NamedGroup::Modp3072 => (MODP3072, 420),
+            NamedGroup::Modp4096 => (MODP4096, 480),
+            NamedGroup::Modp6144 => (MODP6144, 540),
+            NamedGroup::Modp8192 => (MODP8192, 620),
+        };
+        let p = from_hex(p);
+        let q = (p.clone() - BigInt::from_u64(1)) / BigInt::from_u64(2);
+        Group {
+            p,
+            q,
+            g: BigInt::from_u64(2),
+            exponent_bits,
+        }
+    }
+
+    /// A custom group over the safe prime `p` = 2q + 1, big endian, with a generator of order q
+    pub fn new(p: &[u8], g: &[u8]) -> Result<Group, FfdhError> {
+        let p = BigInt::from_be_bytes(p);
+        if p <= BigInt::from_u64(1) {
+            return Err(FfdhError::InvalidGroup);
+        }
+        let q = (p.clone() - BigInt::from_u64(1)) / BigInt::from_u64(2);
+        Group::validated(p, q, BigInt::from_be_bytes(g))
+    }
+
+    /// A custom group with a generator `g` of the subgroup of prime order `q`, all big endian
+    pub fn with_subgroup(p: &[u8], q: &[u8], g: &[u8]) -> Result<Group, FfdhError> {
+        Group::validated(
+            BigInt::from_be_bytes(p),
+            BigInt::from_be_bytes(q),
+            BigInt::from_be_bytes(g),
+        )
+    }
+
+    fn validated(mut p: BigInt, mut q: BigInt, mut g: BigInt) -> Result<Group, FfdhError> {
+        p.trim();
+        q.trim();
+        g.trim();
+        let one = BigInt::from_u64(1);
+        if p.bit_len() < MIN_PRIME_BITS || q.bit_len() < MIN_SUBGROUP_BITS {
+            return Err(FfdhError::InvalidGroup);
+        }
+        let p_minus_one = p.clone() - one.clone();
+        if !(p_minus_one.clone() % q.clone()).is_zero() {
+            return Err(FfdhError::InvalidGroup);
+        }
+        if g <= one || g >= p_minus_one || g.mod_pow(&q, &p) != one {
+            return Err(FfdhError::InvalidGroup);
+        }
+        if !is_probable_prime(&q) || !is_probable_prime(&p) {
+            return Err(FfdhError::InvalidGroup);
+        }
+        let exponent_bits = q.bit_len();
+        Ok(Group {
+            p,
+            q,
+            g,
+            exponent_bits,
+        })
+    }
+
+    /// Size in bytes of p, public keys and shared secrets
+    pub fn size(&self) -> usize {
+        self.p.bit_len().div_ceil(8)
+    }
+}
+
+/// A secret exponent in 1..q-1
+#[derive(Clone)]
+pub struct SecretKey<'a> {
+    group: &'a Group,
+    x: BigInt,
+}
+
+impl<'a> SecretKey<'a> {
+    /// Read a big endian exponent
+    pub fn from_bytes(group: &'a Group, bytes: &[u8]) -> Result<SecretKey<'a>, FfdhError> {
+        let x = BigInt::from_be_bytes(bytes);
+        if x.is_zero() || x >= group.q {
+            return Err(FfdhError::InvalidSecretKey);
+        }
+        Ok(SecretKey { group, x })
+    }
+
+    /// Generate a fresh random exponent of the group's secret length, drawing again whenever the
+    /// value is out of range
+    pub fn generate(group: &'a Group, rng: &mut impl CryptoRng) -> SecretKey<'a> {
+        let size = group.exponent_bits.div_ceil(8);
+        loop {
+            let mut bytes = rng.random_vec(size);
+            bytes[0] &= 0xff >> (8 * size - group.exponent_bits);
+            if let Ok(key) = SecretKey::from_bytes(group, &bytes) {
+                return key;
+            }
+        }
+    }
+
+    /// The exponent as big endian bytes, the length of q
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.x.to_be_bytes(self.group.q.bit_len().div_ceil(8))
+    }
+
+    pub fn group(&self) -> &'a Group {
+        self.group
+    }
+
+    pub fn public_key(&self) -> PublicKey<'a> {
+        PublicKey {
+            group: self.group,
+            y: self.group.g.mod_pow(&self.x, &self.group.p),
+        }
+    }
+
+    /// The shared secret with `peer`, padded to the size of p as RFC 7919 and TLS 1.3 require;
+    /// it should be passed through a KDF before use
+    pub fn diffie_hellman(&self, peer: &PublicKey) -> Result<Vec<u8>, FfdhError> {
+        if self.group != peer.group {
+            return Err(FfdhError::GroupMismatch);
+        }
+        let shared = peer.y.mod_pow(&self.x, &self.group.p);
+        Ok(shared.to_be_bytes(self.group.size()))
+    }
+}
+
+/// A public value g^x mod p, checked to be in the subgroup of order q
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey<'a> {
+    group: &'a Group,
+    y: BigInt,
+}
+
+impl<'a> PublicKey<'a> {
+    /// Read a big endian value of at most the size of p, in 2..p-2 and of order q
+    pub fn from_bytes(group: &'a Group, bytes: &[u8]) -> Result<PublicKey<'a>, FfdhError> {
+        let y = BigInt::from_be_bytes(bytes);
+        let one = BigInt::from_u64(1);
+        if bytes.len() > group.size()
+            || y <= one
+            || y >= group.p.clone() - one.clone()
+            || y.mod_pow(&group.q, &group.p) != one
+        {
+            return Err(FfdhError::InvalidPublicKey);
+        }
+        Ok(PublicKey { group, y })
+    }
+
+    /// The value as big endian bytes, padded to the size of p
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.y.to_be_bytes(self.group.size())
+    }
+
+    pub fn group(&self) -> &'a Group {
+        self.group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffdh::{FfdhError, Group, NamedGroup, PublicKey, SecretKey};
+    use crate::rng::SeededRng;
+    use crate::test_util::hex;
+
+    const ALICE: &str = "6b2a1d6f4e3c5b7a89f0e1d2c3b4a5968778695a4b3c2d1e0f1e2d3c4b5a6978";
+    const BOB: &str = "1f2e3d4c5b6a79880123456789abcdeffedcba9876543210a1b2c3d4e5f60718";
+
+    // checked with python pow, Bob's public value has a leading zero byte
+    #[test]
+    fn ffdhe2048_matches_python() {
+        let group = Group::named(NamedGroup::Ffdhe2048);
+        let alice = SecretKey::from_bytes(&group, &hex(ALICE)).unwrap();
+        let bob = SecretKey::from_bytes(&group, &hex(BOB)).unwrap();
+        assert_eq!(
+            alice.public_key().to_bytes(),
+            hex(
+                "fc821f449837961ce62a15d99507081dfd3af3a2e1122ae740adc1f53d2a0a93\
+                 a319afa6b83438094a3fcad9f1972a07c5961ffd22a87008eca9f9481e1f6f10\
+                 2f7d076ccaf62011d3c5a602e6a9931950afaacaa7f8ca359abbd57258b4c782\
+                 7b76621c3517ec7a67e8eaf9a5e019c44d03f6dc144d3c7b4dfcae1a34d69485\
+                 7ea6eeda1cada1897dffc056aec761d6200c1311a3bb9b736352f724a28bdb1f\
+                 7fb5ab04c6ea6352db07a0fce2718e33ecf6de07fcd0738ace4d695935ad4e74\
+                 a1c9c4d4d4067332ab5ed87868d30a89ea4638fb46fad9b8a57356014e60a175\
+                 8af3810533ab28aceaa66a88a292003f79fce389a8a47795a1cc651bc5a6881d"
+            )
+        );
+        let bob_public = hex(
+            "005682b0efa246326f1bcd41fe517e45b9b8ea579c353ef0d29416f60e4d1140\
+             302a6a27ec072b3818f2258b33139f418203f5d10f91c7523a4f9d86295bd818\
+             4055448980ab467eef234bee41fe6799c695095dcd14f781a712a007364fea98\
+             b788b95f4904fd036e2a14744e832d953b86635317f7ed5088d0b131a3ae7af3\
+             ff7bc52d08524f0e9358b4ff02273d97cab97a1200e914c740988b0b8e4b6e72\
+             a925783a0d876a4e654bd28c05a6f271e2a98549a2f41d5c12f38bbfb9754d3c\
+             5bf2c2d49bfd3584a8f6058c874cccc23cc2faafa64c6aca36ea8274060f611d\
+             8d6c4c0c438bfa11d8bf6856fa22e31e904c4d912abc4939b55b2f37d9ab9cf1",
+        );
+        assert_eq!(bob.public_key().to_bytes(), bob_public);
+
+        // the peer value may also arrive without its leading zero
+        let peer = PublicKey::from_bytes(&group, &bob_public[1..]).unwrap();
+        let test_result = alice.diffie_hellman(&peer).unwrap();
+        assert_eq!(
+            test_result,
+            hex(
+                "563109c7a9cbf5e07eb14e179c4d51ddeefd2892781ba977e577b59285761344\
+                 c419e290ecc2bf1bddac8f254c76dea41f0069b3948788599bf5c767e0a0ce17\
+                 897a586d89c0c0b4eeab11fe495b0c1bfccc5821becdb934599e66c089f281b6\
+                 dc7eb4ce3748c0f8dcddc099456e5da73e5e22bc2ee852bde4f257cab8d6e095\
+                 5b7dd20f99acda3759da820ee9817a9b9e1e0654b90acf2bd72f00f4b2a026cd\
+                 d5b855e5a379751b37da5cd3b71517480fac49af55c69afd3f462eda03beec98\
+                 ef1b3fcaaed6d07f2d7fae65adc13bc2dba2a7d4923ffcd6181e66c6b9b15d89\
+                 cbaa94d1c189bcfe038b26a9304300dde7ac6b250dcef2ccf561b5532f491c40"
+            )
+        );
+        assert_eq!(bob.diffie_hellman(&alice.public_key()), Ok(test_result));
+    }
+
+    // checked with python pow
+    #[test]
+    fn modp1536_matches_python() {
+        let group = Group::named(NamedGroup::Modp1536);
+        let key = SecretKey::from_bytes(&group, &hex(&"0123456789abcdef".repeat(3))).unwrap();
+        assert_eq!(
+            key.public_key().to_bytes(),
+            hex(
+                "5997ac029075c385951abc3ea18e7cbaf9a69c6edf1e964f8560e69f2c8e3e01\
+                 454991b253f30fb193e03cd49fce20503303d8ba27a673ca681d3b44e4943e88\
+                 fbbaa57e85f902c7240aff28270d8cba4e69678a7c708969340f630751214c4a\
+                 a7c56c1d59ea7927a33abab28eef03b4f753d7e31d63003ab761c391fe707dd7\
+                 e300ce8cef349c69eb195a612eb89d462db55499bc21c1cda35058ec800ba0e2\
+                 a399b11c121a020be2568574facd2a5e5bc5099c66f6efdfa2af0ff75e8c9299"
+            )
+        );
+    }
+
+    #[test]
+    fn named_groups() {
+        let mut rng = SeededRng::from_seed(b"ffdh");
+        for (name, bits) in [
+            (NamedGroup::Ffdhe2048, 2048),
+            (NamedGroup::Ffdhe3072, 3072),
+            (NamedGroup::Ffdhe4096, 4096),
+            (NamedGroup::Ffdhe6144, 6144),
+            (NamedGroup::Ffdhe8192, 8192),
+            (NamedGroup::Modp1536, 1536),
+            (NamedGroup::Modp2048, 2048),
+            (NamedGroup::Modp3072, 3072),
+            (NamedGroup::Modp4096, 4096),
+            (NamedGroup::Modp6144, 6144),
+            (NamedGroup::Modp8192, 8192),
+        ] {
+            let group = Group::named(name);
+            assert_eq!(group.size(), bits / 8);
+            assert_eq!(group.p.bit_len(), bits);
+            let alice = SecretKey::generate(&group, &mut rng);
+            let bob = SecretKey::generate(&group, &mut rng);
+            let test_result = alice.diffie_hellman(&bob.public_key()).unwrap();
+            assert_eq!(test_result.len(), bits / 8);
+            assert_eq!(bob.diffie_hellman(&alice.public_key()), Ok(test_result));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_public_keys() {
+        let group = Group::named(NamedGroup::Ffdhe2048);
+        let p = Group::named(NamedGroup::Ffdhe2048).p.to_be_bytes(256);
+        let minus = |k: u8| {
+            let mut value = p.clone();
+            value[255] -= k;
+            value
+        };
+        // 0, 1, p - 1 and p are out of range, -2 is in range but outside the subgroup as 2 is a
+        // square and -1 is not
+        for bad in [
+            vec![0],
+            vec![1],
+            minus(1),
+            p.clone(),
+            minus(2),
+            [vec![0], minus(3)].concat(),
+        ] {
+            assert_eq!(
+                PublicKey::from_bytes(&group, &bad),
+                Err(FfdhError::InvalidPublicKey)
+            );
+        }
+        assert!(PublicKey::from_bytes(&group, &[4]).is_ok());
+
+        let other = Group::named(NamedGroup::Modp2048);
+        let key = SecretKey::from_bytes(&other, &hex(ALICE)).unwrap();
+        let peer = PublicKey::from_bytes(&group, &[4]).unwrap();
+        assert_eq!(key.diffie_hellman(&peer), Err(FfdhError::GroupMismatch));
+        assert_eq!(
+            SecretKey::from_bytes(&group, &[0]).map(|key| key.to_bytes()),
+            Err(FfdhError::InvalidSecretKey)
+        );
+    }
+
+    #[test]
+    fn custom_groups() {
+        let p = Group::named(NamedGroup::Modp1536).p.to_be_bytes(192);
+        let group = Group::new(&p, &[4]).unwrap();
+        let mut rng = SeededRng::from_seed(b"ffdh custom");
+        let alice = SecretKey::generate(&group, &mut rng);
+        let bob = SecretKey::generate(&group, &mut rng);
+        assert_eq!(
+            alice.diffie_hellman(&bob.public_key()),
+            bob.diffie_hellman(&alice.public_key())
+        );
+
+        let minus = |k: u8| {
+            let mut value = p.clone();
+            value[191] -= k;
+            value
+        };
+        let mut not_a_divisor = p.clone();
+        not_a_divisor[0] = 0x7f;
+        let expected = Err(FfdhError::InvalidGroup);
+        // generators of order 1, 2 and 2q
+        for g in [vec![1], minus(1), minus(2)] {
+            assert_eq!(Group::new(&p, &g), expected);
+        }
+        // p - 2 is composite, and 1024 bits are too few
+        assert_eq!(Group::new(&minus(2), &[4]), expected);
+        assert_eq!(Group::new(&p[..128], &[4]), expected);
+        assert_eq!(Group::with_subgroup(&p, &not_a_divisor, &[4]), expected);
+    }
+}
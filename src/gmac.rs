@@ -0,0 +1,139 @@
+/**
+* This is an implementation of GMAC from NIST SP 800-38D, AES-GCM used for authentication only.
+* The message is hashed with GHASH as GCM associated data with an empty ciphertext, and the
+* hash is encrypted with the counter block nonce || 1. Like GCM it takes a 96 bit nonce, which must
+* never be repeated under a key, a repeated nonce leaks the hash key and allows forgeries.
+*/
+use crate::aes::{Aes, AesError, BLOCK_SIZE};
+use crate::aes_gcm::GHash;
+use crate::constant_time;
+
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+
+/// A streaming GMAC computation over one message
+#[derive(Clone)]
+pub struct Gmac {
+    ghash: GHash,
+    ///The encrypted counter block nonce || 1 that masks the hash
+    mask: u128,
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+    len: usize,
+}
+
+impl Gmac {
+    pub fn new(key: &[u8], nonce: &[u8; NONCE_SIZE]) -> Result<Gmac, AesError> {
+        Ok(Gmac::with_cipher(&Aes::new(key)?, nonce))
+    }
+
+    /// GMAC over an already expanded key
+    pub fn with_cipher(cipher: &Aes, nonce: &[u8; NONCE_SIZE]) -> Gmac {
+        let mut h = [0u8; BLOCK_SIZE];
+        cipher.encrypt_block(&mut h);
+        let mut mask = [0u8; BLOCK_SIZE];
+        mask[..NONCE_SIZE].copy_from_slice(nonce);
+        mask[NONCE_SIZE..].copy_from_slice(&1u32.to_be_bytes());
+        cipher.encrypt_block(&mut mask);
+        Gmac {
+            ghash: GHash::new(u128::from_be_bytes(h)),
+            mask: u128::from_be_bytes(mask),
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len();
+        if self.buffer_len > 0 {
+            let take = data.len().min(BLOCK_SIZE - self.buffer_len);
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+            if self.buffer_len < BLOCK_SIZE {
+                return;
+            }
+            self.ghash.update_padded(&self.buffer);
+            self.buffer_len = 0;
+        }
+
+        let whole = data.len() - data.len() % BLOCK_SIZE;
+        self.ghash.update_padded(&data[..whole]);
+        let rest = &data[whole..];
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; TAG_SIZE] {
+        self.ghash.update_padded(&self.buffer[..self.buffer_len]);
+        (self.ghash.finalize(self.len, 0) ^ self.mask).to_be_bytes()
+    }
+
+    /// Compare the tag against `tag` in constant time
+    #[must_use]
+    pub fn verify(self, tag: &[u8]) -> bool {
+        constant_time::eq(&self.finalize(), tag)
+    }
+
+    /// Compute the tag of a complete message in one call
+    pub fn mac(
+        key: &[u8],
+        nonce: &[u8; NONCE_SIZE],
+        data: &[u8],
+    ) -> Result<[u8; TAG_SIZE], AesError> {
+        let mut gmac = Gmac::new(key, nonce)?;
+        gmac.update(data);
+        Ok(gmac.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::Aead;
+    use crate::aes_gcm::Aes256Gcm;
+    use crate::gmac::Gmac;
+    use crate::test_util::hex;
+
+    // gcmEncryptExtIV128 from the NIST CAVP GCM vectors, a test case with associated data only
+    #[test]
+    fn cavp_aad_only() {
+        let test_result = Gmac::mac(
+            &hex("77be63708971c4e240d1cb79e8d77feb"),
+            &hex("e0e00f19fed7ba0136a797f3").try_into().unwrap(),
+            &hex("7a43ec1d9c0a5a78a0b16533a6213cab"),
+        )
+        .unwrap();
+        assert_eq!(
+            test_result.to_vec(),
+            hex("209fcc8d3675ed938e9c7166709dd946")
+        );
+
+        // McGrew and Viega test case 1, the empty message
+        let test_result = Gmac::mac(&[0; 16], &[0; 12], b"").unwrap();
+        assert_eq!(
+            test_result.to_vec(),
+            hex("58e2fccefa7e3061367f1d57a4e7455a")
+        );
+    }
+
+    // checked against AESGCM of the python cryptography package
+    #[test]
+    fn matches_gcm_with_empty_plaintext() {
+        let key: Vec<u8> = (0..32).collect();
+        let message = [0x5a; 41];
+        let sealed = Aes256Gcm::new(&key)
+            .unwrap()
+            .seal(&[0; 12], &message, b"")
+            .unwrap();
+        assert_eq!(sealed, hex("b8052c17eb9eb224a680785961e2dd6c"));
+
+        for split in [0, 1, 15, 16, 17, 40, 41] {
+            let mut gmac = Gmac::new(&key, &[0; 12]).unwrap();
+            gmac.update(&message[..split]);
+            gmac.update(&message[split..]);
+            assert!(gmac.clone().verify(&sealed));
+            assert_eq!(gmac.finalize().to_vec(), sealed);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//Common interface implemented by every hash function in the crate
+
+/// A streaming hash function.
+///
+/// Data is fed in with `update` as many times as needed and the digest is produced by `finalize`,
+/// which consumes the state. Clone a state to keep hashing from a shared prefix.
+pub trait Hash: Clone {
+    /// Size in bytes of the internal block the compression function works on
+    const BLOCK_SIZE: usize;
+    /// Size in bytes of the digest returned by `finalize`
+    const OUTPUT_SIZE: usize;
+
+    /// Create a fresh hash state
+    fn init() -> Self;
+
+    /// Absorb more data into the state
+    fn update(&mut self, data: &[u8]);
+
+    /// Finish the computation and return the digest
+    fn finalize(self) -> Vec<u8>;
+
+    /// Hash a complete message in one call
+    fn digest(data: &[u8]) -> Vec<u8> {
+        let mut state = Self::init();
+        state.update(data);
+        state.finalize()
+    }
+}
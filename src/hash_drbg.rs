@@ -0,0 +1,368 @@
+/**
+* This is an implementation of Hash_DRBG from NIST SP 800-90A, generic over the crate's hash functions.
+* It needs nothing but a hash, which suits devices without a block cipher. The internal state is
+* a value V and a constant C of the seed length: 55 bytes for hashes with outputs up to 256 bits
+* and 111 bytes for longer ones. All arithmetic on them is modulo 2^(8 * seed length).
+*/
+use crate::drbg::{self, DrbgError, MAX_INPUT_SIZE, MAX_REQUEST_SIZE, RESEED_INTERVAL};
+use crate::hash::Hash;
+use crate::rng::CryptoRng;
+use std::marker::PhantomData;
+
+#[derive(Clone)]
+pub struct HashDrbg<H: Hash> {
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: u64,
+    hash: PhantomData<H>,
+}
+
+/// Add the big endian number `value` to `sum`, dropping any carry out of the top byte
+fn add_into(sum: &mut [u8], value: &[u8]) {
+    let mut carry = 0u16;
+    let mut value_bytes = value.iter().rev();
+    for byte in sum.iter_mut().rev() {
+        let total = *byte as u16 + *value_bytes.next().unwrap_or(&0) as u16 + carry;
+        *byte = total as u8;
+        carry = total >> 8;
+    }
+}
+
+impl<H: Hash> HashDrbg<H> {
+    pub fn instantiate(
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> Result<HashDrbg<H>, DrbgError> {
+        check_entropy::<H>(entropy)?;
+        check_input(nonce)?;
+        check_input(personalization)?;
+
+        let v = Self::hash_df(&[entropy, nonce, personalization]);
+        let c = Self::hash_df(&[&[0x00], &v]);
+        Ok(HashDrbg {
+            v,
+            c,
+            reseed_counter: 1,
+            hash: PhantomData,
+        })
+    }
+
+    /// Instantiate taking the entropy and nonce from `rng`
+    pub fn from_rng(
+        rng: &mut impl CryptoRng,
+        personalization: &[u8],
+    ) -> Result<HashDrbg<H>, DrbgError> {
+        let strength = Self::security_strength();
+        let entropy = rng.random_vec(strength);
+        let nonce = rng.random_vec(strength / 2);
+        HashDrbg::instantiate(&entropy, &nonce, personalization)
+    }
+
+    /// The security strength in bytes, the least entropy accepted when (re)seeding
+    pub fn security_strength() -> usize {
+        drbg::hash_security_strength(H::OUTPUT_SIZE)
+    }
+
+    /// The length of V and C in bytes
+    pub fn seed_size() -> usize {
+        if H::OUTPUT_SIZE <= 32 { 55 } else { 111 }
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        check_entropy::<H>(entropy)?;
+        check_input(additional_input)?;
+
+        self.v = Self::hash_df(&[&[0x01], &self.v, entropy, additional_input]);
+        self.c = Self::hash_df(&[&[0x00], &self.v]);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Reseed with entropy taken from `rng`
+    pub fn reseed_from_rng(
+        &mut self,
+        rng: &mut impl CryptoRng,
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        let entropy = rng.random_vec(Self::security_strength());
+        self.reseed(&entropy, additional_input)
+    }
+
+    /// Fill `output` with random bytes, mixing in the optional `additional_input`
+    pub fn generate(
+        &mut self,
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if output.len() > MAX_REQUEST_SIZE {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        check_input(additional_input)?;
+
+        if !additional_input.is_empty() {
+            let w = Self::hash(&[&[0x02], &self.v, additional_input]);
+            add_into(&mut self.v, &w);
+        }
+
+        // Hashgen, hash successive values of V
+        let mut data = self.v.clone();
+        for chunk in output.chunks_mut(H::OUTPUT_SIZE) {
+            let w = Self::hash(&[&data]);
+            chunk.copy_from_slice(&w[..chunk.len()]);
+            add_into(&mut data, &[1]);
+        }
+
+        let h = Self::hash(&[&[0x03], &self.v]);
+        add_into(&mut self.v, &h);
+        let c = self.c.clone();
+        add_into(&mut self.v, &c);
+        add_into(&mut self.v, &self.reseed_counter.to_be_bytes());
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// Reseed with `entropy` and `additional_input`, then generate, as prediction resistance requires
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        output: &mut [u8],
+        entropy: &[u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        self.reseed(entropy, additional_input)?;
+        self.generate(output, &[])
+    }
+
+    /// The number of requests since the last (re)seed, plus one
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn hash(input: &[&[u8]]) -> Vec<u8> {
+        let mut hash = H::init();
+        for part in input {
+            hash.update(part);
+        }
+        hash.finalize()
+    }
+
+    /// Hash_df, derives a seed length value from the concatenation of `input`
+    fn hash_df(input: &[&[u8]]) -> Vec<u8> {
+        let seed_size = Self::seed_size();
+        let bits = (seed_size as u32 * 8).to_be_bytes();
+        let mut output = Vec::with_capacity(seed_size + H::OUTPUT_SIZE);
+        let mut counter = 1u8;
+        while output.len() < seed_size {
+            let mut hash = H::init();
+            hash.update(&[counter]);
+            hash.update(&bits);
+            for part in input {
+                hash.update(part);
+            }
+            output.extend(hash.finalize());
+            counter += 1;
+        }
+        output.truncate(seed_size);
+        output
+    }
+}
+
+fn check_entropy<H: Hash>(entropy: &[u8]) -> Result<(), DrbgError> {
+    if entropy.len() < drbg::hash_security_strength(H::OUTPUT_SIZE)
+        || entropy.len() as u64 > MAX_INPUT_SIZE
+    {
+        return Err(DrbgError::InvalidEntropyLength);
+    }
+    Ok(())
+}
+
+fn check_input(input: &[u8]) -> Result<(), DrbgError> {
+    if input.len() as u64 > MAX_INPUT_SIZE {
+        return Err(DrbgError::InputTooLong);
+    }
+    Ok(())
+}
+
+impl<H: Hash> CryptoRng for HashDrbg<H> {
+    /// Panics once the reseed interval has passed, call `reseed` before then
+    fn fill_bytes(&mut self, output: &mut [u8]) {
+        for chunk in output.chunks_mut(MAX_REQUEST_SIZE) {
+            self.generate(chunk, &[])
+                .expect("Hash_DRBG must be reseeded");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::drbg::DrbgError;
+    use crate::hash_drbg::{self, HashDrbg};
+    use crate::rng::{CryptoRng, SeededRng};
+    use crate::sha2::{Sha256, Sha512};
+    use crate::test_util::hex;
+
+    /// Consecutive bytes, the pattern used for the inputs of the generated tests
+    fn byte_run(first: u8, len: u8) -> Vec<u8> {
+        (first..first + len).collect()
+    }
+
+    #[test]
+    fn add_into_wraps() {
+        let mut sum = [0xff, 0xfe];
+        hash_drbg::add_into(&mut sum, &[0x03]);
+        assert_eq!(sum, [0x00, 0x01]);
+        let mut sum = [0x00, 0x01];
+        hash_drbg::add_into(&mut sum, &[0x01, 0x02, 0x03]);
+        assert_eq!(sum, [0x02, 0x04]);
+    }
+
+    #[test]
+    fn cavp_sha256_prediction_resistance() {
+        // CAVP Hash_DRBG.rsp (CAVS 14.3) [SHA-256] [PredictionResistance = True]
+        // [EntropyInputLen = 256] [NonceLen = 128] [PersonalizationStringLen = 256]
+        // [AdditionalInputLen = 256] [ReturnedBitsLen = 1024] COUNT = 14
+        let mut drbg = HashDrbg::<Sha256>::instantiate(
+            &hex("066dc8ce75b28966a685163fe2a4d427fbdb616650616ba282fc332b4e6f1220"),
+            &hex("559f7c64897083ec2d7370d9f0e5071f"),
+            &hex("886f549aad1ac63d18cbcc6685daa2c2f79eb0894cb4aef1ac544fce57f15e11"),
+        )
+        .unwrap();
+        let mut output = [0u8; 128];
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("ff80b7d26a05bc8a7abe53286b0eeb733b715a205bfa4ff63703deadb6ea0ef4"),
+            &hex("b7215f14ac7bafd0a91772ba22f719afbd20b311636c2b1e83e4a823353fc6ea"),
+        )
+        .unwrap();
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("c73832534681ede37e03846d3c841767297d246c689241d2e775be7ec996293d"),
+            &hex("ced31f7e0dae5bb5c043e246b29473e2fd39512ead4569eee3e3803314aba7a3"),
+        )
+        .unwrap();
+        let test_result = hex(
+            "60c234cfafb468033bf195e578ce266e1465326a96a9e03f8b893670ef62754d\
+             5e80d553a1f84950208b9343079f2ef856e9c570618597b5dc82a2daeaa3fd9b\
+             2fd2a0d71bc62935ccb83da0679805a0e31efee4f0e513b08317faca935e3829\
+             48d272db763e6df32510ff1b99fff8c60eb0dd292ebcbbc80a016ed3b00e4eab",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    // The following expected outputs were generated with OpenSSL's HASH-DRBG
+
+    #[test]
+    fn sha256_reseed_additional_input() {
+        let mut drbg = HashDrbg::<Sha256>::instantiate(
+            &byte_run(0x00, 32),
+            &byte_run(0x20, 16),
+            &byte_run(0x40, 32),
+        )
+        .unwrap();
+        drbg.reseed(&byte_run(0x60, 32), &byte_run(0x80, 32))
+            .unwrap();
+        let mut output = [0u8; 128];
+        drbg.generate(&mut output, &byte_run(0xa0, 32)).unwrap();
+        drbg.generate(&mut output, &byte_run(0xc0, 32)).unwrap();
+        let test_result = hex(
+            "58cb5e571539429fa0be0f72d7ac94ccec65ad25bf703c94624110faf618f668\
+             40f7c00b23460e762dd4972ebde79ec853550697f452fe3dda6caca8450462a2\
+             6a135cd9229fa9e3be7e1e51dbbf148d9ac14d041da9ae7bee982ae2bb86a852\
+             59a6f387554139f475d3433dccd9f7bb1c6e574aa23ec48a3775d6c5abe8993a",
+        );
+        assert_eq!(output.to_vec(), test_result);
+        assert_eq!(drbg.reseed_counter(), 3);
+    }
+
+    #[test]
+    fn sha256_no_inputs() {
+        let mut drbg =
+            HashDrbg::<Sha256>::instantiate(&byte_run(0x00, 32), &byte_run(0x20, 16), &[]).unwrap();
+        let mut output = [0u8; 128];
+        drbg.generate(&mut output, &[]).unwrap();
+        drbg.generate(&mut output, &[]).unwrap();
+        let test_result = hex(
+            "27a3342a35d4bbb8e1dcd8ec0fc1a0d1a25cf906f0445d3b974dbddf4a3ba34e\
+             073302ab655234a703381741af7b15191a96164cc087ad1ef8360960b94dfba7\
+             451ade5f57ff6f74afeb737f8f539304c1ce58a98f3ad4b852b4cec0aceffb2b\
+             d5f153f9395b593dc8d890c6d9cc570107b36cfd4b7081c42102efd89752a1de",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn sha512_long_seed() {
+        let mut drbg = HashDrbg::<Sha512>::instantiate(
+            &byte_run(0x00, 32),
+            &byte_run(0x20, 16),
+            &byte_run(0x40, 32),
+        )
+        .unwrap();
+        drbg.reseed(&byte_run(0x60, 32), &byte_run(0x80, 32))
+            .unwrap();
+        let mut output = [0u8; 256];
+        drbg.generate(&mut output, &byte_run(0xa0, 32)).unwrap();
+        drbg.generate(&mut output, &byte_run(0xc0, 32)).unwrap();
+        let test_result = hex(
+            "6bcfd53f6060bd766bbd8f9abb958f270fda4b6d34647500ab86aae788c81993\
+             14b2a71b2c612e1f541e132513733fcf3539bd0d6e4f469203c3b6e66cdf3df9\
+             71d016bb0dde87ba140703548f99d92f7020dfa386e0961a713606ae003d4741\
+             d67eb5f4fe3843bb266fee32f91fba55852072690b79c28df4cd8c22af1e3159\
+             538a347bca2a3582836cef07424687fee766255b8c931923ac1dc64f91b94a05\
+             a6416221d9157d8daa0c8e7fe5e71e65c65f542dbdac0c20f10fc00f5b6899ab\
+             3aef202f5d0279cbd491ad448fc6763bf8e2358cf626dea9e61fc710e439495e\
+             41be6adfd113dbce853db058d4e044c12df07bb0648fccbe546e2cd9b5a27e4e",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn sha256_prediction_resistance() {
+        let mut drbg = HashDrbg::<Sha256>::instantiate(
+            &byte_run(0x00, 32),
+            &byte_run(0x20, 16),
+            &byte_run(0x40, 32),
+        )
+        .unwrap();
+        let mut output = [0u8; 128];
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &byte_run(0x60, 32),
+            &byte_run(0x80, 32),
+        )
+        .unwrap();
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &byte_run(0xa0, 32),
+            &byte_run(0xc0, 32),
+        )
+        .unwrap();
+        let test_result = hex(
+            "87aa40bbca8729416b7414ab03fead7e6573d628a350601f6d0cdd4d22ff11d5\
+             c68ee88a25d09bb7106ddb937bd2bdba2b9919481333fc4fcb5833570153242f\
+             6dbd3dbe35bda855a3bfc962386e355910c9dd6249d5ba1f99aaca56907fe63c\
+             67b972376097fc610601a0c3b294837804b957c05d7f24392b5f8e61df280573",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn short_entropy_rejected() {
+        assert_eq!(
+            HashDrbg::<Sha512>::instantiate(&[0; 31], &[0; 16], &[]).err(),
+            Some(DrbgError::InvalidEntropyLength)
+        );
+    }
+
+    #[test]
+    fn usable_as_rng() {
+        let mut seed = SeededRng::from_seed(b"hash_drbg");
+        let mut first = HashDrbg::<Sha256>::from_rng(&mut seed, b"app").unwrap();
+        let mut second = first.clone();
+        assert_eq!(first.random_vec(70_000), second.random_vec(70_000));
+        assert_eq!(first.reseed_counter(), 3);
+    }
+}
@@ -0,0 +1,262 @@
+/**
+* This is an implementation of HKDF, the HMAC based key derivation function from RFC 5869.
+* Extract turns input keying material into a pseudorandom key (PRK), and expand stretches the PRK
+* into as many keys as needed, each bound to its own `info` string.
+*/
+use crate::hash::Hash;
+use crate::hmac::Hmac;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HkdfError {
+    ///More than 255 hash outputs of key material were requested
+    OutputTooLong,
+    ///A pseudorandom key shorter than the hash output was supplied
+    InvalidPrkLength,
+}
+
+impl std::fmt::Display for HkdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HkdfError::OutputTooLong => write!(f, "HKDF output is limited to 255 hash lengths"),
+            HkdfError::InvalidPrkLength => write!(f, "HKDF PRK is shorter than the hash output"),
+        }
+    }
+}
+
+impl std::error::Error for HkdfError {}
+
+/// An HKDF pseudorandom key, ready to expand into output keying material
+#[derive(Clone)]
+pub struct Hkdf<H: Hash> {
+    prk: Vec<u8>,
+    hash: PhantomData<H>,
+}
+
+impl<H: Hash> Hkdf<H> {
+    /// The extract step. An empty salt is replaced by a string of zeros the length of the hash.
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> Hkdf<H> {
+        let zero_salt = vec![0u8; H::OUTPUT_SIZE];
+        let salt = if salt.is_empty() {
+            &zero_salt[..]
+        } else {
+            salt
+        };
+        Hkdf {
+            prk: Hmac::<H>::mac(salt, ikm),
+            hash: PhantomData,
+        }
+    }
+
+    /// Skip the extract step and use an existing pseudorandom key
+    pub fn from_prk(prk: &[u8]) -> Result<Hkdf<H>, HkdfError> {
+        if prk.len() < H::OUTPUT_SIZE {
+            return Err(HkdfError::InvalidPrkLength);
+        }
+        Ok(Hkdf {
+            prk: prk.to_vec(),
+            hash: PhantomData,
+        })
+    }
+
+    pub fn prk(&self) -> &[u8] {
+        &self.prk
+    }
+
+    /// The expand step, fills all of `output` with key material bound to `info`
+    pub fn expand_into(&self, info: &[u8], output: &mut [u8]) -> Result<(), HkdfError> {
+        if output.len() > 255 * H::OUTPUT_SIZE {
+            return Err(HkdfError::OutputTooLong);
+        }
+
+        let mut previous: Vec<u8> = Vec::new();
+        for (i, chunk) in output.chunks_mut(H::OUTPUT_SIZE).enumerate() {
+            let mut mac = Hmac::<H>::new(&self.prk);
+            mac.update(&previous);
+            mac.update(info);
+            mac.update(&[(i + 1) as u8]);
+            previous = mac.finalize();
+            chunk.copy_from_slice(&previous[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    /// The expand step, returns `len` bytes of key material bound to `info`
+    pub fn expand(&self, info: &[u8], len: usize) -> Result<Vec<u8>, HkdfError> {
+        let mut output = vec![0u8; len];
+        self.expand_into(info, &mut output)?;
+        Ok(output)
+    }
+
+    /// The expand step into a fixed size array, such as the bytes of an `aes::Key`
+    pub fn expand_array<const N: usize>(&self, info: &[u8]) -> Result<[u8; N], HkdfError> {
+        let mut output = [0u8; N];
+        self.expand_into(info, &mut output)?;
+        Ok(output)
+    }
+}
+
+/// Extract and expand in one call
+pub fn hkdf<H: Hash>(
+    salt: &[u8],
+    ikm: &[u8],
+    info: &[u8],
+    len: usize,
+) -> Result<Vec<u8>, HkdfError> {
+    Hkdf::<H>::extract(salt, ikm).expand(info, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aes;
+    use crate::hkdf::{self, Hkdf, HkdfError};
+    use crate::sha2::{Sha256, Sha384, Sha512};
+    use crate::test_util::hex;
+
+    /// The RFC 5869 test inputs are runs of consecutive bytes
+    fn byte_run(first: u8, last: u8) -> Vec<u8> {
+        (first..=last).collect()
+    }
+
+    fn check_sha256(salt: &[u8], ikm: &[u8], info: &[u8], prk: &str, okm: &str) {
+        let state = Hkdf::<Sha256>::extract(salt, ikm);
+        assert_eq!(state.prk().to_vec(), hex(prk));
+        let test_result = state.expand(info, hex(okm).len()).unwrap();
+        assert_eq!(test_result, hex(okm));
+    }
+
+    // RFC 5869 appendix A.1
+    #[test]
+    fn rfc5869_case_1() {
+        check_sha256(
+            &hex("000102030405060708090a0b0c"),
+            &[0x0b; 22],
+            &hex("f0f1f2f3f4f5f6f7f8f9"),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+             34007208d5b887185865",
+        );
+    }
+
+    // RFC 5869 appendix A.2, longer inputs and outputs
+    #[test]
+    fn rfc5869_case_2() {
+        check_sha256(
+            &byte_run(0x60, 0xaf),
+            &byte_run(0x00, 0x4f),
+            &byte_run(0xb0, 0xff),
+            "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+            "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+             59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+             cc30c58179ec3e87c14c01d5c1f3434f1d87",
+        );
+    }
+
+    // RFC 5869 appendix A.3, empty salt and info
+    #[test]
+    fn rfc5869_case_3() {
+        check_sha256(
+            &[],
+            &[0x0b; 22],
+            &[],
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+             9d201395faa4b61a96c8",
+        );
+    }
+
+    #[test]
+    fn rfc5869_case_1_inputs_sha512() {
+        let ikm = [0x0b; 22];
+        let salt = hex("000102030405060708090a0b0c");
+        let info = hex("f0f1f2f3f4f5f6f7f8f9");
+
+        let state = Hkdf::<Sha512>::extract(&salt, &ikm);
+        assert_eq!(
+            state.prk().to_vec(),
+            hex(
+                "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26\
+                 c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237"
+            )
+        );
+        assert_eq!(
+            state.expand(&info, 42).unwrap(),
+            hex(
+                "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c14815793\
+                 38da362cb8d9f925d7cb"
+            )
+        );
+    }
+
+    #[test]
+    fn rfc5869_case_1_inputs_sha384() {
+        let okm = hkdf::hkdf::<Sha384>(
+            &hex("000102030405060708090a0b0c"),
+            &[0x0b; 22],
+            &hex("f0f1f2f3f4f5f6f7f8f9"),
+            42,
+        );
+        assert_eq!(
+            okm.unwrap(),
+            hex(
+                "9b5097a86038b805309076a44b3a9f38063e25b516dcbf369f394cfab43685f7\
+                 48b6457763e4f0204fc5"
+            )
+        );
+    }
+
+    #[test]
+    fn rfc5869_case_2_inputs_sha512() {
+        let okm = hkdf::hkdf::<Sha512>(
+            &byte_run(0x60, 0xaf),
+            &byte_run(0x00, 0x4f),
+            &byte_run(0xb0, 0xff),
+            82,
+        );
+        assert_eq!(
+            okm.unwrap(),
+            hex(
+                "ce6c97192805b346e6161e821ed165673b84f400a2b514b2fe23d84cd189ddf1\
+                 b695b48cbd1c8388441137b3ce28f16aa64ba33ba466b24df6cfcb021ecff235\
+                 f6a2056ce3af1de44d572097a8505d9e7a93"
+            )
+        );
+    }
+
+    #[test]
+    fn rfc5869_case_3_inputs_sha512() {
+        let okm = hkdf::hkdf::<Sha512>(&[], &[0x0b; 22], &[], 42);
+        assert_eq!(
+            okm.unwrap(),
+            hex(
+                "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90f\
+                 ff22d04836d0e2343bac"
+            )
+        );
+    }
+
+    #[test]
+    fn output_length_limit() {
+        let state = Hkdf::<Sha512>::extract(b"salt", b"secret");
+        assert_eq!(state.expand(b"", 255 * 64).unwrap().len(), 255 * 64);
+        assert_eq!(
+            state.expand(b"", 255 * 64 + 1),
+            Err(HkdfError::OutputTooLong)
+        );
+    }
+
+    #[test]
+    fn short_prk_rejected() {
+        assert!(Hkdf::<Sha512>::from_prk(&[0; 63]).is_err());
+        assert!(Hkdf::<Sha512>::from_prk(&[0; 64]).is_ok());
+    }
+
+    #[test]
+    fn derive_aes_key() {
+        let state = Hkdf::<Sha512>::extract(&byte_run(0x60, 0xaf), &byte_run(0x00, 0x4f));
+        let key = aes::Key {
+            bytes: state.expand_array(b"aes-key").unwrap(),
+        };
+        assert_eq!(key.bytes.to_vec(), hex("6fe1b68e2c1f97b4f73af7fa51ec5639"));
+    }
+}
@@ -0,0 +1,257 @@
+/**
+* This is an implementation of HMAC (RFC 2104, FIPS 198-1), generic over any hash in the crate.
+* Tags can be truncated, following RFC 2104 a truncated tag must keep at least 80 bits.
+*/
+use crate::constant_time;
+use crate::hash::Hash;
+
+///Shortest truncated tag accepted, in bytes
+pub const MIN_TRUNCATED_SIZE: usize = 10;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// A streaming HMAC computation over the hash `H`
+#[derive(Clone)]
+pub struct Hmac<H: Hash> {
+    inner: H,
+    ///The outer hash with the padded key already absorbed
+    outer: H,
+}
+
+impl<H: Hash> Hmac<H> {
+    /// Key the MAC, keys longer than the hash block are hashed first
+    pub fn new(key: &[u8]) -> Hmac<H> {
+        let mut block_key = vec![0u8; H::BLOCK_SIZE];
+        if key.len() > H::BLOCK_SIZE {
+            let hashed = H::digest(key);
+            block_key[..hashed.len()].copy_from_slice(&hashed);
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = H::init();
+        let mut outer = H::init();
+        inner.update(&block_key.iter().map(|b| b ^ IPAD).collect::<Vec<u8>>());
+        outer.update(&block_key.iter().map(|b| b ^ OPAD).collect::<Vec<u8>>());
+        Hmac { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Return the full length tag
+    pub fn finalize(self) -> Vec<u8> {
+        let inner_digest = self.inner.finalize();
+        let mut outer = self.outer;
+        outer.update(&inner_digest);
+        outer.finalize()
+    }
+
+    /// Return the leftmost `len` bytes of the tag.
+    /// Panics if `len` is below `MIN_TRUNCATED_SIZE` or longer than the hash output.
+    pub fn finalize_truncated(self, len: usize) -> Vec<u8> {
+        assert!(
+            (MIN_TRUNCATED_SIZE..=H::OUTPUT_SIZE).contains(&len),
+            "invalid HMAC tag length"
+        );
+        let mut tag = self.finalize();
+        tag.truncate(len);
+        tag
+    }
+
+    /// Check a full length tag in constant time
+    #[must_use]
+    pub fn verify(self, tag: &[u8]) -> bool {
+        constant_time::eq(&self.finalize(), tag)
+    }
+
+    /// Check a truncated tag in constant time, the tag length itself is not secret.
+    /// Tags shorter than `MIN_TRUNCATED_SIZE` are always rejected.
+    #[must_use]
+    pub fn verify_truncated(self, tag: &[u8]) -> bool {
+        if tag.len() < MIN_TRUNCATED_SIZE || tag.len() > H::OUTPUT_SIZE {
+            return false;
+        }
+        constant_time::eq(&self.finalize()[..tag.len()], tag)
+    }
+
+    /// Compute the tag of a complete message in one call
+    pub fn mac(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut state = Self::new(key);
+        state.update(data);
+        state.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hmac::Hmac;
+    use crate::sha2::{Sha224, Sha256, Sha384, Sha512};
+    use crate::sha3::Sha3_256;
+    use crate::test_util::hex;
+
+    #[test]
+    fn rfc4231_case_1() {
+        let key = hex("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b");
+        assert_eq!(
+            Hmac::<Sha224>::mac(&key, b"Hi There"),
+            hex("896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22")
+        );
+        assert_eq!(
+            Hmac::<Sha256>::mac(&key, b"Hi There"),
+            hex("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7")
+        );
+        assert_eq!(
+            Hmac::<Sha384>::mac(&key, b"Hi There"),
+            hex(
+                "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59c\
+                 faea9ea9076ede7f4af152e8b2fa9cb6"
+            )
+        );
+        assert_eq!(
+            Hmac::<Sha512>::mac(&key, b"Hi There"),
+            hex(
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+                 daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+            )
+        );
+    }
+
+    #[test]
+    fn rfc4231_case_2() {
+        let mut state = Hmac::<Sha512>::new(b"Jefe");
+        state.update(b"what do ya want ");
+        state.update(b"for nothing?");
+        assert_eq!(
+            state.finalize(),
+            hex(
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+            )
+        );
+        assert_eq!(
+            Hmac::<Sha224>::mac(b"Jefe", b"what do ya want for nothing?"),
+            hex("a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44")
+        );
+        assert_eq!(
+            Hmac::<Sha256>::mac(b"Jefe", b"what do ya want for nothing?"),
+            hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+    }
+
+    #[test]
+    fn rfc4231_case_4() {
+        let key = hex("0102030405060708090a0b0c0d0e0f10111213141516171819");
+        assert_eq!(
+            Hmac::<Sha224>::mac(&key, &[0xcd; 50]),
+            hex("6c11506874013cac6a2abc1bb382627cec6a90d86efc012de7afec5a")
+        );
+        assert_eq!(
+            Hmac::<Sha256>::mac(&key, &[0xcd; 50]),
+            hex("82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b")
+        );
+        assert_eq!(
+            Hmac::<Sha384>::mac(&key, &[0xcd; 50]),
+            hex(
+                "3e8a69b7783c25851933ab6290af6ca77a9981480850009cc5577c6e1f573b4e\
+                 6801dd23c4a7d679ccf8a386c674cffb"
+            )
+        );
+    }
+
+    #[test]
+    fn rfc4231_case_5_truncation() {
+        let key = [0x0c; 20];
+        let mut state = Hmac::<Sha512>::new(&key);
+        state.update(b"Test With Truncation");
+        assert_eq!(
+            state.finalize_truncated(16),
+            hex("415fad6271580a531d4179bc891d87a6")
+        );
+
+        let mut state = Hmac::<Sha384>::new(&key);
+        state.update(b"Test With Truncation");
+        assert!(state.verify_truncated(&hex("3abf34c3503b2a23a46efc619baef897")));
+
+        let mut state = Hmac::<Sha224>::new(&key);
+        state.update(b"Test With Truncation");
+        assert_eq!(
+            state.finalize_truncated(16),
+            hex("0e2aea68a90c8d37c988bcdb9fca6fa8")
+        );
+        let mut state = Hmac::<Sha256>::new(&key);
+        state.update(b"Test With Truncation");
+        assert_eq!(
+            state.finalize_truncated(16),
+            hex("a3b6167473100ee06e0c796c2955552b")
+        );
+    }
+
+    #[test]
+    fn rfc4231_case_6_long_key() {
+        let data = b"Test Using Larger Than Block-Size Key - Hash Key First";
+        assert_eq!(
+            Hmac::<Sha224>::mac(&[0xaa; 131], data),
+            hex("95e9a0db962095adaebe9b2d6f0dbce2d499f112f2d2b7273fa6870e")
+        );
+        assert_eq!(
+            Hmac::<Sha256>::mac(&[0xaa; 131], data),
+            hex("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+        assert_eq!(
+            Hmac::<Sha512>::mac(&[0xaa; 131], data),
+            hex(
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+                 6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+            )
+        );
+    }
+
+    #[test]
+    fn rfc4231_case_7_long_key_and_data() {
+        let data = b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.";
+        assert_eq!(
+            Hmac::<Sha224>::mac(&[0xaa; 131], data),
+            hex("3a854166ac5d9f023f54d517d0b39dbd946770db9c2b95c9f6f565d1")
+        );
+        assert_eq!(
+            Hmac::<Sha256>::mac(&[0xaa; 131], data),
+            hex("9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2")
+        );
+        assert_eq!(
+            Hmac::<Sha384>::mac(&[0xaa; 131], data),
+            hex(
+                "6617178e941f020d351e2f254e8fd32c602420feb0b8fb9adccebb82461e99c5\
+                 a678cc31e799176d3860e6110c46523e"
+            )
+        );
+    }
+
+    #[test]
+    fn other_hash_families() {
+        assert_eq!(
+            Hmac::<Sha3_256>::mac(b"Jefe", b"what do ya want for nothing?"),
+            hex("c7d4072e788877ae3596bbb0da73b887c9171f93095b294ae857fbe2645e1ba5")
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let tag = hex(
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+        );
+        let mut state = Hmac::<Sha512>::new(b"Jefe");
+        state.update(b"what do ya want for nothing?");
+        assert!(state.clone().verify(&tag));
+
+        let mut bad_tag = tag.clone();
+        bad_tag[63] ^= 1;
+        assert!(!state.clone().verify(&bad_tag));
+        assert!(!state.clone().verify(&tag[..32]));
+        assert!(state.clone().verify_truncated(&tag[..32]));
+        assert!(!state.verify_truncated(&tag[..9]));
+    }
+}
@@ -0,0 +1,315 @@
+/**
+* This is an implementation of HMAC_DRBG from NIST SP 800-90A, generic over the crate's hash functions.
+* The internal state is an HMAC key K and a value V the size of the hash output.
+* Instantiated with a private key as the entropy and a message hash as the nonce it also
+* produces the deterministic signature nonces of RFC 6979.
+*/
+use crate::drbg::{self, DrbgError, MAX_INPUT_SIZE, MAX_REQUEST_SIZE, RESEED_INTERVAL};
+use crate::hash::Hash;
+use crate::hmac::Hmac;
+use crate::rng::CryptoRng;
+use std::marker::PhantomData;
+
+#[derive(Clone)]
+pub struct HmacDrbg<H: Hash> {
+    key: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: u64,
+    hash: PhantomData<H>,
+}
+
+impl<H: Hash> HmacDrbg<H> {
+    pub fn instantiate(
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> Result<HmacDrbg<H>, DrbgError> {
+        check_entropy::<H>(entropy)?;
+        check_input(nonce)?;
+        check_input(personalization)?;
+
+        let mut drbg = HmacDrbg {
+            key: vec![0x00; H::OUTPUT_SIZE],
+            v: vec![0x01; H::OUTPUT_SIZE],
+            reseed_counter: 1,
+            hash: PhantomData,
+        };
+        drbg.update(&[entropy, nonce, personalization]);
+        Ok(drbg)
+    }
+
+    /// The nonce generator of RFC 6979 section 3.2 for a secret key and a reduced message hash,
+    /// both int2octets encoded. Unlike `instantiate` it accepts keys shorter than the security
+    /// strength, such as 160 bit DSA keys with SHA-256
+    pub(crate) fn rfc6979(key: &[u8], digest: &[u8]) -> HmacDrbg<H> {
+        let mut drbg = HmacDrbg {
+            key: vec![0x00; H::OUTPUT_SIZE],
+            v: vec![0x01; H::OUTPUT_SIZE],
+            reseed_counter: 1,
+            hash: PhantomData,
+        };
+        drbg.update(&[key, digest]);
+        drbg
+    }
+
+    /// Instantiate taking the entropy and nonce from `rng`
+    pub fn from_rng(
+        rng: &mut impl CryptoRng,
+        personalization: &[u8],
+    ) -> Result<HmacDrbg<H>, DrbgError> {
+        let strength = Self::security_strength();
+        let entropy = rng.random_vec(strength);
+        let nonce = rng.random_vec(strength / 2);
+        HmacDrbg::instantiate(&entropy, &nonce, personalization)
+    }
+
+    /// The security strength in bytes, the least entropy accepted when (re)seeding
+    pub fn security_strength() -> usize {
+        drbg::hash_security_strength(H::OUTPUT_SIZE)
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        check_entropy::<H>(entropy)?;
+        check_input(additional_input)?;
+
+        self.update(&[entropy, additional_input]);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Reseed with entropy taken from `rng`
+    pub fn reseed_from_rng(
+        &mut self,
+        rng: &mut impl CryptoRng,
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        let entropy = rng.random_vec(Self::security_strength());
+        self.reseed(&entropy, additional_input)
+    }
+
+    /// Fill `output` with random bytes, mixing in the optional `additional_input`
+    pub fn generate(
+        &mut self,
+        output: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if output.len() > MAX_REQUEST_SIZE {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        check_input(additional_input)?;
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for chunk in output.chunks_mut(H::OUTPUT_SIZE) {
+            self.v = Hmac::<H>::mac(&self.key, &self.v);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// Reseed with `entropy` and `additional_input`, then generate, as prediction resistance requires
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        output: &mut [u8],
+        entropy: &[u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        self.reseed(entropy, additional_input)?;
+        self.generate(output, &[])
+    }
+
+    /// The number of requests since the last (re)seed, plus one
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    /// HMAC_DRBG_Update with the concatenation of `provided_data`
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        let empty = provided_data.iter().all(|part| part.is_empty());
+        for round in [0x00, 0x01] {
+            let mut mac = Hmac::<H>::new(&self.key);
+            mac.update(&self.v);
+            mac.update(&[round]);
+            for part in provided_data {
+                mac.update(part);
+            }
+            self.key = mac.finalize();
+            self.v = Hmac::<H>::mac(&self.key, &self.v);
+            if empty {
+                break;
+            }
+        }
+    }
+}
+
+fn check_entropy<H: Hash>(entropy: &[u8]) -> Result<(), DrbgError> {
+    if entropy.len() < drbg::hash_security_strength(H::OUTPUT_SIZE)
+        || entropy.len() as u64 > MAX_INPUT_SIZE
+    {
+        return Err(DrbgError::InvalidEntropyLength);
+    }
+    Ok(())
+}
+
+fn check_input(input: &[u8]) -> Result<(), DrbgError> {
+    if input.len() as u64 > MAX_INPUT_SIZE {
+        return Err(DrbgError::InputTooLong);
+    }
+    Ok(())
+}
+
+impl<H: Hash> CryptoRng for HmacDrbg<H> {
+    /// Panics once the reseed interval has passed, call `reseed` before then
+    fn fill_bytes(&mut self, output: &mut [u8]) {
+        for chunk in output.chunks_mut(MAX_REQUEST_SIZE) {
+            self.generate(chunk, &[])
+                .expect("HMAC_DRBG must be reseeded");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::drbg::DrbgError;
+    use crate::hmac_drbg::HmacDrbg;
+    use crate::rng::{CryptoRng, SeededRng};
+    use crate::sha2::{Sha256, Sha384, Sha512};
+    use crate::test_util::hex;
+
+    #[test]
+    fn cavp_sha512_no_prediction_resistance() {
+        // CAVP HMAC_DRBG.rsp [SHA-512] [PredictionResistance = False]
+        // [EntropyInputLen = 256] [NonceLen = 128] [PersonalizationStringLen = 256]
+        // [AdditionalInputLen = 256] COUNT = 0
+        let mut drbg = HmacDrbg::<Sha512>::instantiate(
+            &hex("da740cbc36057a8e282ae717fe7dfbb245e9e5d49908a0119c5dbcf0a1f2d5ab"),
+            &hex("46561ff612217ba3ff91baa06d4b5440"),
+            &hex("fc227293523ecb5b1e28c87863626627d958acc558a672b148ce19e2abd2dde4"),
+        )
+        .unwrap();
+        drbg.reseed(
+            &hex("1d61d4d8a41c3254b92104fd555adae0569d1835bb52657ec7fbba0fe03579c5"),
+            &hex("b9ed8e35ad018a375b61189c8d365b00507cb1b4510d21cac212356b5bbaa8b2"),
+        )
+        .unwrap();
+
+        let mut output = [0u8; 256];
+        drbg.generate(
+            &mut output,
+            &hex("b7998998eaf9e5d34e64ff7f03de765b31f407899d20535573e670c1b402c26a"),
+        )
+        .unwrap();
+        drbg.generate(
+            &mut output,
+            &hex("2089d49d63e0c4df58879d0cb1ba998e5b3d1a7786b785e7cf13ca5ea5e33cfd"),
+        )
+        .unwrap();
+        let test_result = hex(
+            "5b70f3e4da95264233efbab155b828d4e231b67cc92757feca407cc9615a6608\
+             71cb07ad1a2e9a99412feda8ee34dc9c57fa08d3f8225b30d29887d20907d123\
+             30fffd14d1697ba0756d37491b0a8814106e46c8677d49d9157109c402ad0c24\
+             7a2f50cd5d99e538c850b906937a05dbb8888d984bc77f6ca00b0e3bc97b16d6\
+             d25814a54aa12143afddd8b2263690565d545f4137e593bb3ca88a37b0aadf79\
+             726b95c61906257e6dc47acd5b6b7e4b534243b13c16ad5a0a1163c0099fce43\
+             f428cd27c3e6463cf5e9a9621f4b3d0b3d4654316f4707675df39278d5783823\
+             049477dcce8c57fdbd576711c91301e9bd6bb0d3e72dc46d480ed8f61fd63811",
+        );
+        assert_eq!(output.to_vec(), test_result);
+        assert_eq!(drbg.reseed_counter(), 3);
+    }
+
+    #[test]
+    fn cavp_sha384_no_inputs() {
+        // CAVP HMAC_DRBG.rsp [SHA-384] [PredictionResistance = False]
+        // [PersonalizationStringLen = 0] [AdditionalInputLen = 0] COUNT = 0
+        let mut drbg = HmacDrbg::<Sha384>::instantiate(
+            &hex("096349506f3a7653d54db7ec1d09e93413edd175b6ddbeb00e56752a520ac8ff"),
+            &hex("fc7983b918acadaa71a67e1624f1b502"),
+            &[],
+        )
+        .unwrap();
+        drbg.reseed(
+            &hex("4260a0495fdaba58aae41df82505012d480c8e4f751fd7ebc39f9becd694b2a3"),
+            &[],
+        )
+        .unwrap();
+
+        let mut output = [0u8; 192];
+        drbg.generate(&mut output, &[]).unwrap();
+        drbg.generate(&mut output, &[]).unwrap();
+        let test_result = hex(
+            "f4c7bec0c26cf3892d214549ac6f3d82f34c6966d4295099ee56166e879a70ec\
+             ae130251facda351e903d877b6c5eab5153ce87ba6c7cf8bcc61cbd14cfbe34c\
+             f1ed43678aee69cd87b60e6bcb6ff48ebd44ce9e31982d8fe20aec34fa51d625\
+             f845f61056575969bf785c2ffab4dcc754f13de63423e94bad8d5e166d96a62a\
+             602d3ee4045df162028b89cac45e6207d9097f2b3ac0ab17729251985f276f12\
+             87f5c56cc9ba1a79fbdbb291f3a945fbfdbd63cf13b82ec91f7b1085b33279e3",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn cavp_sha256_prediction_resistance() {
+        // CAVP HMAC_DRBG.rsp [SHA-256] [PredictionResistance = True]
+        // [EntropyInputLen = 256] [NonceLen = 128] [PersonalizationStringLen = 256]
+        // [AdditionalInputLen = 256] COUNT = 0
+        let mut drbg = HmacDrbg::<Sha256>::instantiate(
+            &hex("4294671d493dc085b5184607d7de2ff2b6aceb734a1b026f6cfee7c5a90f03da"),
+            &hex("d071544e599235d5eb38b64b551d2a6e"),
+            &hex("63bc769ae1d95a98bde870e4db7776297041d37c8a5c688d4e024b78d83f4d78"),
+        )
+        .unwrap();
+
+        let mut output = [0u8; 128];
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("db9b4790b62336fbb9a684b82947065393eeef8f57bd2477141ad17e776dac34"),
+            &hex("28848becd3f47696f124f4b14853a456156f69be583a7d4682cff8d44b39e1d3"),
+        )
+        .unwrap();
+        drbg.generate_with_prediction_resistance(
+            &mut output,
+            &hex("4a9abe80f6f522f29878bedf8245b27940a76471006fb4a4110beb4decb6c341"),
+            &hex("8bfce0b7132661c3cd78175d83926f643e36f7608eec2c5dac3ddcbacc8c2182"),
+        )
+        .unwrap();
+        let test_result = hex(
+            "e580dc969194b2b18a97478aef9d1a72390aff14562747bf080d741527a6655c\
+             e7fc135325b457483a9f9c70f91165a811cf4524b50d51199a0df3bd60d12aba\
+             c27d0bf6618e6b114e05420352e23f3603dfe8a225dc19b3d1fff1dc245dc6b1\
+             df24c741744bec3f9437dbbf222df84881a457a589e7815ef132f686b760f012",
+        );
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn short_entropy_rejected() {
+        assert_eq!(
+            HmacDrbg::<Sha256>::instantiate(&[0; 31], &[0; 16], &[]).err(),
+            Some(DrbgError::InvalidEntropyLength)
+        );
+        let mut drbg = HmacDrbg::<Sha256>::instantiate(&[0; 32], &[0; 16], &[]).unwrap();
+        assert_eq!(
+            drbg.reseed(&[0; 16], &[]),
+            Err(DrbgError::InvalidEntropyLength)
+        );
+    }
+
+    #[test]
+    fn usable_as_rng() {
+        let mut seed = SeededRng::from_seed(b"hmac_drbg");
+        let mut first = HmacDrbg::<Sha256>::from_rng(&mut seed, b"app").unwrap();
+        let mut second = first.clone();
+        assert_eq!(first.random_vec(70_000), second.random_vec(70_000));
+
+        first.reseed_from_rng(&mut seed, &[]).unwrap();
+        assert_eq!(first.reseed_counter(), 1);
+        assert_ne!(first.random_vec(32), second.random_vec(32));
+    }
+}
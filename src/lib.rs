@@ -0,0 +1,52 @@
+pub mod aead;
+pub mod aes;
+pub mod aes_cbc_cts;
+pub mod aes_ccm;
+pub mod aes_cfb;
+pub mod aes_ctr;
+pub mod aes_ecb;
+pub mod aes_gcm;
+pub mod aes_gcm_siv;
+pub mod aes_kw;
+pub mod aes_ocb3;
+pub mod aes_ofb;
+pub mod aes_siv;
+pub mod aes_xts;
+pub mod argon2;
+pub mod big_int;
+pub mod bip340;
+pub mod blake2;
+pub mod blake3;
+pub mod chacha20;
+pub mod chacha20_poly1305;
+pub mod cmac;
+pub mod constant_time;
+pub mod ctr_drbg;
+pub mod curve25519;
+pub mod drbg;
+pub mod dsa;
+pub mod ec;
+pub mod ecdh;
+pub mod ecdsa;
+pub mod ed25519;
+pub mod ffdh;
+pub mod gmac;
+pub mod hash;
+pub mod hash_drbg;
+pub mod hkdf;
+pub mod hmac;
+pub mod hmac_drbg;
+pub mod ml_kem;
+pub mod pbkdf2;
+pub mod phc;
+pub mod poly1305;
+pub mod rng;
+pub mod scrypt;
+pub mod sha2;
+pub mod sha3;
+pub mod stream_cipher;
+pub mod x25519;
+pub mod x448;
+
+#[cfg(test)]
+mod test_util;
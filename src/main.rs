@@ -1,11 +1,9 @@
-use crate::aes::STATE;
-
-mod aes;
+use first_rust_proj::aes;
+use first_rust_proj::aes::STATE;
+use first_rust_proj::rng::OsRng;
 
 fn main() {
-    let test_key = aes::Key {
-        bytes: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-    };
+    let test_key = aes::Key::generate(&mut OsRng);
 
     let data: [u8; 16] = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
 
@@ -15,5 +13,5 @@ fn main() {
 
     let mut test_buffer: [u8; 16] = [0; 16];
 
-    test_state.encrypt(&test_buffer);
+    test_state.encrypt(&mut test_buffer);
 }
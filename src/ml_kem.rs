@@ -0,0 +1,1253 @@
+/**
+* This is an implementation of ML-KEM, the module lattice key encapsulation mechanism of FIPS 203,
+* in its three parameter sets ML-KEM-512, ML-KEM-768 and ML-KEM-1024.
+* Polynomials have 256 coefficients in Z_q with q = 3329 and are multiplied in the number theoretic
+* transform domain, where the 17th roots of unity split X^256 + 1 into 128 degree one factors.
+* Secrets and errors come from a centered binomial distribution over SHAKE256 output, the matrix A
+* is sampled from SHAKE128 by rejection, and ciphertexts are rounded (compressed) to fewer bits.
+* Decapsulation re-encrypts the recovered message and, when the ciphertext does not match,
+* returns a pseudorandom key derived from the secret z instead (implicit rejection), choosing
+* between the two without a branch.
+*/
+use crate::constant_time;
+use crate::hash::Hash;
+use crate::rng::CryptoRng;
+use crate::sha3::{Sha3_256, Sha3_512, Shake128, Shake256};
+
+pub const SHARED_SECRET_SIZE: usize = 32;
+///Size of each of the seeds d and z of key generation and of the message m of encapsulation
+pub const SEED_SIZE: usize = 32;
+
+///The modulus q
+const Q: u16 = 3329;
+///Number of coefficients in a polynomial
+const N: usize = 256;
+///Size of a polynomial encoded with 12 bits per coefficient
+const POLY_BYTES: usize = 384;
+
+type Poly = [u16; N];
+
+///Reverse the low 7 bits of i
+const fn bit_rev7(i: usize) -> usize {
+    let mut result = 0;
+    let mut bit = 0;
+    while bit < 7 {
+        result |= ((i >> bit) & 1) << (6 - bit);
+        bit += 1;
+    }
+    result
+}
+
+const fn pow_mod_q(base: u32, mut exponent: usize) -> u16 {
+    let mut result = 1u32;
+    let mut base = base % Q as u32;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % Q as u32;
+        }
+        base = base * base % Q as u32;
+        exponent >>= 1;
+    }
+    result as u16
+}
+
+///17^BitRev7(i) mod q, the twiddle factors of the NTT in the order they are used
+const ZETAS: [u16; 128] = {
+    let mut table = [0u16; 128];
+    let mut i = 0;
+    while i < 128 {
+        table[i] = pow_mod_q(17, bit_rev7(i));
+        i += 1;
+    }
+    table
+};
+
+///17^(2 BitRev7(i) + 1) mod q, the roots of the degree two factors multiplied in the NTT domain
+const GAMMAS: [u16; 128] = {
+    let mut table = [0u16; 128];
+    let mut i = 0;
+    while i < 128 {
+        table[i] = pow_mod_q(17, 2 * bit_rev7(i) + 1);
+        i += 1;
+    }
+    table
+};
+
+///128^-1 mod q, the scaling of the inverse NTT
+const INVERSE_128: u32 = 3303;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MlKemError {
+    ///The encapsulation key has the wrong length or a coefficient that is not reduced mod q
+    InvalidEncapsulationKey,
+    ///The decapsulation key has the wrong length or its embedded hash of the encapsulation key
+    ///does not match
+    InvalidDecapsulationKey,
+    ///The ciphertext has the wrong length for the parameter set
+    InvalidCiphertext,
+}
+
+impl std::fmt::Display for MlKemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MlKemError::InvalidEncapsulationKey => write!(f, "invalid encapsulation key"),
+            MlKemError::InvalidDecapsulationKey => write!(f, "invalid decapsulation key"),
+            MlKemError::InvalidCiphertext => write!(f, "invalid ciphertext length"),
+        }
+    }
+}
+
+impl std::error::Error for MlKemError {}
+
+/// The parameter sets of FIPS 203 section 8
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterSet {
+    MlKem512,
+    MlKem768,
+    MlKem1024,
+}
+
+///The values k, eta1, eta2, du and dv of a parameter set
+struct Params {
+    k: usize,
+    eta1: usize,
+    eta2: usize,
+    du: usize,
+    dv: usize,
+}
+
+impl ParameterSet {
+    fn params(self) -> Params {
+        match self {
+            ParameterSet::MlKem512 => Params {
+                k: 2,
+                eta1: 3,
+                eta2: 2,
+                du: 10,
+                dv: 4,
+            },
+            ParameterSet::MlKem768 => Params {
+                k: 3,
+                eta1: 2,
+                eta2: 2,
+                du: 10,
+                dv: 4,
+            },
+            ParameterSet::MlKem1024 => Params {
+                k: 4,
+                eta1: 2,
+                eta2: 2,
+                du: 11,
+                dv: 5,
+            },
+        }
+    }
+
+    pub fn encapsulation_key_size(self) -> usize {
+        POLY_BYTES * self.params().k + 32
+    }
+
+    pub fn decapsulation_key_size(self) -> usize {
+        2 * POLY_BYTES * self.params().k + 96
+    }
+
+    pub fn ciphertext_size(self) -> usize {
+        let params = self.params();
+        32 * (params.du * params.k + params.dv)
+    }
+}
+
+fn add(a: &Poly, b: &Poly) -> Poly {
+    std::array::from_fn(|i| (a[i] + b[i]) % Q)
+}
+
+fn sub(a: &Poly, b: &Poly) -> Poly {
+    std::array::from_fn(|i| (a[i] + Q - b[i]) % Q)
+}
+
+/// The number theoretic transform of FIPS 203 algorithm 9, in place
+fn ntt(f: &mut Poly) {
+    let mut k = 1;
+    let mut len = 128;
+    while len >= 2 {
+        for start in (0..N).step_by(2 * len) {
+            let zeta = ZETAS[k] as u32;
+            k += 1;
+            for j in start..start + len {
+                let t = (zeta * f[j + len] as u32 % Q as u32) as u16;
+                f[j + len] = (f[j] + Q - t) % Q;
+                f[j] = (f[j] + t) % Q;
+            }
+        }
+        len /= 2;
+    }
+}
+
+/// The inverse transform of FIPS 203 algorithm 10, in place
+fn ntt_inverse(f: &mut Poly) {
+    let mut k = 127;
+    let mut len = 2;
+    while len <= 128 {
+        for start in (0..N).step_by(2 * len) {
+            let zeta = ZETAS[k] as u32;
+            k -= 1;
+            for j in start..start + len {
+                let t = f[j];
+                f[j] = (t + f[j + len]) % Q;
+                f[j + len] = (zeta * ((f[j + len] + Q - t) % Q) as u32 % Q as u32) as u16;
+            }
+        }
+        len *= 2;
+    }
+    for coefficient in f.iter_mut() {
+        *coefficient = (*coefficient as u32 * INVERSE_128 % Q as u32) as u16;
+    }
+}
+
+/// The product of two polynomials in the NTT domain, FIPS 203 algorithms 11 and 12: each pair of
+/// coefficients is a degree one polynomial multiplied modulo X^2 - gamma
+fn multiply_ntts(f: &Poly, g: &Poly) -> Poly {
+    let mut h = [0u16; N];
+    for i in 0..128 {
+        let (a0, a1) = (f[2 * i] as u32, f[2 * i + 1] as u32);
+        let (b0, b1) = (g[2 * i] as u32, g[2 * i + 1] as u32);
+        let q = Q as u32;
+        h[2 * i] = ((a0 * b0 + a1 * b1 % q * GAMMAS[i] as u32) % q) as u16;
+        h[2 * i + 1] = ((a0 * b1 + a1 * b0) % q) as u16;
+    }
+    h
+}
+
+/// The dot product of two vectors of polynomials in the NTT domain
+fn dot(a: &[Poly], b: &[Poly]) -> Poly {
+    a.iter()
+        .zip(b)
+        .fold([0u16; N], |sum, (x, y)| add(&sum, &multiply_ntts(x, y)))
+}
+
+/// Pack coefficients of `d` bits each, least significant bit first (FIPS 203 algorithm 5)
+fn byte_encode(f: &Poly, d: usize, output: &mut Vec<u8>) {
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &coefficient in f {
+        buffer |= (coefficient as u32) << bits;
+        bits += d;
+        while bits >= 8 {
+            output.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    }
+}
+
+/// Unpack 32 d bytes into coefficients of `d` bits each (FIPS 203 algorithm 6). For d = 12 the
+/// values may be up to 4095 and are left unreduced so that callers can check them.
+fn byte_decode(bytes: &[u8], d: usize) -> Poly {
+    let mut f = [0u16; N];
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    let mut bytes = bytes.iter();
+    for coefficient in f.iter_mut() {
+        while bits < d {
+            buffer |= (*bytes.next().expect("input is 32 d bytes") as u32) << bits;
+            bits += 8;
+        }
+        *coefficient = (buffer & ((1 << d) - 1)) as u16;
+        buffer >>= d;
+        bits -= d;
+    }
+    f
+}
+
+/// round(2^d / q * x) mod 2^d. The division is by the constant q, which compiles to a
+/// multiplication rather than a variable time division instruction.
+fn compress(f: &Poly, d: usize) -> Poly {
+    std::array::from_fn(|i| {
+        let scaled = ((f[i] as u32) << d) + Q as u32 / 2;
+        ((scaled / Q as u32) & ((1 << d) - 1)) as u16
+    })
+}
+
+/// round(q / 2^d * y)
+fn decompress(f: &Poly, d: usize) -> Poly {
+    std::array::from_fn(|i| ((f[i] as u32 * Q as u32 + (1 << (d - 1))) >> d) as u16)
+}
+
+/// Rejection sample a polynomial in the NTT domain from SHAKE128(rho || j || i)
+/// (FIPS 203 algorithm 7)
+fn sample_ntt(rho: &[u8], j: u8, i: u8) -> Poly {
+    let mut xof = Shake128::init();
+    xof.update(rho);
+    xof.update(&[j, i]);
+    let mut reader = xof.finalize_xof();
+    let mut f = [0u16; N];
+    let mut count = 0;
+    let mut block = [0u8; 168];
+    while count < N {
+        reader.squeeze(&mut block);
+        for c in block.chunks_exact(3) {
+            let d1 = c[0] as u16 | ((c[1] as u16 & 0x0f) << 8);
+            let d2 = (c[1] as u16 >> 4) | ((c[2] as u16) << 4);
+            for d in [d1, d2] {
+                if d < Q && count < N {
+                    f[count] = d;
+                    count += 1;
+                }
+            }
+        }
+    }
+    f
+}
+
+/// A polynomial with coefficients from the centered binomial distribution with parameter eta,
+/// fed by PRF_eta(s, b) = SHAKE256(s || b) (FIPS 203 algorithm 8)
+fn sample_cbd(s: &[u8], b: u8, eta: usize) -> Poly {
+    let mut prf = Shake256::init();
+    prf.update(s);
+    prf.update(&[b]);
+    let bytes = prf.finalize_xof().squeeze_vec(64 * eta);
+    let bit = |index: usize| ((bytes[index / 8] >> (index % 8)) & 1) as u16;
+    std::array::from_fn(|i| {
+        let x: u16 = (0..eta).map(|j| bit(2 * i * eta + j)).sum();
+        let y: u16 = (0..eta).map(|j| bit(2 * i * eta + eta + j)).sum();
+        (x + Q - y) % Q
+    })
+}
+
+/// Split a 64 byte digest of G = SHA3-512 into its two halves
+fn g(pieces: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut hash = Sha3_512::init();
+    for piece in pieces {
+        hash.update(piece);
+    }
+    let digest = hash.finalize();
+    (
+        digest[..32].try_into().expect("half of 64 bytes"),
+        digest[32..].try_into().expect("half of 64 bytes"),
+    )
+}
+
+/// The matrix A in the NTT domain, entry (i, j) sampled from rho || j || i
+fn expand_a(rho: &[u8], k: usize) -> Vec<Vec<Poly>> {
+    (0..k)
+        .map(|i| (0..k).map(|j| sample_ntt(rho, j as u8, i as u8)).collect())
+        .collect()
+}
+
+/// K-PKE.KeyGen of FIPS 203 algorithm 13, returning the encryption and decryption keys
+fn pke_key_gen(params: &Params, d: &[u8; SEED_SIZE]) -> (Vec<u8>, Vec<u8>) {
+    let (rho, sigma) = g(&[d, &[params.k as u8]]);
+    let a = expand_a(&rho, params.k);
+    let mut s: Vec<Poly> = (0..params.k)
+        .map(|i| sample_cbd(&sigma, i as u8, params.eta1))
+        .collect();
+    let mut e: Vec<Poly> = (0..params.k)
+        .map(|i| sample_cbd(&sigma, (params.k + i) as u8, params.eta1))
+        .collect();
+    s.iter_mut().for_each(ntt);
+    e.iter_mut().for_each(ntt);
+    let mut ek = Vec::with_capacity(POLY_BYTES * params.k + 32);
+    for (row, e) in a.iter().zip(&e) {
+        byte_encode(&add(&dot(row, &s), e), 12, &mut ek);
+    }
+    ek.extend_from_slice(&rho);
+    let mut dk = Vec::with_capacity(POLY_BYTES * params.k);
+    for s in &s {
+        byte_encode(s, 12, &mut dk);
+    }
+    (ek, dk)
+}
+
+/// K-PKE.Encrypt of FIPS 203 algorithm 14, `ek` must already have passed the modulus check
+fn pke_encrypt(params: &Params, ek: &[u8], m: &[u8; 32], r: &[u8; 32]) -> Vec<u8> {
+    let k = params.k;
+    let t: Vec<Poly> = ek[..POLY_BYTES * k]
+        .chunks_exact(POLY_BYTES)
+        .map(|chunk| byte_decode(chunk, 12))
+        .collect();
+    let a = expand_a(&ek[POLY_BYTES * k..], k);
+    let mut y: Vec<Poly> = (0..k)
+        .map(|i| sample_cbd(r, i as u8, params.eta1))
+        .collect();
+    y.iter_mut().for_each(ntt);
+
+    let mut ciphertext = Vec::with_capacity(32 * (params.du * k + params.dv));
+    for i in 0..k {
+        let column: Vec<Poly> = a.iter().map(|row| row[i]).collect();
+        let mut u = dot(&column, &y);
+        ntt_inverse(&mut u);
+        let u = add(&u, &sample_cbd(r, (k + i) as u8, params.eta2));
+        byte_encode(&compress(&u, params.du), params.du, &mut ciphertext);
+    }
+    let mut v = dot(&t, &y);
+    ntt_inverse(&mut v);
+    let mu = decompress(&byte_decode(m, 1), 1);
+    let v = add(&add(&v, &sample_cbd(r, (2 * k) as u8, params.eta2)), &mu);
+    byte_encode(&compress(&v, params.dv), params.dv, &mut ciphertext);
+    ciphertext
+}
+
+/// K-PKE.Decrypt of FIPS 203 algorithm 15
+fn pke_decrypt(params: &Params, dk: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    let (c1, c2) = ciphertext.split_at(32 * params.du * params.k);
+    let mut u: Vec<Poly> = c1
+        .chunks_exact(32 * params.du)
+        .map(|chunk| decompress(&byte_decode(chunk, params.du), params.du))
+        .collect();
+    u.iter_mut().for_each(ntt);
+    let v = decompress(&byte_decode(c2, params.dv), params.dv);
+    let s: Vec<Poly> = dk
+        .chunks_exact(POLY_BYTES)
+        .map(|chunk| byte_decode(chunk, 12))
+        .collect();
+    let mut product = dot(&s, &u);
+    ntt_inverse(&mut product);
+    let mut m = Vec::with_capacity(32);
+    byte_encode(&compress(&sub(&v, &product), 1), 1, &mut m);
+    m.try_into().expect("256 bits are 32 bytes")
+}
+
+/// The public key used to encapsulate a shared secret
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncapsulationKey {
+    parameter_set: ParameterSet,
+    bytes: Vec<u8>,
+}
+
+impl EncapsulationKey {
+    /// Read a key, applying the length and modulus checks of FIPS 203 section 7.2
+    pub fn from_bytes(
+        parameter_set: ParameterSet,
+        bytes: &[u8],
+    ) -> Result<EncapsulationKey, MlKemError> {
+        let k = parameter_set.params().k;
+        if bytes.len() != parameter_set.encapsulation_key_size()
+            || bytes[..POLY_BYTES * k]
+                .chunks_exact(POLY_BYTES)
+                .any(|chunk| byte_decode(chunk, 12).iter().any(|&c| c >= Q))
+        {
+            return Err(MlKemError::InvalidEncapsulationKey);
+        }
+        Ok(EncapsulationKey {
+            parameter_set,
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    pub fn parameter_set(&self) -> ParameterSet {
+        self.parameter_set
+    }
+
+    /// Encapsulate a fresh shared secret, returning the ciphertext and the secret
+    pub fn encapsulate(&self, rng: &mut impl CryptoRng) -> (Vec<u8>, [u8; SHARED_SECRET_SIZE]) {
+        self.encapsulate_with_randomness(&rng.random_array())
+    }
+
+    /// ML-KEM.Encaps_internal of FIPS 203 algorithm 17 with the message m supplied by the caller.
+    /// m must be fresh and secret; this exists for known answer tests.
+    pub fn encapsulate_with_randomness(
+        &self,
+        m: &[u8; SEED_SIZE],
+    ) -> (Vec<u8>, [u8; SHARED_SECRET_SIZE]) {
+        let (shared_secret, r) = g(&[m, &Sha3_256::digest(&self.bytes)]);
+        let ciphertext = pke_encrypt(&self.parameter_set.params(), &self.bytes, m, &r);
+        (ciphertext, shared_secret)
+    }
+}
+
+/// The secret key used to decapsulate, dk_PKE || ek || H(ek) || z
+#[derive(Clone)]
+pub struct DecapsulationKey {
+    parameter_set: ParameterSet,
+    bytes: Vec<u8>,
+}
+
+impl DecapsulationKey {
+    /// Read a key, applying the length and hash checks of FIPS 203 section 7.3
+    pub fn from_bytes(
+        parameter_set: ParameterSet,
+        bytes: &[u8],
+    ) -> Result<DecapsulationKey, MlKemError> {
+        let k = parameter_set.params().k;
+        if bytes.len() != parameter_set.decapsulation_key_size()
+            || Sha3_256::digest(&bytes[POLY_BYTES * k..2 * POLY_BYTES * k + 32])
+                != bytes[2 * POLY_BYTES * k + 32..2 * POLY_BYTES * k + 64]
+        {
+            return Err(MlKemError::InvalidDecapsulationKey);
+        }
+        Ok(DecapsulationKey {
+            parameter_set,
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Generate a key pair from fresh random seeds d and z
+    pub fn generate(parameter_set: ParameterSet, rng: &mut impl CryptoRng) -> DecapsulationKey {
+        DecapsulationKey::from_seed(parameter_set, &rng.random_array(), &rng.random_array())
+    }
+
+    /// ML-KEM.KeyGen_internal of FIPS 203 algorithm 16, deterministic in the seeds d and z.
+    /// The 64 byte d || z is a compact form of the decapsulation key.
+    pub fn from_seed(
+        parameter_set: ParameterSet,
+        d: &[u8; SEED_SIZE],
+        z: &[u8; SEED_SIZE],
+    ) -> DecapsulationKey {
+        let (ek, mut bytes) = pke_key_gen(&parameter_set.params(), d);
+        bytes.extend_from_slice(&ek);
+        bytes.extend_from_slice(&Sha3_256::digest(&ek));
+        bytes.extend_from_slice(z);
+        DecapsulationKey {
+            parameter_set,
+            bytes,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    pub fn parameter_set(&self) -> ParameterSet {
+        self.parameter_set
+    }
+
+    pub fn encapsulation_key(&self) -> EncapsulationKey {
+        let k = self.parameter_set.params().k;
+        EncapsulationKey {
+            parameter_set: self.parameter_set,
+            bytes: self.bytes[POLY_BYTES * k..2 * POLY_BYTES * k + 32].to_vec(),
+        }
+    }
+
+    /// ML-KEM.Decaps of FIPS 203 algorithm 18. A ciphertext of the right length that was not
+    /// produced for this key yields a pseudorandom secret rather than an error, so the caller
+    /// learns nothing from a tampered ciphertext until the secrets fail to agree.
+    pub fn decapsulate(&self, ciphertext: &[u8]) -> Result<[u8; SHARED_SECRET_SIZE], MlKemError> {
+        if ciphertext.len() != self.parameter_set.ciphertext_size() {
+            return Err(MlKemError::InvalidCiphertext);
+        }
+        let params = self.parameter_set.params();
+        let k = params.k;
+        let dk = &self.bytes[..POLY_BYTES * k];
+        let ek = &self.bytes[POLY_BYTES * k..2 * POLY_BYTES * k + 32];
+        let h = &self.bytes[2 * POLY_BYTES * k + 32..2 * POLY_BYTES * k + 64];
+        let z = &self.bytes[2 * POLY_BYTES * k + 64..];
+
+        let m = pke_decrypt(&params, dk, ciphertext);
+        let (shared_secret, r) = g(&[&m, h]);
+        let mut j = Shake256::init();
+        j.update(z);
+        j.update(ciphertext);
+        let mut rejection = [0u8; SHARED_SECRET_SIZE];
+        j.finalize_xof().squeeze(&mut rejection);
+
+        let matches = constant_time::eq(&pke_encrypt(&params, ek, &m, &r), ciphertext);
+        let mask = (matches as u8).wrapping_neg();
+        Ok(std::array::from_fn(|i| {
+            (shared_secret[i] & mask) | (rejection[i] & !mask)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ml_kem::*;
+    use crate::rng::SeededRng;
+    use crate::test_util::hex;
+
+    const PARAMETER_SETS: [ParameterSet; 3] = [
+        ParameterSet::MlKem512,
+        ParameterSet::MlKem768,
+        ParameterSet::MlKem1024,
+    ];
+
+    #[test]
+    fn sizes() {
+        // FIPS 203 table 3
+        let expected = [(800, 1632, 768), (1184, 2400, 1088), (1568, 3168, 1568)];
+        for (set, (ek, dk, ct)) in PARAMETER_SETS.into_iter().zip(expected) {
+            assert_eq!(set.encapsulation_key_size(), ek);
+            assert_eq!(set.decapsulation_key_size(), dk);
+            assert_eq!(set.ciphertext_size(), ct);
+        }
+    }
+
+    #[test]
+    fn ntt_multiplication() {
+        // Multiplying in the NTT domain must agree with schoolbook multiplication mod X^256 + 1
+        let a = sample_cbd(b"ntt test a", 0, 3);
+        let b = sample_ntt(b"ntt test b", 0, 0);
+        let mut expected = [0u16; N];
+        for (i, &x) in a.iter().enumerate() {
+            for (j, &y) in b.iter().enumerate() {
+                let product = (x as u32 * y as u32 % Q as u32) as u16;
+                let index = (i + j) % N;
+                expected[index] = if i + j < N {
+                    (expected[index] + product) % Q
+                } else {
+                    (expected[index] + Q - product) % Q
+                };
+            }
+        }
+        let (mut a_hat, mut b_hat) = (a, b);
+        ntt(&mut a_hat);
+        ntt(&mut b_hat);
+        let mut test_result = multiply_ntts(&a_hat, &b_hat);
+        ntt_inverse(&mut test_result);
+        assert_eq!(test_result, expected);
+
+        ntt_inverse(&mut a_hat);
+        assert_eq!(a_hat, a);
+    }
+
+    #[test]
+    fn compression() {
+        // Decompressing then compressing is the identity, and compressing then decompressing
+        // moves a coefficient by at most round(q / 2^(d + 1))
+        for d in [1, 4, 5, 10, 11] {
+            let values: Poly = std::array::from_fn(|i| (i as u16 * 13) % (1 << d));
+            assert_eq!(compress(&decompress(&values, d), d), values);
+            let coefficients: Poly = std::array::from_fn(|i| (i as u16 * 13) % Q);
+            let bound = (Q as i32 + (1 << (d + 1)) - 1) >> (d + 1);
+            for (x, y) in coefficients
+                .iter()
+                .zip(decompress(&compress(&coefficients, d), d))
+            {
+                let distance = (*x as i32 - y as i32).rem_euclid(Q as i32);
+                assert!(distance.min(Q as i32 - distance) <= bound);
+            }
+            let mut encoded = Vec::new();
+            byte_encode(&values, d, &mut encoded);
+            assert_eq!(encoded.len(), 32 * d);
+            assert_eq!(byte_decode(&encoded, d), values);
+        }
+    }
+
+    // kat_MLKEM_768.rsp count = 0 from the post-quantum-cryptography/KAT collection, the
+    // reference implementation's known answers, as quoted in the rust-openssl tests
+    #[test]
+    fn kat_768_key_generation() {
+        let d = hex("6dbbc4375136df3b07f7c70e639e223e177e7fd53b161b3f4d57791794f12624");
+        let z = hex("f696484048ec21f96cf50a56d0759c448f3779752f0383d37449690694cf7a68");
+        let dk = DecapsulationKey::from_seed(
+            ParameterSet::MlKem768,
+            &d.try_into().unwrap(),
+            &z.try_into().unwrap(),
+        );
+        let test_result = hex(
+            "01f60af1dc8e6360ae78b59d4a5042eb9145a269046d6236b8304f305c2d9dcb\
+             189fe5a62df89b2f5a7bce3bbc753c1e78f730a99869f809aba856b676b707b2\
+             6601d1d909bab32451494eb7d0a2153a6350b79789a9b115f83ea12037256562\
+             f06a1d5aba378da77039d3bdecaca8e6a22a49050a76300a0267cdb38b7ac779\
+             03c50ca53b99283cac6b95fba651b11a4d1a692e4072965060587669f253b1bb\
+             182e661446168ac60221894660020e9bb5f5b7124a0303e2543ea3ea6ce97a24\
+             82b255ca346fb27a847b33b93f3ab2d33064c6e6632d1a23f1144e907b246b47\
+             9f4a5c928929a1e24150f5241258a5b67766a66f6a33846495907828ebe44ecc\
+             5b73124071ba479073910410a16d5d5696b48b194752979795772a91c348f502\
+             b37aa650983ebb89bf3c081ff273544129c9137a6e1834c8f2e7ce14c7870c53\
+             c05b9b94ecd38e6645911b0912336863ec168831f811881075cf38a59de4b5c7\
+             38aa6ef03d779b295588cfb62491cc7b3e08b48473354f9ac8061c152a9e2059\
+             97499b970b69bce66fe42bca2924ccdf0103d0a4c39193c2df25118d72b17aab\
+             26b0c60d4cd2c306ca4696c185de05035f4a09cf970aecc8cc93436f83b1aeaf\
+             452c41929a2eabc151938f74c93b858546df2264eeeab602e04a85c522f8fb1a\
+             5214afd8d4cae57a47b6f381a23126bd9917173128af917f1d483691c450d115\
+             1cfe9a1492d473ed862e27da92500c86a20019e9f975e4f54ad319ba2c5630c4\
+             014219d7ba235456fe530140193d662445e6a941d1e238567ba8d4d95ab1c744\
+             7d690821876d017270cfb169f2d792f03c800720697b410ab41c66f2b2458512\
+             5655eb10aa1087ffcb7750cb887ad4467377500a6a7d3a82976b415a54469577\
+             b4138d919b03f4c9a4d3390bdcb6f1717a5fa4ab25a34f4ba5039bb22c7f3c23\
+             4ea4427347aa7251464e631904d7cac4784f78b49d5f4a104a301809a779f646\
+             6131f9c62bb67147f4cd4973a6aa1c29ae6a8647b6268be089fe048ce990cd63\
+             8743d285c889a707f581b63af41731f0246b054bc4b47aab01b6842a2709d02e\
+             8158ab90f48b69d136082b34cb0673b74aa3f54508ed029fb8f5045ee0639e15\
+             0ee3b3c85f68a310ec0441980100b42abf2bad10d4a9e0c7b2bc5bbcaf73cbcd\
+             c49dc2c949111936779b178974a0392947745a47189bc3fa8a679c80af964a9f\
+             9b1b56577274a2a669d2da6704aa496af407fa1aa964cc3dc3140f5f959a7ea9\
+             74bdb1b83e48a99c0a3e2d75b0669b5c1278962540609166266da18886fc237a\
+             f30cefd569dbe399e6652e45f06a5dfc9a758a4987088ff8e38a3cf36b9d988f\
+             0e070b68d0b88f7bcc41306080d889780c7e238895ccaa4f3577225cca4c8a93\
+             30ce613e717798c9670924b271ac402b51538b8b5967ac490dcab5300e6c54d6\
+             a3632f3b973e4186ee1a7e2e85649185b26370c387235c4df28a9937a49d4078\
+             bf883f4e6346cb3251d9e13f1bda087b285afaa80e262641c5527b0a184b8bc8\
+             4a62e577314658e2029d850064f7a7b81f253e7cc124a9c5b039dc9b179a80c2\
+             f6aee6ea0815172537331a57b505baa76ff5b4c1f0da754b6194f4b39a9b1873\
+             0d3cdab925d691ed77a8db9927ea233ac2a12744fdc27e5d221b9369adb325d8",
+        );
+        assert_eq!(dk.encapsulation_key().to_bytes(), test_result);
+    }
+
+    /// Keys from d = 00..1f and z = 20..3f, encapsulation with m = 64..83, and decapsulation of
+    /// the ciphertext with its last bit flipped, which must give the implicit rejection J(z || c).
+    /// Returns the encapsulation key
+    fn check_known_answer(
+        parameter_set: ParameterSet,
+        encapsulation_key: &str,
+        decapsulation_key: &str,
+        ciphertext: &str,
+        shared_secret: &str,
+        rejection: &str,
+    ) -> Vec<u8> {
+        let d: [u8; 32] = std::array::from_fn(|i| i as u8);
+        let z: [u8; 32] = std::array::from_fn(|i| 32 + i as u8);
+        let m: [u8; 32] = std::array::from_fn(|i| 100 + i as u8);
+
+        let dk = DecapsulationKey::from_seed(parameter_set, &d, &z);
+        let ek = dk.encapsulation_key();
+        assert_eq!(ek.to_bytes(), hex(encapsulation_key));
+        assert_eq!(dk.to_bytes(), hex(decapsulation_key));
+
+        let (mut test_result, shared) = ek.encapsulate_with_randomness(&m);
+        assert_eq!(test_result, hex(ciphertext));
+        assert_eq!(shared.to_vec(), hex(shared_secret));
+        assert_eq!(dk.decapsulate(&test_result).unwrap(), shared);
+
+        let last = test_result.len() - 1;
+        test_result[last] ^= 1;
+        assert_eq!(
+            dk.decapsulate(&test_result).unwrap().to_vec(),
+            hex(rejection)
+        );
+        ek.to_bytes()
+    }
+
+    // Cross-checks against another implementation, covering encapsulation and rejection for
+    // every parameter set: the expected values come from OpenSSL 3.5, genpkey -pkeyopt
+    // hexseed:d||z, then pkeyutl -encap -pkeyopt hexikme:m and -decap of the altered ciphertext
+
+    #[test]
+    fn openssl_cross_check_512() {
+        let mut unreduced = check_known_answer(
+            ParameterSet::MlKem512,
+            "3995815e597d104355cf29aa5333c93251869d5bcdbe487124f602b8b6a66c16\
+             c4761648ad765cf5d8006b515e905a7f0ac076b0c62efa328153e7ca5701699f\
+             1305f1e6bc6f90b0e49b693512b6ce992a8b8016ddfc1a662c7e3f9619cbd869\
+             dd771af30896ccd5918ac6cb77466c5e779996d67ff9aabc97503f2c7b7e2d00\
+             0d86450fb1807ca4cabda465825a31c789a1b7a491ab3872765d320d0b71920f\
+             a213c94093416b83b8124e69f65e62cb5000dcc37aa9a0fff73970c4772f357d\
+             24189ca6f5305568c0e2376a3762a68c605e563c5d209572e0fc7532ca294729\
+             535567b5fc413c5e8792d2464536cc808f98add74664f141566f9016a90a5418\
+             29a98a0464ce41a8bb44c2d4fa3c2c209460728ef14a1a7c4c9b98d12203b4cc\
+             3529160a9ab2d7838f7ff6b53ae05aa31a7d646b7afa6c45932526a3c3755619\
+             be994c211c2a31c05b3447836cb2150be1829dae6b04c5535cff546e392ba797\
+             411720f924f490a5ac5495f21356d550b782a64c1688b6b655bcc7842197a434\
+             c2f6563b5b7f09a78bcc488232783561d16f4cbab6755400050781570c66604b\
+             817ad1252294736e8b01861a4b5a74519b8b6fe51489a5072392e587626c7137\
+             76575d33806a1c8e2732af97c2680f51666331c4eb8bbc0431c4f96832daf1b3\
+             c45528fba153f6c78b1c198702947ccd337727a46fb53ba11de5cb4191346859\
+             516cb6ad72400f3cf209b236aef35a580ac87eb3e30fafd66973ca8a7dd2675a\
+             f41f7a17b61433cd1af80f7708869f665488497980b1ac10a0cdcb636a00ed86\
+             81b35e429124ca80350725b85f83a5eac3a4a3cc1600903e65293560b9b336e5\
+             af0d529dac1a048119302cb7a9bcc110b94851bf02117f199dc485a852b7473f\
+             09b831a6831d5b54c0b790d225cf6bb92d9462a26cdb33dda5123c7aaf0e26a0\
+             b83655eea28bf3a8074725018fd6bae4b601cf61baab71a7a3d35197a343e74b\
+             4a272c125d540896426d85b7958d3b38a6ba987ec37225c7b44cdb12dde4539b\
+             4ab082363683f04bf7a09cc5c41dfe830a1b162e0b324334362f084a14467723\
+             344badd000f8d8c537c48f998f05307cebd1ede0b81c3bc59a065a1b6d63b26c",
+            "70554fd436344f2785b1b3b1bac184b6679003336c26f15a7de878c4825c6be0\
+             3f3c4a480f75b7486aad31d3a00518623fd207ab528dd62721495835ae0062c3\
+             67b74a71baf10aad0e8a2902076be31348beb15ccc0957cdebb4aff226756bbc\
+             601b6568ab784acbaeb34702f0f86a26202118b22b23f83558776c79c14dba98\
+             3379c803e0dcc3160a11757030e69c6919798d81eb698a9a4483a99e5a5cb2c3\
+             1c9a661799f3cc89c790706ea041629045d42a83aed88860e394c69187e2105d\
+             28cc14ec393592d67dd00aa43fe8b4eae4414002866b5c713c6a8d7d16cf78b8\
+             19d6f12e9e5a74233908f0b15e3c4ba8329c5cdda55c84928e3aa8063e5aa967\
+             6403f91735b11010c7f593091364dc86445bc804840a9a21724212469f8a7b0c\
+             e0ac698eb86cad39a7f4824d9a5163aac21ee6808b053c8a3facb0b6744b5262\
+             bbcb26a43f664c8732b64cfc7acf099605f41c796060976ac433833fe00343fb\
+             1828300a424741116e4b45bb276ea81129a0db4c6e60bce611101e8c62547492\
+             5e0222679308a3e7708d1972a7b423eb232851c36d2ed53d3ed3bb7500637061\
+             a5dc2292fa1c466c07354683328bec2c1ed2cb5c99b78eca0969038cf7c34dd1\
+             18724e31cae086206b34302b520f5d177aded5b3cce02acce808ea26bcc07262\
+             5fdb93f17458a5fc1d4da394380a1f57e9cc66109438a075f0d2813fcc4a199c\
+             c76db3823f270b0061594192940411a37ffbafae2c150165cec5c6bf73c595fb\
+             92cd15312607da070778652bd9944bc48bc7d1a534338bad0bad6656c5d502ce\
+             7850ab1587244eeb58f439ab5e08574a718c8aac3d77c798bba1542733be7344\
+             8f23fb70c0e5353a27c88322c5218493afbb38086434d6d60a56ba887dd498c3\
+             ab26a0870993815aa6a40975f218adca1582d64ffc8652fbb3a9a6fbc304f919\
+             45fa4aaef2878fd715df70113d2379f44886f812c83ff2b719a69e1ec74ae4b1\
+             5accd3aed5a53ce76a7b0982471633b973cb40a1a0015d0a424fa11a479c0230\
+             17436d2a2900e993eb5a0a067400c7f4aadf201fc4fa31264a63bae95cc8d65c\
+             3995815e597d104355cf29aa5333c93251869d5bcdbe487124f602b8b6a66c16\
+             c4761648ad765cf5d8006b515e905a7f0ac076b0c62efa328153e7ca5701699f\
+             1305f1e6bc6f90b0e49b693512b6ce992a8b8016ddfc1a662c7e3f9619cbd869\
+             dd771af30896ccd5918ac6cb77466c5e779996d67ff9aabc97503f2c7b7e2d00\
+             0d86450fb1807ca4cabda465825a31c789a1b7a491ab3872765d320d0b71920f\
+             a213c94093416b83b8124e69f65e62cb5000dcc37aa9a0fff73970c4772f357d\
+             24189ca6f5305568c0e2376a3762a68c605e563c5d209572e0fc7532ca294729\
+             535567b5fc413c5e8792d2464536cc808f98add74664f141566f9016a90a5418\
+             29a98a0464ce41a8bb44c2d4fa3c2c209460728ef14a1a7c4c9b98d12203b4cc\
+             3529160a9ab2d7838f7ff6b53ae05aa31a7d646b7afa6c45932526a3c3755619\
+             be994c211c2a31c05b3447836cb2150be1829dae6b04c5535cff546e392ba797\
+             411720f924f490a5ac5495f21356d550b782a64c1688b6b655bcc7842197a434\
+             c2f6563b5b7f09a78bcc488232783561d16f4cbab6755400050781570c66604b\
+             817ad1252294736e8b01861a4b5a74519b8b6fe51489a5072392e587626c7137\
+             76575d33806a1c8e2732af97c2680f51666331c4eb8bbc0431c4f96832daf1b3\
+             c45528fba153f6c78b1c198702947ccd337727a46fb53ba11de5cb4191346859\
+             516cb6ad72400f3cf209b236aef35a580ac87eb3e30fafd66973ca8a7dd2675a\
+             f41f7a17b61433cd1af80f7708869f665488497980b1ac10a0cdcb636a00ed86\
+             81b35e429124ca80350725b85f83a5eac3a4a3cc1600903e65293560b9b336e5\
+             af0d529dac1a048119302cb7a9bcc110b94851bf02117f199dc485a852b7473f\
+             09b831a6831d5b54c0b790d225cf6bb92d9462a26cdb33dda5123c7aaf0e26a0\
+             b83655eea28bf3a8074725018fd6bae4b601cf61baab71a7a3d35197a343e74b\
+             4a272c125d540896426d85b7958d3b38a6ba987ec37225c7b44cdb12dde4539b\
+             4ab082363683f04bf7a09cc5c41dfe830a1b162e0b324334362f084a14467723\
+             344badd000f8d8c537c48f998f05307cebd1ede0b81c3bc59a065a1b6d63b26c\
+             82f101ff648063b376e2bb6c5b7455f655a50c2feadade150efa0e0e6f365aea\
+             202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f",
+            "9e4b58970ffbce62c81909435dd20e68dfecc34500c05354e546d19c96fd68fa\
+             06dc30e4a6aa8a5d2c0e10a1c28b229f493b3a47a1fdf88e434cae3691de3b36\
+             d75942ef39d44cf2fd03afd3fb41a0cb319f27d2f397385fbadb61470a228d7d\
+             5eb316cda18d40524ba0ce10a4775ff000f0aff872e78ce6d080a5ba4c3735e1\
+             f6ee262421201ded25b1f37b54d36f7a31dda0db694e42b0a217fb370363c3b2\
+             c5f5391bd19c270b368d45abf763f69e4f5bab143728e9b0f67efa948f02e7c7\
+             a8a75580519e03919ad381ba11a72f7d6a01704f6f8037b56cf727b97946701e\
+             c3413f98baf4a8d7a3c1d4f5e4bfc5544262f76b302b8d900b6d2f302984b3cc\
+             73daa3a018f7734b48f9af22bb0210210f169be3e6eb636d268fc52f7789c0ef\
+             142d45b42a9322e952b88b4b7b575793017d07c3ea12a6fd5a81f9047a9bd663\
+             39ab51d79a017e593e50ea33e770c00a1d8d6fe52d468c3e2041a9b14d0340c4\
+             c4a695e43176f181dc62cd14e2b377648198913b2d808b2aade7e4c57ac42522\
+             6d73e8af6da28be5be800dedce0c6991b594128aae4a00364846694d22cc490a\
+             ec28e8b810f3677d2ea5939bf8ce873c41f667a51805d84dd946de9fac85c312\
+             348cd3fad076c2c3a501ad1c9ad2f6007bc394fd1dc48452f17262925633a807\
+             7b4ce30b4d5369961bb4abc89e961108f6988e9d08d7b2db943cf61967f08b44\
+             89d2c258a4578bfd2e4365ef6b7e8a165ad362e800aef7586c73eb58bafe7dec\
+             30c750c04eeaaadcdbd366a4bb632692caba90fae7eadd5871fe1aef879b60e0\
+             7a1cd85f74bc8e0674cf8b14e376154dc7434292135ad4c84d35df87904ad679\
+             365467155f68c1e44144075915cc5ca65afb06a8c59a7616f5e79cfee5c47422\
+             ff9dd1caf42c105d94ba963012e2854897c295ae7a1a03ce0463fb2b1c79ac75\
+             3158868ba4a9ff13bd429b616d72c149d0e9e89901839c09fe7acf4e30039f91\
+             a33e43b5d3b7abc6436740c584204a75d8975acda0af2d73e9d39c48a9ef17d2\
+             15ae7f5f74945d7af2ca7769da1ca2f84275a5d6144ac427602af27954fc77c5",
+            "3a607cff6eafff95c45dcfb474aba90719265620a28c465f3f6ffe39d5dc5f18",
+            "6d44f353fe0b1e0492842245616158fb20972498b8d0e86b31399a4e5cdec53c",
+        );
+
+        // The first coefficient of the key above, 0x539, raised to 3329 fails the modulus check,
+        // and OpenSSL refuses to load the same key
+        unreduced[0] = 0x01;
+        unreduced[1] = 0x9d;
+        assert_eq!(
+            EncapsulationKey::from_bytes(ParameterSet::MlKem512, &unreduced),
+            Err(MlKemError::InvalidEncapsulationKey)
+        );
+    }
+
+    #[test]
+    fn openssl_cross_check_768() {
+        check_known_answer(
+            ParameterSet::MlKem768,
+            "298aa10d423c8dda069d02bc59e6cdf03a096b8b3da4cab9b80ca4a14907672c\
+             cef1ec4faf234a0bc5b7e9d473f2b3133b3b26a1d175cb67a7805919699c02f7\
+             6531b99c5f89180704bb4ca4535c5b8972679c660a07c5e514b87009c862eb8f\
+             5157695efb3fc40a9def6b81c1cc02a249ae4f094ad0d9bd3485c1c1c6808052\
+             0a7c8c632032cee738154e5c5176c07da56024776a430fe76eacf665a3f7b832\
+             102215bc82f10939c8355704336a8fac1d81e4bb0485aa5d7c74d6b59bbe5c5e\
+             972a0d8bac411b55b5d5557cd680a1a8f71b4eb86bc48c9a0509731a54bd9d72\
+             90b27963e4372dc9b199cfdcac0b01acd28a62395112e4c43648d622c48c8234\
+             d01440e8cc376c927f23a5afc9ac0474c662274e424525c8552ece3b3fe26516\
+             de901bc7d515bde89558e626c95c80b93342f8010004f39e6c6c94871c5e344c\
+             ab3966c835f9a96a59afd31c40286b38b1c1a78470bab947518934453ce86736\
+             a919f1f5a6d510a86f5454fc3980cb5c765bd2bd5f7b36b1410d6635c8ceb47c\
+             4dda0d76a28eac939c71c3024804866c71626658442163c2c22117e50acefce6\
+             378a985652302a4ef0c2ce0cc716b7796e2b6b2e3777dfa1ac3da259a31b5a9b\
+             530f8cb638a81a62ac301849abaf95a7301bda30068909bfdb7e67dbccbb38a5\
+             551a25b1a3a0f685748ad5753d8880f0016c627486166384c5571fe236590036\
+             4d038311e2d875db366686932b5ec602430a369e87a6ef5c338786657825bd4c\
+             057aceb923eb0935e6905e63b4ced7f80857a773dd64b150d26612ea9ac12052\
+             db2017bf1843ccb4b3281b690dc728adfa85c00281b8e3c09287335f856b4fc2\
+             892f69a2f57921ada01914c40988662d57769662a786351b9b66493dab79594d\
+             986de2100d65ba0ff4ea58b81538d24a4435a258fac25404aa7f41f658b13850\
+             65e158dcb60115732720f40459aaac15e406953a90ac52997d1ccd070060efc6\
+             5db9e653354467fad56ec713c86e7540c423acf2669f52fa6f4ac6888d871ef3\
+             e847c029a8aafbb92e17b24aa079b1f419ba6175b442afb11909d4a56b70a033\
+             5b28739218aa7c9348e2c3c2f3eb3d15a41e6417c0dd94bfeb21419b311a7bb1\
+             3a180bbe833218a9a6b17447cc85f225859587a73077049acbcfd44d0f025438\
+             e15d1538270d586e1bf83192a9459cf63c0e972f85297679831ecf121509851c\
+             b8340f6f107b0fa1a0efd1b36a8189bc085c4f5cb784e553f41b918f80397ce1\
+             956f785bee377ca9aa8be6998ada30c26b7c3d8c6b55254cc96203b20c42aee0\
+             ac4e1ebb408e49a9e3f879d0ab0785eb7025425d1305a2299c015e120d163b0e\
+             19494ce57253d0246d182745cb8197ab7438b3c1bb7972bec5a306eba3567855\
+             c014699fef65ae54c770a0d85c18400cf642aedc660777ba4b138502bd5a7812\
+             f621f84a48296b98dd4322b6f15828b8a8f0e00a8ba44a53c3a8b143571b0740\
+             abd567daf1cde9c79c204b6d5e259d1766a31bbbcb4e6a05cf4502176b301c1c\
+             2f41247750157bcec85e809b30a4d60d7747cdd0f5b99aa8c826987517793aaa\
+             8080a0b124a8558df72bbe37b75f4edbb6be8216d6c633fb2b2280e25113d869\
+             5e43481c3eeb397eb192505229b67a201ea893c3e2cb32da8bc342fa4dea0578",
+            "27d2a77f33756f61208ef113abe82595873d4abc730e5b5d679529bf6a4ceb63\
+             83427231a8612f41550515acba52e48ead8b942833bbe6865d13d14a79d2c5c3\
+             e07f0a056d8de7aadfcaba058c493c80b37cab8c562753bb3ba6b6ec8297f885\
+             eaa7540d530015a84406e55b1366b577e236ce58a26d8a1eb5a44d542323c216\
+             7d9bf4a47f985699ca05bae43b8dec617f02380a3890afd4b8c7ec7ede26553a\
+             025f3ce5bc5d7a62130304235cb1ad4836b566b5b863bd9bdb45a2844a7047b6\
+             c8d383e448525e040b4dc8a2b48c6c37c96d62d43f3fd88e2881c40a205c9e24\
+             8f652b592781a779f86880f2a147b67863f391cc1a5a908c0095e07212291e2e\
+             f8a36eb9a9c0c6073225b34703a4af049382c47573da68fde9245ad444e31b1f\
+             bdb521f1f61f37bc0cef292067e670d28a1ffd904f6f1190a996918a13037a6c\
+             abf3c373bf8296cd37ab33ba7746809cc3f8ade1b3639bd57bfcc69650aaaf1d\
+             e198fc4c0463299e52c461780cc428fc5d04a5c51850cba6c2a5274340675793\
+             dda09be44c29e6395c65f85d2a0a7c6df411e6911b1f2cb6c351cd2e875f51b6\
+             38be776097e93e2f2b2f83da0beef4aa85ba9e763ab64502a0ca5222e9eab5b3\
+             b7088ed52060e8c8269b943a71ab0ae1c5b1b687d2e019cf8036bcf9bf6e7bac\
+             3aaa36e41660faa4540f2648cd93a189ec5c2dea70bacaaa4ffc906f90810ea1\
+             b67bf24f2c78cf6ba881aaea61c0652bff95b1bae4426d1773b9cc2ca82c21e3\
+             8c636e3b1c523244986b0be8a83f5dd5cf2d54762fb3c5ebf59b8e885302b1ce\
+             47033edf760f4e029be40b6d566b19dd758acd5c7412878131244f90172c53f2\
+             6663c21d905301d48baf91c917cc7779e9d8802cc10d89a3705099a2ad3a3a88\
+             96743c1144698093be257dacb66dc785228b912c8d965d14aa28342c3ac4a93f\
+             efa532b20945ddc1020139c14d638b908c4ddde9a0645b95b2e4414d40bb79f0\
+             4413830f15a873c28bb7059c2741002015f20408f058e715b0bf995b5380b7dd\
+             325a056ab97e659a2be0cdf6c33731c683a634b771e8c92a139aee4bb0e49c70\
+             77321d42fc199f7c1f298ca625d223a5c263a03cc48159b7812665b78637e4e1\
+             8720b2c29a6b99f42766a4cbc4dc508ba94ba83b89c3a5c78f8bb26bbd9b79be\
+             b8c8182490f5793ee5b96013b74b7e169e29d162f1315464ea7d72436d89b755\
+             161192c81cc2dd1c8b8bba795ef426ee1cc01c37aaa37b2cff8b0a378b47cbd0\
+             b4d49398cfc2712959699fa0bd8cd84666acc61f541b84fa96b9c854e4e75e91\
+             44addb44b8566a57dfbb545ce423c03346f2b2c1a91780d152a8de1a4d4c9cac\
+             de7392c996888cc2399c02c38b3353adf8acab283924da00a05b76e738c72c93\
+             0d6cba09ae168990faa1fef2226e780861d416eff402f4f759fc648ab1f97100\
+             109087f96e4b148d2cb31e4805314ea0cd95fb023eac0d989474ba4201d7b41d\
+             26f5394b217eea5b34b71a8b37931c0e594271e0b7c733257240233e7ba73560\
+             3e425a87dee77079e37cb28a21764594ce5350d8da2b62a07174943032ec89c9\
+             8809c73b6423d30c1d283a766a64d89703c3d629b497828d48320c346210797a\
+             298aa10d423c8dda069d02bc59e6cdf03a096b8b3da4cab9b80ca4a14907672c\
+             cef1ec4faf234a0bc5b7e9d473f2b3133b3b26a1d175cb67a7805919699c02f7\
+             6531b99c5f89180704bb4ca4535c5b8972679c660a07c5e514b87009c862eb8f\
+             5157695efb3fc40a9def6b81c1cc02a249ae4f094ad0d9bd3485c1c1c6808052\
+             0a7c8c632032cee738154e5c5176c07da56024776a430fe76eacf665a3f7b832\
+             102215bc82f10939c8355704336a8fac1d81e4bb0485aa5d7c74d6b59bbe5c5e\
+             972a0d8bac411b55b5d5557cd680a1a8f71b4eb86bc48c9a0509731a54bd9d72\
+             90b27963e4372dc9b199cfdcac0b01acd28a62395112e4c43648d622c48c8234\
+             d01440e8cc376c927f23a5afc9ac0474c662274e424525c8552ece3b3fe26516\
+             de901bc7d515bde89558e626c95c80b93342f8010004f39e6c6c94871c5e344c\
+             ab3966c835f9a96a59afd31c40286b38b1c1a78470bab947518934453ce86736\
+             a919f1f5a6d510a86f5454fc3980cb5c765bd2bd5f7b36b1410d6635c8ceb47c\
+             4dda0d76a28eac939c71c3024804866c71626658442163c2c22117e50acefce6\
+             378a985652302a4ef0c2ce0cc716b7796e2b6b2e3777dfa1ac3da259a31b5a9b\
+             530f8cb638a81a62ac301849abaf95a7301bda30068909bfdb7e67dbccbb38a5\
+             551a25b1a3a0f685748ad5753d8880f0016c627486166384c5571fe236590036\
+             4d038311e2d875db366686932b5ec602430a369e87a6ef5c338786657825bd4c\
+             057aceb923eb0935e6905e63b4ced7f80857a773dd64b150d26612ea9ac12052\
+             db2017bf1843ccb4b3281b690dc728adfa85c00281b8e3c09287335f856b4fc2\
+             892f69a2f57921ada01914c40988662d57769662a786351b9b66493dab79594d\
+             986de2100d65ba0ff4ea58b81538d24a4435a258fac25404aa7f41f658b13850\
+             65e158dcb60115732720f40459aaac15e406953a90ac52997d1ccd070060efc6\
+             5db9e653354467fad56ec713c86e7540c423acf2669f52fa6f4ac6888d871ef3\
+             e847c029a8aafbb92e17b24aa079b1f419ba6175b442afb11909d4a56b70a033\
+             5b28739218aa7c9348e2c3c2f3eb3d15a41e6417c0dd94bfeb21419b311a7bb1\
+             3a180bbe833218a9a6b17447cc85f225859587a73077049acbcfd44d0f025438\
+             e15d1538270d586e1bf83192a9459cf63c0e972f85297679831ecf121509851c\
+             b8340f6f107b0fa1a0efd1b36a8189bc085c4f5cb784e553f41b918f80397ce1\
+             956f785bee377ca9aa8be6998ada30c26b7c3d8c6b55254cc96203b20c42aee0\
+             ac4e1ebb408e49a9e3f879d0ab0785eb7025425d1305a2299c015e120d163b0e\
+             19494ce57253d0246d182745cb8197ab7438b3c1bb7972bec5a306eba3567855\
+             c014699fef65ae54c770a0d85c18400cf642aedc660777ba4b138502bd5a7812\
+             f621f84a48296b98dd4322b6f15828b8a8f0e00a8ba44a53c3a8b143571b0740\
+             abd567daf1cde9c79c204b6d5e259d1766a31bbbcb4e6a05cf4502176b301c1c\
+             2f41247750157bcec85e809b30a4d60d7747cdd0f5b99aa8c826987517793aaa\
+             8080a0b124a8558df72bbe37b75f4edbb6be8216d6c633fb2b2280e25113d869\
+             5e43481c3eeb397eb192505229b67a201ea893c3e2cb32da8bc342fa4dea0578\
+             a24e16d8f8f9383a95b77050f4d9fd2f5733eec1d63ef3c23ebf9918173669a7\
+             202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f",
+            "d7af68f8d84dc1ab7834a3ba91be5583152be073981e6f8e077b791eeac0b43d\
+             3d609da33620c1b9cc7bb43ea7a519d09274ef0d2acb1b3c6e9ed58339d976ce\
+             f358e0b14cf8be319d1b5e7cc5f2263cee07786e0ba533670c8f4b8c0755d3f1\
+             f386b01c9308f4378da00eeb1acc951d70c1d7c9ac7be8da9c53bc712c3c6717\
+             5436f230588d43bd047181d3814f84a2086bab21feafc86c6c89fe3e0488b4ed\
+             141c4b4732a1782b4b76ed1e3d68fb65b21a74b72577477fc7e902324f4f72dd\
+             723205b1bbd2f5cccdb122f90df9543626796b361910cef924a3d537d2f1e672\
+             c0c4a90969522dcb0bac094c3642802cb0d11ad3ab9b1de3057fc74e8e048602\
+             d9023d439f7ad073e039ddc8e231546ea876e5f61786db01f5211947c715521e\
+             399cba0f951d459d57cc89c392fd63ba0c8c117155e1cefd2caf75ef9670d26e\
+             7f5f4b6c64d0233cc388e1a02c195444f847de44b87d110b9a4d520aad031f57\
+             650412f6b60898045044b367e2647958afa931ac72e56bb9e82a4592bb78be11\
+             60857a81f6765f876657cc747c5d38a92f9383fd49f0a2b07849a7bafa67a51d\
+             35bdb2be537bcc56c48b303170df4045bd2bc949b1aeeeab04b98afb6a0d9701\
+             9e9f2902e93cc30f5f656f049798f5a9d3dc052348dfd30e510f89d16b3635bc\
+             45eafaf94239bb3a219646d7527a596808bc1beec7ce8e84d15e44e2951a6e97\
+             1af3730b37500bc062e09d69417fc8c2505087f8923b3726370d39d167eedb8b\
+             e6f6b26190f351268d6cc49029d7a3dbd2e17064beed541235cfc51c5d9eb364\
+             129e85db396e44388c9a527175cdcc7f0dc9a4a65ecbf8f8a4375ae7058504e0\
+             8c75d27a6e4f5a99ff845e2ec17b93c542f834e4d575c6a0f61ed07bc901d1d5\
+             8b98930cf091279c06f65399eeff5c2976cf385880635c12008f0ff9c1d1a271\
+             d891a92a69d55da9745abc247bf212215c494be7721ebd8dcdcdc6ad39fe4528\
+             ec7022fed434b1808efee58f76d65d92d2d6b03a1c085195a41302a4b4d8c0a9\
+             27c85879a19beb9f8afdf0e732a84974f0d1bc8ec0de0e33a4846bb33950dbfb\
+             8cae3029db3eda5dcfefa467dfc8f72e2835830cad0faa4e1353e367240d1a2d\
+             5c20004ed77c34d00447fcea610abaecf0861e37f1981f20d3d5496e23145115\
+             44416409fdbdbbf4650c917b2221b6f2cd8e7ceaacd52bc3810058fec9aefb8a\
+             4d4e2315a100b6c4d3131645a2d0cbfda92f2b6fb14307d3b801e670af92395d\
+             0831ba92d60cd4095cbf091ad7b8eacdd6bd400dcd218012404db7a3c673efe2\
+             788d94dbb93a65028f6881f8b7a3891f00fe66b451a844e23d125bf1cecc0e11\
+             5bb5beff6df9f8a548c9a65700ae8a583c7f60668125a57b5da708167355ab6a\
+             95782f7c09391e114dec97febcd2dc6f2f098d9a726942754598fc22fcac6870\
+             79620ac19c63ca52411e9a0b60d670e05423a127fbd30bc83d385f2915004b04\
+             504a4d6d1b8046697dd3d3ad9c99ca0ef2f7a0913d8227421fba29af66029bdb",
+            "c5a74110c158acbaf9c01deb86fa6cc10c14533feda54bec1fdd000d61f07e4e",
+            "450f098e8b4a904272b398ab217b3c536e7cea405a6f9a01c04ee76a0fa35320",
+        );
+    }
+
+    #[test]
+    fn openssl_cross_check_1024() {
+        check_known_answer(
+            ParameterSet::MlKem1024,
+            "4b94c29450111191823b3514c9ac1ea3d9825ccb86393a2dfb04654fa2192d37\
+             bfad1c497c6502eee5ca80a73bfce0baf5a54a88585a401397a3d232f426a7af\
+             b082bc21a44317090eaac7592c2ea88a653c4491ea193931335f52e989a3c4cc\
+             56d9c553732d57c470fb41ab759b65d2d04445382fcd9c4e344a1128fa9e11e0\
+             4358e192ed014b23232a7ee2b22e23717f44111ee33575399c37646da9813ec9\
+             b212afe94e5dc5c2330a7294cc1f4234a6d3fbb4f1685ab8892c04acb17cd1c1\
+             70d7b0611b6a7176c794cc8c67f55fc923c2ad203100f365991882c30243d778\
+             13843b5ec7c964032263706092ecf00c7516be64e4598ca4226c069bb5e67e41\
+             75cf2286c8dd5c488a6c5861f31baa0bd0269470e8b551dd3bcd38c86c12f9cd\
+             b176c77dc8b6c02a701f478902c8553f694c0d82727b4c4a5c2c1041212aa127\
+             4808b82111b377ec75214e9b1978f76004d4139d98613f4b8e98d20af7b53407\
+             3a509a959b7a7564f9b40ca218bf61829320a8502017954d328d7ac6c769ec29\
+             700756e7b0685b340d5e118059504a49a9a50a10198eb10a5784678eb427d7b4\
+             babb9552933b062897973e1318eaf0a0eac37584a65401b1703e042accd83753\
+             1483f241cadcd1c1d378119e694429db199ac891e4c5343757085bb3ae783667\
+             350c4458d97672e861e80b1d2679510ea3a6f2360c77a46942c7a06a554d2280\
+             80c84b47aef14db17620cb16c06ab30a1be4cda7082be9f87e9c211c46916349\
+             a5ba8eaa5201c7294a3c0885b53b657452108825ec646c90a04612324ee7d031\
+             afe5343132cbef67b6efb1a5ec2809b773538ce77b3d8b04eb0b3c2256011e4c\
+             716c19a8ba0752bf71492117649f0615c3290fc29a46fde4bd52db9286d60338\
+             8244259c15a7ac2b640a60cc03376a5841a3fb8a473568fa9b1a267215f34c01\
+             697b0f0e627175d72105b7707c29b9e614bdc33a6f6c818a95370b427882d7b4\
+             76796a9ec6eb993274cd9b2391a82ba45e3393d2e9ae9721ca9d6c1b988b5827\
+             713f90a6585de9433528c02b03ce10bb5f720138d0fbb4c30c1266b918e52925\
+             dfe17b37f95d22bca54f475919ac859098c0f0d08ac5875ef29b56fd141e6ef1\
+             5f700a0b66f39595c588177373c4669b21bc071e4c3aa5f0b4a31b6258f35da2\
+             4ac3cd29c7f2092410c5078355b138fb53a6b9ae6e0b9c08243e7baa45c47376\
+             eb8c7f13d4cf51aa736fa31540c9241f370da544bf9f9c28d9a57e2f2a7ca95a\
+             4e4b466e641ab3bcc76adf1139d567a6f12b52f3a65e7ec0aae26bcaa8c55833\
+             b04e59998ebc9a1930fbb6d2233c53d2c1f8b9518e3c2de73a19dee6b380a5b3\
+             2971cf64e129fd6c1fa6e75d4a234501e966dd3a540af5c8f4f34a6b4a253ee2\
+             8492566d5e67c6f55855fcb0506fb06c156744d9a03a31a26fa94cad14f157b7\
+             f303d07a69c773768fcb4d079c09059703a0c3a94de4b99ea3a2f16583d0f917\
+             0a3950db07b4f0bc30802927f9f7961b6259892636a9502a2705303637799dd3\
+             44da451c1cf7bf67840ceb3079ab8c6b8c1927f64053c612450c45c9e603bc16\
+             666e596b3471e103b6f15447424d17022048111ffbd37e1c670f64f14b8a7b32\
+             b94c1a49b45dd2fc38cd5289d910ad63602cf5e13042c64ac6797b89fb551ad0\
+             8e05a92d200cccb7e712ef23c9312cb350f029ab537e287347fd3075ac10906a\
+             783f1c6c07ccb88f41228c4be1c640f790b5c3a5d5d3ca792495d74bc4615626\
+             58c07ac600276b924ab5bc9be1f0494cb76f82f460a7480972663381e1699960\
+             61d799859ec54d4f5ca5c411c01db1597b165977669de13a928a34afbac258fe\
+             a8c4764239c9421dc3119bf5b47699206978327b1c5345ef746a7983841f056e\
+             2534100ab24d4e9abbd0b17c6a95bd4c3c0e40f69e1612aceeb28b99086c9511\
+             6e7204273893390bf46b899b36286b0ebf1947bb9884f732ca27da82b19b5dc0\
+             cc7f8885714910888b2310c4f9319d410b34e6433b9003e2176bb99525745610\
+             6e8952163b8ba592530cc5aa0aeb43ad398fe9e97baa523d7a4431677c3d3af0\
+             719e475db85ca95af5089beabeb05b2faab4896ba60f81c88472a57b46a82882\
+             6a0cdfb446f8189182d2bf5eac4ec1cc5deaf599c8a13e48235406d17ffddc83\
+             44b6c66984a868aa92fa02227a086950eb0c8701ed58dc628776b983882e1175",
+            "f77b7f6b15c73fe2cc546b67fb774ca19b42cd463ea9fbb984ca477a77b6c710\
+             87cbf051abe4736a9072c6e870c8311c55963f500a3c7b1b8f2a58558f49c625\
+             27b6c594b5e7acb3bcf597273a5743517d151208bd4aa61e75ba67b0bd594a99\
+             4919627ac0a804d489e171336bc339f4666706e5134412b366823d50318c8bf2\
+             61ab120a28a04fec01cc15f2b71912cee54aa8eed854694b6ba886b5eb7661e6\
+             d56aac213cc1d814d592b395554fae74476d34371163129bf864527250606cc2\
+             1a53746b20997077bba155733b28a4e7fa0776399524763eb481ceaa11366c34\
+             74a04685f40c3f08b0424f40bff949a0ac92704c3ba0c6eb36f1f5b621d8bf2b\
+             6327beb57cd3facb94186fe3fc9ab0a1434bb291d2c9bb70723057e225405965\
+             6f565919a32cf74579de89681cd2c5a935a52b4aaa2d24cb5d5c9e20729ec549\
+             2ec36961efb8a28cbc00ac303523295f3d8036abc1603307ce70d7848a35657a\
+             5687dd589927ea63731626abb26ec4e431b8eb6b3b0bc1e82573ee73b1a02118\
+             3183528108ae2eacaddb95b464a0b98469c319cc27bfa01bc31054a68c05502b\
+             1662b879fe98a1711c3426f6436cb0214cea379ac3a7e5fb60184a37c1da1eda\
+             61c6c39c1dd4e847845811f2a358a43731528536d4a3291b04158c2c3dc64162\
+             4882678bc7805f58a9d94c7104567846a2044e65aece2a225372b6024799a547\
+             7d60237504aa5c0ac57bc70a3558c08c4de687ef1302b4fcb5594413d22cb959\
+             bc31be423450403c6bc57dc411b3fefac1052ac4bb162c44545a4ca80892657f\
+             a13a0b2c482ced629cc4999d969c593d4aadf073cc3e3a458e78a8aa039408e6\
+             52be93b20c8b42ec5b0e50239dac726052851a6d15312ec39ed208b72209a577\
+             c6b2770112895749d5260e7dd446c0b0118c1000be6801d2611fcf00792a9cc4\
+             f4b49922f9a2d4b9c8fa5a5d0d60506631a7e971cee840b08fa63c13729d7ea5\
+             aac70352a984cdb669331cba758fe87ec3931b3e3161fcc747aa749424689fea\
+             e14bf7c9a2ffba1302b212b80372d8e9049db69a3a1261d0a2859a9b4d57899e\
+             0ba41607a1b67a7c0e12923689f8c6395377d970c7490a4129611a1d05c3b781\
+             3bed945420723f7f9525a87793fafbbfca982e66bb80681c83248a89da084c19\
+             882f48f31e7fc09093a49e9fd09691b021edf463afc519b62853816118346115\
+             fb0b882cc6482f3c5cbcc1c1894697e1239598b34b2a9a7acd15244d0690c881\
+             94097a9beda585e87c437124624c210768e6215d376482653eb89947877c118d\
+             370c696a6ffcc1018ae413a08a8d0ffaa819945da7a167c229913290cad1c80a\
+             369258762610ea253e62dc24226a30c892c12136c326f13f4446664712b0b90b\
+             c063b4028593cbde06cdc22289e240c7e296b59172c1aeda8c99e0512d1a0163\
+             a942ea33148e6937c026029424b81b996b1df22ea0623ec65c6bf093500cf3bf\
+             35374adc392035ca7c583b99685bca541a0807b163acd0888be0385dea820da4\
+             6e4dbb44d2e462c734b83a473fed1364273159257cc259a8c5676c1c76d41d56\
+             b9907ec1c3599c9e8907403a27a705e3619b04b0ad046e8ec8169c17b460d44c\
+             0c0c4464d044c946186bc725965083a892bcc495c0540311ff9b3e5192c303d8\
+             8f8ba46a901c782ef02388f1b2addab6a5350fc3639700e3154337337e4a178d\
+             351cd2b56ee1f0bfea34aacfa33d2ec791e50752d4d034cb1c951572caaa5c4d\
+             90947b6b175a6dd3c62a77bb8f7ac9ae24719b53c2b120a2876986e217b72bd7\
+             cee44a7265b11cee1ab2261762b31a3738386969c0825fb79452e652e1142fc7\
+             3c9df6fba411795b4717922b29ba2d53abe5a8c0dcc1601b096c96d7938fd5a6\
+             8a8797c7b9477a86a472eb5da250cb2fec318d83c8f43bbe8e11c35e377d3493\
+             66c85c4382597f6fc27a0051c0fb00b02c01ca20f9a427f172599477ca690cc1\
+             327e0f025f80ec338a80a159e308c12a27db1a7e1b960a99d37dfc22872e5193\
+             0f28c651ab221f53abaee20bad9a3eabcbab913251bf135beb29617b5754333c\
+             4daadb2238341c2ad9378186280f6449440b784ba78f5dac44d8f65b3b742195\
+             0397c3913a2dd23ec6d1cb717b36a5fc95af191e278296948c1254ea86b4ec00\
+             4b94c29450111191823b3514c9ac1ea3d9825ccb86393a2dfb04654fa2192d37\
+             bfad1c497c6502eee5ca80a73bfce0baf5a54a88585a401397a3d232f426a7af\
+             b082bc21a44317090eaac7592c2ea88a653c4491ea193931335f52e989a3c4cc\
+             56d9c553732d57c470fb41ab759b65d2d04445382fcd9c4e344a1128fa9e11e0\
+             4358e192ed014b23232a7ee2b22e23717f44111ee33575399c37646da9813ec9\
+             b212afe94e5dc5c2330a7294cc1f4234a6d3fbb4f1685ab8892c04acb17cd1c1\
+             70d7b0611b6a7176c794cc8c67f55fc923c2ad203100f365991882c30243d778\
+             13843b5ec7c964032263706092ecf00c7516be64e4598ca4226c069bb5e67e41\
+             75cf2286c8dd5c488a6c5861f31baa0bd0269470e8b551dd3bcd38c86c12f9cd\
+             b176c77dc8b6c02a701f478902c8553f694c0d82727b4c4a5c2c1041212aa127\
+             4808b82111b377ec75214e9b1978f76004d4139d98613f4b8e98d20af7b53407\
+             3a509a959b7a7564f9b40ca218bf61829320a8502017954d328d7ac6c769ec29\
+             700756e7b0685b340d5e118059504a49a9a50a10198eb10a5784678eb427d7b4\
+             babb9552933b062897973e1318eaf0a0eac37584a65401b1703e042accd83753\
+             1483f241cadcd1c1d378119e694429db199ac891e4c5343757085bb3ae783667\
+             350c4458d97672e861e80b1d2679510ea3a6f2360c77a46942c7a06a554d2280\
+             80c84b47aef14db17620cb16c06ab30a1be4cda7082be9f87e9c211c46916349\
+             a5ba8eaa5201c7294a3c0885b53b657452108825ec646c90a04612324ee7d031\
+             afe5343132cbef67b6efb1a5ec2809b773538ce77b3d8b04eb0b3c2256011e4c\
+             716c19a8ba0752bf71492117649f0615c3290fc29a46fde4bd52db9286d60338\
+             8244259c15a7ac2b640a60cc03376a5841a3fb8a473568fa9b1a267215f34c01\
+             697b0f0e627175d72105b7707c29b9e614bdc33a6f6c818a95370b427882d7b4\
+             76796a9ec6eb993274cd9b2391a82ba45e3393d2e9ae9721ca9d6c1b988b5827\
+             713f90a6585de9433528c02b03ce10bb5f720138d0fbb4c30c1266b918e52925\
+             dfe17b37f95d22bca54f475919ac859098c0f0d08ac5875ef29b56fd141e6ef1\
+             5f700a0b66f39595c588177373c4669b21bc071e4c3aa5f0b4a31b6258f35da2\
+             4ac3cd29c7f2092410c5078355b138fb53a6b9ae6e0b9c08243e7baa45c47376\
+             eb8c7f13d4cf51aa736fa31540c9241f370da544bf9f9c28d9a57e2f2a7ca95a\
+             4e4b466e641ab3bcc76adf1139d567a6f12b52f3a65e7ec0aae26bcaa8c55833\
+             b04e59998ebc9a1930fbb6d2233c53d2c1f8b9518e3c2de73a19dee6b380a5b3\
+             2971cf64e129fd6c1fa6e75d4a234501e966dd3a540af5c8f4f34a6b4a253ee2\
+             8492566d5e67c6f55855fcb0506fb06c156744d9a03a31a26fa94cad14f157b7\
+             f303d07a69c773768fcb4d079c09059703a0c3a94de4b99ea3a2f16583d0f917\
+             0a3950db07b4f0bc30802927f9f7961b6259892636a9502a2705303637799dd3\
+             44da451c1cf7bf67840ceb3079ab8c6b8c1927f64053c612450c45c9e603bc16\
+             666e596b3471e103b6f15447424d17022048111ffbd37e1c670f64f14b8a7b32\
+             b94c1a49b45dd2fc38cd5289d910ad63602cf5e13042c64ac6797b89fb551ad0\
+             8e05a92d200cccb7e712ef23c9312cb350f029ab537e287347fd3075ac10906a\
+             783f1c6c07ccb88f41228c4be1c640f790b5c3a5d5d3ca792495d74bc4615626\
+             58c07ac600276b924ab5bc9be1f0494cb76f82f460a7480972663381e1699960\
+             61d799859ec54d4f5ca5c411c01db1597b165977669de13a928a34afbac258fe\
+             a8c4764239c9421dc3119bf5b47699206978327b1c5345ef746a7983841f056e\
+             2534100ab24d4e9abbd0b17c6a95bd4c3c0e40f69e1612aceeb28b99086c9511\
+             6e7204273893390bf46b899b36286b0ebf1947bb9884f732ca27da82b19b5dc0\
+             cc7f8885714910888b2310c4f9319d410b34e6433b9003e2176bb99525745610\
+             6e8952163b8ba592530cc5aa0aeb43ad398fe9e97baa523d7a4431677c3d3af0\
+             719e475db85ca95af5089beabeb05b2faab4896ba60f81c88472a57b46a82882\
+             6a0cdfb446f8189182d2bf5eac4ec1cc5deaf599c8a13e48235406d17ffddc83\
+             44b6c66984a868aa92fa02227a086950eb0c8701ed58dc628776b983882e1175\
+             61349e5c131a7e116a0463861d7d18663c5627c38c7147ddaadfd48acd7a4535\
+             202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f",
+            "1d911ef8af6e6d6cce90095d81dcd8588354663d8532a34c6f7b7a871b160e36\
+             d4b5c3fc0e9b8104d17c21ac71d9206bcbc81e2fb4499444d0c8c035d328b019\
+             1124e913c4ec8ade49f49ea684b7cae6e45b924a6e8c551999cfee8f009dfe78\
+             40547331ef687d206e5b715711d217aa26c9cb717c117dbf662ac9cd32bd2cab\
+             226584fe8dbcdb9ce793e70c51a4c8ad0294496cc4c0ded95d1b0bdd198b2599\
+             d505265602b187b014b5428f06bab27bf17632d5d7a7908f14d0d58b81125d60\
+             94f9a1e73438577a7d9d6a993fa7da7feb7b81f38aa5a2279b0922aa5aa01177\
+             1ffc6b56bf5566c30e75c53645c742619dfd7806890de90dabcfc7fcf2d5dbe6\
+             14b3225b9445cda1edcebb84dd1985cad7558582291b3d1c9990446e10a25dbc\
+             af3d9ce9ccc22399edd862e37b2c4669c96c755b0004a7b7ad88a8128123eedc\
+             81134ff6b5af6635231caca6758d82a53571d41186bb93bef0ced4b91ac1d561\
+             2b84a64026f69f7ae87fae68876728ebdf99f85d5a4b5beb0579fba623cccda6\
+             156aa1bac4d65c915b3b716106af149400f349b64c41539009784b43de63b669\
+             0b7f3e2f72269c544061941beaf4def4957258100b45fa9673368539bf63f31d\
+             58bf8f918d6a5515d10825d4d5971d8111eaecbfeffad6b57be1aad3d9151e2e\
+             4e7b0a73615510325d47b4cfcf46969586a1ec09eb6c2a5b32efef592263b43a\
+             e6fc125828f49e12f6d66978ca6bc4d5ceda3f14f342e267e9551632cd9c29aa\
+             971a46f0d6ad1e75e22a2cff413899d193033d8ef0a4f0a91247643a54497932\
+             68ee4576370731706b9f80601dd2ecc2d623e6801834391b3137076dc4db45db\
+             6255d79a2aad92c3b766d9480cd32f34ed4b878ee48e93d53700acec4782f47c\
+             3afc2f9f38d6d01998c74d4727ea691025b3e6e9334d4532c3d770d718863171\
+             305e2717c5edac9722265fa7aa33f3ad87c28db9864131ec1699b9ead7cc4ff1\
+             bc082c40c38aa3745731c3c906d0887cc8fc043953f36d54b10a158639b34b46\
+             c3000ff7c63f1dcb45aa622fd5c3d93c7ca900285e268b684d623b6aa5ff971b\
+             e1fcca3c86f5a1159d6118964ba89db23cba3cb6457efddc783d976721281ff5\
+             ceba2f4fb2dfe4d213a8cf59fe1cbf04cc18815caf49e53691dc77563f55c341\
+             18b251a02a5efe21eef10139c182f79aa17a277f7d78dd18e75ca94c24761d9f\
+             6ba36f4645844dedd022eeb40a49cbb16aa8cf2a79e801e67ab1ed8135f2ad14\
+             a1c3b74aa84261a66f98dcd299b797545a78f23adfcefea35660098a95c061d6\
+             9d1df1b0adbd3c47c06d3149a47ce8f1ccb07b1c76f1601af4fac5833d72e448\
+             6b1c71ba6d5c54634f9319145f402f249a0eabc564498c3e6840e454631a06ff\
+             6cde87d2bcf1a69335b1991dc1ec2c87c75123f52f14150d426a14a2a18a8b7f\
+             77cccf6e8c1bf69c3370c81dd17f2501014ea0fb2ff9706ac54ae805ac315e44\
+             1b3f7d09ac8a2cebe9c17aa33e42b934818ee71ac6c0ba5321c739f905817bf3\
+             a3002b17fc586a587c455a4cc446603c6cb1e2303534c2a64d01e07c7970f383\
+             e8f555afd563442aa88603d7bdba679aa33d9ef421db15ced4defabf3c916942\
+             0970c85a220f66dad909743a5e6b5eb7ae95828daaf822cf851452cdfd4c0be8\
+             5e5e81260b7c6487e0b07b74698598b3cfee844569ec569250d1cce08f79b19a\
+             77b9b0aa0bf66d930e2ca2bca24589ea5257d390372c235afe2da7fc79feddce\
+             e2941c17e93598d78ff19465c793afb6ee43bf8ee3e35af636c4dcd180f3c0e6\
+             15c01c637116acf865efa030cb9619eb800578969926255556af981bc2f3960f\
+             c0760b7ba34fb5dc6a068f702ad277d6ba1288a1f55fba037f080a4315bd8bfd\
+             bdd926d75012b0075c03dd0202b1a819c9c8a747a1012be664942731c0533bf6\
+             342a1040fb16f854ed2c024c886ea84c7d8075771b1acb35a7d3c89e8fe0b655\
+             5543a57cb8655b718549bfaa1f83dea112bf2b7dfff339c5c4edb29f954fadb6\
+             146d574d0ea0ca6bef21427168ea62fa8aaf5324107ba9f0f3a7f3a61d53544e\
+             f51d6750d3c811f07a886c85713265bbafa3c5ef97cc02f37f85923370434801\
+             37e7545f1a5f46e552e10821e777a8f52cd2fe1f35becdeeff66cbe43413052c\
+             bb04737db661c3433a3bf49378d4c1eb9db1c73233b1b63a9b665d0803880fc7",
+            "f64fab54e0b56ccc058bf30d5d5d9dc2023e266432bf4e8009fbbaf3c8602331",
+            "6095a0f04f414a2162c7a219da1a531866e49df0e19ca81f3321b5c6859fc11a",
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = SeededRng::from_seed(b"ml-kem");
+        for set in PARAMETER_SETS {
+            let dk = DecapsulationKey::generate(set, &mut rng);
+            let ek = EncapsulationKey::from_bytes(set, &dk.encapsulation_key().to_bytes()).unwrap();
+            let (ciphertext, shared_secret) = ek.encapsulate(&mut rng);
+            assert_eq!(ciphertext.len(), set.ciphertext_size());
+            let dk = DecapsulationKey::from_bytes(set, &dk.to_bytes()).unwrap();
+            assert_eq!(dk.decapsulate(&ciphertext).unwrap(), shared_secret);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        let set = ParameterSet::MlKem768;
+        let dk = DecapsulationKey::from_seed(set, &[1; 32], &[2; 32]);
+        let ek = dk.encapsulation_key().to_bytes();
+
+        // A first coefficient of 3329 fails the modulus check
+        let mut unreduced = ek.clone();
+        unreduced[0] = 0x01;
+        unreduced[1] = (unreduced[1] & 0xf0) | 0x0d;
+        assert_eq!(
+            EncapsulationKey::from_bytes(set, &unreduced),
+            Err(MlKemError::InvalidEncapsulationKey)
+        );
+        assert_eq!(
+            EncapsulationKey::from_bytes(set, &ek[1..]),
+            Err(MlKemError::InvalidEncapsulationKey)
+        );
+        assert_eq!(
+            EncapsulationKey::from_bytes(ParameterSet::MlKem512, &ek),
+            Err(MlKemError::InvalidEncapsulationKey)
+        );
+
+        // Changing the embedded encapsulation key breaks the hash check
+        let mut tampered = dk.to_bytes();
+        tampered[POLY_BYTES * 3] ^= 1;
+        assert!(matches!(
+            DecapsulationKey::from_bytes(set, &tampered),
+            Err(MlKemError::InvalidDecapsulationKey)
+        ));
+        assert!(matches!(
+            DecapsulationKey::from_bytes(set, &dk.to_bytes()[1..]),
+            Err(MlKemError::InvalidDecapsulationKey)
+        ));
+
+        assert_eq!(
+            dk.decapsulate(&[0; 1087]),
+            Err(MlKemError::InvalidCiphertext)
+        );
+    }
+}
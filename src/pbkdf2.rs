@@ -0,0 +1,138 @@
+/**
+* This is an implementation of PBKDF2 from RFC 8018 using HMAC over any hash in the crate.
+*/
+use crate::hash::Hash;
+use crate::hmac::Hmac;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pbkdf2Error {
+    ///At least one iteration is required
+    InvalidIterations,
+    ///The output is limited to 2^32 - 1 hash lengths
+    OutputTooLong,
+}
+
+impl std::fmt::Display for Pbkdf2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pbkdf2Error::InvalidIterations => write!(f, "PBKDF2 needs at least one iteration"),
+            Pbkdf2Error::OutputTooLong => write!(f, "PBKDF2 output is too long"),
+        }
+    }
+}
+
+impl std::error::Error for Pbkdf2Error {}
+
+/// Derive `output.len()` bytes from `password` and `salt` with PBKDF2-HMAC-`H`
+pub fn pbkdf2<H: Hash>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output: &mut [u8],
+) -> Result<(), Pbkdf2Error> {
+    if iterations == 0 {
+        return Err(Pbkdf2Error::InvalidIterations);
+    }
+    if output.len() as u64 > u32::MAX as u64 * H::OUTPUT_SIZE as u64 {
+        return Err(Pbkdf2Error::OutputTooLong);
+    }
+
+    // the keyed HMAC state is reused for every iteration
+    let keyed = Hmac::<H>::new(password);
+
+    for (i, block) in output.chunks_mut(H::OUTPUT_SIZE).enumerate() {
+        let mut mac = keyed.clone();
+        mac.update(salt);
+        mac.update(&(i as u32 + 1).to_be_bytes());
+        let mut u = mac.finalize();
+        let mut t = u.clone();
+
+        for _ in 1..iterations {
+            let mut mac = keyed.clone();
+            mac.update(&u);
+            u = mac.finalize();
+            for (t_byte, u_byte) in t.iter_mut().zip(u.iter()) {
+                *t_byte ^= u_byte;
+            }
+        }
+
+        block.copy_from_slice(&t[..block.len()]);
+    }
+    Ok(())
+}
+
+/// Derive `len` bytes with PBKDF2-HMAC-`H` into a new vector
+pub fn pbkdf2_vec<H: Hash>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    len: usize,
+) -> Result<Vec<u8>, Pbkdf2Error> {
+    let mut output = vec![0u8; len];
+    pbkdf2::<H>(password, salt, iterations, &mut output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pbkdf2::{self, Pbkdf2Error};
+    use crate::sha2::{Sha256, Sha512};
+    use crate::test_util::hex;
+
+    #[test]
+    fn sha256_one_iteration() {
+        let test_result = hex("120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b");
+        assert_eq!(
+            pbkdf2::pbkdf2_vec::<Sha256>(b"password", b"salt", 1, 32).unwrap(),
+            test_result
+        );
+    }
+
+    #[test]
+    fn sha256_4096_iterations() {
+        let test_result = hex("c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a");
+        assert_eq!(
+            pbkdf2::pbkdf2_vec::<Sha256>(b"password", b"salt", 4096, 32).unwrap(),
+            test_result
+        );
+    }
+
+    #[test]
+    fn sha256_multiple_blocks() {
+        let test_result = hex(
+            "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1\
+             c635518c7dac47e9",
+        );
+        assert_eq!(
+            pbkdf2::pbkdf2_vec::<Sha256>(
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                40
+            )
+            .unwrap(),
+            test_result
+        );
+    }
+
+    #[test]
+    fn sha512_embedded_nul() {
+        let test_result = hex(
+            "9d9e9c4cd21fe4be24d5b8244c759665f39d98fc12a9ca759bb021db3cfadf34\
+             5844aebe70dd8b2f6966f25f3613e1187bbd24ed2ca43ed13b246e4675be7ab9\
+             ce5cb1e9bd865e2240eecd4ec012b1f9",
+        );
+        assert_eq!(
+            pbkdf2::pbkdf2_vec::<Sha512>(b"pass\0word", b"sa\0lt", 4096, 80).unwrap(),
+            test_result
+        );
+    }
+
+    #[test]
+    fn zero_iterations_rejected() {
+        assert_eq!(
+            pbkdf2::pbkdf2_vec::<Sha512>(b"password", b"salt", 0, 64),
+            Err(Pbkdf2Error::InvalidIterations)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+/*
+* This is an encoder and decoder for the PHC string format used to store password hashes:
+*     $<id>[$v=<version>][$<param>=<value>(,<param>=<value>)*][$<salt>[$<hash>]]
+* The salt and hash are written in the unpadded standard base64 alphabet ("B64").
+*/
+
+const B64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhcError {
+    ///The string does not follow the PHC layout
+    InvalidFormat,
+    ///The salt or hash is not valid B64
+    InvalidBase64,
+}
+
+impl std::fmt::Display for PhcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhcError::InvalidFormat => write!(f, "malformed PHC string"),
+            PhcError::InvalidBase64 => write!(f, "invalid base64 in PHC string"),
+        }
+    }
+}
+
+impl std::error::Error for PhcError {}
+
+/// Encode bytes as unpadded B64
+pub fn b64_encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = ((group[0] as u32) << 16) | ((group[1] as u32) << 8) | group[2] as u32;
+        for i in 0..chunk.len() + 1 {
+            output.push(B64_ALPHABET[((bits >> (18 - 6 * i)) & 0x3f) as usize] as char);
+        }
+    }
+    output
+}
+
+/// Decode unpadded B64, rejecting padding, stray characters and non zero trailing bits
+pub fn b64_decode(text: &str) -> Result<Vec<u8>, PhcError> {
+    let mut values = Vec::with_capacity(text.len());
+    for c in text.bytes() {
+        match B64_ALPHABET.iter().position(|a| *a == c) {
+            Some(value) => values.push(value as u32),
+            None => return Err(PhcError::InvalidBase64),
+        }
+    }
+    if values.len() % 4 == 1 {
+        return Err(PhcError::InvalidBase64);
+    }
+
+    let mut output = Vec::with_capacity(values.len() * 3 / 4);
+    for chunk in values.chunks(4) {
+        let mut bits = 0u32;
+        for (i, value) in chunk.iter().enumerate() {
+            bits |= value << (18 - 6 * i);
+        }
+        let bytes = chunk.len() - 1;
+        // the unused low bits of a partial group must be zero for the encoding to be canonical
+        if bits & (0xffffff >> (8 * bytes)) != 0 {
+            return Err(PhcError::InvalidBase64);
+        }
+        for i in 0..bytes {
+            output.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(output)
+}
+
+/// A parsed PHC string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhcString {
+    pub algorithm: String,
+    pub version: Option<u32>,
+    ///Parameters in the order they appear
+    pub params: Vec<(String, String)>,
+    pub salt: Option<Vec<u8>>,
+    pub hash: Option<Vec<u8>>,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+}
+
+fn is_valid_value(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'/' || c == b'+' || c == b'.' || c == b'-')
+}
+
+impl PhcString {
+    pub fn parse(text: &str) -> Result<PhcString, PhcError> {
+        let mut fields = match text.strip_prefix('$') {
+            Some(rest) => rest.split('$').peekable(),
+            None => return Err(PhcError::InvalidFormat),
+        };
+
+        let algorithm = fields.next().unwrap_or_default();
+        if !is_valid_name(algorithm) {
+            return Err(PhcError::InvalidFormat);
+        }
+
+        let mut version = None;
+        if let Some(field) = fields.peek()
+            && let Some(number) = field.strip_prefix("v=")
+        {
+            version = Some(number.parse().map_err(|_| PhcError::InvalidFormat)?);
+            fields.next();
+        }
+
+        let mut params = Vec::new();
+        if let Some(field) = fields.peek()
+            && field.contains('=')
+        {
+            for pair in field.split(',') {
+                let (name, value) = pair.split_once('=').ok_or(PhcError::InvalidFormat)?;
+                if !is_valid_name(name) || !is_valid_value(value) {
+                    return Err(PhcError::InvalidFormat);
+                }
+                params.push((name.to_string(), value.to_string()));
+            }
+            fields.next();
+        }
+
+        let salt = fields.next().map(b64_decode).transpose()?;
+        let hash = fields.next().map(b64_decode).transpose()?;
+        if fields.next().is_some() {
+            return Err(PhcError::InvalidFormat);
+        }
+
+        Ok(PhcString {
+            algorithm: algorithm.to_string(),
+            version,
+            params,
+            salt,
+            hash,
+        })
+    }
+
+    /// Look up a parameter by name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl std::fmt::Display for PhcString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}", self.algorithm)?;
+        if let Some(version) = self.version {
+            write!(f, "$v={}", version)?;
+        }
+        if !self.params.is_empty() {
+            let pairs: Vec<String> = self
+                .params
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            write!(f, "${}", pairs.join(","))?;
+        }
+        if let Some(salt) = &self.salt {
+            write!(f, "${}", b64_encode(salt))?;
+            if let Some(hash) = &self.hash {
+                write!(f, "${}", b64_encode(hash))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::phc::{self, PhcError, PhcString};
+
+    const EXAMPLE: &str = "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHRzb21lc2FsdA$Gpj7qOY5RCXJvcMzqcdQqvgR3wcPX7SleI4c9NtXk6E";
+
+    #[test]
+    fn b64_round_trip() {
+        for len in 0..10 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + 5) as u8).collect();
+            assert_eq!(phc::b64_decode(&phc::b64_encode(&data)).unwrap(), data);
+        }
+        assert_eq!(phc::b64_encode(b"somesalt"), "c29tZXNhbHQ");
+    }
+
+    #[test]
+    fn b64_rejects_non_canonical() {
+        assert_eq!(
+            phc::b64_decode("c29tZXNhbHQ="),
+            Err(PhcError::InvalidBase64)
+        );
+        assert_eq!(phc::b64_decode("c29tZXNhbHR"), Err(PhcError::InvalidBase64));
+        assert_eq!(phc::b64_decode("c"), Err(PhcError::InvalidBase64));
+    }
+
+    #[test]
+    fn parse_and_encode() {
+        let parsed = PhcString::parse(EXAMPLE).unwrap();
+        assert_eq!(parsed.algorithm, "argon2id");
+        assert_eq!(parsed.version, Some(19));
+        assert_eq!(parsed.param("m"), Some("64"));
+        assert_eq!(parsed.param("p"), Some("1"));
+        assert_eq!(parsed.salt.as_deref(), Some(&b"somesaltsomesalt"[..]));
+        assert_eq!(parsed.hash.as_ref().map(|h| h.len()), Some(32));
+        assert_eq!(parsed.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn parse_minimal() {
+        let parsed = PhcString::parse("$scrypt").unwrap();
+        assert_eq!(parsed.version, None);
+        assert!(parsed.params.is_empty());
+        assert_eq!(parsed.salt, None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(PhcString::parse("argon2id$v=19").is_err());
+        assert!(PhcString::parse("$Argon2id").is_err());
+        assert!(PhcString::parse("$argon2id$v=x").is_err());
+        assert!(PhcString::parse("$argon2id$m=,t=2").is_err());
+        assert!(PhcString::parse(&format!("{}$extra", EXAMPLE)).is_err());
+    }
+}
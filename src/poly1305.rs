@@ -0,0 +1,291 @@
+/**
+* This is an implementation of the Poly1305 one-time authenticator from RFC 8439.
+* The accumulator is kept modulo 2^130 - 5 in three limbs of 44, 44 and 42 bits so that the
+* products fit in a u128, and the final reduction is done without branches.
+* A key must never be used for more than one message.
+*/
+use crate::constant_time;
+
+pub const KEY_SIZE: usize = 32;
+pub const TAG_SIZE: usize = 16;
+const BLOCK_SIZE: usize = 16;
+
+const MASK_44: u64 = (1 << 44) - 1;
+const MASK_42: u64 = (1 << 42) - 1;
+
+/// A streaming Poly1305 computation
+#[derive(Clone)]
+pub struct Poly1305 {
+    r: [u64; 3],
+    ///`r[1]` and `r[2]` multiplied by 20, for folding the high products back down
+    r_folded: [u64; 2],
+    s: [u64; 2],
+    h: [u64; 3],
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+}
+
+impl Poly1305 {
+    /// Key the authenticator with `r || s`, `r` is clamped as the RFC requires
+    pub fn new(key: &[u8; KEY_SIZE]) -> Poly1305 {
+        let t0 = u64::from_le_bytes(key[0..8].try_into().unwrap());
+        let t1 = u64::from_le_bytes(key[8..16].try_into().unwrap());
+        let r = [
+            t0 & 0xffc0fffffff,
+            ((t0 >> 44) | (t1 << 20)) & 0xfffffc0ffff,
+            (t1 >> 24) & 0x00ffffffc0f,
+        ];
+        Poly1305 {
+            r,
+            r_folded: [r[1] * 20, r[2] * 20],
+            s: [
+                u64::from_le_bytes(key[16..24].try_into().unwrap()),
+                u64::from_le_bytes(key[24..32].try_into().unwrap()),
+            ],
+            h: [0; 3],
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.buffer_len > 0 {
+            let take = data.len().min(BLOCK_SIZE - self.buffer_len);
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+            if self.buffer_len < BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.process_block(&block, 1 << 40);
+            self.buffer_len = 0;
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            self.process_block(block.try_into().unwrap(), 1 << 40);
+        }
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    /// Like `update`, but zero pads the data to a multiple of 16 bytes as the AEAD construction does
+    pub fn update_padded(&mut self, data: &[u8]) {
+        self.update(data);
+        if self.buffer_len > 0 {
+            self.update(&[0; BLOCK_SIZE][self.buffer_len..]);
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; TAG_SIZE] {
+        if self.buffer_len > 0 {
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..self.buffer_len].copy_from_slice(&self.buffer[..self.buffer_len]);
+            block[self.buffer_len] = 1;
+            self.process_block(&block, 0);
+        }
+
+        let [mut h0, mut h1, mut h2] = self.h;
+        // fully carry h
+        let mut c = h1 >> 44;
+        h1 &= MASK_44;
+        h2 += c;
+        c = h2 >> 42;
+        h2 &= MASK_42;
+        h0 += c * 5;
+        c = h0 >> 44;
+        h0 &= MASK_44;
+        h1 += c;
+        c = h1 >> 44;
+        h1 &= MASK_44;
+        h2 += c;
+        c = h2 >> 42;
+        h2 &= MASK_42;
+        h0 += c * 5;
+        c = h0 >> 44;
+        h0 &= MASK_44;
+        h1 += c;
+
+        // g = h + 5 - 2^130, which is h mod p whenever it does not go negative
+        let mut g0 = h0 + 5;
+        c = g0 >> 44;
+        g0 &= MASK_44;
+        let mut g1 = h1 + c;
+        c = g1 >> 44;
+        g1 &= MASK_44;
+        let g2 = (h2 + c).wrapping_sub(1 << 42);
+
+        // all ones when g is non negative
+        let mask = (g2 >> 63).wrapping_sub(1);
+        h0 = (h0 & !mask) | (g0 & mask);
+        h1 = (h1 & !mask) | (g1 & mask);
+        h2 = (h2 & !mask) | (g2 & mask);
+
+        // h + s mod 2^128
+        let [s0, s1] = self.s;
+        h0 += s0 & MASK_44;
+        c = h0 >> 44;
+        h0 &= MASK_44;
+        h1 += (((s0 >> 44) | (s1 << 20)) & MASK_44) + c;
+        c = h1 >> 44;
+        h1 &= MASK_44;
+        h2 += ((s1 >> 24) & MASK_42) + c;
+        h2 &= MASK_42;
+
+        let mut tag = [0u8; TAG_SIZE];
+        tag[..8].copy_from_slice(&(h0 | (h1 << 44)).to_le_bytes());
+        tag[8..].copy_from_slice(&((h1 >> 20) | (h2 << 24)).to_le_bytes());
+        tag
+    }
+
+    /// Compare the tag against `tag` in constant time
+    #[must_use]
+    pub fn verify(self, tag: &[u8]) -> bool {
+        constant_time::eq(&self.finalize(), tag)
+    }
+
+    /// Authenticate `data` in one call
+    pub fn mac(key: &[u8; KEY_SIZE], data: &[u8]) -> [u8; TAG_SIZE] {
+        let mut poly = Poly1305::new(key);
+        poly.update(data);
+        poly.finalize()
+    }
+
+    /// h = (h + block) * r mod 2^130 - 5, `high_bit` is the 2^128 bit of the block in the top limb
+    fn process_block(&mut self, block: &[u8; BLOCK_SIZE], high_bit: u64) {
+        let t0 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let t1 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        let h0 = (self.h[0] + (t0 & MASK_44)) as u128;
+        let h1 = (self.h[1] + (((t0 >> 44) | (t1 << 20)) & MASK_44)) as u128;
+        let h2 = (self.h[2] + (((t1 >> 24) & MASK_42) | high_bit)) as u128;
+
+        let [r0, r1, r2] = self.r.map(|limb| limb as u128);
+        let [s1, s2] = self.r_folded.map(|limb| limb as u128);
+
+        let d0 = h0 * r0 + h1 * s2 + h2 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0;
+
+        d1 += d0 >> 44;
+        d2 += d1 >> 44;
+        let mut h0 = (d0 as u64) & MASK_44;
+        let mut h1 = (d1 as u64) & MASK_44;
+        let h2 = (d2 as u64) & MASK_42;
+        h0 += ((d2 >> 42) as u64) * 5;
+        h1 += h0 >> 44;
+        h0 &= MASK_44;
+        self.h = [h0, h1, h2];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poly1305::Poly1305;
+    use crate::test_util::hex;
+
+    fn check(key: &str, message: &str, tag: &str) {
+        let key = hex(key).try_into().unwrap();
+        let test_result = Poly1305::mac(&key, &hex(message));
+        assert_eq!(test_result.to_vec(), hex(tag));
+    }
+
+    #[test]
+    fn rfc8439_section_2_5_2() {
+        let key = hex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b");
+        let test_result = Poly1305::mac(
+            &key.try_into().unwrap(),
+            b"Cryptographic Forum Research Group",
+        );
+        assert_eq!(
+            test_result.to_vec(),
+            hex("a8061dc1305136c6c22b8baf0c0127a9")
+        );
+    }
+
+    #[test]
+    fn rfc8439_zero_key() {
+        check(&"00".repeat(32), &"00".repeat(64), &"00".repeat(16));
+    }
+
+    // the edge cases of RFC 8439 appendix A.3, vectors 5 to 11
+    #[test]
+    fn rfc8439_reduction_edge_cases() {
+        let r1 = format!("01{}{}", "00".repeat(15), "00".repeat(16));
+        let r2 = format!("02{}{}", "00".repeat(15), "00".repeat(16));
+        check(&r2, &"ff".repeat(16), "03000000000000000000000000000000");
+        check(
+            &format!("02{}{}", "00".repeat(15), "ff".repeat(16)),
+            "02000000000000000000000000000000",
+            "03000000000000000000000000000000",
+        );
+        check(
+            &r1,
+            &format!(
+                "{}f0{}11{}",
+                "ff".repeat(16),
+                "ff".repeat(15),
+                "00".repeat(15)
+            ),
+            "05000000000000000000000000000000",
+        );
+        check(
+            &r1,
+            &format!(
+                "{}fb{}{}",
+                "ff".repeat(16),
+                "fe".repeat(15),
+                "01".repeat(16)
+            ),
+            "00000000000000000000000000000000",
+        );
+        check(
+            &r2,
+            &format!("fd{}", "ff".repeat(15)),
+            "faffffffffffffffffffffffffffffff",
+        );
+        let r = "0100000000000000040000000000000000000000000000000000000000000000";
+        check(
+            r,
+            "e33594d7505e43b90000000000000000\
+             3394d7505e4379cd0100000000000000\
+             00000000000000000000000000000000\
+             01000000000000000000000000000000",
+            "14000000000000005500000000000000",
+        );
+        check(
+            r,
+            "e33594d7505e43b90000000000000000\
+             3394d7505e4379cd0100000000000000\
+             00000000000000000000000000000000",
+            "13000000000000000000000000000000",
+        );
+    }
+
+    #[test]
+    fn pieces_match_one_pass() {
+        let key = [7u8; 32];
+        let data: Vec<u8> = (0..=255).collect();
+        let expected = Poly1305::mac(&key, &data);
+        for split in [1, 15, 16, 17, 100] {
+            let mut poly = Poly1305::new(&key);
+            for piece in data.chunks(split) {
+                poly.update(piece);
+            }
+            assert_eq!(poly.finalize(), expected);
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let key = [3u8; 32];
+        let mut tag = Poly1305::mac(&key, b"message");
+        let mut poly = Poly1305::new(&key);
+        poly.update(b"message");
+        assert!(poly.clone().verify(&tag));
+        tag[15] ^= 1;
+        assert!(!poly.verify(&tag));
+    }
+}
@@ -0,0 +1,185 @@
+/**
+* This is the source of randomness for everything in the crate that generates keys, nonces or primes.
+* Those APIs take a `CryptoRng` so that real code can use the operating system generator (`OsRng`)
+* while tests inject a seeded, reproducible generator (`SeededRng`).
+*/
+use crate::sha3::{Shake256, XofReader};
+use std::fs::File;
+use std::io::Read;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngError {
+    ///The operating system could not provide random bytes
+    EntropyUnavailable,
+}
+
+impl std::fmt::Display for RngError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RngError::EntropyUnavailable => write!(f, "operating system entropy is unavailable"),
+        }
+    }
+}
+
+impl std::error::Error for RngError {}
+
+/// A generator of cryptographically secure random bytes
+pub trait CryptoRng {
+    /// Fill all of `output` with random bytes
+    fn fill_bytes(&mut self, output: &mut [u8]);
+
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// A fixed size array of random bytes, such as a key or nonce
+    fn random_array<const N: usize>(&mut self) -> [u8; N]
+    where
+        Self: Sized,
+    {
+        let mut output = [0u8; N];
+        self.fill_bytes(&mut output);
+        output
+    }
+
+    /// A vector of `len` random bytes
+    fn random_vec(&mut self, len: usize) -> Vec<u8> {
+        let mut output = vec![0u8; len];
+        self.fill_bytes(&mut output);
+        output
+    }
+}
+
+impl<R: CryptoRng + ?Sized> CryptoRng for &mut R {
+    fn fill_bytes(&mut self, output: &mut [u8]) {
+        (**self).fill_bytes(output)
+    }
+}
+
+#[cfg(target_os = "linux")]
+unsafe extern "C" {
+    fn getrandom(buf: *mut u8, buflen: usize, flags: u32) -> isize;
+}
+
+/// The operating system generator, `getrandom` on Linux with `/dev/urandom` as the fallback
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRng;
+
+impl OsRng {
+    pub fn try_fill_bytes(&mut self, output: &mut [u8]) -> Result<(), RngError> {
+        #[cfg(target_os = "linux")]
+        {
+            let mut filled = 0;
+            while filled < output.len() {
+                let remaining = &mut output[filled..];
+                let result = unsafe { getrandom(remaining.as_mut_ptr(), remaining.len(), 0) };
+                if result > 0 {
+                    filled += result as usize;
+                    continue;
+                }
+                match std::io::Error::last_os_error().kind() {
+                    std::io::ErrorKind::Interrupted => continue,
+                    // kernels older than 3.17 have no getrandom system call
+                    _ => break,
+                }
+            }
+            if filled == output.len() {
+                return Ok(());
+            }
+        }
+
+        let mut urandom = File::open("/dev/urandom").map_err(|_| RngError::EntropyUnavailable)?;
+        urandom
+            .read_exact(output)
+            .map_err(|_| RngError::EntropyUnavailable)
+    }
+}
+
+impl CryptoRng for OsRng {
+    /// Panics if the operating system cannot provide entropy, there is no safe way to continue
+    fn fill_bytes(&mut self, output: &mut [u8]) {
+        self.try_fill_bytes(output)
+            .expect("operating system entropy is unavailable")
+    }
+}
+
+/// A deterministic generator that expands a seed with SHAKE256.
+/// The same seed always gives the same stream, so it must only be used for tests and known answers.
+#[derive(Clone)]
+pub struct SeededRng {
+    reader: XofReader,
+}
+
+impl SeededRng {
+    pub fn from_seed(seed: &[u8]) -> SeededRng {
+        let mut shake = Shake256::init();
+        shake.update(b"rust_security seeded rng");
+        shake.update(seed);
+        SeededRng {
+            reader: shake.finalize_xof(),
+        }
+    }
+}
+
+impl CryptoRng for SeededRng {
+    fn fill_bytes(&mut self, output: &mut [u8]) {
+        self.reader.squeeze(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::{CryptoRng, OsRng, SeededRng};
+
+    #[test]
+    fn os_rng_fills_output() {
+        let mut rng = OsRng;
+        let first: [u8; 32] = rng.random_array();
+        let second: [u8; 32] = rng.random_array();
+        assert_ne!(first, second);
+        assert_ne!(first, [0u8; 32]);
+
+        // larger than a single getrandom call is guaranteed to return
+        let mut large = vec![0u8; 1 << 20];
+        rng.try_fill_bytes(&mut large).unwrap();
+        assert!(large.iter().any(|b| *b != 0));
+    }
+
+    #[test]
+    fn seeded_rng_is_reproducible() {
+        let mut first = SeededRng::from_seed(b"seed");
+        let mut second = SeededRng::from_seed(b"seed");
+        assert_eq!(first.random_vec(100), second.random_vec(100));
+        assert_eq!(first.next_u64(), second.next_u64());
+
+        let mut other = SeededRng::from_seed(b"other seed");
+        assert_ne!(first.random_vec(32), other.random_vec(32));
+    }
+
+    #[test]
+    fn seeded_rng_stream_is_split_independent() {
+        let mut whole = SeededRng::from_seed(b"seed");
+        let mut pieces = SeededRng::from_seed(b"seed");
+        let expected = whole.random_vec(200);
+        let mut output = Vec::new();
+        for len in [1, 135, 64] {
+            output.extend(pieces.random_vec(len));
+        }
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn usable_through_trait_object() {
+        let mut seeded = SeededRng::from_seed(b"seed");
+        let rng: &mut dyn CryptoRng = &mut seeded;
+        assert_eq!(rng.random_vec(8).len(), 8);
+    }
+}
@@ -0,0 +1,226 @@
+/**
+* This is an implementation of the scrypt password based key derivation function from RFC 7914.
+* scrypt wraps the memory hard ROMix function, built on BlockMix and the Salsa20/8 core,
+* between two passes of PBKDF2-HMAC-SHA256.
+*/
+use crate::pbkdf2::pbkdf2;
+use crate::sha2::Sha256;
+
+///Largest N * r accepted. ROMix holds 128 N r bytes, so this caps it at 4 GiB, well above the
+///N = 2^20, r = 8 (1 GiB) RFC 7914 example, and stops tampered parameters from exhausting memory.
+pub const MAX_COST_TIMES_BLOCK_SIZE: u64 = 1 << 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScryptError {
+    ///N must be a power of two greater than 1 and below 2^(16 r)
+    InvalidCost,
+    ///r and p must be non zero with r * p < 2^30
+    InvalidBlockSizeOrParallelism,
+    ///The output must be between 1 and (2^32 - 1) * 32 bytes
+    InvalidOutputLength,
+    ///N * r is above `MAX_COST_TIMES_BLOCK_SIZE`
+    MemoryTooHigh,
+}
+
+impl std::fmt::Display for ScryptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScryptError::InvalidCost => write!(f, "invalid scrypt cost parameter N"),
+            ScryptError::InvalidBlockSizeOrParallelism => {
+                write!(f, "invalid scrypt block size r or parallelism p")
+            }
+            ScryptError::InvalidOutputLength => write!(f, "invalid scrypt output length"),
+            ScryptError::MemoryTooHigh => write!(f, "scrypt N * r is above the memory limit"),
+        }
+    }
+}
+
+impl std::error::Error for ScryptError {}
+
+/// The Salsa20/8 core, applied in place to a 64 byte block held as 16 little endian words
+pub fn salsa20_8(block: &mut [u32; 16]) {
+    let mut x = *block;
+    let quarter = |x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+
+    for _ in 0..4 {
+        // columns
+        quarter(&mut x, 0, 4, 8, 12);
+        quarter(&mut x, 5, 9, 13, 1);
+        quarter(&mut x, 10, 14, 2, 6);
+        quarter(&mut x, 15, 3, 7, 11);
+        // rows
+        quarter(&mut x, 0, 1, 2, 3);
+        quarter(&mut x, 5, 6, 7, 4);
+        quarter(&mut x, 10, 11, 8, 9);
+        quarter(&mut x, 15, 12, 13, 14);
+    }
+
+    for (word, mixed) in block.iter_mut().zip(x) {
+        *word = word.wrapping_add(mixed);
+    }
+}
+
+/// BlockMix with Salsa20/8 over `2 * r` 64 byte blocks, writes the result to `output`
+pub fn block_mix(input: &[u32], output: &mut [u32]) {
+    let blocks = input.len() / 16;
+    let r = blocks / 2;
+    let mut x: [u32; 16] = input[input.len() - 16..].try_into().unwrap();
+
+    for i in 0..blocks {
+        for (x_word, in_word) in x.iter_mut().zip(&input[16 * i..16 * (i + 1)]) {
+            *x_word ^= in_word;
+        }
+        salsa20_8(&mut x);
+        // even blocks go to the first half of the output and odd blocks to the second
+        let position = (i / 2) + (i % 2) * r;
+        output[16 * position..16 * (position + 1)].copy_from_slice(&x);
+    }
+}
+
+/// ROMix, the memory hard part of scrypt, applied in place to `128 * r` bytes held as words
+pub fn ro_mix(block: &mut [u32], n: u64) {
+    let words = block.len();
+    let mut v = vec![0u32; words * n as usize];
+    let mut scratch = vec![0u32; words];
+
+    for i in 0..n as usize {
+        v[words * i..words * (i + 1)].copy_from_slice(block);
+        block_mix(block, &mut scratch);
+        block.copy_from_slice(&scratch);
+    }
+
+    for _ in 0..n {
+        // integerify, the first 8 bytes of the last 64 byte block
+        let last = words - 16;
+        let j = ((block[last] as u64) | ((block[last + 1] as u64) << 32)) & (n - 1);
+        let row = &v[words * j as usize..words * (j as usize + 1)];
+        for (word, v_word) in block.iter_mut().zip(row) {
+            *word ^= v_word;
+        }
+        block_mix(block, &mut scratch);
+        block.copy_from_slice(&scratch);
+    }
+}
+
+/// Derive `output.len()` bytes from `password` and `salt` with scrypt.
+/// `n` is the CPU/memory cost, `r` the block size and `p` the parallelization.
+pub fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    n: u64,
+    r: u32,
+    p: u32,
+    output: &mut [u8],
+) -> Result<(), ScryptError> {
+    if r == 0 || p == 0 || (r as u64) * (p as u64) >= 1 << 30 {
+        return Err(ScryptError::InvalidBlockSizeOrParallelism);
+    }
+    if n < 2 || !n.is_power_of_two() || (r < 4 && n >= 1 << (16 * r)) {
+        return Err(ScryptError::InvalidCost);
+    }
+    if n.saturating_mul(r as u64) > MAX_COST_TIMES_BLOCK_SIZE {
+        return Err(ScryptError::MemoryTooHigh);
+    }
+    if output.is_empty() || output.len() as u64 > u32::MAX as u64 * 32 {
+        return Err(ScryptError::InvalidOutputLength);
+    }
+
+    let block_len = 128 * r as usize;
+    let mut b = vec![0u8; block_len * p as usize];
+    pbkdf2::<Sha256>(password, salt, 1, &mut b).unwrap();
+
+    let mut words = vec![0u32; block_len / 4];
+    for chunk in b.chunks_exact_mut(block_len) {
+        for (word, bytes) in words.iter_mut().zip(chunk.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        ro_mix(&mut words, n);
+        for (word, bytes) in words.iter().zip(chunk.chunks_exact_mut(4)) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    pbkdf2::<Sha256>(password, &b, 1, output).unwrap();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scrypt::{self, ScryptError};
+    use crate::test_util::hex;
+
+    fn words(bytes: &[u8]) -> Vec<u32> {
+        bytes
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn salsa20_8_core() {
+        let input = hex(
+            "7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+             ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e",
+        );
+        let test_result = hex(
+            "a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+             b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81",
+        );
+        let mut block: [u32; 16] = words(&input).try_into().unwrap();
+        scrypt::salsa20_8(&mut block);
+        assert_eq!(block.to_vec(), words(&test_result));
+    }
+
+    #[test]
+    fn rfc7914_empty() {
+        let test_result = hex(
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906",
+        );
+        let mut output = [0u8; 64];
+        scrypt::scrypt(b"", b"", 16, 1, 1, &mut output).unwrap();
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn rfc7914_password_nacl() {
+        let test_result = hex(
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+        );
+        let mut output = [0u8; 64];
+        scrypt::scrypt(b"password", b"NaCl", 1024, 8, 16, &mut output).unwrap();
+        assert_eq!(output.to_vec(), test_result);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let mut output = [0u8; 32];
+        assert_eq!(
+            scrypt::scrypt(b"", b"", 1000, 8, 1, &mut output),
+            Err(ScryptError::InvalidCost)
+        );
+        assert_eq!(
+            scrypt::scrypt(b"", b"", 1 << 16, 1, 1, &mut output),
+            Err(ScryptError::InvalidCost)
+        );
+        assert_eq!(
+            scrypt::scrypt(b"", b"", 16, 0, 1, &mut output),
+            Err(ScryptError::InvalidBlockSizeOrParallelism)
+        );
+        assert_eq!(
+            scrypt::scrypt(b"", b"", 16, 1, 1, &mut []),
+            Err(ScryptError::InvalidOutputLength)
+        );
+        // 8 GiB of ROMix memory
+        assert_eq!(
+            scrypt::scrypt(b"", b"", 1 << 23, 8, 1, &mut output),
+            Err(ScryptError::MemoryTooHigh)
+        );
+    }
+}
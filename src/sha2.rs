@@ -0,0 +1,671 @@
+/**
+* This is an implementation of the SHA-2 family from FIPS 180-4.
+* The 64-bit members SHA-384, SHA-512, SHA-512/224, SHA-512/256 and the general SHA-512/t
+* share the SHA-512 compression function and only differ in their initial hash value and
+* in how much of the final state is output. SHA-224 and SHA-256 do the same with the
+* 32-bit SHA-256 compression function.
+*/
+use crate::hash::Hash;
+
+///SHA-512 works on 1024 bit blocks
+const BLOCK_SIZE: usize = 128;
+
+///Round constants, the first 64 bits of the fractional parts of the cube roots of the first 80 primes
+const K: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+///Initial hash values for each member of the family
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+const SHA512_224_IV: [u64; 8] = [
+    0x8c3d37c819544da2,
+    0x73e1996689dcd4d6,
+    0x1dfab7ae32ff9c82,
+    0x679dd514582f9fcf,
+    0x0f6d2b697bd44da8,
+    0x77e36f7304c48942,
+    0x3f9d85a86a1d36c8,
+    0x1112e6ad91d692a1,
+];
+const SHA512_256_IV: [u64; 8] = [
+    0x22312194fc2bf72c,
+    0x9f555fa3c84c64c2,
+    0x2393b86b6f53b151,
+    0x963877195940eabd,
+    0x96283ee2a88effe3,
+    0xbe5e1e2553863992,
+    0x2b0199fc2c85b8aa,
+    0x0eb72ddc81c52ca2,
+];
+
+///The value the SHA-512 IV is xored with to produce the IV generator for SHA-512/t
+const SHA512_T_IV_MASK: u64 = 0xa5a5a5a5a5a5a5a5;
+
+/// State shared by every member of the family
+#[derive(Clone)]
+struct Sha512Core {
+    state: [u64; 8],
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+    ///Total message length in bytes, the padding needs it in bits as a 128 bit number
+    length: u128,
+}
+
+impl Sha512Core {
+    fn init(iv: [u64; 8]) -> Sha512Core {
+        Sha512Core {
+            state: iv,
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u128;
+
+        // top up a partially filled buffer first
+        if self.buffer_len > 0 {
+            let take = std::cmp::min(BLOCK_SIZE - self.buffer_len, data.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+            if self.buffer_len < BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            compress(&mut self.state, &block);
+            self.buffer_len = 0;
+        }
+
+        // then compress whole blocks straight from the input
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            compress(&mut self.state, block);
+        }
+
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    /// Pad the message and return the full 64 byte final state
+    fn finalize(mut self) -> [u8; 64] {
+        let bit_length = self.length.wrapping_mul(8);
+
+        // a single 1 bit, then zeros until there are 16 bytes left for the length
+        let mut padding = [0u8; 2 * BLOCK_SIZE];
+        padding[0] = 0x80;
+        let pad_len = if self.buffer_len < BLOCK_SIZE - 16 {
+            BLOCK_SIZE - 16 - self.buffer_len
+        } else {
+            2 * BLOCK_SIZE - 16 - self.buffer_len
+        };
+        padding[pad_len..pad_len + 16].copy_from_slice(&bit_length.to_be_bytes());
+
+        let length = self.length;
+        self.update(&padding[..pad_len + 16]);
+        debug_assert_eq!(self.buffer_len, 0);
+        self.length = length;
+
+        let mut output = [0u8; 64];
+        for (chunk, word) in output.chunks_exact_mut(8).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        output
+    }
+}
+
+/// The SHA-512 compression function, processes one 128 byte block
+fn compress(state: &mut [u64; 8], block: &[u8]) {
+    let mut w = [0u64; 80];
+    for (i, chunk) in block.chunks_exact(8).enumerate() {
+        w[i] = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    for t in 16..80 {
+        let s0 = w[t - 15].rotate_right(1) ^ w[t - 15].rotate_right(8) ^ (w[t - 15] >> 7);
+        let s1 = w[t - 2].rotate_right(19) ^ w[t - 2].rotate_right(61) ^ (w[t - 2] >> 6);
+        w[t] = w[t - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for t in 0..80 {
+        let big_s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(big_s1)
+            .wrapping_add(ch)
+            .wrapping_add(K[t])
+            .wrapping_add(w[t]);
+        let big_s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = big_s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (word, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(new);
+    }
+}
+
+/// Generate the initial hash value for SHA-512/t as described in FIPS 180-4 section 5.3.6
+fn sha512_t_iv(t: usize) -> [u64; 8] {
+    let mut iv = SHA512_IV;
+    for word in iv.iter_mut() {
+        *word ^= SHA512_T_IV_MASK;
+    }
+    let mut generator = Sha512Core::init(iv);
+    generator.update(format!("SHA-512/{}", t).as_bytes());
+    let digest = generator.finalize();
+
+    let mut result = [0u64; 8];
+    for (word, chunk) in result.iter_mut().zip(digest.chunks_exact(8)) {
+        *word = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    result
+}
+
+macro_rules! sha512_variant {
+    ($(#[$doc:meta])* $name:ident, $iv:expr, $output_size:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            core: Sha512Core,
+        }
+
+        impl Hash for $name {
+            const BLOCK_SIZE: usize = BLOCK_SIZE;
+            const OUTPUT_SIZE: usize = $output_size;
+
+            fn init() -> Self {
+                $name {
+                    core: Sha512Core::init($iv),
+                }
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.core.update(data);
+            }
+
+            fn finalize(self) -> Vec<u8> {
+                self.core.finalize()[..Self::OUTPUT_SIZE].to_vec()
+            }
+        }
+    };
+}
+
+sha512_variant!(
+    /// SHA-512 with a 64 byte digest
+    Sha512,
+    SHA512_IV,
+    64
+);
+sha512_variant!(
+    /// SHA-384 with a 48 byte digest
+    Sha384,
+    SHA384_IV,
+    48
+);
+sha512_variant!(
+    /// SHA-512/224 with a 28 byte digest
+    Sha512_224,
+    SHA512_224_IV,
+    28
+);
+sha512_variant!(
+    /// SHA-512/256 with a 32 byte digest
+    Sha512_256,
+    SHA512_256_IV,
+    32
+);
+
+/// SHA-512/t for any output length t that is a whole number of bytes.
+///
+/// The output length is only known at runtime so this does not implement `Hash`, use
+/// `Sha512_224` or `Sha512_256` for the two approved lengths.
+#[derive(Clone)]
+pub struct Sha512T {
+    core: Sha512Core,
+    output_size: usize,
+}
+
+impl Sha512T {
+    /// Create a SHA-512/t state, t is in bits and must be a multiple of 8 below 512 other than 384
+    pub fn init(t: usize) -> Option<Sha512T> {
+        if t == 0 || t >= 512 || t == 384 || !t.is_multiple_of(8) {
+            return None;
+        }
+        Some(Sha512T {
+            core: Sha512Core::init(sha512_t_iv(t)),
+            output_size: t / 8,
+        })
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.core.update(data);
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        self.core.finalize()[..self.output_size].to_vec()
+    }
+}
+
+///SHA-256 works on 512 bit blocks
+const SHA256_BLOCK_SIZE: usize = 64;
+
+///Round constants, the first 32 bits of the fractional parts of the cube roots of the first 64 primes
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+const SHA224_IV: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+/// State shared by SHA-224 and SHA-256
+#[derive(Clone)]
+struct Sha256Core {
+    state: [u32; 8],
+    buffer: [u8; SHA256_BLOCK_SIZE],
+    buffer_len: usize,
+    ///Total message length in bytes
+    length: u64,
+}
+
+impl Sha256Core {
+    fn init(iv: [u32; 8]) -> Sha256Core {
+        Sha256Core {
+            state: iv,
+            buffer: [0; SHA256_BLOCK_SIZE],
+            buffer_len: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+
+        if self.buffer_len > 0 {
+            let take = std::cmp::min(SHA256_BLOCK_SIZE - self.buffer_len, data.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+            if self.buffer_len < SHA256_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            compress256(&mut self.state, &block);
+            self.buffer_len = 0;
+        }
+
+        let mut blocks = data.chunks_exact(SHA256_BLOCK_SIZE);
+        for block in &mut blocks {
+            compress256(&mut self.state, block);
+        }
+
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    /// Pad the message and return the full 32 byte final state
+    fn finalize(mut self) -> [u8; 32] {
+        let bit_length = self.length.wrapping_mul(8);
+
+        // a single 1 bit, then zeros until there are 8 bytes left for the length
+        let mut padding = [0u8; 2 * SHA256_BLOCK_SIZE];
+        padding[0] = 0x80;
+        let pad_len = if self.buffer_len < SHA256_BLOCK_SIZE - 8 {
+            SHA256_BLOCK_SIZE - 8 - self.buffer_len
+        } else {
+            2 * SHA256_BLOCK_SIZE - 8 - self.buffer_len
+        };
+        padding[pad_len..pad_len + 8].copy_from_slice(&bit_length.to_be_bytes());
+        self.update(&padding[..pad_len + 8]);
+        debug_assert_eq!(self.buffer_len, 0);
+
+        let mut output = [0u8; 32];
+        for (chunk, word) in output.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        output
+    }
+}
+
+/// The SHA-256 compression function, processes one 64 byte block
+fn compress256(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (i, chunk) in block.chunks_exact(4).enumerate() {
+        w[i] = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    for t in 16..64 {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w[t] = w[t - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for t in 0..64 {
+        let big_s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(big_s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA256_K[t])
+            .wrapping_add(w[t]);
+        let big_s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = big_s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (word, new) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(new);
+    }
+}
+
+macro_rules! sha256_variant {
+    ($(#[$doc:meta])* $name:ident, $iv:expr, $output_size:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            core: Sha256Core,
+        }
+
+        impl Hash for $name {
+            const BLOCK_SIZE: usize = SHA256_BLOCK_SIZE;
+            const OUTPUT_SIZE: usize = $output_size;
+
+            fn init() -> Self {
+                $name {
+                    core: Sha256Core::init($iv),
+                }
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.core.update(data);
+            }
+
+            fn finalize(self) -> Vec<u8> {
+                self.core.finalize()[..Self::OUTPUT_SIZE].to_vec()
+            }
+        }
+    };
+}
+
+sha256_variant!(
+    /// SHA-256 with a 32 byte digest
+    Sha256,
+    SHA256_IV,
+    32
+);
+sha256_variant!(
+    /// SHA-224 with a 28 byte digest
+    Sha224,
+    SHA224_IV,
+    28
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::Hash;
+    use crate::sha2;
+    use crate::test_util::hex;
+
+    const TWO_BLOCK_MESSAGE: &[u8] = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+    #[test]
+    fn sha512_empty() {
+        let test_result = hex(
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+             47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+        );
+        assert_eq!(sha2::Sha512::digest(b""), test_result);
+    }
+
+    #[test]
+    fn sha512_abc() {
+        let test_result = hex(
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        );
+        assert_eq!(sha2::Sha512::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha512_two_blocks() {
+        let test_result = hex(
+            "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018\
+             501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+        );
+        assert_eq!(sha2::Sha512::digest(TWO_BLOCK_MESSAGE), test_result);
+    }
+
+    #[test]
+    fn sha512_million_a_incremental() {
+        let test_result = hex(
+            "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb\
+             de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b",
+        );
+        let mut state = sha2::Sha512::init();
+        let chunk = [b'a'; 1000];
+        for _ in 0..1000 {
+            state.update(&chunk);
+        }
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn update_split_matches_one_shot() {
+        let mut state = sha2::Sha512::init();
+        for byte in TWO_BLOCK_MESSAGE.chunks(7) {
+            state.update(byte);
+        }
+        assert_eq!(state.finalize(), sha2::Sha512::digest(TWO_BLOCK_MESSAGE));
+    }
+
+    #[test]
+    fn sha384_abc() {
+        let test_result = hex(
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+             8086072ba1e7cc2358baeca134c825a7",
+        );
+        assert_eq!(sha2::Sha384::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha384_two_blocks() {
+        let test_result = hex(
+            "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712\
+             fcc7c71a557e2db966c3e9fa91746039",
+        );
+        assert_eq!(sha2::Sha384::digest(TWO_BLOCK_MESSAGE), test_result);
+    }
+
+    #[test]
+    fn sha512_224_abc() {
+        let test_result = hex("4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa");
+        assert_eq!(sha2::Sha512_224::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha512_256_abc() {
+        let test_result = hex("53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23");
+        assert_eq!(sha2::Sha512_256::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha512_t_generated_iv_matches_constants() {
+        let mut state = sha2::Sha512T::init(256).unwrap();
+        state.update(TWO_BLOCK_MESSAGE);
+        assert_eq!(
+            state.finalize(),
+            sha2::Sha512_256::digest(TWO_BLOCK_MESSAGE)
+        );
+
+        let mut state = sha2::Sha512T::init(224).unwrap();
+        state.update(b"abc");
+        assert_eq!(state.finalize(), sha2::Sha512_224::digest(b"abc"));
+    }
+
+    #[test]
+    fn sha512_t_rejects_invalid_lengths() {
+        assert!(sha2::Sha512T::init(384).is_none());
+        assert!(sha2::Sha512T::init(512).is_none());
+        assert!(sha2::Sha512T::init(100).is_none());
+    }
+
+    #[test]
+    fn sha256_abc() {
+        let test_result = hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(sha2::Sha256::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha256_two_blocks_incremental() {
+        let test_result = hex("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+        let mut state = sha2::Sha256::init();
+        for chunk in b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".chunks(5) {
+            state.update(chunk);
+        }
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn sha224_abc() {
+        let test_result = hex("23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7");
+        assert_eq!(sha2::Sha224::digest(b"abc"), test_result);
+    }
+}
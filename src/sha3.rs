@@ -0,0 +1,595 @@
+/**
+* This is an implementation of the Keccak-f[1600] permutation and the sponge construction built on it,
+* along with the functions standardised on top of them:
+* SHA3-224/256/384/512 and SHAKE128/256 from FIPS 202,
+* cSHAKE128/256 and KMAC128/256 from NIST SP 800-185.
+*/
+use crate::hash::Hash;
+
+///Number of rounds in Keccak-f[1600]
+const ROUNDS: usize = 24;
+
+///Round constants for the iota step
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+///Rotation offsets for the rho step, in the order lanes are visited by the pi step
+const RHO_OFFSETS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+///Lane visiting order for the combined rho and pi steps
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+///Domain separation suffixes, these already include the first bit of the pad10*1 padding
+const SHA3_SUFFIX: u8 = 0x06;
+const SHAKE_SUFFIX: u8 = 0x1f;
+const CSHAKE_SUFFIX: u8 = 0x04;
+
+///Rates in bytes for each security level
+const RATE_128: usize = 168;
+const RATE_256: usize = 136;
+
+/// Apply the Keccak-f[1600] permutation to a state of 25 lanes.
+/// Lane (x, y) is stored at index x + 5 * y.
+pub fn keccak_f1600(state: &mut [u64; 25]) {
+    for round_constant in ROUND_CONSTANTS {
+        // theta
+        let mut column = [0u64; 5];
+        for x in 0..5 {
+            column[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = column[(x + 4) % 5] ^ column[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut current = state[1];
+        for (lane, offset) in PI_LANES.iter().zip(RHO_OFFSETS) {
+            let next = state[*lane];
+            state[*lane] = current.rotate_left(offset);
+            current = next;
+        }
+
+        // chi
+        for y in 0..5 {
+            let row = [
+                state[5 * y],
+                state[5 * y + 1],
+                state[5 * y + 2],
+                state[5 * y + 3],
+                state[5 * y + 4],
+            ];
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        state[0] ^= round_constant;
+    }
+}
+
+/// A sponge over Keccak-f[1600] with a byte aligned rate.
+///
+/// Data is absorbed with `absorb`, and the first call to `squeeze` pads the input with the domain
+/// suffix and pad10*1. After that only `squeeze` may be called, as many times as needed.
+#[derive(Clone)]
+pub struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    suffix: u8,
+    position: usize,
+    squeezing: bool,
+}
+
+impl Sponge {
+    /// Create a sponge with `rate` bytes per block and the given domain suffix bits,
+    /// the suffix is written least significant bit first and ends with the first padding bit
+    pub fn init(rate: usize, suffix: u8) -> Sponge {
+        assert!(rate > 0 && rate < 200, "rate must leave some capacity");
+        Sponge {
+            state: [0; 25],
+            rate,
+            suffix,
+            position: 0,
+            squeezing: false,
+        }
+    }
+
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn xor_byte(&mut self, index: usize, byte: u8) {
+        self.state[index / 8] ^= (byte as u64) << (8 * (index % 8));
+    }
+
+    fn get_byte(&self, index: usize) -> u8 {
+        (self.state[index / 8] >> (8 * (index % 8))) as u8
+    }
+
+    pub fn absorb(&mut self, data: &[u8]) {
+        assert!(!self.squeezing, "cannot absorb after squeezing has started");
+        for byte in data {
+            self.xor_byte(self.position, *byte);
+            self.position += 1;
+            if self.position == self.rate {
+                keccak_f1600(&mut self.state);
+                self.position = 0;
+            }
+        }
+    }
+
+    /// Pad the absorbed data with zeros up to the end of the current block
+    fn zero_pad_block(&mut self) {
+        if self.position != 0 {
+            keccak_f1600(&mut self.state);
+            self.position = 0;
+        }
+    }
+
+    fn pad(&mut self) {
+        self.xor_byte(self.position, self.suffix);
+        self.xor_byte(self.rate - 1, 0x80);
+        keccak_f1600(&mut self.state);
+        self.position = 0;
+        self.squeezing = true;
+    }
+
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        if !self.squeezing {
+            self.pad();
+        }
+        for byte in output.iter_mut() {
+            if self.position == self.rate {
+                keccak_f1600(&mut self.state);
+                self.position = 0;
+            }
+            *byte = self.get_byte(self.position);
+            self.position += 1;
+        }
+    }
+}
+
+macro_rules! sha3_variant {
+    ($(#[$doc:meta])* $name:ident, $output_size:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+        }
+
+        impl Hash for $name {
+            const BLOCK_SIZE: usize = 200 - 2 * $output_size;
+            const OUTPUT_SIZE: usize = $output_size;
+
+            fn init() -> Self {
+                $name {
+                    sponge: Sponge::init(Self::BLOCK_SIZE, SHA3_SUFFIX),
+                }
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.sponge.absorb(data);
+            }
+
+            fn finalize(mut self) -> Vec<u8> {
+                let mut output = vec![0; Self::OUTPUT_SIZE];
+                self.sponge.squeeze(&mut output);
+                output
+            }
+        }
+    };
+}
+
+sha3_variant!(
+    /// SHA3-224 with a 28 byte digest
+    Sha3_224,
+    28
+);
+sha3_variant!(
+    /// SHA3-256 with a 32 byte digest
+    Sha3_256,
+    32
+);
+sha3_variant!(
+    /// SHA3-384 with a 48 byte digest
+    Sha3_384,
+    48
+);
+sha3_variant!(
+    /// SHA3-512 with a 64 byte digest
+    Sha3_512,
+    64
+);
+
+/// The squeezing half of an extendable output function, read as many bytes as needed from it
+#[derive(Clone)]
+pub struct XofReader {
+    sponge: Sponge,
+}
+
+impl XofReader {
+    pub fn squeeze(&mut self, output: &mut [u8]) {
+        self.sponge.squeeze(output);
+    }
+
+    /// Squeeze `len` bytes into a new vector
+    pub fn squeeze_vec(&mut self, len: usize) -> Vec<u8> {
+        let mut output = vec![0; len];
+        self.sponge.squeeze(&mut output);
+        output
+    }
+}
+
+macro_rules! shake_variant {
+    ($(#[$doc:meta])* $name:ident, $rate:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+        }
+
+        impl $name {
+            pub fn init() -> Self {
+                $name {
+                    sponge: Sponge::init($rate, SHAKE_SUFFIX),
+                }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.sponge.absorb(data);
+            }
+
+            /// Stop absorbing and return a reader for the output stream
+            pub fn finalize_xof(self) -> XofReader {
+                XofReader {
+                    sponge: self.sponge,
+                }
+            }
+
+            /// Hash `data` to `len` bytes of output in one call
+            pub fn digest(data: &[u8], len: usize) -> Vec<u8> {
+                let mut state = Self::init();
+                state.update(data);
+                state.finalize_xof().squeeze_vec(len)
+            }
+        }
+    };
+}
+
+shake_variant!(
+    /// SHAKE128, the extendable output function with 128 bit security
+    Shake128,
+    RATE_128
+);
+shake_variant!(
+    /// SHAKE256, the extendable output function with 256 bit security
+    Shake256,
+    RATE_256
+);
+
+/// left_encode from SP 800-185: the byte length of `value` followed by its big endian bytes
+fn left_encode(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = std::cmp::min(bytes.iter().take_while(|b| **b == 0).count(), 7);
+    let mut output = vec![(8 - skip) as u8];
+    output.extend_from_slice(&bytes[skip..]);
+    output
+}
+
+/// right_encode from SP 800-185: the big endian bytes of `value` followed by their length
+fn right_encode(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = std::cmp::min(bytes.iter().take_while(|b| **b == 0).count(), 7);
+    let mut output = bytes[skip..].to_vec();
+    output.push((8 - skip) as u8);
+    output
+}
+
+/// encode_string from SP 800-185: the bit length of `data` followed by the data
+fn encode_string(data: &[u8]) -> Vec<u8> {
+    let mut output = left_encode(8 * data.len() as u64);
+    output.extend_from_slice(data);
+    output
+}
+
+/// Absorb bytepad(encode_string(a) || encode_string(b) .., rate) into a fresh sponge
+fn absorb_bytepad(sponge: &mut Sponge, strings: &[&[u8]]) {
+    sponge.absorb(&left_encode(sponge.rate() as u64));
+    for string in strings {
+        sponge.absorb(&encode_string(string));
+    }
+    sponge.zero_pad_block();
+}
+
+macro_rules! cshake_variant {
+    ($(#[$doc:meta])* $name:ident, $shake:ident, $rate:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge,
+        }
+
+        impl $name {
+            /// Create a state for function name `function_name` and customization string `customization`.
+            /// With both empty this is exactly SHAKE.
+            pub fn init(function_name: &[u8], customization: &[u8]) -> Self {
+                if function_name.is_empty() && customization.is_empty() {
+                    return $name {
+                        sponge: $shake::init().sponge,
+                    };
+                }
+                let mut sponge = Sponge::init($rate, CSHAKE_SUFFIX);
+                absorb_bytepad(&mut sponge, &[function_name, customization]);
+                $name { sponge }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.sponge.absorb(data);
+            }
+
+            /// Stop absorbing and return a reader for the output stream
+            pub fn finalize_xof(self) -> XofReader {
+                XofReader {
+                    sponge: self.sponge,
+                }
+            }
+        }
+    };
+}
+
+cshake_variant!(
+    /// cSHAKE128, customizable SHAKE128
+    CShake128,
+    Shake128,
+    RATE_128
+);
+cshake_variant!(
+    /// cSHAKE256, customizable SHAKE256
+    CShake256,
+    Shake256,
+    RATE_256
+);
+
+macro_rules! kmac_variant {
+    ($(#[$doc:meta])* $name:ident, $cshake:ident, $rate:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            cshake: $cshake,
+        }
+
+        impl $name {
+            /// Create a KMAC state keyed with `key` and the customization string `customization`
+            pub fn new(key: &[u8], customization: &[u8]) -> Self {
+                let mut cshake = $cshake::init(b"KMAC", customization);
+                absorb_bytepad(&mut cshake.sponge, &[key]);
+                $name { cshake }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.cshake.update(data);
+            }
+
+            /// Produce a tag of `len` bytes, the requested length is bound into the tag
+            pub fn finalize(mut self, len: usize) -> Vec<u8> {
+                self.cshake.update(&right_encode(8 * len as u64));
+                self.cshake.finalize_xof().squeeze_vec(len)
+            }
+
+            /// KMACXOF, an output stream whose contents do not depend on how much is read
+            pub fn finalize_xof(mut self) -> XofReader {
+                self.cshake.update(&right_encode(0));
+                self.cshake.finalize_xof()
+            }
+        }
+    };
+}
+
+kmac_variant!(
+    /// KMAC128, a keyed hash built on cSHAKE128
+    Kmac128,
+    CShake128,
+    RATE_128
+);
+kmac_variant!(
+    /// KMAC256, a keyed hash built on cSHAKE256
+    Kmac256,
+    CShake256,
+    RATE_256
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::Hash;
+    use crate::sha3;
+    use crate::test_util::hex;
+
+    const KMAC_KEY: &str = "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f";
+
+    fn two_hundred_bytes() -> Vec<u8> {
+        (0..200).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn keccak_f1600_zero_state() {
+        let mut state = [0u64; 25];
+        sha3::keccak_f1600(&mut state);
+        assert_eq!(state[0], 0xf1258f7940e1dde7);
+        assert_eq!(state[24], 0xeaf1ff7b5ceca249);
+    }
+
+    #[test]
+    fn sha3_224_abc() {
+        let test_result = hex("e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf");
+        assert_eq!(sha3::Sha3_224::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha3_256_empty() {
+        let test_result = hex("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a");
+        assert_eq!(sha3::Sha3_256::digest(b""), test_result);
+    }
+
+    #[test]
+    fn sha3_256_1600_bits_incremental() {
+        let test_result = hex("79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787");
+        let mut state = sha3::Sha3_256::init();
+        for _ in 0..40 {
+            state.update(&[0xa3; 5]);
+        }
+        assert_eq!(state.finalize(), test_result);
+    }
+
+    #[test]
+    fn sha3_384_abc() {
+        let test_result = hex(
+            "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b2\
+             98d88cea927ac7f539f1edf228376d25",
+        );
+        assert_eq!(sha3::Sha3_384::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn sha3_512_abc() {
+        let test_result = hex(
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+             10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+        );
+        assert_eq!(sha3::Sha3_512::digest(b"abc"), test_result);
+    }
+
+    #[test]
+    fn shake128_empty() {
+        let test_result = hex("7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26");
+        assert_eq!(sha3::Shake128::digest(b"", 32), test_result);
+    }
+
+    #[test]
+    fn shake128_streaming_squeeze() {
+        let test_result = hex("44c9fb359fd56ac0a9a75a743cff6862f17d7259ab075216c0699511643b6439");
+        let mut state = sha3::Shake128::init();
+        state.update(&[0xa3; 200]);
+        let mut reader = state.finalize_xof();
+        let mut output = [0u8; 512];
+        for chunk in output.chunks_mut(13) {
+            reader.squeeze(chunk);
+        }
+        assert_eq!(output[480..].to_vec(), test_result);
+    }
+
+    #[test]
+    fn shake256_empty() {
+        let test_result = hex(
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+             d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+        );
+        assert_eq!(sha3::Shake256::digest(b"", 64), test_result);
+    }
+
+    #[test]
+    fn cshake128_sample_1() {
+        let test_result = hex("c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5");
+        let mut state = sha3::CShake128::init(b"", b"Email Signature");
+        state.update(&hex("00010203"));
+        assert_eq!(state.finalize_xof().squeeze_vec(32), test_result);
+    }
+
+    #[test]
+    fn cshake128_sample_2() {
+        let test_result = hex("c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b");
+        let mut state = sha3::CShake128::init(b"", b"Email Signature");
+        state.update(&two_hundred_bytes());
+        assert_eq!(state.finalize_xof().squeeze_vec(32), test_result);
+    }
+
+    #[test]
+    fn cshake256_sample_3() {
+        let test_result = hex(
+            "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1\
+             64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c",
+        );
+        let mut state = sha3::CShake256::init(b"", b"Email Signature");
+        state.update(&hex("00010203"));
+        assert_eq!(state.finalize_xof().squeeze_vec(64), test_result);
+    }
+
+    #[test]
+    fn cshake_without_customization_is_shake() {
+        let mut state = sha3::CShake256::init(b"", b"");
+        state.update(b"abc");
+        assert_eq!(
+            state.finalize_xof().squeeze_vec(100),
+            sha3::Shake256::digest(b"abc", 100)
+        );
+    }
+
+    #[test]
+    fn kmac128_sample_1() {
+        let test_result = hex("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e");
+        let mut state = sha3::Kmac128::new(&hex(KMAC_KEY), b"");
+        state.update(&hex("00010203"));
+        assert_eq!(state.finalize(32), test_result);
+    }
+
+    #[test]
+    fn kmac128_sample_2() {
+        let test_result = hex("3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5");
+        let mut state = sha3::Kmac128::new(&hex(KMAC_KEY), b"My Tagged Application");
+        state.update(&hex("00010203"));
+        assert_eq!(state.finalize(32), test_result);
+    }
+
+    #[test]
+    fn kmac256_sample_5() {
+        let test_result = hex(
+            "b58618f71f92e1d56c1b8c55ddd7cd188b97b4ca4d99831eb2699a837da2e4d9\
+             70fbacfde50033aea585f1a2708510c32d07880801bd182898fe476876fc8965",
+        );
+        let mut state = sha3::Kmac256::new(&hex(KMAC_KEY), b"My Tagged Application");
+        state.update(&two_hundred_bytes());
+        assert_eq!(state.finalize(64), test_result);
+    }
+
+    #[test]
+    fn kmacxof256_sample_5() {
+        let test_result = hex(
+            "d5be731c954ed7732846bb59dbe3a8e30f83e77a4bff4459f2f1c2b4ecebb8ce\
+             67ba01c62e8ab8578d2d499bd1bb276768781190020a306a97de281dcc30305d",
+        );
+        let mut state = sha3::Kmac256::new(&hex(KMAC_KEY), b"My Tagged Application");
+        state.update(&two_hundred_bytes());
+        assert_eq!(state.finalize_xof().squeeze_vec(64), test_result);
+    }
+}
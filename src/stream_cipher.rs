@@ -0,0 +1,16 @@
+//Common interface implemented by the stream ciphers and block cipher counter modes in the crate
+
+/// A cipher that encrypts by xoring a seekable keystream into the data.
+///
+/// Encryption and decryption are the same operation, and data can be processed in pieces of any
+/// size. ChaCha20 and AES-CTR both implement it, so one can be swapped for the other.
+pub trait StreamCipher {
+    /// Xor the next `data.len()` bytes of keystream into `data`
+    fn apply_keystream(&mut self, data: &mut [u8]);
+
+    /// Move to byte `position` of the keystream
+    fn seek(&mut self, position: u64);
+
+    /// The current byte position in the keystream
+    fn position(&self) -> u64;
+}
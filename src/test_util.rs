@@ -0,0 +1,37 @@
+//Helpers shared by the unit tests of each module
+use crate::aead::{Aead, AeadError};
+
+/// Decode a hex string (whitespace is ignored) into bytes
+pub fn hex(input: &str) -> Vec<u8> {
+    let digits: Vec<u8> = input
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|c| (c as char).to_digit(16).expect("invalid hex digit") as u8)
+        .collect();
+    assert!(digits.len().is_multiple_of(2), "odd number of hex digits");
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect()
+}
+
+/// Seal `plaintext` with an `A` built from `key`, compare with `sealed` and open it again
+pub fn check_aead<A: Aead>(key: &str, nonce: &str, aad: &str, plaintext: &str, sealed: &str) {
+    let aead = A::new(&hex(key)).unwrap();
+    let test_result = aead.seal(&hex(nonce), &hex(aad), &hex(plaintext)).unwrap();
+    assert_eq!(test_result, hex(sealed));
+    let opened = aead.open(&hex(nonce), &hex(aad), &test_result).unwrap();
+    assert_eq!(opened, hex(plaintext));
+}
+
+/// Open in place with a corrupted tag and check that the buffer still holds the ciphertext
+pub fn check_failed_open_leaves_ciphertext<A: Aead>(key: &[u8], nonce: &[u8], plaintext: &[u8]) {
+    let aead = A::new(key).unwrap();
+    let mut data = plaintext.to_vec();
+    let mut tag = aead.seal_in_place_detached(nonce, b"", &mut data).unwrap();
+    let ciphertext = data.clone();
+    tag[0] ^= 1;
+    let result = aead.open_in_place_detached(nonce, b"", &mut data, &tag);
+    assert_eq!(result, Err(AeadError));
+    assert_eq!(data, ciphertext);
+}
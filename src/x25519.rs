@@ -0,0 +1,224 @@
+/**
+* This is an implementation of the X25519 Diffie-Hellman function from RFC 7748.
+* Only the u coordinate of Curve25519 points is used, and the scalar multiplication is the
+* Montgomery ladder of RFC 7748 section 5: the same 255 steps for every scalar, with the two
+* working points exchanged by masked swaps rather than branches.
+* A peer key of small order makes the shared secret all zeros, which is rejected so a malicious
+* peer cannot force a known key.
+*/
+use crate::constant_time;
+use crate::curve25519::FieldElement;
+use crate::rng::CryptoRng;
+
+pub const KEY_SIZE: usize = 32;
+///The u coordinate of the base point, 9
+pub const BASE_POINT: [u8; KEY_SIZE] = {
+    let mut u = [0u8; KEY_SIZE];
+    u[0] = 9;
+    u
+};
+///(A - 2) / 4 for the curve constant A = 486662
+const A24: FieldElement = FieldElement([121665, 0, 0, 0, 0]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum X25519Error {
+    ///The peer public key has small order, so the shared secret is all zeros
+    LowOrderPoint,
+}
+
+impl std::fmt::Display for X25519Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            X25519Error::LowOrderPoint => write!(f, "the public key is a point of small order"),
+        }
+    }
+}
+
+impl std::error::Error for X25519Error {}
+
+/// The X25519 function, `scalar` is clamped and the top bit of `u` is ignored
+pub fn x25519(scalar: &[u8; KEY_SIZE], u: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    let x_1 = FieldElement::from_bytes(u);
+    let (mut x_2, mut z_2) = (FieldElement::ONE, FieldElement::ZERO);
+    let (mut x_3, mut z_3) = (x_1, FieldElement::ONE);
+    let mut swap = false;
+    for t in (0..255).rev() {
+        let k_t = (k[t / 8] >> (t % 8)) & 1 == 1;
+        swap ^= k_t;
+        FieldElement::swap(&mut x_2, &mut x_3, swap);
+        FieldElement::swap(&mut z_2, &mut z_3, swap);
+        swap = k_t;
+
+        let a = x_2 + z_2;
+        let aa = a.square();
+        let b = x_2 - z_2;
+        let bb = b.square();
+        let e = aa - bb;
+        let c = x_3 + z_3;
+        let d = x_3 - z_3;
+        let da = d * a;
+        let cb = c * b;
+        x_3 = (da + cb).square();
+        z_3 = x_1 * (da - cb).square();
+        x_2 = aa * bb;
+        z_2 = e * (aa + A24 * e);
+    }
+    FieldElement::swap(&mut x_2, &mut x_3, swap);
+    FieldElement::swap(&mut z_2, &mut z_3, swap);
+    (x_2 * z_2.invert()).to_bytes()
+}
+
+/// An X25519 public key, the u coordinate of a point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey([u8; KEY_SIZE]);
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8; KEY_SIZE]) -> PublicKey {
+        PublicKey(*bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_SIZE] {
+        self.0
+    }
+}
+
+/// An X25519 secret key
+#[derive(Clone)]
+pub struct SecretKey([u8; KEY_SIZE]);
+
+impl SecretKey {
+    pub fn from_bytes(bytes: &[u8; KEY_SIZE]) -> SecretKey {
+        SecretKey(*bytes)
+    }
+
+    /// Generate a fresh random key
+    pub fn generate(rng: &mut impl CryptoRng) -> SecretKey {
+        SecretKey(rng.random_array())
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_SIZE] {
+        self.0
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(x25519(&self.0, &BASE_POINT))
+    }
+
+    /// The shared secret with `peer`, it should be passed through a KDF before use
+    pub fn diffie_hellman(&self, peer: &PublicKey) -> Result<[u8; KEY_SIZE], X25519Error> {
+        let shared = x25519(&self.0, &peer.0);
+        if constant_time::eq(&shared, &[0; KEY_SIZE]) {
+            return Err(X25519Error::LowOrderPoint);
+        }
+        Ok(shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::SeededRng;
+    use crate::test_util::hex;
+    use crate::x25519::{BASE_POINT, PublicKey, SecretKey, X25519Error, x25519};
+
+    fn array(s: &str) -> [u8; 32] {
+        hex(s).try_into().unwrap()
+    }
+
+    // RFC 7748 section 5.2
+    #[test]
+    fn rfc7748_vectors() {
+        let test_result = x25519(
+            &array("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+            &array("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c"),
+        );
+        assert_eq!(
+            test_result.to_vec(),
+            hex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+        // the top bit of this u is set and must be ignored
+        let test_result = x25519(
+            &array("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d"),
+            &array("e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493"),
+        );
+        assert_eq!(
+            test_result.to_vec(),
+            hex("95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957")
+        );
+    }
+
+    // RFC 7748 section 5.2, the iterated test up to 1000 rounds (the million round value takes
+    // too long for a unit test)
+    #[test]
+    fn rfc7748_iterated() {
+        let mut k = BASE_POINT;
+        let mut u = BASE_POINT;
+        for i in 1..=1000 {
+            (k, u) = (x25519(&k, &u), k);
+            if i == 1 {
+                assert_eq!(
+                    k.to_vec(),
+                    hex("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079")
+                );
+            }
+        }
+        assert_eq!(
+            k.to_vec(),
+            hex("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51")
+        );
+    }
+
+    // RFC 7748 section 6.1
+    #[test]
+    fn rfc7748_diffie_hellman() {
+        let alice = SecretKey::from_bytes(&array(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ));
+        let bob = SecretKey::from_bytes(&array(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        ));
+        assert_eq!(
+            alice.public_key().to_bytes().to_vec(),
+            hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob.public_key().to_bytes().to_vec(),
+            hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+        let test_result = alice.diffie_hellman(&bob.public_key()).unwrap();
+        assert_eq!(
+            test_result.to_vec(),
+            hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+        assert_eq!(bob.diffie_hellman(&alice.public_key()), Ok(test_result));
+    }
+
+    #[test]
+    fn rejects_low_order_points() {
+        let mut rng = SeededRng::from_seed(b"x25519");
+        let key = SecretKey::generate(&mut rng);
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        // 0 and 1, and a point of order 8 from the libsodium blocklist
+        for u in [
+            [0u8; 32],
+            one,
+            array("e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800"),
+        ] {
+            assert_eq!(
+                key.diffie_hellman(&PublicKey::from_bytes(&u)),
+                Err(X25519Error::LowOrderPoint)
+            );
+        }
+
+        let other = SecretKey::generate(&mut rng);
+        assert_eq!(
+            key.diffie_hellman(&other.public_key()),
+            other.diffie_hellman(&key.public_key())
+        );
+    }
+}
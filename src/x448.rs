@@ -0,0 +1,434 @@
+/**
+* This is an implementation of the X448 Diffie-Hellman function from RFC 7748.
+* Field elements of GF(2^448 - 2^224 - 1) are held in eight 56 bit limbs. As 2^448 = 2^224 + 1
+* mod p, and 224 is four limbs, the upper half of a product folds back by adding each limb to the
+* two places four and eight limbs down.
+* The scalar multiplication is the same constant time Montgomery ladder as X25519, over 448 bits,
+* and all zero shared secrets from small order peer keys are rejected.
+*/
+use crate::constant_time;
+use crate::rng::CryptoRng;
+use std::ops;
+
+pub const KEY_SIZE: usize = 56;
+///The u coordinate of the base point, 5
+pub const BASE_POINT: [u8; KEY_SIZE] = {
+    let mut u = [0u8; KEY_SIZE];
+    u[0] = 5;
+    u
+};
+
+const MASK_56: u64 = (1 << 56) - 1;
+///p = 2^448 - 2^224 - 1 in limbs
+const P: [u64; 8] = [
+    MASK_56,
+    MASK_56,
+    MASK_56,
+    MASK_56,
+    MASK_56 - 1,
+    MASK_56,
+    MASK_56,
+    MASK_56,
+];
+///p - 2, the exponent of inversion, little endian
+const P_MINUS_2: [u8; KEY_SIZE] = {
+    let mut exponent = [0xff; KEY_SIZE];
+    exponent[0] = 0xfd;
+    exponent[28] = 0xfe;
+    exponent
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum X448Error {
+    ///The peer public key has small order, so the shared secret is all zeros
+    LowOrderPoint,
+}
+
+impl std::fmt::Display for X448Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            X448Error::LowOrderPoint => write!(f, "the public key is a point of small order"),
+        }
+    }
+}
+
+impl std::error::Error for X448Error {}
+
+/// An element of GF(2^448 - 2^224 - 1)
+#[derive(Clone, Copy)]
+struct FieldElement([u64; 8]);
+
+impl FieldElement {
+    const ZERO: FieldElement = FieldElement([0; 8]);
+    const ONE: FieldElement = FieldElement([1, 0, 0, 0, 0, 0, 0, 0]);
+    ///(A - 2) / 4 for the curve constant A = 156326
+    const A24: FieldElement = FieldElement([39081, 0, 0, 0, 0, 0, 0, 0]);
+
+    /// Read a little endian encoding, values of p and above are reduced
+    fn from_bytes(bytes: &[u8; KEY_SIZE]) -> FieldElement {
+        let mut limbs = [0u64; 8];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(7)) {
+            let mut word = [0u8; 8];
+            word[..7].copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(word);
+        }
+        FieldElement(limbs)
+    }
+
+    /// The canonical little endian encoding, fully reduced mod p
+    fn to_bytes(self) -> [u8; KEY_SIZE] {
+        let limbs = Self::carry(Self::carry(Self::carry(self.0).0).0).0;
+        // the value is now below 2^448 < 2p, subtract p once if that does not go negative
+        let mut reduced = [0u64; 8];
+        let mut borrow = 0u64;
+        for i in 0..8 {
+            let difference = limbs[i].wrapping_sub(P[i]).wrapping_sub(borrow);
+            borrow = difference >> 63;
+            reduced[i] = difference & MASK_56;
+        }
+        let mask = borrow.wrapping_sub(1);
+
+        let mut bytes = [0u8; KEY_SIZE];
+        for ((chunk, limb), reduced_limb) in bytes.chunks_exact_mut(7).zip(limbs).zip(reduced) {
+            let limb = (reduced_limb & mask) | (limb & !mask);
+            chunk.copy_from_slice(&limb.to_le_bytes()[..7]);
+        }
+        bytes
+    }
+
+    /// Carry every limb into the next, the top limb wraps around into limbs 0 and 4
+    fn carry(mut limbs: [u64; 8]) -> FieldElement {
+        for i in 0..7 {
+            limbs[i + 1] += limbs[i] >> 56;
+            limbs[i] &= MASK_56;
+        }
+        let top = limbs[7] >> 56;
+        limbs[7] &= MASK_56;
+        limbs[0] += top;
+        limbs[4] += top;
+        FieldElement(limbs)
+    }
+
+    fn square(&self) -> FieldElement {
+        *self * *self
+    }
+
+    /// The inverse, zero maps to zero
+    fn invert(&self) -> FieldElement {
+        let mut result = FieldElement::ONE;
+        for i in (0..448).rev() {
+            result = result.square();
+            if (P_MINUS_2[i / 8] >> (i % 8)) & 1 == 1 {
+                result = result * *self;
+            }
+        }
+        result
+    }
+
+    /// Exchange `a` and `b` if `choice` is set
+    fn swap(a: &mut FieldElement, b: &mut FieldElement, choice: bool) {
+        let mask = 0u64.wrapping_sub(choice as u64);
+        for (a_limb, b_limb) in a.0.iter_mut().zip(b.0.iter_mut()) {
+            let difference = (*a_limb ^ *b_limb) & mask;
+            *a_limb ^= difference;
+            *b_limb ^= difference;
+        }
+    }
+}
+
+impl ops::Add for FieldElement {
+    type Output = FieldElement;
+    fn add(self, rhs: FieldElement) -> FieldElement {
+        let mut limbs = self.0;
+        for (limb, rhs_limb) in limbs.iter_mut().zip(rhs.0) {
+            *limb += rhs_limb;
+        }
+        FieldElement::carry(limbs)
+    }
+}
+
+impl ops::Sub for FieldElement {
+    type Output = FieldElement;
+    fn sub(self, rhs: FieldElement) -> FieldElement {
+        // add 2p first so no limb goes negative
+        let mut limbs = self.0;
+        for ((limb, rhs_limb), p_limb) in limbs.iter_mut().zip(rhs.0).zip(P) {
+            *limb = *limb + 2 * p_limb - rhs_limb;
+        }
+        FieldElement::carry(limbs)
+    }
+}
+
+impl ops::Mul for FieldElement {
+    type Output = FieldElement;
+    fn mul(self, rhs: FieldElement) -> FieldElement {
+        let mut wide = [0u128; 16];
+        for (i, a) in self.0.iter().enumerate() {
+            for (j, b) in rhs.0.iter().enumerate() {
+                wide[i + j] += *a as u128 * *b as u128;
+            }
+        }
+        // from the top down, so limbs folded into 8..12 are folded again
+        for k in (8..16).rev() {
+            wide[k - 8] += wide[k];
+            wide[k - 4] += wide[k];
+        }
+        for i in 0..7 {
+            wide[i + 1] += wide[i] >> 56;
+            wide[i] &= MASK_56 as u128;
+        }
+        let top = wide[7] >> 56;
+        wide[7] &= MASK_56 as u128;
+        wide[0] += top;
+        wide[4] += top;
+
+        let mut limbs = [0u64; 8];
+        for (limb, column) in limbs.iter_mut().zip(wide) {
+            *limb = column as u64;
+        }
+        FieldElement::carry(limbs)
+    }
+}
+
+/// The X448 function, `scalar` is clamped
+pub fn x448(scalar: &[u8; KEY_SIZE], u: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let mut k = *scalar;
+    k[0] &= 252;
+    k[55] |= 128;
+
+    let x_1 = FieldElement::from_bytes(u);
+    let (mut x_2, mut z_2) = (FieldElement::ONE, FieldElement::ZERO);
+    let (mut x_3, mut z_3) = (x_1, FieldElement::ONE);
+    let mut swap = false;
+    for t in (0..448).rev() {
+        let k_t = (k[t / 8] >> (t % 8)) & 1 == 1;
+        swap ^= k_t;
+        FieldElement::swap(&mut x_2, &mut x_3, swap);
+        FieldElement::swap(&mut z_2, &mut z_3, swap);
+        swap = k_t;
+
+        let a = x_2 + z_2;
+        let aa = a.square();
+        let b = x_2 - z_2;
+        let bb = b.square();
+        let e = aa - bb;
+        let c = x_3 + z_3;
+        let d = x_3 - z_3;
+        let da = d * a;
+        let cb = c * b;
+        x_3 = (da + cb).square();
+        z_3 = x_1 * (da - cb).square();
+        x_2 = aa * bb;
+        z_2 = e * (aa + FieldElement::A24 * e);
+    }
+    FieldElement::swap(&mut x_2, &mut x_3, swap);
+    FieldElement::swap(&mut z_2, &mut z_3, swap);
+    (x_2 * z_2.invert()).to_bytes()
+}
+
+/// An X448 public key, the u coordinate of a point
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey([u8; KEY_SIZE]);
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8; KEY_SIZE]) -> PublicKey {
+        PublicKey(*bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_SIZE] {
+        self.0
+    }
+}
+
+/// An X448 secret key
+#[derive(Clone)]
+pub struct SecretKey([u8; KEY_SIZE]);
+
+impl SecretKey {
+    pub fn from_bytes(bytes: &[u8; KEY_SIZE]) -> SecretKey {
+        SecretKey(*bytes)
+    }
+
+    /// Generate a fresh random key
+    pub fn generate(rng: &mut impl CryptoRng) -> SecretKey {
+        SecretKey(rng.random_array())
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_SIZE] {
+        self.0
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(x448(&self.0, &BASE_POINT))
+    }
+
+    /// The shared secret with `peer`, it should be passed through a KDF before use
+    pub fn diffie_hellman(&self, peer: &PublicKey) -> Result<[u8; KEY_SIZE], X448Error> {
+        let shared = x448(&self.0, &peer.0);
+        if constant_time::eq(&shared, &[0; KEY_SIZE]) {
+            return Err(X448Error::LowOrderPoint);
+        }
+        Ok(shared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::big_int::BigInt;
+    use crate::rng::{CryptoRng, SeededRng};
+    use crate::test_util::hex;
+    use crate::x448::{BASE_POINT, FieldElement, PublicKey, SecretKey, X448Error, x448};
+
+    fn array(s: &str) -> [u8; 56] {
+        hex(s).try_into().unwrap()
+    }
+
+    // p reduces to zero, and sums, differences and products of random elements match mod p
+    #[test]
+    fn field_matches_big_int() {
+        let mut p_bytes = [0xff; 56];
+        p_bytes[28] = 0xfe;
+        let p = BigInt::new(p_bytes.to_vec());
+        let to_big = |element: &FieldElement| BigInt::new(element.to_bytes().to_vec());
+        assert_eq!(FieldElement::from_bytes(&p_bytes).to_bytes(), [0; 56]);
+
+        let mut rng = SeededRng::from_seed(b"x448 field");
+        for _ in 0..4 {
+            let a_bytes: [u8; 56] = rng.random_array();
+            let b_bytes: [u8; 56] = rng.random_array();
+            let a = FieldElement::from_bytes(&a_bytes);
+            let b = FieldElement::from_bytes(&b_bytes);
+            let big_a = BigInt::new(a_bytes.to_vec()) % p.clone();
+            let big_b = BigInt::new(b_bytes.to_vec()) % p.clone();
+
+            assert_eq!(
+                to_big(&(a + b)),
+                (big_a.clone() + big_b.clone()) % p.clone()
+            );
+            assert_eq!(
+                to_big(&(a - b)),
+                (big_a.clone() + p.clone() - big_b.clone()) % p.clone()
+            );
+            assert_eq!(
+                to_big(&(a * b)),
+                (big_a.clone() * big_b.clone()) % p.clone()
+            );
+            assert_eq!(to_big(&(a * a.invert())), BigInt::from_u64(1));
+        }
+    }
+
+    // RFC 7748 section 5.2
+    #[test]
+    fn rfc7748_vectors() {
+        let test_result = x448(
+            &array(
+                "3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c\
+                 984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3",
+            ),
+            &array(
+                "06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031\
+                 ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086",
+            ),
+        );
+        assert_eq!(
+            test_result.to_vec(),
+            hex(
+                "ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaad\
+                 eb445fc66a01b0779d98223961111e21766282f73dd96b6f"
+            )
+        );
+        let test_result = x448(
+            &array(
+                "203d494428b8399352665ddca42f9de8fef600908e0d461cb021f8c538345dd7\
+                 7c3e4806e25f46d3315c44e0a5b4371282dd2c8d5be3095f",
+            ),
+            &array(
+                "0fbcc2f993cd56d3305b0b7d9e55d4c1a8fb5dbb52f8e9a1e9b6201b165d0158\
+                 94e56c4d3570bee52fe205e28a78b91cdfbde71ce8d157db",
+            ),
+        );
+        assert_eq!(
+            test_result.to_vec(),
+            hex(
+                "884a02576239ff7a2f2f63b2db6a9ff37047ac13568e1e30fe63c4a7ad1b3ee3\
+                 a5700df34321d62077e63633c575c1c954514e99da7c179d"
+            )
+        );
+    }
+
+    // RFC 7748 section 5.2, the iterated test up to 1000 rounds
+    #[test]
+    fn rfc7748_iterated() {
+        let mut k = BASE_POINT;
+        let mut u = BASE_POINT;
+        for i in 1..=1000 {
+            (k, u) = (x448(&k, &u), k);
+            if i == 1 {
+                assert_eq!(
+                    k.to_vec(),
+                    hex("3f482c8a9f19b01e6c46ee9711d9dc14fd4bf67af30765c2ae2b846a\
+                         4d23a8cd0db897086239492caf350b51f833868b9bc2b3bca9cf4113")
+                );
+            }
+        }
+        assert_eq!(
+            k.to_vec(),
+            hex("aa3b4749d55b9daf1e5b00288826c467274ce3ebbdd5c17b975e09d4\
+                 af6c67cf10d087202db88286e2b79fceea3ec353ef54faa26e219f38")
+        );
+    }
+
+    // RFC 7748 section 6.2
+    #[test]
+    fn rfc7748_diffie_hellman() {
+        let alice = SecretKey::from_bytes(&array(
+            "9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf5\
+             74a9419744897391006382a6f127ab1d9ac2d8c0a598726b",
+        ));
+        let bob = SecretKey::from_bytes(&array(
+            "1c306a7ac2a0e2e0990b294470cba339e6453772b075811d8fad0d1d6927c120\
+             bb5ee8972b0d3e21374c9c921b09d1b0366f10b65173992d",
+        ));
+        assert_eq!(
+            alice.public_key().to_bytes().to_vec(),
+            hex(
+                "9b08f7cc31b7e3e67d22d5aea121074a273bd2b83de09c63faa73d2c22c5d9bb\
+                 c836647241d953d40c5b12da88120d53177f80e532c41fa0"
+            )
+        );
+        assert_eq!(
+            bob.public_key().to_bytes().to_vec(),
+            hex(
+                "3eb7a829b0cd20f5bcfc0b599b6feccf6da4627107bdb0d4f345b43027d8b972\
+                 fc3e34fb4232a13ca706dcb57aec3dae07bdc1c67bf33609"
+            )
+        );
+        let test_result = alice.diffie_hellman(&bob.public_key()).unwrap();
+        assert_eq!(
+            test_result.to_vec(),
+            hex(
+                "07fff4181ac6cc95ec1c16a94a0f74d12da232ce40a77552281d282bb60c0b56\
+                 fd2464c335543936521c24403085d59a449a5037514a879d"
+            )
+        );
+        assert_eq!(bob.diffie_hellman(&alice.public_key()), Ok(test_result));
+    }
+
+    #[test]
+    fn rejects_low_order_points() {
+        let mut rng = SeededRng::from_seed(b"x448");
+        let key = SecretKey::generate(&mut rng);
+        let mut one = [0u8; 56];
+        one[0] = 1;
+        // 0, 1 and p - 1 are the points of small order
+        let mut p_minus_1 = [0xff; 56];
+        p_minus_1[0] = 0xfe;
+        p_minus_1[28] = 0xfe;
+        for u in [[0u8; 56], one, p_minus_1] {
+            assert_eq!(
+                key.diffie_hellman(&PublicKey::from_bytes(&u)),
+                Err(X448Error::LowOrderPoint)
+            );
+        }
+    }
+}